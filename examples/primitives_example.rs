@@ -1,4 +1,4 @@
-use plinth_core::{PlinthApp, PlinthRenderer, start_app, Graphics};
+use plinth_core::{PlinthApp, PlinthRenderer, start_app, Graphics, Context};
 use plinth_primitives::{Circle, Color, PrimitiveRenderer, Transform};
 use plinth_styles::{ClassMapper, CssClass, ColorProperty};
 use glam::Vec2;
@@ -50,11 +50,11 @@ impl PrimitivesExample {
 }
 
 impl PlinthApp for PrimitivesExample {
-    fn init(&mut self) {
+    fn init(&mut self, _ctx: &mut Context) {
         println!("Primitives example initialized!");
     }
 
-    fn before_render(&mut self) {
+    fn before_render(&mut self, _ctx: &mut Context) {
         // Apply CSS class overrides
         for circle in &mut self.circles {
             if let Some(ref class_name) = circle.css_class {
@@ -65,7 +65,7 @@ impl PlinthApp for PrimitivesExample {
         }
     }
 
-    fn after_render(&mut self) {
+    fn after_render(&mut self, _ctx: &mut Context) {
         // Could add any post-render logic here
     }
 }
@@ -82,10 +82,8 @@ impl PlinthRenderer for PrimitivesExample {
         self.primitive_renderer.render(&gfx.device, &gfx.queue, &gfx.surface, &gfx.surface_config);
     }
 
-    fn create_pipeline(&mut self, gfx: &mut Graphics) -> wgpu::RenderPipeline {
-        // We don't need to create a pipeline here since the primitive renderer handles it
-        // Just return a dummy pipeline or the first one from the graphics context
-        gfx.render_pipelines[0].clone()
+    fn create_pipelines(&mut self, _gfx: &mut Graphics) {
+        // We don't need to register any pipelines here since the primitive renderer handles it
     }
 }
 
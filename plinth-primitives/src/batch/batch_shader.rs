@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use wgpu::{BlendState, Device};
+
+use crate::shaders::ShaderPreprocessor;
+
+// The shader side of a batch: its WGSL source, the defines it is preprocessed with and the
+// targets its pipelines are built for. Batches own one and hand their pipeline builder to
+// `build`. Defines are toggles such as PREMULTIPLIED_ALPHA or DEBUG_OVERLAY; toggling one,
+// changing the targets or editing the shader on disk all mean rebuilding the batch's
+// pipelines, and a rebuild that fails keeps the previous pipeline in use.
+#[derive(Debug, Clone)]
+pub struct BatchShader {
+    // Used in logs, e.g. "circle"
+    name: &'static str,
+    shader: ShaderSource,
+    preprocessor: ShaderPreprocessor,
+    // Unset until the batch's first pipeline is built
+    targets: Option<PipelineTargets>,
+    // Text of the shader and its includes as last read from the ShaderLibrary, by path, for
+    // the files that differ from their embedded copies. Every build reads through it, so
+    // later define or target changes keep hot-reloaded edits.
+    edited: HashMap<&'static str, String>,
+}
+
+impl BatchShader {
+    pub fn new(name: &'static str, shader: ShaderSource) -> Self {
+        Self {
+            name,
+            shader,
+            preprocessor: ShaderPreprocessor::new(),
+            targets: None,
+            edited: HashMap::new(),
+        }
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        &self.preprocessor
+    }
+
+    pub fn targets(&self) -> Option<PipelineTargets> {
+        self.targets
+    }
+
+    // Returns whether the targets changed, i.e. whether pipelines have to be rebuilt
+    pub fn set_targets(&mut self, targets: PipelineTargets) -> bool {
+        if self.targets == Some(targets) {
+            return false;
+        }
+        self.targets = Some(targets);
+        true
+    }
+
+    // Returns whether the define changed, i.e. whether pipelines have to be rebuilt
+    pub fn set_define(&mut self, name: &str, enabled: bool) -> bool {
+        if self.preprocessor.is_defined(name) == enabled {
+            return false;
+        }
+        self.preprocessor.set_define(name, enabled);
+        true
+    }
+
+    // Watches the shader and its includes through `shaders` and takes in their edits. Returns
+    // whether one of them changed on disk since the last poll.
+    pub fn reload(&mut self, shaders: &mut ShaderLibrary) -> bool {
+        let sources: Vec<ShaderSource> = std::iter::once(self.shader).chain(self.preprocessor.includes().copied()).collect();
+        let paths: Vec<&'static str> = sources.iter().filter_map(|source| source.dev_path).collect();
+        for path in &paths {
+            shaders.watch(path);
+        }
+        if !paths.iter().any(|path| shaders.has_changed(path)) {
+            return false;
+        }
+        log::info!("Rebuilding {} pipelines, shader changed", self.name);
+        for source in &sources {
+            let Some(path) = source.dev_path else {
+                continue;
+            };
+            let text = shaders.source(source);
+            if text == source.embedded {
+                self.edited.remove(path);
+            } else {
+                self.edited.insert(path, text.into_owned());
+            }
+        }
+        true
+    }
+
+    // The shader or include as last reloaded
+    fn read(&self, shader: &ShaderSource) -> String {
+        shader
+            .dev_path
+            .and_then(|path| self.edited.get(path))
+            .cloned()
+            .unwrap_or_else(|| shader.embedded.to_string())
+    }
+
+    // Preprocesses the shader with `defines` on top of the batch's own, then builds a pipeline
+    // from it for the current targets. Failures are logged and give None, as does building
+    // before the targets are set.
+    pub fn build<T>(
+        &self,
+        device: &Device,
+        defines: &[&str],
+        build: impl FnOnce(&str, PipelineTargets, BlendState) -> T,
+    ) -> Option<T> {
+        let targets = self.targets?;
+        let mut preprocessor = self.preprocessor.clone();
+        for define in defines {
+            preprocessor.define(*define);
+        }
+        let source = match preprocessor.process_with(&self.read(&self.shader), &|shader| self.read(shader)) {
+            Ok(source) => source,
+            Err(e) => {
+                log::error!("Keeping previous {} pipeline, shader failed to preprocess: {}", self.name, e);
+                return None;
+            }
+        };
+        let blend = preprocessor.blend_state();
+        match plinth_core::shaders::try_build(device, || build(&source, targets, blend)) {
+            Ok(pipeline) => Some(pipeline),
+            Err(e) => {
+                log::error!("Keeping previous {} pipeline, shader failed to compile:\n{}", self.name, e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+// Format of the stencil buffer rounded and path clips are drawn into. Stencil8 alone isn't
+// renderable on every backend; the depth half goes unused.
+pub const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+// The clip a primitive was added under, handed out by PrimitiveRenderer's push_clip_*
+// methods. NONE draws unclipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ClipId(pub u32);
+
+impl ClipId {
+    pub const NONE: ClipId = ClipId(0);
+}
+
+// Consecutive instances (or indices, for meshes) drawn under the same clip
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipRun {
+    pub instances: Range<usize>,
+    pub clip: ClipId,
+}
+
+impl ClipRun {
+    // Adds `instances` to the last run if it ends where they start under the same clip,
+    // starting a new run otherwise. Empty ranges are skipped.
+    pub fn push(runs: &mut Vec<ClipRun>, instances: Range<usize>, clip: ClipId) {
+        if instances.is_empty() {
+            return;
+        }
+        if let Some(last) = runs.last_mut() {
+            if last.clip == clip && last.instances.end == instances.start {
+                last.instances.end = instances.end;
+                return;
+            }
+        }
+        runs.push(ClipRun { instances, clip });
+    }
+}
+
+// Sets up a clip around each run a batch draws, see PrimitiveRenderer. Batches set their
+// pipeline again after `begin`, which may draw stencil masks with its own.
+pub trait ClipScope {
+    // False when nothing under `clip` can show, e.g. it lies off screen; the run is skipped
+    // and `end` not called
+    fn begin(&mut self, render_pass: &mut wgpu::RenderPass, clip: ClipId) -> bool;
+    // Undoes whatever `begin` left behind that would clip later draws
+    fn end(&mut self, render_pass: &mut wgpu::RenderPass, clip: ClipId);
+}
+
+// Draws `runs` in order, each inside its clip. `draw` sets up the batch's pipeline and draws
+// one range; unclipped runs skip the scope.
+pub fn render_clip_runs(
+    render_pass: &mut wgpu::RenderPass,
+    runs: &[ClipRun],
+    scope: &mut dyn ClipScope,
+    mut draw: impl FnMut(&mut wgpu::RenderPass, Range<usize>),
+) {
+    for run in runs {
+        if run.clip == ClipId::NONE {
+            draw(render_pass, run.instances.clone());
+        } else if scope.begin(render_pass, run.clip) {
+            draw(render_pass, run.instances.clone());
+            scope.end(render_pass, run.clip);
+        }
+    }
+}
+
+// What every batch pipeline tests the stencil with: a fragment is drawn where the stencil
+// equals the reference, which clips set to the number of masks they drew and is 0 otherwise
+pub fn clip_depth_stencil() -> wgpu::DepthStencilState {
+    let face = wgpu::StencilFaceState {
+        compare: wgpu::CompareFunction::Equal,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op: wgpu::StencilOperation::Keep,
+    };
+    wgpu::DepthStencilState {
+        format: STENCIL_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: 0xff,
+            write_mask: 0,
+        },
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
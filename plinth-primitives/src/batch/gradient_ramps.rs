@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use wgpu::{BindGroup, BindGroupLayout, Device};
+
+use crate::types::{resolve_stops, Color, ColorStop};
+
+// Texels across one ramp; the shader samples between them
+pub const RAMP_WIDTH: u32 = 256;
+
+// The gradients a batch draws, each baked into one row of a lookup texture that
+// plinth/paint.wgsl samples at the fragment's position along the gradient. Rows are rebuilt
+// whenever the batch rebuilds its instances; gradients with the same stops share a row.
+pub struct GradientRamps {
+    // RGBA8 rows of RAMP_WIDTH texels, straight alpha
+    pixels: Vec<u8>,
+    // The rows before the last `clear`
+    previous: Vec<u8>,
+    rows: HashMap<Vec<u32>, u32>,
+    // What the texture holds, so unchanged ramps aren't uploaded again
+    uploaded: Option<Vec<u8>>,
+    bind_group_layout: Option<BindGroupLayout>,
+    sampler: Option<wgpu::Sampler>,
+    // The texture's height in rows
+    capacity: u32,
+    texture: Option<wgpu::Texture>,
+    bind_group: Option<BindGroup>,
+}
+
+impl GradientRamps {
+    pub fn new() -> Self {
+        Self {
+            pixels: Vec::new(),
+            previous: Vec::new(),
+            rows: HashMap::new(),
+            uploaded: None,
+            bind_group_layout: None,
+            sampler: None,
+            capacity: 0,
+            texture: None,
+            bind_group: None,
+        }
+    }
+
+    // Forgets every row, ahead of adding the ones still in use
+    pub fn clear(&mut self) {
+        self.previous = std::mem::take(&mut self.pixels);
+        self.rows.clear();
+    }
+
+    // Whether the rows differ from the ones before the last `clear`
+    pub fn rows_changed(&self) -> bool {
+        self.pixels != self.previous
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // The row holding `stops`, baking it if no gradient used them yet
+    pub fn row(&mut self, stops: &[ColorStop]) -> u32 {
+        let stops = resolve_stops(stops);
+        let key: Vec<u32> = stops
+            .iter()
+            .flat_map(|(offset, color)| [*offset, color.r, color.g, color.b, color.a])
+            .map(f32::to_bits)
+            .collect();
+        if let Some(&row) = self.rows.get(&key) {
+            return row;
+        }
+        let row = self.rows.len() as u32;
+        for x in 0..RAMP_WIDTH {
+            let color = sample(&stops, x as f32 / (RAMP_WIDTH - 1) as f32);
+            let channels = [color.r, color.g, color.b, color.a];
+            self.pixels.extend(channels.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8));
+        }
+        self.rows.insert(key, row);
+        row
+    }
+
+    pub fn bind_group_layout(&mut self, device: &Device) -> &BindGroupLayout {
+        self.bind_group_layout.get_or_insert_with(|| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Gradient Ramps Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            })
+        })
+    }
+
+    // Writes the ramps to the texture if they changed, growing it when needed. There is
+    // always a texture afterwards, even with no gradients, since every draw binds one.
+    pub fn upload(&mut self, device: &Device, queue: &wgpu::Queue) {
+        if self.uploaded.as_deref() == Some(self.pixels.as_slice()) && self.bind_group.is_some() {
+            return;
+        }
+        let rows = self.rows.len().max(1) as u32;
+        if self.texture.is_none() || rows > self.capacity {
+            self.create_texture(device, rows.next_power_of_two());
+        }
+        if let Some(texture) = &self.texture {
+            if !self.rows.is_empty() {
+                queue.write_texture(
+                    wgpu::TexelCopyTextureInfo {
+                        texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    &self.pixels,
+                    wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(RAMP_WIDTH * 4),
+                        rows_per_image: Some(self.rows.len() as u32),
+                    },
+                    wgpu::Extent3d {
+                        width: RAMP_WIDTH,
+                        height: self.rows.len() as u32,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+        self.uploaded = Some(self.pixels.clone());
+    }
+
+    fn create_texture(&mut self, device: &Device, capacity: u32) {
+        self.bind_group_layout(device);
+        let sampler = self.sampler.get_or_insert_with(|| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Gradient Ramps Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            })
+        });
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Gradient Ramps"),
+            size: wgpu::Extent3d {
+                width: RAMP_WIDTH,
+                height: capacity,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Gradient Ramps Bind Group"),
+            layout: self.bind_group_layout.as_ref().unwrap(),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        }));
+        self.texture = Some(texture);
+        self.capacity = capacity;
+    }
+
+    // None until the first upload
+    pub fn bind_group(&self) -> Option<&BindGroup> {
+        self.bind_group.as_ref()
+    }
+}
+
+impl Default for GradientRamps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The color at `t` between resolved stops, interpolated with premultiplied alpha like CSS
+// so fading to transparent doesn't pass through the transparent color's hue
+fn sample(stops: &[(f32, Color)], t: f32) -> Color {
+    let Some(&(first_offset, first)) = stops.first() else {
+        return Color::TRANSPARENT;
+    };
+    if t <= first_offset {
+        return first;
+    }
+    for pair in stops.windows(2) {
+        let ((from_offset, from), (to_offset, to)) = (pair[0], pair[1]);
+        if t > to_offset {
+            continue;
+        }
+        let span = to_offset - from_offset;
+        let f = if span > 0.0 { (t - from_offset) / span } else { 1.0 };
+        let alpha = from.a + (to.a - from.a) * f;
+        if alpha <= 0.0 {
+            return Color::TRANSPARENT;
+        }
+        let mix = |a: f32, b: f32| (a * from.a * (1.0 - f) + b * to.a * f) / alpha;
+        return Color::new(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b), alpha);
+    }
+    stops[stops.len() - 1].1
+}
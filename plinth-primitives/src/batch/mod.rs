@@ -1,3 +1,11 @@
+pub mod batch_shader;
+pub mod clipping;
+pub mod gradient_ramps;
 pub mod instance_batch;
+pub mod style_cache;
 
+pub use batch_shader::*;
+pub use clipping::*;
+pub use gradient_ramps::*;
 pub use instance_batch::*;
+pub use style_cache::*;
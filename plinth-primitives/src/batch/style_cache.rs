@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
+
+use bytemuck::Pod;
+use glam::Vec2;
+
+use crate::primitives::{Interactive, StyleAnimation, StyleResolver, Styled};
+use crate::types::{InteractionState, Style, StyleViewport};
+
+// One primitive's styled copy and animation state
+#[derive(Debug)]
+struct Entry<T> {
+    // The primitive with the styles of its classes and state applied, None when it's drawn
+    // as added (no classes or no resolver)
+    styled: Option<T>,
+    animation: Option<Box<dyn StyleAnimation>>,
+}
+
+// Styles a batch resolved per (class list, state), kept while the resolver's generation
+// stays the same, and an entry per primitive holding its styled copy and animation state.
+// Entries are created for primitives as they are added and dropped with them when the batch
+// is cleared, so a primitive keeps its own transitions for as long as it's in the batch.
+// Only primitives whose class styles may have changed are styled again.
+#[derive(Debug)]
+pub struct StyleCache<T> {
+    styles: HashMap<(String, InteractionState), Style>,
+    generation: Option<u64>,
+    entries: Vec<Entry<T>>,
+    // Indices of the primitives the batch drew last, in the order it drew them
+    drawn: Vec<usize>,
+    time: Duration,
+    viewport: StyleViewport,
+    // Every primitive is styled again on the next `restyle`, e.g. after the viewport changed
+    restyle_all: bool,
+    // A primitive's interaction state may have changed since the last `restyle`
+    states_changed: bool,
+}
+
+impl<T: Styled + Clone> StyleCache<T> {
+    pub fn new() -> Self {
+        Self {
+            styles: HashMap::new(),
+            generation: None,
+            entries: Vec::new(),
+            drawn: Vec::new(),
+            time: Duration::ZERO,
+            viewport: StyleViewport::default(),
+            restyle_all: false,
+            states_changed: false,
+        }
+    }
+
+    // Drops cached styles if `resolver` changed since the last call. Returns whether it did,
+    // a state changed or a primitive is animating, i.e. whether `restyle` has work to do.
+    pub fn sync(&mut self, resolver: Option<&dyn StyleResolver>) -> bool {
+        let generation = resolver.map(|resolver| resolver.generation());
+        if generation != self.generation {
+            self.styles.clear();
+            self.generation = generation;
+            self.restyle_all = true;
+        }
+        self.restyle_all || self.states_changed || self.is_animating()
+    }
+
+    // Time animations are sampled at from now on
+    pub fn set_time(&mut self, time: Duration) {
+        self.time = time;
+    }
+
+    // What CSS lengths are converted against from now on. Restyles the batch when it changes.
+    pub fn set_viewport(&mut self, viewport: StyleViewport) {
+        if viewport != self.viewport {
+            self.viewport = viewport;
+            self.restyle_all = true;
+        }
+    }
+
+    // Styles every primitive again on the next `restyle`, e.g. after they were edited in place
+    pub fn invalidate(&mut self) {
+        self.restyle_all = true;
+    }
+
+    // Flag the renderer's pointer handling sets when it changed a primitive's state, so only
+    // primitives whose state differs from the one they were styled in are restyled
+    pub fn states_changed_mut(&mut self) -> &mut bool {
+        &mut self.states_changed
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.animation.as_ref())
+            .any(|animation| animation.is_animating())
+    }
+
+    // Forgets every primitive's styled copy and animation state, when the batch is cleared
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.drawn.clear();
+    }
+
+    // Which primitives the batch drew, as it sorted them for drawing
+    pub fn set_drawn(&mut self, drawn: impl IntoIterator<Item = usize>) {
+        self.drawn.clear();
+        self.drawn.extend(drawn);
+    }
+
+    // The primitives of the batch as last drawn, in draw order after those that weren't,
+    // for hit-testing what is on screen, and the flag to set when their state changed, see
+    // `states_changed_mut`
+    pub fn drawn_mut<'a>(&'a mut self, primitives: &'a mut [T]) -> (Vec<Drawn<'a, T>>, &'a mut bool) {
+        let mut primitives: Vec<Option<&mut T>> = primitives.iter_mut().map(Some).collect();
+        let mut drawn: Vec<Drawn<'a, T>> = Vec::with_capacity(primitives.len());
+        for index in &self.drawn {
+            if let Some(primitive) = primitives.get_mut(*index).and_then(Option::take) {
+                let styled = self.entries.get(*index).and_then(|entry| entry.styled.as_ref());
+                drawn.push(Drawn { primitive, styled, drawn: true });
+            }
+        }
+        let hidden = primitives.into_iter().flatten().map(|primitive| Drawn { primitive, styled: None, drawn: false });
+        drawn.splice(0..0, hidden);
+        (drawn, &mut self.states_changed)
+    }
+
+    // Applies the styles of their classes and state from `resolver`, as animated by it, to
+    // the primitives that were added, changed state or are animating since the last call, or
+    // to all of them after `invalidate` or a new generation. Returns the indices restyled.
+    pub fn restyle(&mut self, resolver: Option<&dyn StyleResolver>, primitives: &[T]) -> Vec<usize> {
+        let restyle_all = std::mem::take(&mut self.restyle_all);
+        let states_changed = std::mem::take(&mut self.states_changed);
+        let styled_before = self.entries.len();
+        self.entries.resize_with(primitives.len(), || Entry {
+            styled: None,
+            animation: None,
+        });
+
+        let mut restyled = Vec::new();
+        for (index, (primitive, entry)) in primitives.iter().zip(&mut self.entries).enumerate() {
+            let stale = restyle_all
+                || index >= styled_before
+                || entry.animation.as_ref().is_some_and(|animation| animation.is_animating())
+                || (states_changed && entry.styled.as_ref().is_some_and(|styled| styled.state() != primitive.state()));
+            if !stale {
+                continue;
+            }
+            entry.styled = None;
+            if let (Some(resolver), Some(class_list)) = (resolver, primitive.css_class()) {
+                let state = primitive.state();
+                let style = self.styles.entry((class_list.to_string(), state)).or_insert_with(|| {
+                    let classes: Vec<&str> = class_list.split_whitespace().collect();
+                    resolver.resolve_style(&classes, state)
+                });
+                if entry.animation.is_none() {
+                    entry.animation = resolver.animation();
+                }
+                let mut styled = primitive.clone();
+                match entry.animation.as_mut() {
+                    Some(animation) => {
+                        let animated = resolver.animate(animation.as_mut(), style, self.time);
+                        styled.apply_css_style(&animated, &self.viewport)
+                    }
+                    None => styled.apply_css_style(style, &self.viewport),
+                }
+                entry.styled = Some(styled);
+            }
+            restyled.push(index);
+        }
+        restyled
+    }
+
+    // The `index`th primitive of the batch as of the last `restyle`
+    pub fn styled<'a>(&'a self, index: usize, primitive: &'a T) -> &'a T {
+        self.entries
+            .get(index)
+            .and_then(|entry| entry.styled.as_ref())
+            .unwrap_or(primitive)
+    }
+}
+
+impl<T: Styled + Clone> Default for StyleCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A primitive as its batch last drew it: hit-tested with the styles it was drawn in, never
+// hit if it wasn't drawn, its state kept on the primitive itself. Lists of them come in draw
+// order, so the last one hit is the one on top whatever its z-index.
+pub struct Drawn<'a, T> {
+    primitive: &'a mut T,
+    styled: Option<&'a T>,
+    drawn: bool,
+}
+
+impl<T: Interactive> Interactive for Drawn<'_, T> {
+    fn hit_test(&self, point: Vec2) -> bool {
+        self.drawn && self.styled.unwrap_or(&*self.primitive).hit_test(point)
+    }
+
+    fn hit_test_in_surface(&self, point: Vec2, surface_size: (u32, u32)) -> bool {
+        self.drawn && self.styled.unwrap_or(&*self.primitive).hit_test_in_surface(point, surface_size)
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        self.primitive.state_mut()
+    }
+}
+
+// Which primitive each run of a batch's instances was built from. When a rebuild lays the
+// instances out the same way, only those of restyled primitives are marked for upload;
+// otherwise the new instances are compared with the old ones.
+#[derive(Debug, Default)]
+pub struct InstanceSources {
+    runs: Vec<(usize, Range<usize>)>,
+}
+
+impl InstanceSources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Compares the next rebuild's instances in full, e.g. after something every instance
+    // depends on changed
+    pub fn forget(&mut self) {
+        self.runs.clear();
+    }
+
+    // The instances that differ between `old` and `new`, where `new` was built with one run
+    // per drawn primitive in `runs` and `restyled` are the primitives styled again for it
+    pub fn changed<I: Pod>(
+        &mut self,
+        runs: Vec<(usize, Range<usize>)>,
+        restyled: &[usize],
+        old: &[I],
+        new: &[I],
+    ) -> Option<Range<usize>> {
+        if runs != self.runs || old.len() != new.len() {
+            self.runs = runs;
+            return changed_instances(old, new);
+        }
+        let mut changed: Option<Range<usize>> = None;
+        for (_, run) in self.runs.iter().filter(|(primitive, _)| restyled.binary_search(primitive).is_ok()) {
+            let before: &[u8] = bytemuck::cast_slice(&old[run.clone()]);
+            if before == bytemuck::cast_slice::<I, u8>(&new[run.clone()]) {
+                continue;
+            }
+            changed = Some(match changed {
+                Some(changed) => changed.start.min(run.start)..changed.end.max(run.end),
+                None => run.clone(),
+            });
+        }
+        changed
+    }
+}
+
+// The smallest range holding every instance that differs between `old` and `new`, all of
+// `new` if their lengths differ
+pub fn changed_instances<I: Pod>(old: &[I], new: &[I]) -> Option<Range<usize>> {
+    if old.len() != new.len() {
+        return Some(0..new.len());
+    }
+    let differs = |index: &usize| bytemuck::bytes_of(&old[*index]) != bytemuck::bytes_of(&new[*index]);
+    let first = (0..new.len()).find(differs);
+    let last = (0..new.len()).rev().find(differs);
+    first.zip(last).map(|(first, last)| first..last + 1)
+}
@@ -0,0 +1,302 @@
+use bytemuck::{Pod, Zeroable};
+use plinth_core::shader_source;
+use plinth_core::shaders::ShaderSource;
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, BindGroupLayout, CommandEncoder, Device, RenderPipeline, TextureFormat, TextureView};
+use std::borrow::Cow;
+
+use crate::effects::RenderTarget;
+use crate::shaders::ShaderPreprocessor;
+
+pub const BLUR_SHADER: ShaderSource = shader_source!("src/shaders/blur.wgsl");
+
+// Weights the blur shader has room for, the center's included. Wider blurs space their taps
+// out instead, leaning on linear filtering between texels.
+pub const MAX_BLUR_TAPS: usize = 64;
+
+// One side of a normalized Gaussian, sampled every `spacing` texels out to three standard
+// deviations
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussianKernel {
+    // The center first; every other weight applies on both sides
+    pub weights: Vec<f32>,
+    pub spacing: f32,
+}
+
+impl GaussianKernel {
+    pub fn new(sigma: f32) -> Self {
+        if sigma <= 0.0 {
+            return Self { weights: vec![1.0], spacing: 1.0 };
+        }
+        let reach = (sigma * 3.0).ceil() as usize;
+        let (taps, spacing) = if reach < MAX_BLUR_TAPS {
+            (reach + 1, 1.0)
+        } else {
+            (MAX_BLUR_TAPS, reach as f32 / (MAX_BLUR_TAPS - 1) as f32)
+        };
+        let mut weights: Vec<f32> = (0..taps)
+            .map(|i| {
+                let x = i as f32 * spacing;
+                (-(x * x) / (2.0 * sigma * sigma)).exp()
+            })
+            .collect();
+        let total = weights[0] + 2.0 * weights[1..].iter().sum::<f32>();
+        for weight in &mut weights {
+            *weight /= total;
+        }
+        Self { weights, spacing }
+    }
+}
+
+// Uniforms of fs_blur in blur.wgsl
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct BlurParams {
+    direction: [f32; 2],
+    taps: f32,
+    _padding: f32,
+    weights: [[f32; 4]; MAX_BLUR_TAPS / 4],
+}
+
+// Separable Gaussian blur between render targets of one format, plus the passes that draw a
+// target back over another. Targets hold premultiplied color, which is what batches leave
+// behind when they blend over a cleared target.
+pub struct GaussianBlur {
+    texture_layout: BindGroupLayout,
+    params_layout: BindGroupLayout,
+    sampler: wgpu::Sampler,
+    blur_pipeline: RenderPipeline,
+    // Draws a target over another
+    composite_pipeline: RenderPipeline,
+    // Replaces a target's contents with another's
+    copy_pipeline: RenderPipeline,
+}
+
+impl GaussianBlur {
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blur Texture Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blur Params Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Blur Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let source = ShaderPreprocessor::new()
+            .process(BLUR_SHADER.embedded)
+            .expect("Failed to preprocess blur shader");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(BLUR_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let pipeline = |label: &str, entry_point: &str, layouts: &[&BindGroupLayout], blend: Option<wgpu::BlendState>| {
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: layouts,
+                push_constant_ranges: &[],
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_fullscreen"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let blur_pipeline = pipeline("Blur Pipeline", "fs_blur", &[&texture_layout, &params_layout], None);
+        let composite_pipeline = pipeline(
+            "Blur Composite Pipeline",
+            "fs_copy",
+            &[&texture_layout],
+            Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        );
+        let copy_pipeline = pipeline("Blur Copy Pipeline", "fs_copy", &[&texture_layout], None);
+
+        Self {
+            texture_layout,
+            params_layout,
+            sampler,
+            blur_pipeline,
+            composite_pipeline,
+            copy_pipeline,
+        }
+    }
+
+    // Layout of a texture and sampler at bindings 0 and 1, what `bind_texture` creates
+    pub fn texture_layout(&self) -> &BindGroupLayout {
+        &self.texture_layout
+    }
+
+    pub fn bind_texture(&self, device: &Device, view: &TextureView) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blur Texture Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        })
+    }
+
+    // Blurs `source` into `destination` by `sigma` pixels, horizontally into `scratch` and
+    // then vertically. `source` and `destination` may be the same target; `scratch` must
+    // be neither and all three the same size.
+    pub fn blur(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &RenderTarget,
+        scratch: &RenderTarget,
+        destination: &RenderTarget,
+        sigma: f32,
+    ) {
+        let kernel = GaussianKernel::new(sigma);
+        let (width, height) = source.size();
+        let step = kernel.spacing;
+        self.blur_pass(device, encoder, source, scratch, &kernel, [step / width as f32, 0.0]);
+        self.blur_pass(device, encoder, scratch, destination, &kernel, [0.0, step / height as f32]);
+    }
+
+    fn blur_pass(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &RenderTarget,
+        destination: &RenderTarget,
+        kernel: &GaussianKernel,
+        direction: [f32; 2],
+    ) {
+        let mut params = BlurParams {
+            direction,
+            taps: kernel.weights.len() as f32,
+            _padding: 0.0,
+            weights: [[0.0; 4]; MAX_BLUR_TAPS / 4],
+        };
+        for (index, weight) in kernel.weights.iter().enumerate() {
+            params.weights[index / 4][index % 4] = *weight;
+        }
+        // Passes are recorded before any of them run, so each gets its own uniforms
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Blur Params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let params_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blur Params Bind Group"),
+            layout: &self.params_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        let texture = self.bind_texture(device, source.view());
+        let mut render_pass = Self::begin(encoder, destination.view(), "Blur Pass", true);
+        render_pass.set_pipeline(&self.blur_pipeline);
+        render_pass.set_bind_group(0, &texture, &[]);
+        render_pass.set_bind_group(1, &params_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    // Draws `source` over what `destination` already holds
+    pub fn composite(&self, device: &Device, encoder: &mut CommandEncoder, source: &RenderTarget, destination: &TextureView) {
+        self.draw_texture(device, encoder, source, destination, &self.composite_pipeline, false);
+    }
+
+    // Replaces what `destination` holds with `source`, e.g. to present a target
+    pub fn copy(&self, device: &Device, encoder: &mut CommandEncoder, source: &RenderTarget, destination: &TextureView) {
+        self.draw_texture(device, encoder, source, destination, &self.copy_pipeline, true);
+    }
+
+    fn draw_texture(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        source: &RenderTarget,
+        destination: &TextureView,
+        pipeline: &RenderPipeline,
+        clear: bool,
+    ) {
+        let texture = self.bind_texture(device, source.view());
+        let mut render_pass = Self::begin(encoder, destination, "Blur Composite Pass", clear);
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &texture, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn begin<'a>(encoder: &'a mut CommandEncoder, view: &TextureView, label: &str, clear: bool) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: if clear { wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT) } else { wgpu::LoadOp::Load },
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+}
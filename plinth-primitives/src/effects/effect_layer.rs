@@ -0,0 +1,43 @@
+use std::ops::Range;
+
+use crate::batch::ClipId;
+use crate::types::Appearance;
+
+// A run of instances, in draw order, sharing the post-process effects and the clip they need.
+// PrimitiveRenderer draws layers without effects straight into the frame, draws a blurred
+// layer into a target of its own to blur it there, and blurs the frame drawn so far before
+// a backdrop layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectLayer {
+    pub instances: Range<usize>,
+    // Standard deviations in pixels, 0 for none
+    pub blur: f32,
+    pub backdrop_blur: f32,
+    pub clip: ClipId,
+}
+
+impl EffectLayer {
+    pub fn has_effects(&self) -> bool {
+        self.blur > 0.0 || self.backdrop_blur > 0.0
+    }
+
+    // Adds the instance at `index` to the last layer if it needs the same effects and clip,
+    // starting a new layer otherwise. Backdrops never share a layer, since each blurs whatever
+    // is behind it, earlier backdrops included.
+    pub fn push(layers: &mut Vec<EffectLayer>, index: usize, appearance: &Appearance, clip: ClipId) {
+        let (blur, backdrop_blur) = (appearance.blur, appearance.backdrop_blur);
+        if let Some(last) = layers.last_mut() {
+            let merges = last.instances.end == index && last.clip == clip && last.blur == blur;
+            if merges && last.backdrop_blur == 0.0 && backdrop_blur == 0.0 {
+                last.instances.end = index + 1;
+                return;
+            }
+        }
+        layers.push(EffectLayer {
+            instances: index..index + 1,
+            blur,
+            backdrop_blur,
+            clip,
+        });
+    }
+}
@@ -0,0 +1,9 @@
+// Post-processing over offscreen render targets, for the CSS filters primitives can't draw
+// analytically
+pub mod blur;
+pub mod effect_layer;
+pub mod render_target;
+
+pub use blur::*;
+pub use effect_layer::*;
+pub use render_target::*;
@@ -0,0 +1,76 @@
+use wgpu::{Device, Texture, TextureFormat, TextureView};
+
+// An offscreen color texture that passes draw into and later passes sample
+pub struct RenderTarget {
+    texture: Texture,
+    view: TextureView,
+    label: String,
+}
+
+impl RenderTarget {
+    pub fn new(device: &Device, width: u32, height: u32, format: TextureFormat, label: impl Into<String>) -> Self {
+        Self::multisampled(device, width, height, format, 1, label)
+    }
+
+    // A target with `sample_count` samples per pixel. Above 1 it can't be sampled, only
+    // drawn into and resolved.
+    pub fn multisampled(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        sample_count: u32,
+        label: impl Into<String>,
+    ) -> Self {
+        let label = label.into();
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view, label }
+    }
+
+    // Recreates the texture if it isn't `width` by `height`, losing its contents
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        if self.size() != (width.max(1), height.max(1)) {
+            let (format, sample_count) = (self.format(), self.sample_count());
+            *self = Self::multisampled(device, width, height, format, sample_count, std::mem::take(&mut self.label));
+        }
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.texture.width(), self.texture.height())
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.texture.sample_count()
+    }
+}
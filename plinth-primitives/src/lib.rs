@@ -3,7 +3,12 @@ pub mod shaders;
 pub mod renderer;
 pub mod types;
 pub mod batch;
+pub mod effects;
+#[cfg(feature = "serde")]
+pub mod scene;
 
 pub use primitives::*;
 pub use renderer::PrimitiveRenderer;
 pub use types::*;
+#[cfg(feature = "serde")]
+pub use scene::*;
@@ -1,16 +1,34 @@
 use glam::Vec2;
-use wgpu::{Device, RenderPipeline, ShaderModuleDescriptor, ShaderSource};
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use wgpu::{BindGroupLayout, Device, RenderPipeline, ShaderModuleDescriptor};
 use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
 
-use crate::types::{Color, Transform};
-use crate::batch::InstanceBatch;
+use crate::types::{
+    Appearance, Color, InteractionState, Paint, PaintData, Shadow, ShadowData, Style, StyleViewport, Transform,
+};
+use crate::primitives::{Interactive, StyleResolver, Styled};
+use crate::batch::{
+    clip_depth_stencil, render_clip_runs, BatchShader, ClipId, ClipRun, ClipScope, Drawn, GradientRamps,
+    InstanceBatch, InstanceSources, StyleCache,
+};
+use crate::effects::EffectLayer;
+use crate::shaders::ShaderPreprocessor;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Circle {
     pub center: Vec2,
     pub radius: f32,
     pub color: Color,
+    // Fills the circle instead of `color` when set
+    pub paint: Option<Paint>,
     pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
     pub css_class: Option<String>,
 }
 
@@ -20,7 +38,10 @@ impl Circle {
             center,
             radius,
             color: Color::default(),
+            paint: None,
             transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
             css_class: None,
         }
     }
@@ -30,6 +51,13 @@ impl Circle {
         self
     }
 
+    // Gradients span the circle's bounding box and turn with its rotation
+    pub fn with_paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = Some(paint.into());
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
     pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
         self.css_class = Some(class.into());
         self
@@ -40,10 +68,93 @@ impl Circle {
         self
     }
 
+    // A box-shadow as round as the circle, drawn outside it only
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.appearance.shadow = Some(shadow);
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
     // This method will be called during render to apply CSS class overrides
     pub fn apply_css_override(&mut self, color: Color) {
         self.color = color;
     }
+
+    // Applies every property `style` sets. width/height set the diameter (the smaller one
+    // when both are given); border-radius doesn't apply to circles. A background color
+    // replaces any paint, a background gradient becomes it.
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        match style.fill_paint() {
+            Some(Paint::Solid(color)) => {
+                self.color = color;
+                self.paint = None;
+            }
+            Some(paint) => self.paint = Some(paint),
+            None => {}
+        }
+        let size = viewport.clip(Vec2::new(style.width.unwrap_or(0.0), style.height.unwrap_or(0.0)));
+        if let Some(diameter) = match (style.width, style.height) {
+            (Some(_), Some(_)) => Some(size.x.min(size.y)),
+            (Some(_), None) => Some(size.x),
+            (None, Some(_)) => Some(size.y),
+            (None, None) => None,
+        } {
+            self.radius = diameter.max(0.0) / 2.0;
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+}
+
+impl Default for Circle {
+    fn default() -> Self {
+        Self::new(Vec2::ZERO, 0.5)
+    }
+}
+
+impl Styled for Circle {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Circle::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Circle {
+    fn hit_test(&self, point: Vec2) -> bool {
+        if !self.appearance.visible {
+            return false;
+        }
+        // Same geometry as circle.wgsl: `radius` around the center before the transform, which
+        // scales and turns it around the center
+        let center = self.center + self.transform.position;
+        self.transform.with_position(center).inverse_transform_point(point).length() <= self.radius
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
 }
 
 // Instance data for instanced rendering
@@ -57,6 +168,9 @@ pub struct CircleInstance {
     pub transform_scale: [f32; 2],
     pub transform_rotation: f32,
     pub _padding: f32, // For alignment
+    // Gradient over the circle's box around its center, before rotation
+    pub paint: PaintData,
+    pub shadow: ShadowData,
 }
 
 impl From<&Circle> for CircleInstance {
@@ -64,68 +178,279 @@ impl From<&Circle> for CircleInstance {
         Self {
             center: [circle.center.x, circle.center.y],
             radius: circle.radius,
-            color: [circle.color.r, circle.color.g, circle.color.b, circle.color.a],
+            color: {
+                let base = circle.paint.as_ref().map_or(circle.color, Paint::base_color);
+                let color = circle.appearance.apply_opacity(base);
+                [color.r, color.g, color.b, color.a]
+            },
             transform_position: [circle.transform.position.x, circle.transform.position.y],
             transform_scale: [circle.transform.scale.x, circle.transform.scale.y],
             transform_rotation: circle.transform.rotation,
             _padding: 0.0,
+            paint: circle.paint.as_ref().map_or(PaintData::SOLID, |paint| {
+                paint.data(Vec2::splat(-circle.radius), Vec2::splat(circle.radius))
+            }),
+            shadow: circle.appearance.shadow_data(),
         }
     }
 }
 
+pub const CIRCLE_SHADER: ShaderSource = shader_source!("src/shaders/circle.wgsl");
+
 pub struct CircleBatch {
+    // Circles as added, in insertion order, before any class styles are applied
+    circles: Vec<Circle>,
+    // The clip each circle was added under
+    clips: Vec<ClipId>,
+    // The clip circles added from now on are drawn in
+    clip: ClipId,
+    // What gets drawn: visible circles sorted by z-index, insertion order within the same
+    // z-index. Also what the instance buffer currently holds once uploaded.
     instances: Vec<CircleInstance>,
     instance_buffer: Option<wgpu::Buffer>,
+    // Instances the buffer has room for
+    buffer_capacity: usize,
     render_pipeline: Option<RenderPipeline>,
-    needs_update: bool,
+    // Draws the blurred frame under backdrop-filtered circles, built on first use
+    backdrop_pipeline: Option<RenderPipeline>,
+    shader: BatchShader,
+    gradients: GradientRamps,
+    style_cache: StyleCache<Circle>,
+    // Which circle each instance was built from
+    sources: InstanceSources,
+    // Runs of instances by the post-process effects they need
+    layers: Vec<EffectLayer>,
+    // Runs of instances by the clip they are drawn in
+    clip_runs: Vec<ClipRun>,
+    // Circles were added or removed since instances were last built
+    needs_rebuild: bool,
+    // Instances that changed since the last upload
+    dirty: Option<Range<usize>>,
 }
 
 impl CircleBatch {
     pub fn new() -> Self {
         Self {
+            circles: Vec::new(),
+            clips: Vec::new(),
+            clip: ClipId::NONE,
             instances: Vec::new(),
             instance_buffer: None,
+            buffer_capacity: 0,
             render_pipeline: None,
-            needs_update: true,
+            backdrop_pipeline: None,
+            shader: BatchShader::new("circle", CIRCLE_SHADER),
+            gradients: GradientRamps::new(),
+            style_cache: StyleCache::new(),
+            sources: InstanceSources::new(),
+            layers: Vec::new(),
+            clip_runs: Vec::new(),
+            needs_rebuild: true,
+            dirty: None,
         }
     }
 
+    // Circles without classes that are hidden are dropped here; styled ones are kept since
+    // their classes may show them again
     pub fn add_circle(&mut self, circle: Circle) {
-        self.instances.push(CircleInstance::from(&circle));
-        self.needs_update = true;
+        if !circle.appearance.visible && circle.css_class.is_none() {
+            return;
+        }
+        self.circles.push(circle);
+        self.clips.push(self.clip);
+        self.needs_rebuild = true;
     }
 
     pub fn add_circles(&mut self, circles: impl IntoIterator<Item = Circle>) {
-        self.instances.extend(circles.into_iter().map(|c| CircleInstance::from(&c)));
-        self.needs_update = true;
+        for circle in circles {
+            self.add_circle(circle);
+        }
+    }
+
+    // Draws circles added from now on in `clip`, see PrimitiveRenderer::push_clip_rect
+    pub fn set_clip(&mut self, clip: ClipId) {
+        self.clip = clip;
+    }
+
+    pub fn clip(&self) -> ClipId {
+        self.clip
     }
 
     pub fn clear(&mut self) {
-        self.instances.clear();
-        self.needs_update = true;
+        self.circles.clear();
+        self.clips.clear();
+        self.style_cache.clear();
+        self.needs_rebuild = true;
+    }
+
+    // Time style transitions and animations are sampled at on the next `resolve_styles`
+    pub fn set_style_time(&mut self, time: Duration) {
+        self.style_cache.set_time(time);
+    }
+
+    // What CSS lengths in styles are converted against, restyling the batch when it changes
+    pub fn set_style_viewport(&mut self, viewport: StyleViewport) {
+        self.style_cache.set_viewport(viewport);
+    }
+
+    // Whether a style transition or animation is running, so the batch restyles every frame
+    pub fn is_animating(&self) -> bool {
+        self.style_cache.is_animating()
     }
 
     pub fn len(&self) -> usize {
-        self.instances.len()
+        self.circles.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.instances.is_empty()
+        self.circles.is_empty()
+    }
+
+    // Circles as added, to move them or change their state in place. They are
+    // restyled on the next `resolve_styles`.
+    pub fn circles_mut(&mut self) -> &mut [Circle] {
+        self.style_cache.invalidate();
+        self.needs_rebuild = true;
+        &mut self.circles
+    }
+
+    // The circles as last drawn, in draw order, for the renderer's pointer handling, which
+    // only flags a state change so just the circles whose state changed are restyled
+    pub(crate) fn interaction_mut(&mut self) -> (Vec<Drawn<'_, Circle>>, &mut bool) {
+        self.style_cache.drawn_mut(&mut self.circles)
+    }
+
+    // Rebuilds the instances, applying the styles of each circle's classes and state from
+    // `resolver`. Only circles that were added, changed state or are animating are restyled,
+    // all of them once the resolver's generation changes. While the draw order stays the
+    // same only their instances are marked for upload, otherwise those that come out different.
+    pub fn resolve_styles(&mut self, resolver: Option<&dyn StyleResolver>) {
+        if !self.style_cache.sync(resolver) && !self.needs_rebuild {
+            return;
+        }
+        let rebuild = std::mem::take(&mut self.needs_rebuild);
+        let restyled = self.style_cache.restyle(resolver, &self.circles);
+        if restyled.is_empty() && !rebuild {
+            return;
+        }
+
+        self.gradients.clear();
+        let mut drawn: Vec<(i32, usize, CircleInstance, Appearance, ClipId)> = Vec::with_capacity(self.circles.len());
+        for (index, (circle, clip)) in self.circles.iter().zip(&self.clips).enumerate() {
+            let circle = self.style_cache.styled(index, circle);
+            if circle.appearance.visible {
+                let mut instance = CircleInstance::from(circle);
+                if let Some(stops) = circle.paint.as_ref().and_then(Paint::stops) {
+                    instance.paint = instance.paint.with_ramp_row(self.gradients.row(stops));
+                }
+                drawn.push((circle.appearance.z_index, index, instance, circle.appearance, *clip));
+            }
+        }
+        // Stable, so insertion order is kept within a z-index
+        drawn.sort_by_key(|(z_index, _, _, _, _)| *z_index);
+        self.layers.clear();
+        self.clip_runs.clear();
+        for (index, (_, _, _, appearance, clip)) in drawn.iter().enumerate() {
+            EffectLayer::push(&mut self.layers, index, appearance, *clip);
+            ClipRun::push(&mut self.clip_runs, index..index + 1, *clip);
+        }
+        self.style_cache.set_drawn(drawn.iter().map(|(_, circle, ..)| *circle));
+        let runs = drawn.iter().enumerate().map(|(index, (_, circle, ..))| (*circle, index..index + 1)).collect();
+        let instances: Vec<CircleInstance> = drawn.into_iter().map(|(_, _, instance, _, _)| instance).collect();
+
+        // Gradient rows are handed out in draw order, so other instances' rows may have moved
+        if rebuild || self.gradients.rows_changed() {
+            self.sources.forget();
+        }
+        let changed = self.sources.changed(runs, &restyled, &self.instances, &instances);
+        if let Some(changed) = changed {
+            self.dirty = Some(match self.dirty.take() {
+                Some(dirty) => dirty.start.min(changed.start)..dirty.end.max(changed.end),
+                None => changed,
+            });
+        }
+        self.instances = instances;
     }
 
     pub fn create_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat) -> RenderPipeline {
+        self.shader.set_targets(PipelineTargets { format: surface_format, sample_count: 1 });
+        self.rebuild_pipelines(device);
+        self.render_pipeline.clone().expect("Failed to build circle pipeline")
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        self.shader.preprocessor()
+    }
+
+    // The gradients of the circles as of the last `resolve_styles`
+    pub fn gradients(&self) -> &GradientRamps {
+        &self.gradients
+    }
+
+    // The instances split by the blur filters they need, as of the last `resolve_styles`
+    pub fn layers(&self) -> &[EffectLayer] {
+        &self.layers
+    }
+
+    // The instances split by the clips they are drawn in, as of the last `resolve_styles`
+    pub fn clip_runs(&self) -> &[ClipRun] {
+        &self.clip_runs
+    }
+
+    pub fn instances(&self) -> &[CircleInstance] {
+        &self.instances
+    }
+
+    // Rebuilds the pipelines for color targets of `targets`, e.g. multisampled
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.shader.set_targets(targets) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines with the define toggled, see BatchShader
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        if self.shader.set_define(name, enabled) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines if circle.wgsl or one of its includes changed on disk
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        self.shader.reload(shaders) && self.rebuild_pipelines(device)
+    }
+
+    fn rebuild_pipelines(&mut self, device: &Device) -> bool {
+        let layout = self.gradients.bind_group_layout(device);
+        let Some(render_pipeline) = self.shader.build(device, &[], |source, targets, blend| {
+            Self::build_pipeline(device, targets, blend, source, &[layout])
+        }) else {
+            return false;
+        };
+        self.render_pipeline = Some(render_pipeline);
+        self.backdrop_pipeline = None;
+        true
+    }
+
+    fn build_pipeline(
+        device: &Device,
+        targets: PipelineTargets,
+        blend: wgpu::BlendState,
+        source: &str,
+        layouts: &[&BindGroupLayout],
+    ) -> RenderPipeline {
         let shader = device.create_shader_module(ShaderModuleDescriptor {
-            label: Some("Circle Shader"),
-            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("../shaders/circle.wgsl"))),
+            label: Some(CIRCLE_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
         });
 
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Circle Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: layouts,
             push_constant_ranges: &[],
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Circle Pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
@@ -165,6 +490,26 @@ impl CircleBatch {
                             shader_location: 5,
                             format: wgpu::VertexFormat::Float32, // transform_rotation
                         },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
+                            shader_location: 6,
+                            format: wgpu::VertexFormat::Float32x4, // paint.params
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                            shader_location: 7,
+                            format: wgpu::VertexFormat::Float32x4, // paint.geometry
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 21]>() as wgpu::BufferAddress,
+                            shader_location: 8,
+                            format: wgpu::VertexFormat::Float32x4, // shadow.params
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 25]>() as wgpu::BufferAddress,
+                            shader_location: 9,
+                            format: wgpu::VertexFormat::Float32x4, // shadow.color
+                        },
                     ],
                 }],
                 compilation_options: Default::default(),
@@ -172,7 +517,11 @@ impl CircleBatch {
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: Some("fs_main"),
-                targets: &[Some(surface_format.into())],
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: targets.format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
                 compilation_options: Default::default(),
             }),
             primitive: wgpu::PrimitiveState {
@@ -184,45 +533,102 @@ impl CircleBatch {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
+            depth_stencil: Some(clip_depth_stencil()),
+            multisample: targets.multisample(),
             multiview: None,
             cache: None,
-        });
-
-        self.render_pipeline = Some(render_pipeline);
-        self.render_pipeline.clone().unwrap()
+        })
     }
 
+    // Uploads instances that changed since the last upload, growing the buffer when needed.
+    // Circles are drawn unstyled unless `resolve_styles` ran with a resolver first.
     pub fn update_buffer(&mut self, device: &Device, queue: &wgpu::Queue) {
-        if self.needs_update && !self.instances.is_empty() {
-            let buffer_size = (self.instances.len() * std::mem::size_of::<CircleInstance>()) as wgpu::BufferAddress;
-            
+        if self.needs_rebuild {
+            self.resolve_styles(None);
+        }
+        self.gradients.upload(device, queue);
+        let Some(mut dirty) = self.dirty.take() else {
+            return;
+        };
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instance_buffer.is_none() || self.instances.len() > self.buffer_capacity {
+            // Room to grow without reallocating every time a circle is added
+            self.buffer_capacity = self.instances.len().next_power_of_two();
+            let buffer_size = (self.buffer_capacity * std::mem::size_of::<CircleInstance>()) as wgpu::BufferAddress;
             self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("Circle Instance Buffer"),
                 size: buffer_size,
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false,
             }));
+            dirty = 0..self.instances.len();
+        }
 
-            if let Some(ref buffer) = self.instance_buffer {
-                queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.instances));
-            }
-            
-            self.needs_update = false;
+        if let Some(ref buffer) = self.instance_buffer {
+            let dirty = dirty.start.min(self.instances.len())..dirty.end.min(self.instances.len());
+            let offset = (dirty.start * std::mem::size_of::<CircleInstance>()) as wgpu::BufferAddress;
+            queue.write_buffer(buffer, offset, bytemuck::cast_slice(&self.instances[dirty]));
         }
     }
 
     pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
-        if let (Some(ref pipeline), Some(ref buffer), count) = (&self.render_pipeline, &self.instance_buffer, self.instances.len()) {
-            if count > 0 {
+        self.render_range(render_pass, 0..self.instances.len());
+    }
+
+    // Draws the instances run by run, each in the clip its circle was added under
+    pub fn render_clipped(&self, render_pass: &mut wgpu::RenderPass, scope: &mut dyn ClipScope) {
+        render_clip_runs(render_pass, &self.clip_runs, scope, |render_pass, instances| {
+            self.render_range(render_pass, instances)
+        });
+    }
+
+    // Draws some of the instances, e.g. one of `layers`
+    pub fn render_range(&self, render_pass: &mut wgpu::RenderPass, instances: Range<usize>) {
+        let instances = instances.start.min(self.instances.len())..instances.end.min(self.instances.len());
+        if let (Some(ref pipeline), Some(ref buffer), Some(gradients)) =
+            (&self.render_pipeline, &self.instance_buffer, self.gradients.bind_group())
+        {
+            if !instances.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, gradients, &[]);
+                let size = (self.instances.len() * std::mem::size_of::<CircleInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                // 6 vertices for a quad per instance
+                render_pass.draw(0..6, instances.start as u32..instances.end as u32);
+            }
+        }
+    }
+
+    // Draws `backdrop`, the blurred frame behind the instances, cut to the circles. It is
+    // bound at group 1 with `layout`, a texture and sampler (see GaussianBlur::texture_layout).
+    // The instances themselves are drawn over it with `render_range` afterwards.
+    pub fn render_backdrop(
+        &mut self,
+        device: &Device,
+        render_pass: &mut wgpu::RenderPass,
+        instances: Range<usize>,
+        layout: &BindGroupLayout,
+        backdrop: &wgpu::BindGroup,
+    ) {
+        if self.backdrop_pipeline.is_none() {
+            let layouts = [self.gradients.bind_group_layout(device), layout];
+            self.backdrop_pipeline = self.shader.build(device, &["BACKDROP"], |source, targets, blend| {
+                Self::build_pipeline(device, targets, blend, source, &layouts)
+            });
+        }
+        let instances = instances.start.min(self.instances.len())..instances.end.min(self.instances.len());
+        if let (Some(pipeline), Some(buffer), Some(gradients)) =
+            (&self.backdrop_pipeline, &self.instance_buffer, self.gradients.bind_group())
+        {
+            if !instances.is_empty() {
                 render_pass.set_pipeline(pipeline);
-                render_pass.set_vertex_buffer(0, buffer.slice(..));
-                render_pass.draw(0..6, 0..count as u32); // 6 vertices for a quad, count instances
+                render_pass.set_bind_group(0, gradients, &[]);
+                render_pass.set_bind_group(1, backdrop, &[]);
+                let size = (self.instances.len() * std::mem::size_of::<CircleInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                render_pass.draw(0..6, instances.start as u32..instances.end as u32);
             }
         }
     }
@@ -236,15 +642,14 @@ impl Default for CircleBatch {
 
 impl InstanceBatch for CircleBatch {
     fn len(&self) -> usize {
-        self.instances.len()
+        CircleBatch::len(self)
     }
 
     fn is_empty(&self) -> bool {
-        self.instances.is_empty()
+        CircleBatch::is_empty(self)
     }
 
     fn clear(&mut self) {
-        self.instances.clear();
-        self.needs_update = true;
+        CircleBatch::clear(self);
     }
 }
@@ -0,0 +1,111 @@
+use glam::Vec2;
+use plinth_core::input::{PointerButton, PointerEvent, PointerEventKind};
+
+use crate::types::InteractionState;
+
+// A primitive that can be hit-tested and carries :hover/:active/:focus/:disabled state
+pub trait Interactive {
+    // `point` is in the same (clip space) coordinates the primitive is drawn in
+    fn hit_test(&self, point: Vec2) -> bool;
+
+    // Same as `hit_test` knowing the surface size in physical pixels, for primitives sized
+    // in pixels. PointerTracker uses this one.
+    fn hit_test_in_surface(&self, point: Vec2, _surface_size: (u32, u32)) -> bool {
+        self.hit_test(point)
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState;
+
+    // Higher z-indices are on top when primitives overlap
+    fn z_index(&self) -> i32 {
+        0
+    }
+}
+
+// Turns pointer events into interaction state. Only the topmost primitive under the pointer
+// is hovered (ties go to the later one, which is drawn last); a primary press makes it
+// active and focused until release; pressing empty space clears focus. Disabled primitives
+// block the pointer but never become hovered, active or focused.
+#[derive(Debug, Default)]
+pub struct PointerTracker {
+    position: Option<Vec2>,
+    // From the last event, (0, 0) before the first one
+    surface_size: (u32, u32),
+}
+
+impl PointerTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Last pointer position in clip space, None once it left the surface
+    pub fn position(&self) -> Option<Vec2> {
+        self.position
+    }
+
+    // Applies one event to `primitives`; `surface_size` is in physical pixels. Returns
+    // whether any state changed, i.e. styles need resolving again.
+    pub fn handle_event<'a>(
+        &mut self,
+        event: &PointerEvent,
+        surface_size: (u32, u32),
+        primitives: impl IntoIterator<Item = &'a mut dyn Interactive>,
+    ) -> bool {
+        let (x, y) = event.clip_position(surface_size);
+        self.surface_size = surface_size;
+        self.position = match event.kind {
+            PointerEventKind::Left => None,
+            _ => Some(Vec2::new(x, y)),
+        };
+
+        let mut primitives: Vec<&'a mut dyn Interactive> = primitives.into_iter().collect();
+        let target = self.position.and_then(|position| topmost_hit(&primitives, position, surface_size));
+        let mut changed = false;
+        for (index, primitive) in primitives.iter_mut().enumerate() {
+            let is_target = target == Some(index);
+            let state = primitive.state_mut();
+            let before = *state;
+            let enabled = !state.contains(InteractionState::DISABLED);
+            state.set(InteractionState::HOVER, is_target && enabled);
+            match event.kind {
+                PointerEventKind::Pressed(PointerButton::Primary) => {
+                    state.set(InteractionState::ACTIVE, is_target && enabled);
+                    state.set(InteractionState::FOCUS, is_target && enabled);
+                }
+                PointerEventKind::Released(PointerButton::Primary) | PointerEventKind::Left => {
+                    state.remove(InteractionState::ACTIVE);
+                }
+                _ => {}
+            }
+            if !enabled {
+                state.remove(InteractionState::ACTIVE | InteractionState::FOCUS);
+            }
+            changed |= *state != before;
+        }
+        changed
+    }
+
+    // Re-evaluates hover against the last pointer position, for primitives that moved
+    // under a stationary pointer
+    pub fn refresh<'a>(&self, primitives: impl IntoIterator<Item = &'a mut dyn Interactive>) -> bool {
+        let mut primitives: Vec<&'a mut dyn Interactive> = primitives.into_iter().collect();
+        let target = self.position.and_then(|position| topmost_hit(&primitives, position, self.surface_size));
+        let mut changed = false;
+        for (index, primitive) in primitives.iter_mut().enumerate() {
+            let state = primitive.state_mut();
+            let hovered = target == Some(index) && !state.contains(InteractionState::DISABLED);
+            changed |= state.contains(InteractionState::HOVER) != hovered;
+            state.set(InteractionState::HOVER, hovered);
+        }
+        changed
+    }
+}
+
+fn topmost_hit(primitives: &[&mut dyn Interactive], position: Vec2, surface_size: (u32, u32)) -> Option<usize> {
+    primitives
+        .iter()
+        .enumerate()
+        .filter(|(_, primitive)| primitive.hit_test_in_surface(position, surface_size))
+        .max_by_key(|(index, primitive)| (primitive.z_index(), *index))
+        .map(|(index, _)| index)
+}
@@ -0,0 +1,491 @@
+use glam::Vec2;
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPipeline, ShaderModuleDescriptor};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::primitives::{Line, LineInstance, Polyline, StyleResolver};
+use crate::batch::{
+    clip_depth_stencil, render_clip_runs, BatchShader, ClipId, ClipRun, ClipScope, Drawn, InstanceBatch,
+    InstanceSources, StyleCache,
+};
+use crate::shaders::ShaderPreprocessor;
+use crate::types::StyleViewport;
+
+pub const LINE_SHADER: ShaderSource = shader_source!("src/shaders/line.wgsl");
+
+pub struct LineBatch {
+    // Lines and polylines as added, in insertion order, before any class styles are applied
+    lines: Vec<Polyline>,
+    // The clip each line was added under
+    clips: Vec<ClipId>,
+    // The clip lines added from now on are drawn in
+    clip: ClipId,
+    // One instance per segment of the visible lines, sorted by z-index with insertion order
+    // kept within the same z-index. Also what the instance buffer currently holds once uploaded.
+    instances: Vec<LineInstance>,
+    // Runs of instances by the clip they are drawn in
+    clip_runs: Vec<ClipRun>,
+    instance_buffer: Option<wgpu::Buffer>,
+    // Instances the buffer has room for
+    buffer_capacity: usize,
+    render_pipeline: Option<RenderPipeline>,
+    bind_group_layout: Option<BindGroupLayout>,
+    viewport_buffer: Option<wgpu::Buffer>,
+    bind_group: Option<BindGroup>,
+    // In physical pixels, for pixel-wide lines
+    surface_size: Vec2,
+    shader: BatchShader,
+    style_cache: StyleCache<Polyline>,
+    // Which line each run of instances was built from
+    sources: InstanceSources,
+    // Lines were added or removed, or the surface resized, since instances were last built
+    needs_rebuild: bool,
+    // Instances that changed since the last upload
+    dirty: Option<Range<usize>>,
+    viewport_dirty: bool,
+}
+
+impl LineBatch {
+    pub fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            clips: Vec::new(),
+            clip: ClipId::NONE,
+            instances: Vec::new(),
+            clip_runs: Vec::new(),
+            instance_buffer: None,
+            buffer_capacity: 0,
+            render_pipeline: None,
+            bind_group_layout: None,
+            viewport_buffer: None,
+            bind_group: None,
+            surface_size: Vec2::ONE,
+            shader: BatchShader::new("line", LINE_SHADER),
+            style_cache: StyleCache::new(),
+            sources: InstanceSources::new(),
+            needs_rebuild: true,
+            dirty: None,
+            viewport_dirty: true,
+        }
+    }
+
+    // Lines without classes that are hidden are dropped here; styled ones are kept since
+    // their classes may show them again
+    pub fn add_polyline(&mut self, polyline: Polyline) {
+        if !polyline.appearance.visible && polyline.css_class.is_none() {
+            return;
+        }
+        self.lines.push(polyline);
+        self.clips.push(self.clip);
+        self.needs_rebuild = true;
+    }
+
+    pub fn add_polylines(&mut self, polylines: impl IntoIterator<Item = Polyline>) {
+        for polyline in polylines {
+            self.add_polyline(polyline);
+        }
+    }
+
+    pub fn add_line(&mut self, line: Line) {
+        self.add_polyline(line.into());
+    }
+
+    pub fn add_lines(&mut self, lines: impl IntoIterator<Item = Line>) {
+        self.add_polylines(lines.into_iter().map(Polyline::from));
+    }
+
+    // Draws lines added from now on in `clip`, see PrimitiveRenderer::push_clip_rect
+    pub fn set_clip(&mut self, clip: ClipId) {
+        self.clip = clip;
+    }
+
+    pub fn clip(&self) -> ClipId {
+        self.clip
+    }
+
+    // The instances split by the clips they are drawn in, as of the last `resolve_styles`
+    pub fn clip_runs(&self) -> &[ClipRun] {
+        &self.clip_runs
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.clips.clear();
+        self.style_cache.clear();
+        self.needs_rebuild = true;
+    }
+
+    // Time style transitions and animations are sampled at on the next `resolve_styles`
+    pub fn set_style_time(&mut self, time: Duration) {
+        self.style_cache.set_time(time);
+    }
+
+    // What CSS lengths in styles are converted against, restyling the batch when it changes
+    pub fn set_style_viewport(&mut self, viewport: StyleViewport) {
+        self.style_cache.set_viewport(viewport);
+    }
+
+    // Whether a style transition or animation is running, so the batch restyles every frame
+    pub fn is_animating(&self) -> bool {
+        self.style_cache.is_animating()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    // Lines as added, to move them or change their state in place. They are
+    // restyled on the next `resolve_styles`.
+    pub fn lines_mut(&mut self) -> &mut [Polyline] {
+        self.style_cache.invalidate();
+        self.needs_rebuild = true;
+        &mut self.lines
+    }
+
+    // The lines as last drawn, in draw order, for the renderer's pointer handling, which
+    // only flags a state change so just the lines whose state changed are restyled
+    pub(crate) fn interaction_mut(&mut self) -> (Vec<Drawn<'_, Polyline>>, &mut bool) {
+        self.style_cache.drawn_mut(&mut self.lines)
+    }
+
+    // Surface size in physical pixels. Pixel widths and dashes are measured against it.
+    pub fn set_surface_size(&mut self, width: u32, height: u32) {
+        let size = Vec2::new(width.max(1) as f32, height.max(1) as f32);
+        if size != self.surface_size {
+            self.surface_size = size;
+            self.viewport_dirty = true;
+            self.needs_rebuild = true;
+        }
+    }
+
+    // Rebuilds the instances, applying the styles of each line's classes and state from
+    // `resolver`. Only lines that were added, changed state or are animating are restyled,
+    // all of them once the resolver's generation changes. While the draw order stays the
+    // same only their instances are marked for upload, otherwise those that come out different.
+    pub fn resolve_styles(&mut self, resolver: Option<&dyn StyleResolver>) {
+        if !self.style_cache.sync(resolver) && !self.needs_rebuild {
+            return;
+        }
+        let rebuild = std::mem::take(&mut self.needs_rebuild);
+        let restyled = self.style_cache.restyle(resolver, &self.lines);
+        if restyled.is_empty() && !rebuild {
+            return;
+        }
+
+        let mut drawn: Vec<(i32, usize, Vec<LineInstance>, ClipId)> = Vec::with_capacity(self.lines.len());
+        for (index, (line, clip)) in self.lines.iter().zip(&self.clips).enumerate() {
+            let line = self.style_cache.styled(index, line);
+            if line.appearance.visible {
+                drawn.push((line.appearance.z_index, index, line.instances(self.surface_size), *clip));
+            }
+        }
+        // Stable, so insertion order is kept within a z-index
+        drawn.sort_by_key(|(z_index, _, _, _)| *z_index);
+        let mut instances: Vec<LineInstance> = Vec::new();
+        let mut runs = Vec::with_capacity(drawn.len());
+        self.clip_runs.clear();
+        for (_, index, line, clip) in drawn {
+            let start = instances.len();
+            instances.extend(line);
+            runs.push((index, start..instances.len()));
+            ClipRun::push(&mut self.clip_runs, start..instances.len(), clip);
+        }
+
+        // Pixel-wide lines depend on the surface size
+        if rebuild {
+            self.sources.forget();
+        }
+        self.style_cache.set_drawn(runs.iter().map(|(index, _)| *index));
+        let changed = self.sources.changed(runs, &restyled, &self.instances, &instances);
+        if let Some(changed) = changed {
+            self.dirty = Some(match self.dirty.take() {
+                Some(dirty) => dirty.start.min(changed.start)..dirty.end.max(changed.end),
+                None => changed,
+            });
+        }
+        self.instances = instances;
+    }
+
+    pub fn create_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat) -> RenderPipeline {
+        if self.bind_group_layout.is_none() {
+            self.create_viewport_binding(device);
+        }
+        self.shader.set_targets(PipelineTargets { format: surface_format, sample_count: 1 });
+        self.rebuild_pipelines(device);
+        self.render_pipeline.clone().expect("Failed to build line pipeline")
+    }
+
+    fn create_viewport_binding(&mut self, device: &Device) {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Line Viewport Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Line Viewport Buffer"),
+            size: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Line Viewport Bind Group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        }));
+        self.bind_group_layout = Some(layout);
+        self.viewport_buffer = Some(buffer);
+        self.viewport_dirty = true;
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        self.shader.preprocessor()
+    }
+
+    // Rebuilds the pipelines for color targets of `targets`, e.g. multisampled
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.shader.set_targets(targets) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines with the define toggled, see BatchShader
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        if self.shader.set_define(name, enabled) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines if line.wgsl or one of its includes changed on disk
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        self.shader.reload(shaders) && self.rebuild_pipelines(device)
+    }
+
+    fn rebuild_pipelines(&mut self, device: &Device) -> bool {
+        let Some(layout) = &self.bind_group_layout else {
+            return false;
+        };
+        let Some(render_pipeline) = self.shader.build(device, &[], |source, targets, blend| {
+            Self::build_pipeline(device, targets, blend, source, layout)
+        }) else {
+            return false;
+        };
+        self.render_pipeline = Some(render_pipeline);
+        true
+    }
+
+    fn build_pipeline(
+        device: &Device,
+        targets: PipelineTargets,
+        blend: wgpu::BlendState,
+        source: &str,
+        layout: &BindGroupLayout,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(LINE_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Line Pipeline Layout"),
+            bind_group_layouts: &[layout],
+            push_constant_ranges: &[],
+        });
+
+        let offset = |floats: usize| (floats * std::mem::size_of::<f32>()) as wgpu::BufferAddress;
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Line Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<LineInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2, // start
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(2),
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x2, // end
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(4),
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x2, // previous
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(6),
+                            shader_location: 3,
+                            format: wgpu::VertexFormat::Float32x2, // next
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(8),
+                            shader_location: 4,
+                            format: wgpu::VertexFormat::Float32x4, // start_color
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(12),
+                            shader_location: 5,
+                            format: wgpu::VertexFormat::Float32x4, // end_color
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(16),
+                            shader_location: 6,
+                            format: wgpu::VertexFormat::Float32x4, // params
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(20),
+                            shader_location: 7,
+                            format: wgpu::VertexFormat::Float32x4, // dashes 0-3
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(24),
+                            shader_location: 8,
+                            format: wgpu::VertexFormat::Float32x4, // dashes 4-7
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(28),
+                            shader_location: 9,
+                            format: wgpu::VertexFormat::Uint32, // flags
+                        },
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: targets.format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // The quad is built along the segment, so its winding follows the direction
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(clip_depth_stencil()),
+            multisample: targets.multisample(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // Uploads the viewport and the instances that changed since the last upload, growing
+    // the buffer when needed. Lines are drawn unstyled unless `resolve_styles` ran with a
+    // resolver first.
+    pub fn update_buffer(&mut self, device: &Device, queue: &wgpu::Queue) {
+        if self.viewport_dirty {
+            if let Some(ref buffer) = self.viewport_buffer {
+                let viewport = [self.surface_size.x, self.surface_size.y, 0.0, 0.0];
+                queue.write_buffer(buffer, 0, bytemuck::cast_slice(&viewport));
+                self.viewport_dirty = false;
+            }
+        }
+        if self.needs_rebuild {
+            self.resolve_styles(None);
+        }
+        let Some(mut dirty) = self.dirty.take() else {
+            return;
+        };
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instance_buffer.is_none() || self.instances.len() > self.buffer_capacity {
+            // Room to grow without reallocating every time a line is added
+            self.buffer_capacity = self.instances.len().next_power_of_two();
+            let buffer_size = (self.buffer_capacity * std::mem::size_of::<LineInstance>()) as wgpu::BufferAddress;
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Line Instance Buffer"),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            dirty = 0..self.instances.len();
+        }
+
+        if let Some(ref buffer) = self.instance_buffer {
+            let dirty = dirty.start.min(self.instances.len())..dirty.end.min(self.instances.len());
+            let offset = (dirty.start * std::mem::size_of::<LineInstance>()) as wgpu::BufferAddress;
+            queue.write_buffer(buffer, offset, bytemuck::cast_slice(&self.instances[dirty]));
+        }
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render_range(render_pass, 0..self.instances.len());
+    }
+
+    // Draws the instances run by run, each in the clip its line was added under
+    pub fn render_clipped(&self, render_pass: &mut wgpu::RenderPass, scope: &mut dyn ClipScope) {
+        render_clip_runs(render_pass, &self.clip_runs, scope, |render_pass, instances| {
+            self.render_range(render_pass, instances)
+        });
+    }
+
+    // Draws some of the instances, e.g. one of `clip_runs`
+    pub fn render_range(&self, render_pass: &mut wgpu::RenderPass, instances: Range<usize>) {
+        if let (Some(pipeline), Some(bind_group), Some(buffer)) = (&self.render_pipeline, &self.bind_group, &self.instance_buffer) {
+            let count = self.instances.len();
+            let instances = instances.start.min(count)..instances.end.min(count);
+            if !instances.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                let size = (count * std::mem::size_of::<LineInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                // A quad per segment
+                render_pass.draw(0..6, instances.start as u32..instances.end as u32);
+            }
+        }
+    }
+}
+
+impl Default for LineBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstanceBatch for LineBatch {
+    fn len(&self) -> usize {
+        LineBatch::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        LineBatch::is_empty(self)
+    }
+
+    fn clear(&mut self) {
+        LineBatch::clear(self);
+    }
+}
@@ -0,0 +1,569 @@
+pub mod line_batch;
+
+pub use line_batch::*;
+
+use glam::Vec2;
+
+use crate::primitives::{Interactive, LineCap, LineJoin, Styled};
+use crate::types::{Appearance, Color, InteractionState, Style, StyleViewport, Transform};
+
+// Surface size pixel widths are measured against when hit testing without one
+const DEFAULT_HIT_SURFACE: Vec2 = Vec2::splat(1000.0);
+
+// Dash and gap lengths the line shader supports per line
+pub const MAX_DASHES: usize = 8;
+
+// How thick a line is drawn. Pixel widths stay the same at any transform and surface size;
+// world widths are in the clip space units primitives are positioned in and scale with
+// the transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineWidth {
+    Pixels(f32),
+    World(f32),
+}
+
+impl LineWidth {
+    pub fn value(self) -> f32 {
+        match self {
+            LineWidth::Pixels(width) | LineWidth::World(width) => width,
+        }
+    }
+
+    // The same kind of width with another value
+    pub fn with_value(self, value: f32) -> Self {
+        match self {
+            LineWidth::Pixels(_) => LineWidth::Pixels(value),
+            LineWidth::World(_) => LineWidth::World(value),
+        }
+    }
+
+    // The same kind of width, `px` CSS pixels wide
+    pub fn with_css_length(self, px: f32, viewport: &StyleViewport) -> Self {
+        let value = match self {
+            LineWidth::Pixels(_) => viewport.pixels(px),
+            LineWidth::World(_) => viewport.clip_length(px),
+        };
+        self.with_value(value.max(0.0))
+    }
+}
+
+impl Default for LineWidth {
+    fn default() -> Self {
+        LineWidth::Pixels(1.0)
+    }
+}
+
+// Connected segments through `points`, drawn with an SDF per segment so caps, joins and
+// dashes stay crisp at any width. `colors` gives each point its own color, blended along
+// the segments; when empty (or shorter than `points`) `color` is used. Dash lengths are in
+// the same unit as the width.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Polyline {
+    pub points: Vec<Vec2>,
+    pub colors: Vec<Color>,
+    pub color: Color,
+    pub width: LineWidth,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f32,
+    pub dashes: Vec<f32>,
+    pub dash_offset: f32,
+    // Connects the last point back to the first, with a join instead of caps
+    pub closed: bool,
+    pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
+    pub css_class: Option<String>,
+}
+
+impl Polyline {
+    pub fn new(points: impl IntoIterator<Item = Vec2>) -> Self {
+        Self {
+            points: points.into_iter().collect(),
+            colors: Vec::new(),
+            color: Color::default(),
+            width: LineWidth::default(),
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: 4.0,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+            closed: false,
+            transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
+            css_class: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    // One color per point
+    pub fn with_colors(mut self, colors: impl IntoIterator<Item = Color>) -> Self {
+        self.colors = colors.into_iter().collect();
+        self
+    }
+
+    pub fn with_width(mut self, width: LineWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    // Alternating dash and gap lengths like stroke-dasharray, starting `offset` into the pattern
+    pub fn with_dashes(mut self, dashes: impl Into<Vec<f32>>, offset: f32) -> Self {
+        self.dashes = dashes.into();
+        self.dash_offset = offset;
+        self
+    }
+
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
+    pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
+        self.css_class = Some(class.into());
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn apply_css_override(&mut self, color: Color) {
+        self.color = color;
+        self.colors.clear();
+    }
+
+    // Applies every property `style` sets: the color (replacing per-point colors) and
+    // border-width as the width, in the line's current unit
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        if let Some(color) = style.fill_color() {
+            self.apply_css_override(color);
+        }
+        if let Some(width) = style.border_width {
+            self.width = self.width.with_css_length(width, viewport);
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+
+    // Points after the transform, without repeats since a zero length segment has no direction
+    fn transformed_points(&self) -> Vec<(Vec2, Color)> {
+        let mut points: Vec<(Vec2, Color)> = Vec::with_capacity(self.points.len());
+        for (index, point) in self.points.iter().enumerate() {
+            let point = self.transform.transform_point(*point);
+            if points.last().is_some_and(|(last, _)| *last == point) {
+                continue;
+            }
+            let color = self.colors.get(index).copied().unwrap_or(self.color);
+            points.push((point, self.appearance.apply_opacity(color)));
+        }
+        if self.closed && points.len() > 2 && points.first().map(|(point, _)| *point) == points.last().map(|(point, _)| *point) {
+            points.pop();
+        }
+        points
+    }
+
+    // Width in the space the line is drawn in: pixels, or clip space scaled by the transform
+    fn drawn_width(&self) -> f32 {
+        match self.width {
+            LineWidth::Pixels(width) => width,
+            LineWidth::World(width) => width * (self.transform.scale.x * self.transform.scale.y).abs().sqrt(),
+        }
+    }
+
+    // One instance per segment. `surface_size` in physical pixels is needed to measure
+    // dashes along pixel-wide lines.
+    pub fn instances(&self, surface_size: Vec2) -> Vec<LineInstance> {
+        let points = self.transformed_points();
+        let count = points.len();
+        if count < 2 {
+            return Vec::new();
+        }
+        let closed = self.closed && count > 2;
+        let segments = if closed { count } else { count - 1 };
+
+        let pixels = matches!(self.width, LineWidth::Pixels(_));
+        let to_drawn_space = if pixels { surface_size / 2.0 } else { Vec2::ONE };
+        let (dashes, dash_count) = dash_pattern(&self.dashes);
+
+        let mut flags = cap_flags(self.cap) | join_flags(self.join);
+        if pixels {
+            flags |= LineInstance::PIXEL_WIDTH;
+        }
+        let mut distance = self.dash_offset;
+        let mut instances = Vec::with_capacity(segments);
+        for index in 0..segments {
+            let (start, start_color) = points[index];
+            let (end, end_color) = points[(index + 1) % count];
+            let has_previous = closed || index > 0;
+            let has_next = closed || index + 2 < count;
+            let previous = if has_previous { points[(index + count - 1) % count].0 } else { start };
+            let next = if has_next { points[(index + 2) % count].0 } else { end };
+
+            let mut segment_flags = flags;
+            if has_previous {
+                segment_flags |= LineInstance::HAS_PREVIOUS;
+            }
+            if has_next {
+                segment_flags |= LineInstance::HAS_NEXT;
+            }
+            instances.push(LineInstance {
+                start: start.into(),
+                end: end.into(),
+                previous: previous.into(),
+                next: next.into(),
+                start_color: [start_color.r, start_color.g, start_color.b, start_color.a],
+                end_color: [end_color.r, end_color.g, end_color.b, end_color.a],
+                params: [self.drawn_width(), self.miter_limit.max(1.0), distance, dash_count as f32],
+                dashes,
+                flags: segment_flags,
+            });
+            distance += ((end - start) * to_drawn_space).length();
+        }
+        instances
+    }
+
+    // Distance from `point` to the nearest segment, measured in pixels of `surface_size`
+    // for pixel-wide lines and in clip space otherwise
+    fn distance_in_drawn_space(&self, point: Vec2, surface_size: Vec2) -> f32 {
+        let scale = match self.width {
+            LineWidth::Pixels(_) => surface_size / 2.0,
+            LineWidth::World(_) => Vec2::ONE,
+        };
+        let points: Vec<Vec2> = self.transformed_points().into_iter().map(|(point, _)| point * scale).collect();
+        let point = point * scale;
+        let closing = if self.closed && points.len() > 2 { points.last().zip(points.first()) } else { None };
+        points
+            .windows(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .chain(closing)
+            .map(|(start, end)| segment_distance(point, *start, *end))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn hits(&self, point: Vec2, surface_size: Vec2) -> bool {
+        self.appearance.visible && self.distance_in_drawn_space(point, surface_size) <= self.drawn_width() / 2.0
+    }
+}
+
+impl Default for Polyline {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl Styled for Polyline {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Polyline::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Polyline {
+    // Within half the width of a segment, with pixel widths measured on a 1000 pixel surface
+    fn hit_test(&self, point: Vec2) -> bool {
+        self.hits(point, DEFAULT_HIT_SURFACE)
+    }
+
+    fn hit_test_in_surface(&self, point: Vec2, surface_size: (u32, u32)) -> bool {
+        match surface_size {
+            (0, _) | (_, 0) => self.hit_test(point),
+            (width, height) => self.hits(point, Vec2::new(width as f32, height as f32)),
+        }
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
+}
+
+// A single segment, drawn as a capsule-like SDF. `end_color` blends from `color` along it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Line {
+    pub start: Vec2,
+    pub end: Vec2,
+    pub color: Color,
+    pub end_color: Option<Color>,
+    pub width: LineWidth,
+    pub cap: LineCap,
+    pub dashes: Vec<f32>,
+    pub dash_offset: f32,
+    pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
+    pub css_class: Option<String>,
+}
+
+impl Line {
+    pub fn new(start: Vec2, end: Vec2) -> Self {
+        Self {
+            start,
+            end,
+            color: Color::default(),
+            end_color: None,
+            width: LineWidth::default(),
+            cap: LineCap::default(),
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+            transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
+            css_class: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_end_color(mut self, end_color: Color) -> Self {
+        self.end_color = Some(end_color);
+        self
+    }
+
+    pub fn with_width(mut self, width: LineWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    // Alternating dash and gap lengths like stroke-dasharray, starting `offset` into the pattern
+    pub fn with_dashes(mut self, dashes: impl Into<Vec<f32>>, offset: f32) -> Self {
+        self.dashes = dashes.into();
+        self.dash_offset = offset;
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
+    pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
+        self.css_class = Some(class.into());
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn apply_css_override(&mut self, color: Color) {
+        self.color = color;
+        self.end_color = None;
+    }
+
+    // Same properties as Polyline::apply_css_style
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        if let Some(color) = style.fill_color() {
+            self.apply_css_override(color);
+        }
+        if let Some(width) = style.border_width {
+            self.width = self.width.with_css_length(width, viewport);
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self::new(Vec2::ZERO, Vec2::X)
+    }
+}
+
+impl From<&Line> for Polyline {
+    fn from(line: &Line) -> Self {
+        Polyline {
+            points: vec![line.start, line.end],
+            colors: line.end_color.map(|end_color| vec![line.color, end_color]).unwrap_or_default(),
+            color: line.color,
+            width: line.width,
+            cap: line.cap,
+            join: LineJoin::default(),
+            miter_limit: 4.0,
+            dashes: line.dashes.clone(),
+            dash_offset: line.dash_offset,
+            closed: false,
+            transform: line.transform,
+            appearance: line.appearance,
+            state: line.state,
+            css_class: line.css_class.clone(),
+        }
+    }
+}
+
+impl From<Line> for Polyline {
+    fn from(line: Line) -> Self {
+        Polyline::from(&line)
+    }
+}
+
+impl Styled for Line {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Line::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Line {
+    fn hit_test(&self, point: Vec2) -> bool {
+        Polyline::from(self).hit_test(point)
+    }
+
+    fn hit_test_in_surface(&self, point: Vec2, surface_size: (u32, u32)) -> bool {
+        Polyline::from(self).hit_test_in_surface(point, surface_size)
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
+}
+
+// One segment of a polyline with its neighbours for joins, as the line shader takes it
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct LineInstance {
+    pub start: [f32; 2],
+    pub end: [f32; 2],
+    // The points before `start` and after `end`, equal to them at open ends
+    pub previous: [f32; 2],
+    pub next: [f32; 2],
+    pub start_color: [f32; 4],
+    pub end_color: [f32; 4],
+    // width, miter limit, distance along the line at `start` (dash offset included), dash count
+    pub params: [f32; 4],
+    pub dashes: [f32; MAX_DASHES],
+    pub flags: u32,
+}
+
+impl LineInstance {
+    pub const PIXEL_WIDTH: u32 = 1 << 0;
+    // Two bits each: butt/round/square and miter/round/bevel
+    pub const CAP_SHIFT: u32 = 1;
+    pub const JOIN_SHIFT: u32 = 3;
+    pub const HAS_PREVIOUS: u32 = 1 << 5;
+    pub const HAS_NEXT: u32 = 1 << 6;
+}
+
+fn cap_flags(cap: LineCap) -> u32 {
+    let cap = match cap {
+        LineCap::Butt => 0,
+        LineCap::Round => 1,
+        LineCap::Square => 2,
+    };
+    cap << LineInstance::CAP_SHIFT
+}
+
+fn join_flags(join: LineJoin) -> u32 {
+    let join = match join {
+        LineJoin::Miter => 0,
+        LineJoin::Round => 1,
+        LineJoin::Bevel => 2,
+    };
+    join << LineInstance::JOIN_SHIFT
+}
+
+// The dash pattern as the shader takes it: an even number of lengths, odd lists repeated
+// like stroke-dasharray, and no pattern at all when it has no length
+fn dash_pattern(dashes: &[f32]) -> ([f32; MAX_DASHES], usize) {
+    let mut pattern: Vec<f32> = dashes.iter().map(|dash| dash.max(0.0)).collect();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_within(..);
+    }
+    let period: f32 = pattern.iter().sum();
+    if period.is_nan() || period <= 0.0 {
+        return ([0.0; MAX_DASHES], 0);
+    }
+    if pattern.len() > MAX_DASHES {
+        log::warn!("Line dash patterns are limited to {} lengths, ignoring the rest", MAX_DASHES);
+        pattern.truncate(MAX_DASHES);
+    }
+    let mut packed = [0.0; MAX_DASHES];
+    packed[..pattern.len()].copy_from_slice(&pattern);
+    (packed, pattern.len())
+}
+
+fn segment_distance(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let direction = end - start;
+    let length_squared = direction.length_squared();
+    let t = if length_squared > 0.0 {
+        ((point - start).dot(direction) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(start + direction * t)
+}
@@ -1,7 +1,19 @@
 pub mod circle;
+pub mod interaction;
+pub mod line;
+pub mod path;
 pub mod rectangle;
+pub mod sprite;
+pub mod styled;
+pub mod text;
 pub mod triangle;
 
 pub use circle::*;
+pub use interaction::*;
+pub use line::*;
+pub use path::*;
 pub use rectangle::*;
+pub use sprite::*;
+pub use styled::*;
+pub use text::*;
 pub use triangle::*;
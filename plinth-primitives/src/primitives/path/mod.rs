@@ -0,0 +1,523 @@
+pub mod path_batch;
+pub mod path_data;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod tessellation;
+
+pub use path_batch::*;
+pub use path_data::*;
+#[cfg(feature = "svg")]
+pub use svg::*;
+pub use tessellation::*;
+
+use glam::{Affine2, Vec2};
+use lyon::geom::{ArcFlags, SvgArc};
+use lyon::math::{point, vector, Angle};
+use lyon::path::builder::SvgPathBuilder;
+
+use crate::primitives::{Interactive, Styled};
+use crate::types::{Appearance, Color, InteractionState, Paint, Style, StyleViewport, Transform};
+
+// One segment of a path, in absolute coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathCommand {
+    MoveTo(Vec2),
+    LineTo(Vec2),
+    QuadTo { ctrl: Vec2, to: Vec2 },
+    CubicTo { ctrl1: Vec2, ctrl2: Vec2, to: Vec2 },
+    // Elliptical arc as in SVG: `x_rotation` in radians, and the flags pick one of the four
+    // arcs through the current point and `to`
+    ArcTo { radii: Vec2, x_rotation: f32, large_arc: bool, sweep: bool, to: Vec2 },
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillRule {
+    #[default]
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+// Outline of a path. `dashes` alternates dash and gap lengths like stroke-dasharray (an
+// odd count is repeated to make it even); empty means solid.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Stroke {
+    pub color: Color,
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    pub miter_limit: f32,
+    pub dashes: Vec<f32>,
+    pub dash_offset: f32,
+}
+
+impl Stroke {
+    pub fn new(color: Color, width: f32) -> Self {
+        Self {
+            color,
+            width,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    pub fn with_dashes(mut self, dashes: impl Into<Vec<f32>>, offset: f32) -> Self {
+        self.dashes = dashes.into();
+        self.dash_offset = offset;
+        self
+    }
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            width: 1.0,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            // SVG's default
+            miter_limit: 4.0,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+}
+
+// An arbitrary shape made of lines, curves and arcs, filled and/or stroked. Built up with
+// move_to/line_to/... like a canvas path, or parsed from SVG path data.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Path {
+    // Written as SVG path data in scene files
+    #[cfg_attr(feature = "serde", serde(with = "path_data::serde_commands"))]
+    pub commands: Vec<PathCommand>,
+    pub fill: Option<Color>,
+    // Fills the path instead of the `fill` color when set; a path without a fill stays unfilled
+    pub paint: Option<Paint>,
+    pub fill_rule: FillRule,
+    pub stroke: Option<Stroke>,
+    pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
+    pub css_class: Option<String>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self {
+            commands: Vec::new(),
+            fill: Some(Color::default()),
+            paint: None,
+            fill_rule: FillRule::default(),
+            stroke: None,
+            transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
+            css_class: None,
+        }
+    }
+
+    pub fn from_commands(commands: impl Into<Vec<PathCommand>>) -> Self {
+        Self {
+            commands: commands.into(),
+            ..Self::new()
+        }
+    }
+
+    // Parses the `d` attribute syntax of SVG, e.g. "M 0 0 L 1 0 Q 1 1 0 1 Z"
+    pub fn from_svg_path_data(data: &str) -> Result<Self, PathDataError> {
+        Ok(Self::from_commands(parse_path_data(data)?))
+    }
+
+    pub fn rect(position: Vec2, size: Vec2) -> Self {
+        Self::polygon([position, position + Vec2::new(size.x, 0.0), position + size, position + Vec2::new(0.0, size.y)])
+    }
+
+    // A rectangle with elliptical corners, radii clamped to half the size like SVG's rx/ry
+    pub fn rounded_rect(position: Vec2, size: Vec2, radii: Vec2) -> Self {
+        let radii = radii.abs().min(size.abs() / 2.0);
+        if radii.x <= 0.0 || radii.y <= 0.0 {
+            return Self::rect(position, size);
+        }
+        let (x0, y0, x1, y1) = (position.x, position.y, position.x + size.x, position.y + size.y);
+        let corner = |to: Vec2| PathCommand::ArcTo {
+            radii,
+            x_rotation: 0.0,
+            large_arc: false,
+            sweep: true,
+            to,
+        };
+        Self::from_commands([
+            PathCommand::MoveTo(Vec2::new(x0 + radii.x, y0)),
+            PathCommand::LineTo(Vec2::new(x1 - radii.x, y0)),
+            corner(Vec2::new(x1, y0 + radii.y)),
+            PathCommand::LineTo(Vec2::new(x1, y1 - radii.y)),
+            corner(Vec2::new(x1 - radii.x, y1)),
+            PathCommand::LineTo(Vec2::new(x0 + radii.x, y1)),
+            corner(Vec2::new(x0, y1 - radii.y)),
+            PathCommand::LineTo(Vec2::new(x0, y0 + radii.y)),
+            corner(Vec2::new(x0 + radii.x, y0)),
+            PathCommand::Close,
+        ])
+    }
+
+    pub fn ellipse(center: Vec2, radii: Vec2) -> Self {
+        let half = |to: Vec2| PathCommand::ArcTo {
+            radii,
+            x_rotation: 0.0,
+            large_arc: false,
+            sweep: true,
+            to,
+        };
+        Self::from_commands([
+            PathCommand::MoveTo(center + Vec2::new(radii.x, 0.0)),
+            half(center - Vec2::new(radii.x, 0.0)),
+            half(center + Vec2::new(radii.x, 0.0)),
+            PathCommand::Close,
+        ])
+    }
+
+    pub fn circle(center: Vec2, radius: f32) -> Self {
+        Self::ellipse(center, Vec2::splat(radius))
+    }
+
+    // Closed, filled shape through `points`
+    pub fn polygon(points: impl IntoIterator<Item = Vec2>) -> Self {
+        let mut commands = polyline_commands(points);
+        commands.push(PathCommand::Close);
+        Self::from_commands(commands)
+    }
+
+    // Open shape through `points`, stroked only
+    pub fn polyline(points: impl IntoIterator<Item = Vec2>) -> Self {
+        Self {
+            fill: None,
+            stroke: Some(Stroke::default()),
+            ..Self::from_commands(polyline_commands(points))
+        }
+    }
+
+    pub fn move_to(mut self, to: Vec2) -> Self {
+        self.commands.push(PathCommand::MoveTo(to));
+        self
+    }
+
+    pub fn line_to(mut self, to: Vec2) -> Self {
+        self.commands.push(PathCommand::LineTo(to));
+        self
+    }
+
+    pub fn quad_to(mut self, ctrl: Vec2, to: Vec2) -> Self {
+        self.commands.push(PathCommand::QuadTo { ctrl, to });
+        self
+    }
+
+    pub fn cubic_to(mut self, ctrl1: Vec2, ctrl2: Vec2, to: Vec2) -> Self {
+        self.commands.push(PathCommand::CubicTo { ctrl1, ctrl2, to });
+        self
+    }
+
+    pub fn arc_to(mut self, radii: Vec2, x_rotation: f32, large_arc: bool, sweep: bool, to: Vec2) -> Self {
+        self.commands.push(PathCommand::ArcTo {
+            radii,
+            x_rotation,
+            large_arc,
+            sweep,
+            to,
+        });
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    // None for an outline-only path
+    pub fn with_fill(mut self, fill: Option<Color>) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    pub fn with_color(self, color: Color) -> Self {
+        self.with_fill(Some(color))
+    }
+
+    // Gradients span the path's bounding box before its transform. Fills the path if it
+    // wasn't already.
+    pub fn with_paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.fill.get_or_insert(Color::WHITE);
+        self.paint = Some(paint.into());
+        self
+    }
+
+    pub fn with_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    pub fn with_stroke(mut self, stroke: Option<Stroke>) -> Self {
+        self.stroke = stroke;
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
+    pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
+        self.css_class = Some(class.into());
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn apply_css_override(&mut self, color: Color) {
+        self.fill = Some(color);
+    }
+
+    // Applies every property `style` sets: the fill color or gradient, and
+    // border-width/border-color as the stroke (a border width adds a stroke to paths without one)
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        match style.fill_paint() {
+            Some(Paint::Solid(color)) => {
+                self.fill = Some(color);
+                self.paint = None;
+            }
+            Some(paint) => {
+                self.fill.get_or_insert(Color::WHITE);
+                self.paint = Some(paint);
+            }
+            None => {}
+        }
+        match (&mut self.stroke, style.border_width.map(|width| viewport.clip_length(width))) {
+            (Some(stroke), width) => {
+                if let Some(width) = width {
+                    stroke.width = width.max(0.0);
+                }
+                if let Some(color) = style.stroke_color() {
+                    stroke.color = color;
+                }
+            }
+            (None, Some(width)) if width > 0.0 => {
+                self.stroke = Some(Stroke::new(style.stroke_color().unwrap_or(Color::BLACK), width));
+            }
+            (None, _) => {}
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+
+    // The same shape with `transform` applied to its points; stroke widths scale with it.
+    // Arcs become cubic curves since a skewed ellipse isn't an SVG arc anymore.
+    pub fn transformed(&self, transform: &Affine2) -> Path {
+        let map = |point: Vec2| transform.transform_point2(point);
+        let mut commands = Vec::with_capacity(self.commands.len());
+        let mut current = Vec2::ZERO;
+        let mut start = Vec2::ZERO;
+        for command in &self.commands {
+            match *command {
+                PathCommand::MoveTo(to) => {
+                    commands.push(PathCommand::MoveTo(map(to)));
+                    (current, start) = (to, to);
+                }
+                PathCommand::LineTo(to) => {
+                    commands.push(PathCommand::LineTo(map(to)));
+                    current = to;
+                }
+                PathCommand::QuadTo { ctrl, to } => {
+                    commands.push(PathCommand::QuadTo { ctrl: map(ctrl), to: map(to) });
+                    current = to;
+                }
+                PathCommand::CubicTo { ctrl1, ctrl2, to } => {
+                    commands.push(PathCommand::CubicTo {
+                        ctrl1: map(ctrl1),
+                        ctrl2: map(ctrl2),
+                        to: map(to),
+                    });
+                    current = to;
+                }
+                PathCommand::ArcTo {
+                    radii,
+                    x_rotation,
+                    large_arc,
+                    sweep,
+                    to,
+                } => {
+                    svg_arc(current, radii, x_rotation, large_arc, sweep, to).for_each_cubic_bezier(&mut |cubic| {
+                        commands.push(PathCommand::CubicTo {
+                            ctrl1: map(Vec2::new(cubic.ctrl1.x, cubic.ctrl1.y)),
+                            ctrl2: map(Vec2::new(cubic.ctrl2.x, cubic.ctrl2.y)),
+                            to: map(Vec2::new(cubic.to.x, cubic.to.y)),
+                        });
+                    });
+                    current = to;
+                }
+                PathCommand::Close => {
+                    commands.push(PathCommand::Close);
+                    current = start;
+                }
+            }
+        }
+        let scale = transform.matrix2.determinant().abs().sqrt();
+        let stroke = self.stroke.clone().map(|mut stroke| {
+            stroke.width *= scale;
+            stroke.dashes.iter_mut().for_each(|dash| *dash *= scale);
+            stroke.dash_offset *= scale;
+            stroke
+        });
+        Path {
+            commands,
+            stroke,
+            ..self.clone()
+        }
+    }
+
+    // The commands as a lyon path, before `transform`
+    pub fn to_lyon(&self) -> lyon::path::Path {
+        let mut builder = lyon::path::Path::svg_builder();
+        for command in &self.commands {
+            match *command {
+                PathCommand::MoveTo(to) => {
+                    builder.move_to(point(to.x, to.y));
+                }
+                PathCommand::LineTo(to) => {
+                    builder.line_to(point(to.x, to.y));
+                }
+                PathCommand::QuadTo { ctrl, to } => {
+                    builder.quadratic_bezier_to(point(ctrl.x, ctrl.y), point(to.x, to.y));
+                }
+                PathCommand::CubicTo { ctrl1, ctrl2, to } => {
+                    builder.cubic_bezier_to(point(ctrl1.x, ctrl1.y), point(ctrl2.x, ctrl2.y), point(to.x, to.y));
+                }
+                // Zero radii make the arc a straight line, as in SVG
+                PathCommand::ArcTo { radii, to, .. } if radii.x == 0.0 || radii.y == 0.0 => {
+                    builder.line_to(point(to.x, to.y));
+                }
+                PathCommand::ArcTo {
+                    radii,
+                    x_rotation,
+                    large_arc,
+                    sweep,
+                    to,
+                } => builder.arc_to(
+                    vector(radii.x.abs(), radii.y.abs()),
+                    Angle::radians(x_rotation),
+                    ArcFlags { large_arc, sweep },
+                    point(to.x, to.y),
+                ),
+                PathCommand::Close => builder.close(),
+            }
+        }
+        builder.build()
+    }
+}
+
+fn polyline_commands(points: impl IntoIterator<Item = Vec2>) -> Vec<PathCommand> {
+    points
+        .into_iter()
+        .enumerate()
+        .map(|(index, point)| if index == 0 { PathCommand::MoveTo(point) } else { PathCommand::LineTo(point) })
+        .collect()
+}
+
+fn svg_arc(from: Vec2, radii: Vec2, x_rotation: f32, large_arc: bool, sweep: bool, to: Vec2) -> SvgArc<f32> {
+    SvgArc {
+        from: point(from.x, from.y),
+        to: point(to.x, to.y),
+        radii: vector(radii.x.abs(), radii.y.abs()),
+        x_rotation: Angle::radians(x_rotation),
+        flags: ArcFlags { large_arc, sweep },
+    }
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Styled for Path {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Path::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Path {
+    // Inside the fill (per the fill rule) or within half the stroke width of the outline
+    fn hit_test(&self, point: Vec2) -> bool {
+        if !self.appearance.visible {
+            return false;
+        }
+        let local = self.transform.inverse_transform_point(point);
+        let path = self.to_lyon();
+        if self.fill.is_some() && contains(&path, local, self.fill_rule) {
+            return true;
+        }
+        self.stroke
+            .as_ref()
+            .is_some_and(|stroke| distance_to_outline(&path, local) <= stroke.width / 2.0)
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
+}
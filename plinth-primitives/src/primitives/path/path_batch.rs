@@ -0,0 +1,432 @@
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use wgpu::{BindGroupLayout, Device, RenderPipeline, ShaderModuleDescriptor};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::primitives::{tessellate_path_with_ramp, MeshBuffers, MeshVertex, Path, StyleResolver, DEFAULT_TOLERANCE};
+use crate::batch::{
+    clip_depth_stencil, render_clip_runs, BatchShader, ClipId, ClipRun, ClipScope, Drawn, GradientRamps,
+    InstanceBatch, StyleCache,
+};
+use crate::types::{Paint, StyleViewport};
+use crate::shaders::ShaderPreprocessor;
+
+pub const MESH_SHADER: ShaderSource = shader_source!("src/shaders/mesh.wgsl");
+
+// Paths tessellated into one indexed triangle mesh. Unlike circles there is no per-instance
+// data to patch, so any change re-tessellates every path; the mesh is only uploaded when
+// it comes out different.
+pub struct PathBatch {
+    // Paths as added, in insertion order, before any class styles are applied
+    paths: Vec<Path>,
+    // The clip each path was added under
+    clips: Vec<ClipId>,
+    // The clip paths added from now on are drawn in
+    clip: ClipId,
+    // Visible paths sorted by z-index, insertion order within the same z-index
+    geometry: MeshBuffers,
+    // Runs of indices by the clip they are drawn in
+    clip_runs: Vec<ClipRun>,
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    // Vertices and indices the buffers have room for
+    vertex_capacity: usize,
+    index_capacity: usize,
+    render_pipeline: Option<RenderPipeline>,
+    shader: BatchShader,
+    gradients: GradientRamps,
+    tolerance: f32,
+    style_cache: StyleCache<Path>,
+    // Paths were added or removed, or the tolerance changed, since the mesh was last built
+    needs_rebuild: bool,
+    // The mesh changed since the last upload
+    needs_upload: bool,
+}
+
+impl PathBatch {
+    pub fn new() -> Self {
+        Self {
+            paths: Vec::new(),
+            clips: Vec::new(),
+            clip: ClipId::NONE,
+            geometry: MeshBuffers::new(),
+            clip_runs: Vec::new(),
+            vertex_buffer: None,
+            index_buffer: None,
+            vertex_capacity: 0,
+            index_capacity: 0,
+            render_pipeline: None,
+            shader: BatchShader::new("mesh", MESH_SHADER),
+            gradients: GradientRamps::new(),
+            tolerance: DEFAULT_TOLERANCE,
+            style_cache: StyleCache::new(),
+            needs_rebuild: true,
+            needs_upload: false,
+        }
+    }
+
+    // Paths without classes that are hidden are dropped here; styled ones are kept since
+    // their classes may show them again
+    pub fn add_path(&mut self, path: Path) {
+        if !path.appearance.visible && path.css_class.is_none() {
+            return;
+        }
+        self.paths.push(path);
+        self.clips.push(self.clip);
+        self.needs_rebuild = true;
+    }
+
+    pub fn add_paths(&mut self, paths: impl IntoIterator<Item = Path>) {
+        for path in paths {
+            self.add_path(path);
+        }
+    }
+
+    // Draws paths added from now on in `clip`, see PrimitiveRenderer::push_clip_rect
+    pub fn set_clip(&mut self, clip: ClipId) {
+        self.clip = clip;
+    }
+
+    pub fn clip(&self) -> ClipId {
+        self.clip
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.clips.clear();
+        self.style_cache.clear();
+        self.needs_rebuild = true;
+    }
+
+    // Time style transitions and animations are sampled at on the next `resolve_styles`
+    pub fn set_style_time(&mut self, time: Duration) {
+        self.style_cache.set_time(time);
+    }
+
+    // What CSS lengths in styles are converted against, restyling the batch when it changes
+    pub fn set_style_viewport(&mut self, viewport: StyleViewport) {
+        self.style_cache.set_viewport(viewport);
+    }
+
+    // Whether a style transition or animation is running, so the batch restyles every frame
+    pub fn is_animating(&self) -> bool {
+        self.style_cache.is_animating()
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    // Paths as added, to move them or change their state in place. They are
+    // restyled on the next `resolve_styles`.
+    pub fn paths_mut(&mut self) -> &mut [Path] {
+        self.style_cache.invalidate();
+        self.needs_rebuild = true;
+        &mut self.paths
+    }
+
+    // The paths as last drawn, in draw order, for the renderer's pointer handling, which
+    // only flags a state change so just the paths whose state changed are restyled
+    pub(crate) fn interaction_mut(&mut self) -> (Vec<Drawn<'_, Path>>, &mut bool) {
+        self.style_cache.drawn_mut(&mut self.paths)
+    }
+
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    // Maximum distance between curves and the segments they are flattened into, in clip
+    // space. Smaller is smoother and costs more triangles.
+    pub fn set_tolerance(&mut self, tolerance: f32) {
+        let tolerance = tolerance.max(f32::EPSILON);
+        if tolerance != self.tolerance {
+            self.tolerance = tolerance;
+            self.needs_rebuild = true;
+        }
+    }
+
+    // The tessellated mesh as of the last `resolve_styles`
+    pub fn geometry(&self) -> &MeshBuffers {
+        &self.geometry
+    }
+
+    // The mesh's indices split by the clips they are drawn in, as of the last `resolve_styles`
+    pub fn clip_runs(&self) -> &[ClipRun] {
+        &self.clip_runs
+    }
+
+    // The gradients of the path fills as of the last `resolve_styles`
+    pub fn gradients(&self) -> &GradientRamps {
+        &self.gradients
+    }
+
+    // Re-tessellates the paths with the styles of their classes and state from `resolver`.
+    // Skipped when no path was added, changed state or is animating and the resolver's
+    // generation is unchanged; only those paths are restyled. Paths that fail to tessellate
+    // are logged and left out.
+    pub fn resolve_styles(&mut self, resolver: Option<&dyn StyleResolver>) {
+        if !self.style_cache.sync(resolver) && !self.needs_rebuild {
+            return;
+        }
+        let rebuild = std::mem::take(&mut self.needs_rebuild);
+        let restyled = self.style_cache.restyle(resolver, &self.paths);
+        if restyled.is_empty() && !rebuild {
+            return;
+        }
+
+        let mut drawn: Vec<(i32, usize, &Path, ClipId)> = Vec::with_capacity(self.paths.len());
+        for (index, (path, clip)) in self.paths.iter().zip(&self.clips).enumerate() {
+            let path = self.style_cache.styled(index, path);
+            if path.appearance.visible {
+                drawn.push((path.appearance.z_index, index, path, *clip));
+            }
+        }
+        // Stable, so insertion order is kept within a z-index
+        drawn.sort_by_key(|(z_index, _, _, _)| *z_index);
+        let order: Vec<usize> = drawn.iter().map(|(_, index, _, _)| *index).collect();
+
+        self.gradients.clear();
+        let mut geometry = MeshBuffers::new();
+        self.clip_runs.clear();
+        for (_, _, path, clip) in &drawn {
+            let row = match (path.fill, path.paint.as_ref().and_then(Paint::stops)) {
+                (Some(_), Some(stops)) => self.gradients.row(stops),
+                _ => 0,
+            };
+            let (vertices, indices) = (geometry.vertices.len(), geometry.indices.len());
+            if let Err(e) = tessellate_path_with_ramp(path, self.tolerance, row, &mut geometry) {
+                log::warn!("Skipping path that failed to tessellate: {:?}", e);
+                geometry.vertices.truncate(vertices);
+                geometry.indices.truncate(indices);
+            }
+            ClipRun::push(&mut self.clip_runs, indices..geometry.indices.len(), *clip);
+        }
+        self.style_cache.set_drawn(order);
+
+        if geometry.vertices != self.geometry.vertices || geometry.indices != self.geometry.indices {
+            self.geometry = geometry;
+            self.needs_upload = true;
+        }
+    }
+
+    pub fn create_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat) -> RenderPipeline {
+        self.shader.set_targets(PipelineTargets { format: surface_format, sample_count: 1 });
+        self.rebuild_pipelines(device);
+        self.render_pipeline.clone().expect("Failed to build mesh pipeline")
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        self.shader.preprocessor()
+    }
+
+    // Rebuilds the pipelines for color targets of `targets`, e.g. multisampled
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.shader.set_targets(targets) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines with the define toggled, see BatchShader
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        if self.shader.set_define(name, enabled) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines if mesh.wgsl or one of its includes changed on disk
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        self.shader.reload(shaders) && self.rebuild_pipelines(device)
+    }
+
+    fn rebuild_pipelines(&mut self, device: &Device) -> bool {
+        let layout = self.gradients.bind_group_layout(device);
+        let Some(render_pipeline) = self.shader.build(device, &[], |source, targets, blend| {
+            Self::build_pipeline(device, targets, blend, source, layout)
+        }) else {
+            return false;
+        };
+        self.render_pipeline = Some(render_pipeline);
+        true
+    }
+
+    fn build_pipeline(
+        device: &Device,
+        targets: PipelineTargets,
+        blend: wgpu::BlendState,
+        source: &str,
+        layout: &BindGroupLayout,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(MESH_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &[layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<MeshVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2, // position
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x4, // color
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x2, // local
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                            shader_location: 3,
+                            format: wgpu::VertexFormat::Float32x4, // paint.params
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                            shader_location: 4,
+                            format: wgpu::VertexFormat::Float32x4, // paint.geometry
+                        },
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: targets.format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // Tessellated triangles come in either winding, and the y flip of SVG
+                // imports reverses it anyway
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(clip_depth_stencil()),
+            multisample: targets.multisample(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // Uploads the mesh if it changed since the last upload, growing the buffers when needed.
+    // Paths are drawn unstyled unless `resolve_styles` ran with a resolver first.
+    pub fn update_buffer(&mut self, device: &Device, queue: &wgpu::Queue) {
+        if self.needs_rebuild {
+            self.resolve_styles(None);
+        }
+        self.gradients.upload(device, queue);
+        if !self.needs_upload || self.geometry.indices.is_empty() {
+            return;
+        }
+        self.needs_upload = false;
+
+        let (vertices, indices) = (&self.geometry.vertices, &self.geometry.indices);
+        if self.vertex_buffer.is_none() || vertices.len() > self.vertex_capacity {
+            // Room to grow without reallocating every time a path is added
+            self.vertex_capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Path Vertex Buffer"),
+                size: (self.vertex_capacity * std::mem::size_of::<MeshVertex>()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if self.index_buffer.is_none() || indices.len() > self.index_capacity {
+            self.index_capacity = indices.len().next_power_of_two();
+            self.index_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Path Index Buffer"),
+                size: (self.index_capacity * std::mem::size_of::<u32>()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        if let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer) {
+            queue.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(vertices));
+            queue.write_buffer(index_buffer, 0, bytemuck::cast_slice(indices));
+        }
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render_range(render_pass, 0..self.geometry.indices.len());
+    }
+
+    // Draws the mesh run by run, each in the clip its path was added under
+    pub fn render_clipped(&self, render_pass: &mut wgpu::RenderPass, scope: &mut dyn ClipScope) {
+        render_clip_runs(render_pass, &self.clip_runs, scope, |render_pass, indices| {
+            self.render_range(render_pass, indices)
+        });
+    }
+
+    // Draws some of the mesh's indices, e.g. one of `clip_runs`
+    pub fn render_range(&self, render_pass: &mut wgpu::RenderPass, indices: Range<usize>) {
+        let count = self.geometry.indices.len();
+        let indices = indices.start.min(count)..indices.end.min(count);
+        if let (Some(pipeline), Some(vertex_buffer), Some(index_buffer), Some(gradients)) =
+            (&self.render_pipeline, &self.vertex_buffer, &self.index_buffer, self.gradients.bind_group())
+        {
+            if !indices.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, gradients, &[]);
+                let vertices = (self.geometry.vertices.len() * std::mem::size_of::<MeshVertex>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..vertices));
+                let size = (count * std::mem::size_of::<u32>()) as wgpu::BufferAddress;
+                render_pass.set_index_buffer(index_buffer.slice(..size), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(indices.start as u32..indices.end as u32, 0, 0..1);
+            }
+        }
+    }
+}
+
+impl Default for PathBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstanceBatch for PathBatch {
+    fn len(&self) -> usize {
+        PathBatch::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        PathBatch::is_empty(self)
+    }
+
+    fn clear(&mut self) {
+        PathBatch::clear(self);
+    }
+}
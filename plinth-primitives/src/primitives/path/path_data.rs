@@ -0,0 +1,280 @@
+use std::fmt::Write as _;
+
+use glam::Vec2;
+
+use crate::primitives::PathCommand;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PathDataError {
+    #[error("Offset {position}: path data must start with a moveto")]
+    MissingMoveTo { position: usize },
+    #[error("Offset {position}: unexpected '{character}'")]
+    UnexpectedCharacter { position: usize, character: char },
+    #[error("Offset {position}: expected a number")]
+    ExpectedNumber { position: usize },
+    #[error("Offset {position}: expected an arc flag (0 or 1)")]
+    ExpectedFlag { position: usize },
+}
+
+// Parses SVG path data (the `d` attribute) into absolute commands. Supports every command
+// in upper and lower case (MLHVCSQTAZ), implicit repeats, and the compact number syntax
+// (`M1.5.5-2e1`, arc flags without separators). H/V become lines and S/T get their
+// reflected control point.
+pub fn parse_path_data(data: &str) -> Result<Vec<PathCommand>, PathDataError> {
+    let mut lexer = Lexer { data: data.as_bytes(), position: 0 };
+    let mut commands = Vec::new();
+    let mut current = Vec2::ZERO;
+    let mut subpath_start = Vec2::ZERO;
+    // Control point S/T reflect: the last cubic's second control point or the last quad's
+    let mut last_cubic_ctrl: Option<Vec2> = None;
+    let mut last_quad_ctrl: Option<Vec2> = None;
+    let mut previous: Option<u8> = None;
+
+    loop {
+        lexer.skip_separators();
+        let Some(next) = lexer.peek() else {
+            break;
+        };
+        let position = lexer.position;
+        let command = if next.is_ascii_alphabetic() {
+            lexer.position += 1;
+            next
+        } else {
+            match previous {
+                // Coordinates after a moveto are implicit linetos
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(command) if !command.eq_ignore_ascii_case(&b'z') => command,
+                _ => {
+                    return Err(PathDataError::UnexpectedCharacter {
+                        position,
+                        character: data[position..].chars().next().unwrap_or_default(),
+                    })
+                }
+            }
+        };
+        if commands.is_empty() && !command.eq_ignore_ascii_case(&b'm') {
+            return Err(PathDataError::MissingMoveTo { position });
+        }
+
+        let relative = command.is_ascii_lowercase();
+        let origin = if relative { current } else { Vec2::ZERO };
+        let (mut cubic_ctrl, mut quad_ctrl) = (None, None);
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::MoveTo(to));
+                (current, subpath_start) = (to, to);
+            }
+            b'L' => {
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::LineTo(to));
+                current = to;
+            }
+            b'H' => {
+                let x = lexer.number()?;
+                current = Vec2::new(if relative { current.x + x } else { x }, current.y);
+                commands.push(PathCommand::LineTo(current));
+            }
+            b'V' => {
+                let y = lexer.number()?;
+                current = Vec2::new(current.x, if relative { current.y + y } else { y });
+                commands.push(PathCommand::LineTo(current));
+            }
+            b'C' => {
+                let ctrl1 = origin + lexer.point()?;
+                let ctrl2 = origin + lexer.point()?;
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::CubicTo { ctrl1, ctrl2, to });
+                (current, cubic_ctrl) = (to, Some(ctrl2));
+            }
+            b'S' => {
+                let ctrl1 = last_cubic_ctrl.map_or(current, |ctrl| current * 2.0 - ctrl);
+                let ctrl2 = origin + lexer.point()?;
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::CubicTo { ctrl1, ctrl2, to });
+                (current, cubic_ctrl) = (to, Some(ctrl2));
+            }
+            b'Q' => {
+                let ctrl = origin + lexer.point()?;
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::QuadTo { ctrl, to });
+                (current, quad_ctrl) = (to, Some(ctrl));
+            }
+            b'T' => {
+                let ctrl = last_quad_ctrl.map_or(current, |ctrl| current * 2.0 - ctrl);
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::QuadTo { ctrl, to });
+                (current, quad_ctrl) = (to, Some(ctrl));
+            }
+            b'A' => {
+                let radii = Vec2::new(lexer.number()?, lexer.number()?);
+                let x_rotation = lexer.number()?.to_radians();
+                let large_arc = lexer.flag()?;
+                let sweep = lexer.flag()?;
+                let to = origin + lexer.point()?;
+                commands.push(PathCommand::ArcTo {
+                    radii,
+                    x_rotation,
+                    large_arc,
+                    sweep,
+                    to,
+                });
+                current = to;
+            }
+            b'Z' => {
+                commands.push(PathCommand::Close);
+                current = subpath_start;
+            }
+            _ => {
+                return Err(PathDataError::UnexpectedCharacter {
+                    position,
+                    character: command as char,
+                })
+            }
+        }
+        (last_cubic_ctrl, last_quad_ctrl) = (cubic_ctrl, quad_ctrl);
+        previous = Some(command);
+    }
+    Ok(commands)
+}
+
+// Absolute SVG path data for `commands`, which `parse_path_data` reads back
+pub fn write_path_data(commands: &[PathCommand]) -> String {
+    let mut data = String::new();
+    for command in commands {
+        if !data.is_empty() {
+            data.push(' ');
+        }
+        let _ = match *command {
+            PathCommand::MoveTo(to) => write!(data, "M{} {}", to.x, to.y),
+            PathCommand::LineTo(to) => write!(data, "L{} {}", to.x, to.y),
+            PathCommand::QuadTo { ctrl, to } => write!(data, "Q{} {} {} {}", ctrl.x, ctrl.y, to.x, to.y),
+            PathCommand::CubicTo { ctrl1, ctrl2, to } => {
+                write!(data, "C{} {} {} {} {} {}", ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y)
+            }
+            PathCommand::ArcTo {
+                radii,
+                x_rotation,
+                large_arc,
+                sweep,
+                to,
+            } => write!(
+                data,
+                "A{} {} {} {} {} {} {}",
+                radii.x,
+                radii.y,
+                x_rotation.to_degrees(),
+                large_arc as u8,
+                sweep as u8,
+                to.x,
+                to.y
+            ),
+            PathCommand::Close => write!(data, "Z"),
+        };
+    }
+    data
+}
+
+// A list of numbers in path data syntax, as in the `points` and `transform` attributes
+#[cfg(feature = "svg")]
+pub(crate) fn parse_numbers(text: &str) -> Result<Vec<f32>, PathDataError> {
+    let mut lexer = Lexer { data: text.as_bytes(), position: 0 };
+    let mut numbers = Vec::new();
+    loop {
+        lexer.skip_separators();
+        if lexer.peek().is_none() {
+            return Ok(numbers);
+        }
+        numbers.push(lexer.number()?);
+    }
+}
+
+struct Lexer<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Lexer<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace() || c == b',') {
+            self.position += 1;
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    fn number(&mut self) -> Result<f32, PathDataError> {
+        self.skip_separators();
+        let start = self.position;
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.position += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.position = start;
+            return Err(PathDataError::ExpectedNumber { position: start });
+        }
+        // An exponent only if digits follow, so "2e" leaves the e for the next token
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.position = mantissa_end;
+            }
+        }
+        std::str::from_utf8(&self.data[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or(PathDataError::ExpectedNumber { position: start })
+    }
+
+    fn point(&mut self) -> Result<Vec2, PathDataError> {
+        Ok(Vec2::new(self.number()?, self.number()?))
+    }
+
+    fn flag(&mut self) -> Result<bool, PathDataError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(PathDataError::ExpectedFlag { position: self.position }),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_commands {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::{parse_path_data, write_path_data};
+    use crate::primitives::PathCommand;
+
+    pub fn serialize<S: Serializer>(commands: &[PathCommand], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&write_path_data(commands))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PathCommand>, D::Error> {
+        let data = String::deserialize(deserializer)?;
+        parse_path_data(&data).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,375 @@
+use glam::{Affine2, Vec2};
+use roxmltree::{Document, Node};
+
+use crate::primitives::{parse_numbers, parse_path_data, FillRule, LineCap, LineJoin, Path, PathCommand, Stroke};
+use crate::types::{Color, CssColor};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SvgError {
+    #[error("Invalid SVG document: {0}")]
+    Xml(String),
+    #[error("Root element is <{0}>, expected <svg>")]
+    NotSvg(String),
+    #[error("Failed to read {0}: {1}")]
+    Io(String, String),
+}
+
+// The shapes of an SVG document as paths, in the document's user units (y pointing down).
+// Use `fitted` to place them in clip space.
+#[derive(Debug, Clone, Default)]
+pub struct SvgDocument {
+    // min-x, min-y, width, height
+    pub view_box: Option<[f32; 4]>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub paths: Vec<Path>,
+}
+
+impl SvgDocument {
+    // Imports <path>, <rect>, <circle>, <ellipse>, <line>, <polyline> and <polygon> inside
+    // <svg> and <g> elements, with fill, stroke and the stroke-* properties (as attributes or
+    // in `style`, inherited through groups), opacity and transforms. `class` becomes the
+    // path's css_class. Gradients, patterns, <use>, text and clipping aren't supported; paint
+    // that can't be used is treated as none.
+    pub fn parse(text: &str) -> Result<Self, SvgError> {
+        let document = Document::parse(text).map_err(|e| SvgError::Xml(e.to_string()))?;
+        let root = document.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err(SvgError::NotSvg(root.tag_name().name().to_string()));
+        }
+        let mut svg = SvgDocument {
+            view_box: root
+                .attribute("viewBox")
+                .and_then(|view_box| parse_numbers(view_box).ok())
+                .and_then(|numbers| <[f32; 4]>::try_from(numbers).ok())
+                .filter(|[_, _, width, height]| *width > 0.0 && *height > 0.0),
+            width: root.attribute("width").and_then(parse_length),
+            height: root.attribute("height").and_then(parse_length),
+            paths: Vec::new(),
+        };
+        svg.import_children(root, &Presentation::default());
+        Ok(svg)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, SvgError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| SvgError::Io(path.display().to_string(), e.to_string()))?;
+        Self::parse(&text)
+    }
+
+    // The area the document shows as (min, size): its viewBox, else its width and height
+    pub fn bounds(&self) -> Option<(Vec2, Vec2)> {
+        match (self.view_box, self.width, self.height) {
+            (Some([x, y, width, height]), _, _) => Some((Vec2::new(x, y), Vec2::new(width, height))),
+            (None, Some(width), Some(height)) if width > 0.0 && height > 0.0 => Some((Vec2::ZERO, Vec2::new(width, height))),
+            _ => None,
+        }
+    }
+
+    // The paths scaled to fit a `size` area centered at `center` keeping the aspect ratio,
+    // with y flipped to point up as in clip space. Documents without a size are fitted by
+    // the extent of their points.
+    pub fn fitted(&self, center: Vec2, size: Vec2) -> Vec<Path> {
+        let Some((min, extent)) = self.bounds().or_else(|| self.content_bounds()) else {
+            return self.paths.clone();
+        };
+        let scale = (size / extent).min_element();
+        let transform = Affine2::from_translation(center)
+            * Affine2::from_scale(Vec2::new(scale, -scale))
+            * Affine2::from_translation(-(min + extent / 2.0));
+        self.paths.iter().map(|path| path.transformed(&transform)).collect()
+    }
+
+    // Bounds of every point and control point, which contain the curves they describe
+    fn content_bounds(&self) -> Option<(Vec2, Vec2)> {
+        let points = self.paths.iter().flat_map(|path| &path.commands).flat_map(|command| match *command {
+            PathCommand::MoveTo(to) | PathCommand::LineTo(to) | PathCommand::ArcTo { to, .. } => vec![to],
+            PathCommand::QuadTo { ctrl, to } => vec![ctrl, to],
+            PathCommand::CubicTo { ctrl1, ctrl2, to } => vec![ctrl1, ctrl2, to],
+            PathCommand::Close => vec![],
+        });
+        let (min, max) = points.fold((Vec2::INFINITY, Vec2::NEG_INFINITY), |(min, max), point| (min.min(point), max.max(point)));
+        let extent = max - min;
+        (extent.is_finite() && extent.max_element() > 0.0).then_some((min, extent))
+    }
+
+    fn import_children(&mut self, node: Node, inherited: &Presentation) {
+        for child in node.children().filter(Node::is_element) {
+            let Some(presentation) = inherited.child(child) else {
+                continue;
+            };
+            let name = child.tag_name().name();
+            if matches!(name, "svg" | "g" | "a") {
+                self.import_children(child, &presentation);
+                continue;
+            }
+            match shape_commands(child) {
+                Some(commands) if commands.is_empty() => {}
+                Some(commands) => self.paths.push(presentation.path(commands, child.attribute("class"))),
+                None => log::debug!("SVG: skipping <{}>", name),
+            }
+        }
+    }
+}
+
+// Outline of a shape element, None if it isn't one or can't be drawn
+fn shape_commands(node: Node) -> Option<Vec<PathCommand>> {
+    let length = |name: &str| node.attribute(name).and_then(parse_length);
+    let point = |x: &str, y: &str| Vec2::new(length(x).unwrap_or(0.0), length(y).unwrap_or(0.0));
+    let shape = match node.tag_name().name() {
+        "path" => match parse_path_data(node.attribute("d").unwrap_or_default()) {
+            Ok(commands) => return Some(commands),
+            Err(e) => {
+                log::warn!("SVG: skipping <path> with invalid data: {}", e);
+                return None;
+            }
+        },
+        "rect" => {
+            let size = point("width", "height");
+            if size.min_element() <= 0.0 {
+                return None;
+            }
+            // A missing rx or ry takes the other's value
+            let radii = match (length("rx"), length("ry")) {
+                (Some(rx), Some(ry)) => Vec2::new(rx, ry),
+                (Some(radius), None) | (None, Some(radius)) => Vec2::splat(radius),
+                (None, None) => Vec2::ZERO,
+            };
+            Path::rounded_rect(point("x", "y"), size, radii)
+        }
+        "circle" => Path::circle(point("cx", "cy"), length("r").filter(|r| *r > 0.0)?),
+        "ellipse" => {
+            let radii = match (length("rx"), length("ry")) {
+                (Some(rx), Some(ry)) => Vec2::new(rx, ry),
+                (Some(radius), None) | (None, Some(radius)) => Vec2::splat(radius),
+                (None, None) => return None,
+            };
+            if radii.min_element() <= 0.0 {
+                return None;
+            }
+            Path::ellipse(point("cx", "cy"), radii)
+        }
+        "line" => Path::polyline([point("x1", "y1"), point("x2", "y2")]),
+        "polyline" | "polygon" => {
+            let numbers = parse_numbers(node.attribute("points").unwrap_or_default()).ok()?;
+            let points = numbers.chunks_exact(2).map(|pair| Vec2::new(pair[0], pair[1]));
+            if node.tag_name().name() == "polygon" {
+                Path::polygon(points)
+            } else {
+                Path::polyline(points)
+            }
+        }
+        _ => return None,
+    };
+    Some(shape.commands)
+}
+
+// Inherited presentation properties, plus the opacity and transform accumulated through
+// the ancestors
+#[derive(Debug, Clone)]
+struct Presentation {
+    // `color`, what currentColor refers to
+    color: Color,
+    fill: Option<CssColor>,
+    fill_opacity: f32,
+    fill_rule: FillRule,
+    stroke: Option<CssColor>,
+    stroke_opacity: f32,
+    // Width, joins, caps and dashes; the color comes from `stroke`
+    stroke_style: Stroke,
+    opacity: f32,
+    transform: Affine2,
+}
+
+impl Default for Presentation {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            fill: Some(CssColor::Color(Color::BLACK)),
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            stroke: None,
+            stroke_opacity: 1.0,
+            stroke_style: Stroke::default(),
+            opacity: 1.0,
+            transform: Affine2::IDENTITY,
+        }
+    }
+}
+
+impl Presentation {
+    // The properties `node` ends up with, None if it isn't displayed. The `style` attribute
+    // wins over presentation attributes; values that don't parse are ignored like in SVG.
+    fn child(&self, node: Node) -> Option<Self> {
+        let style = node.attribute("style").unwrap_or_default();
+        let declarations = node
+            .attributes()
+            .map(|attribute| (attribute.name(), attribute.value()))
+            .chain(style.split(';').filter_map(|declaration| declaration.split_once(':')))
+            .map(|(name, value)| (name.trim(), value.trim()));
+
+        let mut presentation = self.clone();
+        let mut opacity = 1.0;
+        let mut transform = Affine2::IDENTITY;
+        for (name, value) in declarations {
+            match name {
+                "display" if value == "none" => return None,
+                // Not inherited, but they compound through groups
+                "opacity" => opacity = parse_opacity(value).unwrap_or(opacity),
+                "transform" => match parse_transform(value) {
+                    Some(parsed) => transform = parsed,
+                    None => log::debug!("SVG: ignoring invalid transform '{}'", value),
+                },
+                _ => presentation.set(name, value),
+            }
+        }
+        presentation.opacity *= opacity;
+        presentation.transform *= transform;
+        Some(presentation)
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        if value == "inherit" {
+            return;
+        }
+        let length = parse_length(value);
+        match name {
+            "color" => {
+                if let Ok(color) = Color::from_css_color_with_current(value, self.color) {
+                    self.color = color;
+                }
+            }
+            "fill" => {
+                if let Some(paint) = parse_paint(value) {
+                    self.fill = paint;
+                }
+            }
+            "stroke" => {
+                if let Some(paint) = parse_paint(value) {
+                    self.stroke = paint;
+                }
+            }
+            "fill-opacity" => self.fill_opacity = parse_opacity(value).unwrap_or(self.fill_opacity),
+            "stroke-opacity" => self.stroke_opacity = parse_opacity(value).unwrap_or(self.stroke_opacity),
+            "fill-rule" => match value {
+                "nonzero" => self.fill_rule = FillRule::NonZero,
+                "evenodd" => self.fill_rule = FillRule::EvenOdd,
+                _ => {}
+            },
+            "stroke-width" => self.stroke_style.width = length.unwrap_or(self.stroke_style.width),
+            "stroke-linejoin" => match value {
+                "miter" | "miter-clip" | "arcs" => self.stroke_style.join = LineJoin::Miter,
+                "round" => self.stroke_style.join = LineJoin::Round,
+                "bevel" => self.stroke_style.join = LineJoin::Bevel,
+                _ => {}
+            },
+            "stroke-linecap" => match value {
+                "butt" => self.stroke_style.cap = LineCap::Butt,
+                "round" => self.stroke_style.cap = LineCap::Round,
+                "square" => self.stroke_style.cap = LineCap::Square,
+                _ => {}
+            },
+            "stroke-miterlimit" => {
+                self.stroke_style.miter_limit = value.parse().unwrap_or(self.stroke_style.miter_limit)
+            }
+            "stroke-dasharray" if value == "none" => self.stroke_style.dashes.clear(),
+            "stroke-dasharray" => {
+                if let Ok(dashes) = parse_numbers(&value.replace("px", "")) {
+                    self.stroke_style.dashes = dashes;
+                }
+            }
+            "stroke-dashoffset" => self.stroke_style.dash_offset = length.unwrap_or(self.stroke_style.dash_offset),
+            _ => {}
+        }
+    }
+
+    fn path(&self, commands: Vec<PathCommand>, class: Option<&str>) -> Path {
+        let paint = |paint: CssColor, opacity: f32| {
+            let color = paint.resolve(self.color);
+            Color { a: color.a * opacity, ..color }
+        };
+        let fill = self.fill.map(|fill| paint(fill, self.fill_opacity));
+        let stroke = self.stroke.filter(|_| self.stroke_style.width > 0.0).map(|stroke| Stroke {
+            color: paint(stroke, self.stroke_opacity),
+            ..self.stroke_style.clone()
+        });
+        let mut path = Path::from_commands(commands)
+            .with_fill(fill)
+            .with_fill_rule(self.fill_rule)
+            .with_stroke(stroke);
+        path.appearance.opacity = self.opacity;
+        if let Some(class) = class.map(str::trim).filter(|class| !class.is_empty()) {
+            path = path.with_css_class(class);
+        }
+        if self.transform != Affine2::IDENTITY {
+            path = path.transformed(&self.transform);
+        }
+        path
+    }
+}
+
+// A user-unit length; other units and percentages aren't supported
+fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim();
+    value.strip_suffix("px").unwrap_or(value).trim().parse().ok()
+}
+
+fn parse_opacity(value: &str) -> Option<f32> {
+    let opacity = match value.strip_suffix('%') {
+        Some(percentage) => percentage.trim().parse::<f32>().ok()? / 100.0,
+        None => value.parse().ok()?,
+    };
+    Some(opacity.clamp(0.0, 1.0))
+}
+
+// Some(None) for no paint, None for a value to ignore. Paint servers (url(...)) use their
+// fallback color if there is one and are none otherwise.
+fn parse_paint(value: &str) -> Option<Option<CssColor>> {
+    if value == "none" {
+        return Some(None);
+    }
+    if let Some(reference) = value.strip_prefix("url(") {
+        let fallback = reference.split_once(')').map(|(_, fallback)| fallback.trim()).unwrap_or_default();
+        if fallback.is_empty() || fallback == "none" {
+            log::debug!("SVG: paint server '{}' isn't supported, using none", value);
+            return Some(None);
+        }
+        return parse_paint(fallback);
+    }
+    match CssColor::parse(value) {
+        Ok(color) => Some(Some(color)),
+        Err(e) => {
+            log::debug!("SVG: ignoring paint '{}': {}", value, e);
+            None
+        }
+    }
+}
+
+// The `transform` attribute: a list of matrix/translate/scale/rotate/skewX/skewY
+fn parse_transform(value: &str) -> Option<Affine2> {
+    let mut transform = Affine2::IDENTITY;
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let (name, arguments) = rest.split_once('(')?;
+        let (arguments, remainder) = arguments.split_once(')')?;
+        let arguments = parse_numbers(arguments).ok()?;
+        transform *= match (name.trim(), arguments.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Affine2::from_cols_array(&[a, b, c, d, e, f]),
+            ("translate", &[x]) => Affine2::from_translation(Vec2::new(x, 0.0)),
+            ("translate", &[x, y]) => Affine2::from_translation(Vec2::new(x, y)),
+            ("scale", &[scale]) => Affine2::from_scale(Vec2::splat(scale)),
+            ("scale", &[x, y]) => Affine2::from_scale(Vec2::new(x, y)),
+            ("rotate", &[angle]) => Affine2::from_angle(angle.to_radians()),
+            ("rotate", &[angle, x, y]) => {
+                let center = Vec2::new(x, y);
+                Affine2::from_translation(center) * Affine2::from_angle(angle.to_radians()) * Affine2::from_translation(-center)
+            }
+            ("skewX", &[angle]) => Affine2::from_cols_array(&[1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0]),
+            ("skewY", &[angle]) => Affine2::from_cols_array(&[1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+            _ => return None,
+        };
+        rest = remainder.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Some(transform)
+}
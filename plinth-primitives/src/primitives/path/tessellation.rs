@@ -0,0 +1,248 @@
+use glam::Vec2;
+use lyon::algorithms::aabb::bounding_box;
+use lyon::algorithms::hit_test::hit_test_path;
+use lyon::math::point;
+use lyon::path::iterator::PathIterator;
+use lyon::path::PathEvent;
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator, StrokeVertex,
+    TessellationError, VertexBuffers,
+};
+
+use crate::primitives::{FillRule, LineCap, LineJoin, Path};
+use crate::types::{Color, PaintData};
+
+// Default flattening tolerance in clip space, about half a pixel on a 1000 pixel viewport
+pub const DEFAULT_TOLERANCE: f32 = 0.001;
+
+// How close to the outline counts when hit testing curves, in the path's local units
+const HIT_TOLERANCE: f32 = 0.0005;
+
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct MeshVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+    // Before the transform, where the fill's gradient is laid out
+    pub local: [f32; 2],
+    pub paint: PaintData,
+}
+
+pub type MeshBuffers = VertexBuffers<MeshVertex, u32>;
+
+// Appends the triangles of `path`'s fill and then its stroke to `output`, with the transform
+// and opacity baked into the vertices. `tolerance` is the maximum distance between curves
+// and their flattened segments after the transform. A gradient fill samples ramp row 0.
+pub fn tessellate_path(path: &Path, tolerance: f32, output: &mut MeshBuffers) -> Result<(), TessellationError> {
+    tessellate_path_with_ramp(path, tolerance, 0, output)
+}
+
+// tessellate_path with the fill's gradient sampling ramp `row` of the batch's GradientRamps
+pub fn tessellate_path_with_ramp(
+    path: &Path,
+    tolerance: f32,
+    row: u32,
+    output: &mut MeshBuffers,
+) -> Result<(), TessellationError> {
+    let outline = path.to_lyon();
+    let transform = path.transform;
+    // Tessellation happens before the transform, so scale the tolerance back to local units
+    let tolerance = tolerance / transform.scale.abs().max_element().max(f32::EPSILON);
+    let vertex = |position: lyon::math::Point, color: [f32; 4], paint: PaintData| MeshVertex {
+        position: transform.transform_point(Vec2::new(position.x, position.y)).into(),
+        color,
+        local: [position.x, position.y],
+        paint,
+    };
+
+    if let Some(fill) = path.fill {
+        let (color, paint) = match &path.paint {
+            Some(paint) => {
+                let bounds = bounding_box(outline.iter());
+                let data = paint.data(Vec2::new(bounds.min.x, bounds.min.y), Vec2::new(bounds.max.x, bounds.max.y));
+                (paint.base_color(), data.with_ramp_row(row))
+            }
+            None => (fill, PaintData::SOLID),
+        };
+        let color = rgba(path.appearance.apply_opacity(color));
+        let options = FillOptions::tolerance(tolerance).with_fill_rule(path.fill_rule.into());
+        FillTessellator::new().tessellate_path(
+            &outline,
+            &options,
+            &mut BuffersBuilder::new(output, |v: FillVertex| vertex(v.position(), color, paint)),
+        )?;
+    }
+
+    if let Some(stroke) = path.stroke.as_ref().filter(|stroke| stroke.width > 0.0) {
+        let color = rgba(path.appearance.apply_opacity(stroke.color));
+        let options = StrokeOptions::tolerance(tolerance)
+            .with_line_width(stroke.width)
+            .with_line_join(stroke.join.into())
+            .with_line_cap(stroke.cap.into())
+            .with_miter_limit(stroke.miter_limit.max(StrokeOptions::MINIMUM_MITER_LIMIT));
+        let dashed = dash_path(&outline, &stroke.dashes, stroke.dash_offset, tolerance);
+        StrokeTessellator::new().tessellate_path(
+            dashed.as_ref().unwrap_or(&outline),
+            &options,
+            &mut BuffersBuilder::new(output, |v: StrokeVertex| vertex(v.position(), color, PaintData::SOLID)),
+        )?;
+    }
+    Ok(())
+}
+
+// Splits `path` into the dashes of a stroke-dasharray pattern, starting `offset` into the
+// pattern. The pattern restarts on every subpath like in SVG. None when the pattern is
+// empty or has no length, meaning a solid stroke.
+pub fn dash_path(path: &lyon::path::Path, dashes: &[f32], offset: f32, tolerance: f32) -> Option<lyon::path::Path> {
+    let mut pattern: Vec<f32> = dashes.iter().map(|dash| dash.max(0.0)).collect();
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_within(..);
+    }
+    let period: f32 = pattern.iter().sum();
+    if pattern.is_empty() || period.is_nan() || period <= 0.0 {
+        return None;
+    }
+
+    let mut dasher = Dasher {
+        builder: lyon::path::Path::builder(),
+        pattern: &pattern,
+        index: 0,
+        remaining: 0.0,
+        open: false,
+    };
+    for event in path.iter().flattened(tolerance) {
+        match event {
+            PathEvent::Begin { .. } => dasher.restart(offset.rem_euclid(period)),
+            PathEvent::Line { from, to } => dasher.segment(vec2(from), vec2(to)),
+            PathEvent::End { last, first, close } => {
+                if close {
+                    dasher.segment(vec2(last), vec2(first));
+                }
+                dasher.end_dash();
+            }
+            _ => {}
+        }
+    }
+    Some(dasher.builder.build())
+}
+
+struct Dasher<'a> {
+    builder: lyon::path::path::Builder,
+    pattern: &'a [f32],
+    // Current pattern entry; even entries are dashes, odd ones gaps
+    index: usize,
+    remaining: f32,
+    open: bool,
+}
+
+impl Dasher<'_> {
+    fn restart(&mut self, mut offset: f32) {
+        self.index = 0;
+        while offset >= self.pattern[self.index] {
+            offset -= self.pattern[self.index];
+            self.index = (self.index + 1) % self.pattern.len();
+        }
+        self.remaining = self.pattern[self.index] - offset;
+    }
+
+    fn segment(&mut self, from: Vec2, to: Vec2) {
+        let length = from.distance(to);
+        let mut travelled = 0.0;
+        while travelled < length {
+            let step = self.remaining.min(length - travelled);
+            if self.index.is_multiple_of(2) {
+                if !self.open {
+                    let start = from.lerp(to, travelled / length);
+                    self.builder.begin(point(start.x, start.y));
+                    self.open = true;
+                }
+                let end = from.lerp(to, (travelled + step) / length);
+                self.builder.line_to(point(end.x, end.y));
+            }
+            travelled += step;
+            self.remaining -= step;
+            if self.remaining <= 0.0 {
+                self.end_dash();
+                self.index = (self.index + 1) % self.pattern.len();
+                self.remaining = self.pattern[self.index];
+            }
+        }
+    }
+
+    fn end_dash(&mut self) {
+        if self.open {
+            self.builder.end(false);
+            self.open = false;
+        }
+    }
+}
+
+// Whether `position` is inside the filled area of `path` under `fill_rule`
+pub fn contains(path: &lyon::path::Path, position: Vec2, fill_rule: FillRule) -> bool {
+    hit_test_path(&point(position.x, position.y), path.iter(), fill_rule.into(), HIT_TOLERANCE)
+}
+
+// Distance from `position` to the nearest point on the outline of `path`, closing segments
+// included. Infinite for an empty path.
+pub fn distance_to_outline(path: &lyon::path::Path, position: Vec2) -> f32 {
+    let mut distance = f32::INFINITY;
+    for event in path.iter().flattened(HIT_TOLERANCE) {
+        match event {
+            PathEvent::Begin { at } => distance = distance.min(position.distance(vec2(at))),
+            PathEvent::Line { from, to } => distance = distance.min(segment_distance(position, vec2(from), vec2(to))),
+            PathEvent::End { last, first, close: true } => {
+                distance = distance.min(segment_distance(position, vec2(last), vec2(first)))
+            }
+            _ => {}
+        }
+    }
+    distance
+}
+
+fn segment_distance(position: Vec2, from: Vec2, to: Vec2) -> f32 {
+    let direction = to - from;
+    let length_squared = direction.length_squared();
+    let t = if length_squared > 0.0 {
+        ((position - from).dot(direction) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    position.distance(from + direction * t)
+}
+
+fn vec2(point: lyon::math::Point) -> Vec2 {
+    Vec2::new(point.x, point.y)
+}
+
+fn rgba(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+impl From<FillRule> for lyon::tessellation::FillRule {
+    fn from(fill_rule: FillRule) -> Self {
+        match fill_rule {
+            FillRule::NonZero => Self::NonZero,
+            FillRule::EvenOdd => Self::EvenOdd,
+        }
+    }
+}
+
+impl From<LineJoin> for lyon::tessellation::LineJoin {
+    fn from(join: LineJoin) -> Self {
+        match join {
+            LineJoin::Miter => Self::Miter,
+            LineJoin::Round => Self::Round,
+            LineJoin::Bevel => Self::Bevel,
+        }
+    }
+}
+
+impl From<LineCap> for lyon::tessellation::LineCap {
+    fn from(cap: LineCap) -> Self {
+        match cap {
+            LineCap::Butt => Self::Butt,
+            LineCap::Round => Self::Round,
+            LineCap::Square => Self::Square,
+        }
+    }
+}
@@ -1,12 +1,35 @@
 use glam::Vec2;
-use crate::types::{Color, Transform};
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use wgpu::{BindGroupLayout, Device, RenderPipeline, ShaderModuleDescriptor};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::types::{
+    Appearance, Color, InteractionState, Paint, PaintData, Shadow, ShadowData, Style, StyleViewport, Transform,
+};
+use crate::primitives::{Interactive, StyleResolver, Styled};
+use crate::batch::{
+    clip_depth_stencil, render_clip_runs, BatchShader, ClipId, ClipRun, ClipScope, Drawn, GradientRamps,
+    InstanceBatch, InstanceSources, StyleCache,
+};
+use crate::effects::EffectLayer;
+use crate::shaders::ShaderPreprocessor;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Rectangle {
     pub position: Vec2,
     pub size: Vec2,
     pub color: Color,
+    // Fills the rectangle instead of `color` when set
+    pub paint: Option<Paint>,
     pub transform: Transform,
+    pub corner_radius: f32,
+    pub appearance: Appearance,
+    pub state: InteractionState,
     pub css_class: Option<String>,
 }
 
@@ -16,7 +39,11 @@ impl Rectangle {
             position,
             size,
             color: Color::default(),
+            paint: None,
             transform: Transform::default(),
+            corner_radius: 0.0,
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
             css_class: None,
         }
     }
@@ -26,6 +53,13 @@ impl Rectangle {
         self
     }
 
+    // Gradients span the rectangle before its transform
+    pub fn with_paint(mut self, paint: impl Into<Paint>) -> Self {
+        self.paint = Some(paint.into());
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
     pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
         self.css_class = Some(class.into());
         self
@@ -36,9 +70,93 @@ impl Rectangle {
         self
     }
 
+    pub fn with_corner_radius(mut self, corner_radius: f32) -> Self {
+        self.corner_radius = corner_radius;
+        self
+    }
+
+    // A box-shadow following the rounded corners, drawn outside the rectangle only
+    pub fn with_shadow(mut self, shadow: Shadow) -> Self {
+        self.appearance.shadow = Some(shadow);
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
     pub fn apply_css_override(&mut self, color: Color) {
         self.color = color;
     }
+
+    // Applies every property `style` sets; width/height resize around the current position.
+    // A background color replaces any paint, a background gradient becomes it.
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        match style.fill_paint() {
+            Some(Paint::Solid(color)) => {
+                self.color = color;
+                self.paint = None;
+            }
+            Some(paint) => self.paint = Some(paint),
+            None => {}
+        }
+        let size = viewport.clip(Vec2::new(style.width.unwrap_or(0.0), style.height.unwrap_or(0.0)));
+        if style.width.is_some() {
+            self.size.x = size.x.max(0.0);
+        }
+        if style.height.is_some() {
+            self.size.y = size.y.max(0.0);
+        }
+        if let Some(radius) = style.border_radius {
+            self.corner_radius = viewport.clip_length(radius).max(0.0);
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+}
+
+impl Styled for Rectangle {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Rectangle::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Rectangle {
+    fn hit_test(&self, point: Vec2) -> bool {
+        if !self.appearance.visible {
+            return false;
+        }
+        let local = self.transform.inverse_transform_point(point);
+        let half_size = self.size.abs() / 2.0;
+        let radius = self.corner_radius.min(half_size.min_element());
+        // Rounded box distance, <= 0 inside
+        let q = (local - (self.position + self.size / 2.0)).abs() - half_size + radius;
+        q.max(Vec2::ZERO).length() + q.max_element().min(0.0) - radius <= 0.0
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
 }
 
 impl Default for Rectangle {
@@ -46,3 +164,508 @@ impl Default for Rectangle {
         Self::new(Vec2::ZERO, Vec2::ONE)
     }
 }
+
+// Instance data for instanced rendering
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct RectangleInstance {
+    // Bottom-left corner and size before the transform, the size never negative
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [f32; 4],
+    pub transform_position: [f32; 2],
+    pub transform_scale: [f32; 2],
+    pub transform_rotation: f32,
+    pub corner_radius: f32,
+    pub paint: PaintData,
+    pub shadow: ShadowData,
+}
+
+impl From<&Rectangle> for RectangleInstance {
+    fn from(rectangle: &Rectangle) -> Self {
+        let min = rectangle.position.min(rectangle.position + rectangle.size);
+        let max = rectangle.position.max(rectangle.position + rectangle.size);
+        let base = rectangle.paint.as_ref().map_or(rectangle.color, Paint::base_color);
+        let color = rectangle.appearance.apply_opacity(base);
+        Self {
+            position: min.into(),
+            size: (max - min).into(),
+            color: [color.r, color.g, color.b, color.a],
+            transform_position: rectangle.transform.position.into(),
+            transform_scale: rectangle.transform.scale.into(),
+            transform_rotation: rectangle.transform.rotation,
+            corner_radius: rectangle.corner_radius.max(0.0),
+            paint: rectangle.paint.as_ref().map_or(PaintData::SOLID, |paint| paint.data(min, max)),
+            shadow: rectangle.appearance.shadow_data(),
+        }
+    }
+}
+
+pub const RECTANGLE_SHADER: ShaderSource = shader_source!("src/shaders/rectangle.wgsl");
+
+// Rounded rectangles drawn as instanced quads cut by a signed distance field, the same way
+// circles are
+pub struct RectangleBatch {
+    // Rectangles as added, in insertion order, before any class styles are applied
+    rectangles: Vec<Rectangle>,
+    // The clip each rectangle was added under
+    clips: Vec<ClipId>,
+    // The clip rectangles added from now on are drawn in
+    clip: ClipId,
+    // What gets drawn: visible rectangles sorted by z-index, insertion order within the same
+    // z-index. Also what the instance buffer currently holds once uploaded.
+    instances: Vec<RectangleInstance>,
+    instance_buffer: Option<wgpu::Buffer>,
+    // Instances the buffer has room for
+    buffer_capacity: usize,
+    render_pipeline: Option<RenderPipeline>,
+    // Draws the blurred frame under backdrop-filtered rectangles, built on first use
+    backdrop_pipeline: Option<RenderPipeline>,
+    shader: BatchShader,
+    gradients: GradientRamps,
+    style_cache: StyleCache<Rectangle>,
+    // Which rectangle each instance was built from
+    sources: InstanceSources,
+    // Runs of instances by the post-process effects they need
+    layers: Vec<EffectLayer>,
+    // Runs of instances by the clip they are drawn in
+    clip_runs: Vec<ClipRun>,
+    // Rectangles were added or removed since instances were last built
+    needs_rebuild: bool,
+    // Instances that changed since the last upload
+    dirty: Option<Range<usize>>,
+}
+
+impl RectangleBatch {
+    pub fn new() -> Self {
+        Self {
+            rectangles: Vec::new(),
+            clips: Vec::new(),
+            clip: ClipId::NONE,
+            instances: Vec::new(),
+            instance_buffer: None,
+            buffer_capacity: 0,
+            render_pipeline: None,
+            backdrop_pipeline: None,
+            shader: BatchShader::new("rectangle", RECTANGLE_SHADER),
+            gradients: GradientRamps::new(),
+            style_cache: StyleCache::new(),
+            sources: InstanceSources::new(),
+            layers: Vec::new(),
+            clip_runs: Vec::new(),
+            needs_rebuild: true,
+            dirty: None,
+        }
+    }
+
+    // Rectangles without classes that are hidden are dropped here; styled ones are kept
+    // since their classes may show them again
+    pub fn add_rectangle(&mut self, rectangle: Rectangle) {
+        if !rectangle.appearance.visible && rectangle.css_class.is_none() {
+            return;
+        }
+        self.rectangles.push(rectangle);
+        self.clips.push(self.clip);
+        self.needs_rebuild = true;
+    }
+
+    pub fn add_rectangles(&mut self, rectangles: impl IntoIterator<Item = Rectangle>) {
+        for rectangle in rectangles {
+            self.add_rectangle(rectangle);
+        }
+    }
+
+    // Draws rectangles added from now on in `clip`, see PrimitiveRenderer::push_clip_rect
+    pub fn set_clip(&mut self, clip: ClipId) {
+        self.clip = clip;
+    }
+
+    pub fn clip(&self) -> ClipId {
+        self.clip
+    }
+
+    pub fn clear(&mut self) {
+        self.rectangles.clear();
+        self.clips.clear();
+        self.style_cache.clear();
+        self.needs_rebuild = true;
+    }
+
+    // Time style transitions and animations are sampled at on the next `resolve_styles`
+    pub fn set_style_time(&mut self, time: Duration) {
+        self.style_cache.set_time(time);
+    }
+
+    // What CSS lengths in styles are converted against, restyling the batch when it changes
+    pub fn set_style_viewport(&mut self, viewport: StyleViewport) {
+        self.style_cache.set_viewport(viewport);
+    }
+
+    // Whether a style transition or animation is running, so the batch restyles every frame
+    pub fn is_animating(&self) -> bool {
+        self.style_cache.is_animating()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rectangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rectangles.is_empty()
+    }
+
+    // Rectangles as added, to move them or change their state in place. They are
+    // restyled on the next `resolve_styles`.
+    pub fn rectangles_mut(&mut self) -> &mut [Rectangle] {
+        self.style_cache.invalidate();
+        self.needs_rebuild = true;
+        &mut self.rectangles
+    }
+
+    // The rectangles as last drawn, in draw order, for the renderer's pointer handling, which
+    // only flags a state change so just the rectangles whose state changed are restyled
+    pub(crate) fn interaction_mut(&mut self) -> (Vec<Drawn<'_, Rectangle>>, &mut bool) {
+        self.style_cache.drawn_mut(&mut self.rectangles)
+    }
+
+    pub fn instances(&self) -> &[RectangleInstance] {
+        &self.instances
+    }
+
+    // The gradients of the rectangles as of the last `resolve_styles`
+    pub fn gradients(&self) -> &GradientRamps {
+        &self.gradients
+    }
+
+    // The instances split by the blur filters they need, as of the last `resolve_styles`
+    pub fn layers(&self) -> &[EffectLayer] {
+        &self.layers
+    }
+
+    // The instances split by the clips they are drawn in, as of the last `resolve_styles`
+    pub fn clip_runs(&self) -> &[ClipRun] {
+        &self.clip_runs
+    }
+
+    // Rebuilds the instances, applying the styles of each rectangle's classes and state from
+    // `resolver`. Only rectangles that were added, changed state or are animating are
+    // restyled, all of them once the resolver's generation changes. While the draw order
+    // stays the same only their instances are marked for upload, otherwise those that come
+    // out different.
+    pub fn resolve_styles(&mut self, resolver: Option<&dyn StyleResolver>) {
+        if !self.style_cache.sync(resolver) && !self.needs_rebuild {
+            return;
+        }
+        let rebuild = std::mem::take(&mut self.needs_rebuild);
+        let restyled = self.style_cache.restyle(resolver, &self.rectangles);
+        if restyled.is_empty() && !rebuild {
+            return;
+        }
+
+        self.gradients.clear();
+        let mut drawn: Vec<(i32, usize, RectangleInstance, Appearance, ClipId)> =
+            Vec::with_capacity(self.rectangles.len());
+        for (index, (rectangle, clip)) in self.rectangles.iter().zip(&self.clips).enumerate() {
+            let rectangle = self.style_cache.styled(index, rectangle);
+            if rectangle.appearance.visible {
+                let mut instance = RectangleInstance::from(rectangle);
+                if let Some(stops) = rectangle.paint.as_ref().and_then(Paint::stops) {
+                    instance.paint = instance.paint.with_ramp_row(self.gradients.row(stops));
+                }
+                drawn.push((rectangle.appearance.z_index, index, instance, rectangle.appearance, *clip));
+            }
+        }
+        // Stable, so insertion order is kept within a z-index
+        drawn.sort_by_key(|(z_index, _, _, _, _)| *z_index);
+        self.layers.clear();
+        self.clip_runs.clear();
+        for (index, (_, _, _, appearance, clip)) in drawn.iter().enumerate() {
+            EffectLayer::push(&mut self.layers, index, appearance, *clip);
+            ClipRun::push(&mut self.clip_runs, index..index + 1, *clip);
+        }
+        self.style_cache.set_drawn(drawn.iter().map(|(_, rectangle, ..)| *rectangle));
+        let runs = drawn.iter().enumerate().map(|(index, (_, rectangle, ..))| (*rectangle, index..index + 1)).collect();
+        let instances: Vec<RectangleInstance> = drawn.into_iter().map(|(_, _, instance, _, _)| instance).collect();
+
+        // Gradient rows are handed out in draw order, so other instances' rows may have moved
+        if rebuild || self.gradients.rows_changed() {
+            self.sources.forget();
+        }
+        let changed = self.sources.changed(runs, &restyled, &self.instances, &instances);
+        if let Some(changed) = changed {
+            self.dirty = Some(match self.dirty.take() {
+                Some(dirty) => dirty.start.min(changed.start)..dirty.end.max(changed.end),
+                None => changed,
+            });
+        }
+        self.instances = instances;
+    }
+
+    pub fn create_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat) -> RenderPipeline {
+        self.shader.set_targets(PipelineTargets { format: surface_format, sample_count: 1 });
+        self.rebuild_pipelines(device);
+        self.render_pipeline.clone().expect("Failed to build rectangle pipeline")
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        self.shader.preprocessor()
+    }
+
+    // Rebuilds the pipelines for color targets of `targets`, e.g. multisampled
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.shader.set_targets(targets) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines with the define toggled, see BatchShader
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        if self.shader.set_define(name, enabled) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines if rectangle.wgsl or one of its includes changed on disk
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        self.shader.reload(shaders) && self.rebuild_pipelines(device)
+    }
+
+    fn rebuild_pipelines(&mut self, device: &Device) -> bool {
+        let layout = self.gradients.bind_group_layout(device);
+        let Some(render_pipeline) = self.shader.build(device, &[], |source, targets, blend| {
+            Self::build_pipeline(device, targets, blend, source, &[layout])
+        }) else {
+            return false;
+        };
+        self.render_pipeline = Some(render_pipeline);
+        self.backdrop_pipeline = None;
+        true
+    }
+
+    fn build_pipeline(
+        device: &Device,
+        targets: PipelineTargets,
+        blend: wgpu::BlendState,
+        source: &str,
+        layouts: &[&BindGroupLayout],
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(RECTANGLE_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Rectangle Pipeline Layout"),
+            bind_group_layouts: layouts,
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Rectangle Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<RectangleInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2, // position
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x2, // size
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x4, // color
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                            shader_location: 3,
+                            format: wgpu::VertexFormat::Float32x2, // transform_position
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                            shader_location: 4,
+                            format: wgpu::VertexFormat::Float32x2, // transform_scale
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                            shader_location: 5,
+                            format: wgpu::VertexFormat::Float32, // transform_rotation
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
+                            shader_location: 6,
+                            format: wgpu::VertexFormat::Float32, // corner_radius
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 14]>() as wgpu::BufferAddress,
+                            shader_location: 7,
+                            format: wgpu::VertexFormat::Float32x4, // paint.params
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 18]>() as wgpu::BufferAddress,
+                            shader_location: 8,
+                            format: wgpu::VertexFormat::Float32x4, // paint.geometry
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 22]>() as wgpu::BufferAddress,
+                            shader_location: 9,
+                            format: wgpu::VertexFormat::Float32x4, // shadow.params
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 26]>() as wgpu::BufferAddress,
+                            shader_location: 10,
+                            format: wgpu::VertexFormat::Float32x4, // shadow.color
+                        },
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: targets.format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // Negative scales mirror the quad
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(clip_depth_stencil()),
+            multisample: targets.multisample(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // Uploads instances and gradients that changed since the last upload, growing the buffer
+    // when needed. Rectangles are drawn unstyled unless `resolve_styles` ran with a resolver
+    // first.
+    pub fn update_buffer(&mut self, device: &Device, queue: &wgpu::Queue) {
+        if self.needs_rebuild {
+            self.resolve_styles(None);
+        }
+        self.gradients.upload(device, queue);
+        let Some(mut dirty) = self.dirty.take() else {
+            return;
+        };
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instance_buffer.is_none() || self.instances.len() > self.buffer_capacity {
+            // Room to grow without reallocating every time a rectangle is added
+            self.buffer_capacity = self.instances.len().next_power_of_two();
+            let buffer_size = (self.buffer_capacity * std::mem::size_of::<RectangleInstance>()) as wgpu::BufferAddress;
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Rectangle Instance Buffer"),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            dirty = 0..self.instances.len();
+        }
+
+        if let Some(ref buffer) = self.instance_buffer {
+            let dirty = dirty.start.min(self.instances.len())..dirty.end.min(self.instances.len());
+            let offset = (dirty.start * std::mem::size_of::<RectangleInstance>()) as wgpu::BufferAddress;
+            queue.write_buffer(buffer, offset, bytemuck::cast_slice(&self.instances[dirty]));
+        }
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render_range(render_pass, 0..self.instances.len());
+    }
+
+    // Draws the instances run by run, each in the clip its rectangle was added under
+    pub fn render_clipped(&self, render_pass: &mut wgpu::RenderPass, scope: &mut dyn ClipScope) {
+        render_clip_runs(render_pass, &self.clip_runs, scope, |render_pass, instances| {
+            self.render_range(render_pass, instances)
+        });
+    }
+
+    // Draws some of the instances, e.g. one of `layers`
+    pub fn render_range(&self, render_pass: &mut wgpu::RenderPass, instances: Range<usize>) {
+        let instances = instances.start.min(self.instances.len())..instances.end.min(self.instances.len());
+        if let (Some(pipeline), Some(buffer), Some(gradients)) =
+            (&self.render_pipeline, &self.instance_buffer, self.gradients.bind_group())
+        {
+            if !instances.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, gradients, &[]);
+                let size = (self.instances.len() * std::mem::size_of::<RectangleInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                render_pass.draw(0..6, instances.start as u32..instances.end as u32);
+            }
+        }
+    }
+
+    // Draws `backdrop`, the blurred frame behind the instances, cut to their shapes. It is
+    // bound at group 1 with `layout`, a texture and sampler (see GaussianBlur::texture_layout).
+    // The instances themselves are drawn over it with `render_range` afterwards.
+    pub fn render_backdrop(
+        &mut self,
+        device: &Device,
+        render_pass: &mut wgpu::RenderPass,
+        instances: Range<usize>,
+        layout: &BindGroupLayout,
+        backdrop: &wgpu::BindGroup,
+    ) {
+        if self.backdrop_pipeline.is_none() {
+            let layouts = [self.gradients.bind_group_layout(device), layout];
+            self.backdrop_pipeline = self.shader.build(device, &["BACKDROP"], |source, targets, blend| {
+                Self::build_pipeline(device, targets, blend, source, &layouts)
+            });
+        }
+        let instances = instances.start.min(self.instances.len())..instances.end.min(self.instances.len());
+        if let (Some(pipeline), Some(buffer), Some(gradients)) =
+            (&self.backdrop_pipeline, &self.instance_buffer, self.gradients.bind_group())
+        {
+            if !instances.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, gradients, &[]);
+                render_pass.set_bind_group(1, backdrop, &[]);
+                let size = (self.instances.len() * std::mem::size_of::<RectangleInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                render_pass.draw(0..6, instances.start as u32..instances.end as u32);
+            }
+        }
+    }
+}
+
+impl Default for RectangleBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstanceBatch for RectangleBatch {
+    fn len(&self) -> usize {
+        RectangleBatch::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        RectangleBatch::is_empty(self)
+    }
+
+    fn clear(&mut self) {
+        RectangleBatch::clear(self);
+    }
+}
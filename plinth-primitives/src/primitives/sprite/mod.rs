@@ -0,0 +1,215 @@
+pub mod sprite_batch;
+
+pub use sprite_batch::*;
+
+use glam::Vec2;
+use plinth_core::textures::TextureRegion;
+
+use crate::primitives::{Interactive, Styled};
+use crate::types::{Appearance, Color, InteractionState, Style, StyleViewport, Transform};
+
+// The part of a texture a sprite shows, as fractions of its width and height with y down
+// from its top-left corner. A `min` past `max` mirrors the sprite along that axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UvRect {
+    pub const FULL: UvRect = UvRect { min: Vec2::ZERO, max: Vec2::ONE };
+
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    // A rect given in texels of a `texture_size` texture
+    pub fn from_pixels(position: Vec2, size: Vec2, texture_size: Vec2) -> Self {
+        Self::new(position / texture_size, (position + size) / texture_size)
+    }
+
+    // Cell `index` of a sprite sheet laid out in a grid, counting across rows from the top-left
+    pub fn frame(index: u32, columns: u32, rows: u32) -> Self {
+        let cells = Vec2::new(columns.max(1) as f32, rows.max(1) as f32);
+        let cell = Vec2::new((index % columns.max(1)) as f32, (index / columns.max(1)) as f32);
+        Self::new(cell / cells, (cell + 1.0) / cells)
+    }
+
+    pub fn flip_x(self) -> Self {
+        Self::new(Vec2::new(self.max.x, self.min.y), Vec2::new(self.min.x, self.max.y))
+    }
+
+    pub fn flip_y(self) -> Self {
+        Self::new(Vec2::new(self.min.x, self.max.y), Vec2::new(self.max.x, self.min.y))
+    }
+}
+
+impl Default for UvRect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+// An image drawn from a texture loaded into the TextureManager on Graphics, found by name.
+// Like a rectangle, `position` is its bottom-left corner and `size` extends up and right;
+// the top of `uv` is drawn at the top. Texels are multiplied by `tint`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Sprite {
+    pub texture: String,
+    pub position: Vec2,
+    pub size: Vec2,
+    pub uv: UvRect,
+    pub tint: Color,
+    pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
+    pub css_class: Option<String>,
+}
+
+impl Sprite {
+    pub fn new(texture: impl Into<String>, position: Vec2, size: Vec2) -> Self {
+        Self {
+            texture: texture.into(),
+            position,
+            size,
+            uv: UvRect::FULL,
+            tint: Color::WHITE,
+            transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
+            css_class: None,
+        }
+    }
+
+    pub fn with_texture(mut self, texture: impl Into<String>) -> Self {
+        self.texture = texture.into();
+        self
+    }
+
+    pub fn with_uv(mut self, uv: UvRect) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
+    pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
+        self.css_class = Some(class.into());
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn apply_css_override(&mut self, color: Color) {
+        self.tint = color;
+    }
+
+    // Applies every property `style` sets: color tints the texture, width/height resize
+    // around the current position
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        if let Some(color) = style.color {
+            self.apply_css_override(color);
+        }
+        let size = viewport.clip(Vec2::new(style.width.unwrap_or(0.0), style.height.unwrap_or(0.0)));
+        if style.width.is_some() {
+            self.size.x = size.x.max(0.0);
+        }
+        if style.height.is_some() {
+            self.size.y = size.y.max(0.0);
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+
+    // The sprite's quad in clip space, sampling `uv` of the texture at `region`
+    pub fn instance(&self, region: TextureRegion) -> SpriteInstance {
+        let origin = self.transform.transform_point(self.position + Vec2::new(0.0, self.size.y));
+        let top_right = self.transform.transform_point(self.position + self.size);
+        let bottom_left = self.transform.transform_point(self.position);
+        let position = Vec2::new(region.position[0] as f32, region.position[1] as f32);
+        let size = Vec2::new(region.size[0] as f32, region.size[1] as f32);
+        let color = self.appearance.apply_opacity(self.tint);
+        SpriteInstance {
+            origin: origin.into(),
+            axis_x: (top_right - origin).into(),
+            axis_y: (bottom_left - origin).into(),
+            uv_min: (position + self.uv.min * size).into(),
+            uv_max: (position + self.uv.max * size).into(),
+            color: [color.r, color.g, color.b, color.a],
+        }
+    }
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self::new("", Vec2::ZERO, Vec2::ONE)
+    }
+}
+
+impl Styled for Sprite {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Sprite::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Sprite {
+    // Anywhere on the quad, transparent texels included
+    fn hit_test(&self, point: Vec2) -> bool {
+        if !self.appearance.visible {
+            return false;
+        }
+        let local = self.transform.inverse_transform_point(point);
+        let corner = self.position + self.size;
+        local.cmpge(self.position.min(corner)).all() && local.cmple(self.position.max(corner)).all()
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
+}
+
+// One sprite quad in clip space: `origin` is its top-left corner and the axes run along its
+// width and down its height. UVs are in texels of the sprite's texture page.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct SpriteInstance {
+    pub origin: [f32; 2],
+    pub axis_x: [f32; 2],
+    pub axis_y: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub color: [f32; 4],
+}
@@ -0,0 +1,533 @@
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use plinth_core::textures::{TextureFilter, TextureManager};
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPipeline, ShaderModuleDescriptor};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::primitives::{Sprite, SpriteInstance, StyleResolver};
+use crate::batch::{
+    clip_depth_stencil, render_clip_runs, BatchShader, ClipId, ClipRun, ClipScope, Drawn, InstanceBatch,
+    InstanceSources, StyleCache,
+};
+use crate::shaders::ShaderPreprocessor;
+use crate::types::StyleViewport;
+
+pub const SPRITE_SHADER: ShaderSource = shader_source!("src/shaders/sprite.wgsl");
+
+// A run of instances drawn from the same texture page in the same clip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteGroup {
+    pub page: usize,
+    pub instances: Range<u32>,
+    pub clip: ClipId,
+}
+
+pub struct SpriteBatch {
+    // Sprites as added, in insertion order, before any class styles are applied
+    sprites: Vec<Sprite>,
+    // The clip each sprite was added under
+    clips: Vec<ClipId>,
+    // The clip sprites added from now on are drawn in
+    clip: ClipId,
+    // One instance per visible sprite whose texture is loaded, sorted by z-index and then
+    // by texture page so each page is bound once per z-index. Insertion order is kept
+    // within the same page and z-index.
+    instances: Vec<SpriteInstance>,
+    groups: Vec<SpriteGroup>,
+    // Runs of instances by the clip they are drawn in
+    clip_runs: Vec<ClipRun>,
+    instance_buffer: Option<wgpu::Buffer>,
+    // Instances the buffer has room for
+    buffer_capacity: usize,
+    render_pipeline: Option<RenderPipeline>,
+    bind_group_layout: Option<BindGroupLayout>,
+    nearest_sampler: Option<wgpu::Sampler>,
+    linear_sampler: Option<wgpu::Sampler>,
+    // Per texture page, with the page generation they were created for
+    bind_groups: Vec<Option<(u64, BindGroup)>>,
+    shader: BatchShader,
+    style_cache: StyleCache<Sprite>,
+    // Which sprite each instance was built from
+    sources: InstanceSources,
+    // Sprites were added or removed since instances were last built
+    needs_rebuild: bool,
+    // Textures loaded when instances were last built; sprites may be waiting on new ones
+    texture_count: usize,
+    // Instances that changed since the last upload
+    dirty: Option<Range<usize>>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        Self {
+            sprites: Vec::new(),
+            clips: Vec::new(),
+            clip: ClipId::NONE,
+            instances: Vec::new(),
+            groups: Vec::new(),
+            clip_runs: Vec::new(),
+            instance_buffer: None,
+            buffer_capacity: 0,
+            render_pipeline: None,
+            bind_group_layout: None,
+            nearest_sampler: None,
+            linear_sampler: None,
+            bind_groups: Vec::new(),
+            shader: BatchShader::new("sprite", SPRITE_SHADER),
+            style_cache: StyleCache::new(),
+            sources: InstanceSources::new(),
+            needs_rebuild: true,
+            texture_count: 0,
+            dirty: None,
+        }
+    }
+
+    // Sprites without classes that are hidden are dropped here; styled ones are kept since
+    // their classes may show them again
+    pub fn add_sprite(&mut self, sprite: Sprite) {
+        if !sprite.appearance.visible && sprite.css_class.is_none() {
+            return;
+        }
+        self.sprites.push(sprite);
+        self.clips.push(self.clip);
+        self.needs_rebuild = true;
+    }
+
+    pub fn add_sprites(&mut self, sprites: impl IntoIterator<Item = Sprite>) {
+        for sprite in sprites {
+            self.add_sprite(sprite);
+        }
+    }
+
+    // Draws sprites added from now on in `clip`, see PrimitiveRenderer::push_clip_rect
+    pub fn set_clip(&mut self, clip: ClipId) {
+        self.clip = clip;
+    }
+
+    pub fn clip(&self) -> ClipId {
+        self.clip
+    }
+
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+        self.clips.clear();
+        self.style_cache.clear();
+        self.needs_rebuild = true;
+    }
+
+    // Time style transitions and animations are sampled at on the next `resolve_styles`
+    pub fn set_style_time(&mut self, time: Duration) {
+        self.style_cache.set_time(time);
+    }
+
+    // What CSS lengths in styles are converted against, restyling the batch when it changes
+    pub fn set_style_viewport(&mut self, viewport: StyleViewport) {
+        self.style_cache.set_viewport(viewport);
+    }
+
+    // Whether a style transition or animation is running, so the batch restyles every frame
+    pub fn is_animating(&self) -> bool {
+        self.style_cache.is_animating()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    // Sprites as added, to move them or change their state in place. They are
+    // restyled on the next `resolve_styles`.
+    pub fn sprites_mut(&mut self) -> &mut [Sprite] {
+        self.style_cache.invalidate();
+        self.needs_rebuild = true;
+        &mut self.sprites
+    }
+
+    // The sprites as last drawn, in draw order, for the renderer's pointer handling, which
+    // only flags a state change so just the sprites whose state changed are restyled
+    pub(crate) fn interaction_mut(&mut self) -> (Vec<Drawn<'_, Sprite>>, &mut bool) {
+        self.style_cache.drawn_mut(&mut self.sprites)
+    }
+
+    pub fn instances(&self) -> &[SpriteInstance] {
+        &self.instances
+    }
+
+    // Draw calls, one per run of instances sharing a texture page and clip
+    pub fn groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+
+    // The instances split by the clips they are drawn in, as of the last `resolve_styles`
+    pub fn clip_runs(&self) -> &[ClipRun] {
+        &self.clip_runs
+    }
+
+    // Rebuilds the instances from `textures`, applying the styles of each sprite's classes
+    // and state from `resolver`. Sprites whose texture isn't loaded are skipped until it is.
+    // Only sprites that were added, changed state or are animating are restyled, all of them
+    // once the resolver's generation changes. While the draw order and textures stay the same
+    // only their instances are marked for upload, otherwise those that come out different.
+    pub fn resolve_styles(&mut self, resolver: Option<&dyn StyleResolver>, textures: &TextureManager) {
+        let textures_changed = textures.len() != self.texture_count;
+        if !self.style_cache.sync(resolver) && !self.needs_rebuild && !textures_changed {
+            return;
+        }
+        let rebuild = std::mem::take(&mut self.needs_rebuild) || textures_changed;
+        self.texture_count = textures.len();
+        let restyled = self.style_cache.restyle(resolver, &self.sprites);
+        if restyled.is_empty() && !rebuild {
+            return;
+        }
+
+        let mut drawn: Vec<(i32, usize, usize, SpriteInstance, ClipId)> = Vec::with_capacity(self.sprites.len());
+        for (index, (sprite, clip)) in self.sprites.iter().zip(&self.clips).enumerate() {
+            let sprite = self.style_cache.styled(index, sprite);
+            if !sprite.appearance.visible {
+                continue;
+            }
+            if let Some(region) = textures.find(&sprite.texture).and_then(|id| textures.region(id)) {
+                drawn.push((sprite.appearance.z_index, region.page, index, sprite.instance(region), *clip));
+            }
+        }
+        // Stable, so insertion order is kept within a z-index and page
+        drawn.sort_by_key(|(z_index, page, _, _, _)| (*z_index, *page));
+
+        self.groups.clear();
+        self.clip_runs.clear();
+        for (index, (_, page, _, _, clip)) in drawn.iter().enumerate() {
+            ClipRun::push(&mut self.clip_runs, index..index + 1, *clip);
+            let index = index as u32;
+            match self.groups.last_mut() {
+                Some(group) if group.page == *page && group.clip == *clip => group.instances.end = index + 1,
+                _ => self.groups.push(SpriteGroup {
+                    page: *page,
+                    instances: index..index + 1,
+                    clip: *clip,
+                }),
+            }
+        }
+        self.style_cache.set_drawn(drawn.iter().map(|(_, _, sprite, ..)| *sprite));
+        let runs = drawn.iter().enumerate().map(|(index, (_, _, sprite, ..))| (*sprite, index..index + 1)).collect();
+        let instances: Vec<SpriteInstance> = drawn.into_iter().map(|(_, _, _, instance, _)| instance).collect();
+
+        // A texture loaded again under a sprite's name moves it to another region
+        if rebuild {
+            self.sources.forget();
+        }
+        let changed = self.sources.changed(runs, &restyled, &self.instances, &instances);
+        if let Some(changed) = changed {
+            self.dirty = Some(match self.dirty.take() {
+                Some(dirty) => dirty.start.min(changed.start)..dirty.end.max(changed.end),
+                None => changed,
+            });
+        }
+        self.instances = instances;
+    }
+
+    pub fn create_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat) -> RenderPipeline {
+        if self.bind_group_layout.is_none() {
+            self.create_texture_layout(device);
+        }
+        self.shader.set_targets(PipelineTargets { format: surface_format, sample_count: 1 });
+        self.rebuild_pipelines(device);
+        self.render_pipeline.clone().expect("Failed to build sprite pipeline")
+    }
+
+    fn create_texture_layout(&mut self, device: &Device) {
+        self.bind_group_layout = Some(device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite Texture Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        }));
+        let sampler = |filter: TextureFilter| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Sprite Sampler"),
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                mag_filter: filter.filter_mode(),
+                min_filter: filter.filter_mode(),
+                ..Default::default()
+            })
+        };
+        self.nearest_sampler = Some(sampler(TextureFilter::Nearest));
+        self.linear_sampler = Some(sampler(TextureFilter::Linear));
+    }
+
+    // Bind groups for pages that are new or whose texture was recreated
+    fn update_bind_groups(&mut self, device: &Device, textures: &TextureManager) {
+        let Some(layout) = &self.bind_group_layout else {
+            return;
+        };
+        self.bind_groups.resize_with(textures.page_count(), || None);
+        self.bind_groups.truncate(textures.page_count());
+        for (page, bind_group) in self.bind_groups.iter_mut().enumerate() {
+            let generation = textures.page_generation(page);
+            if bind_group.as_ref().is_some_and(|(created, _)| *created == generation) {
+                continue;
+            }
+            let sampler = match textures.page_filter(page) {
+                Some(TextureFilter::Nearest) => &self.nearest_sampler,
+                _ => &self.linear_sampler,
+            };
+            let (Some(view), Some(sampler)) = (textures.page_view(page), sampler) else {
+                *bind_group = None;
+                continue;
+            };
+            *bind_group = Some((
+                generation,
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Sprite Bind Group"),
+                    layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(sampler),
+                        },
+                    ],
+                }),
+            ));
+        }
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        self.shader.preprocessor()
+    }
+
+    // Rebuilds the pipelines for color targets of `targets`, e.g. multisampled
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.shader.set_targets(targets) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines with the define toggled, see BatchShader
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        if self.shader.set_define(name, enabled) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines if sprite.wgsl or one of its includes changed on disk
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        self.shader.reload(shaders) && self.rebuild_pipelines(device)
+    }
+
+    fn rebuild_pipelines(&mut self, device: &Device) -> bool {
+        let Some(layout) = &self.bind_group_layout else {
+            return false;
+        };
+        let Some(render_pipeline) = self.shader.build(device, &[], |source, targets, blend| {
+            Self::build_pipeline(device, targets, blend, source, layout)
+        }) else {
+            return false;
+        };
+        self.render_pipeline = Some(render_pipeline);
+        true
+    }
+
+    fn build_pipeline(
+        device: &Device,
+        targets: PipelineTargets,
+        blend: wgpu::BlendState,
+        source: &str,
+        layout: &BindGroupLayout,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(SPRITE_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[layout],
+            push_constant_ranges: &[],
+        });
+
+        let offset = |floats: usize| (floats * std::mem::size_of::<f32>()) as wgpu::BufferAddress;
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2, // origin
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(2),
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x2, // axis_x
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(4),
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x2, // axis_y
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(6),
+                            shader_location: 3,
+                            format: wgpu::VertexFormat::Float32x2, // uv_min
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(8),
+                            shader_location: 4,
+                            format: wgpu::VertexFormat::Float32x2, // uv_max
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(10),
+                            shader_location: 5,
+                            format: wgpu::VertexFormat::Float32x4, // color
+                        },
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: targets.format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // Mirroring transforms flip the quad's winding
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(clip_depth_stencil()),
+            multisample: targets.multisample(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // Uploads the instances that changed since the last upload, growing the buffer when
+    // needed, and binds the pages of `textures`, which should be uploaded first. Sprites are
+    // drawn unstyled unless `resolve_styles` ran with a resolver first.
+    pub fn update_buffer(&mut self, device: &Device, queue: &wgpu::Queue, textures: &TextureManager) {
+        if self.needs_rebuild || textures.len() != self.texture_count {
+            self.resolve_styles(None, textures);
+        }
+        self.update_bind_groups(device, textures);
+
+        let Some(mut dirty) = self.dirty.take() else {
+            return;
+        };
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instance_buffer.is_none() || self.instances.len() > self.buffer_capacity {
+            // Room to grow without reallocating every time a sprite is added
+            self.buffer_capacity = self.instances.len().next_power_of_two();
+            let buffer_size = (self.buffer_capacity * std::mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress;
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Sprite Instance Buffer"),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            dirty = 0..self.instances.len();
+        }
+
+        if let Some(ref buffer) = self.instance_buffer {
+            let dirty = dirty.start.min(self.instances.len())..dirty.end.min(self.instances.len());
+            let offset = (dirty.start * std::mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress;
+            queue.write_buffer(buffer, offset, bytemuck::cast_slice(&self.instances[dirty]));
+        }
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render_range(render_pass, 0..self.instances.len());
+    }
+
+    // Draws the instances run by run, each in the clip its sprite was added under
+    pub fn render_clipped(&self, render_pass: &mut wgpu::RenderPass, scope: &mut dyn ClipScope) {
+        render_clip_runs(render_pass, &self.clip_runs, scope, |render_pass, instances| {
+            self.render_range(render_pass, instances)
+        });
+    }
+
+    // Draws the groups within some of the instances, e.g. one of `clip_runs`
+    pub fn render_range(&self, render_pass: &mut wgpu::RenderPass, instances: Range<usize>) {
+        if let (Some(pipeline), Some(buffer)) = (&self.render_pipeline, &self.instance_buffer) {
+            let count = self.instances.len();
+            let instances = instances.start.min(count) as u32..instances.end.min(count) as u32;
+            if !instances.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                let size = (count * std::mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                for group in &self.groups {
+                    let drawn = group.instances.start.max(instances.start)..group.instances.end.min(instances.end);
+                    if drawn.is_empty() {
+                        continue;
+                    }
+                    if let Some(Some((_, bind_group))) = self.bind_groups.get(group.page) {
+                        render_pass.set_bind_group(0, bind_group, &[]);
+                        render_pass.draw(0..6, drawn); // A quad per sprite
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for SpriteBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstanceBatch for SpriteBatch {
+    fn len(&self) -> usize {
+        SpriteBatch::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        SpriteBatch::is_empty(self)
+    }
+
+    fn clear(&mut self) {
+        SpriteBatch::clear(self);
+    }
+}
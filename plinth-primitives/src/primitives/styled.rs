@@ -0,0 +1,57 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use crate::types::{InteractionState, Style, StyleViewport};
+
+// A primitive that can be themed from CSS classes
+pub trait Styled {
+    // Space-separated class list, like an HTML `class` attribute
+    fn css_class(&self) -> Option<&str>;
+
+    // Pseudo-classes the primitive is currently in
+    fn state(&self) -> InteractionState {
+        InteractionState::NONE
+    }
+
+    // Applies every property `style` sets, its CSS pixel lengths measured against `viewport`
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport);
+
+    fn css_classes(&self) -> impl Iterator<Item = &str> {
+        self.css_class().unwrap_or_default().split_whitespace()
+    }
+
+    fn has_css_class(&self, class: &str) -> bool {
+        self.css_classes().any(|c| c == class)
+    }
+}
+
+// Source of computed styles for class lists, e.g. plinth-styles' ClassMapper. Renderers
+// resolve primitives' classes through it when uploading their batches.
+pub trait StyleResolver {
+    // Changes whenever any style it resolves may have changed, so cached results can be reused
+    // while it stays the same
+    fn generation(&self) -> u64;
+
+    fn resolve_style(&self, classes: &[&str], state: InteractionState) -> Style;
+
+    // Per-primitive state for transitions and keyframe animations between resolved styles.
+    // Without one, styles apply as soon as they change.
+    fn animation(&self) -> Option<Box<dyn StyleAnimation>> {
+        None
+    }
+
+    // The style a primitive shows at `time` on its way to `style`, the one just resolved for it
+    fn animate(&self, animation: &mut dyn StyleAnimation, style: &Style, time: Duration) -> Style {
+        let _ = (animation, time);
+        style.clone()
+    }
+}
+
+// State a StyleResolver keeps for one primitive, e.g. plinth-styles' StyleAnimator
+pub trait StyleAnimation: Any + Debug {
+    // Whether the style still changes over time, so the primitive needs restyling every frame
+    fn is_animating(&self) -> bool;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
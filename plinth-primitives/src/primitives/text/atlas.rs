@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ab_glyph::{point, Font as _, GlyphId};
+use etagere::{size2, BucketedAtlasAllocator};
+use glam::{UVec2, Vec2};
+
+use crate::primitives::{px_scale, FontId, FontLibrary};
+
+// Glyphs are rasterized at sizes rounded to a quarter pixel, so nearby sizes share them
+const SIZE_STEPS_PER_PIXEL: f32 = 4.0;
+// Empty texels around each glyph so linear filtering doesn't pick up its neighbours
+const PADDING: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: FontId,
+    pub id: GlyphId,
+    size: u32,
+}
+
+impl GlyphKey {
+    // `pixel_size` is the font size in pixels the glyph is drawn at
+    pub fn new(font: FontId, id: GlyphId, pixel_size: f32) -> Self {
+        Self {
+            font,
+            id,
+            size: (pixel_size * SIZE_STEPS_PER_PIXEL).round().max(1.0) as u32,
+        }
+    }
+
+    // The size the glyph is rasterized at, close to the one it was created with
+    pub fn pixel_size(&self) -> f32 {
+        self.size as f32 / SIZE_STEPS_PER_PIXEL
+    }
+}
+
+// A rasterized glyph's texels in the atlas, padding included. `offset` goes from the pen
+// position to its top-left texel, in pixels at the key's size with y down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasGlyph {
+    pub position: UVec2,
+    pub size: UVec2,
+    pub offset: Vec2,
+}
+
+// Coverage of rasterized glyphs packed into one single channel texture. It starts small and
+// doubles whenever a glyph doesn't fit, up to `max_size`. The texels are kept on the CPU to
+// upload the rows that changed, or everything once it grew.
+pub struct GlyphAtlas {
+    allocator: BucketedAtlasAllocator,
+    size: u32,
+    max_size: u32,
+    pixels: Vec<u8>,
+    // None for glyphs with nothing to draw, like spaces
+    glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+    // Rows written since the last upload
+    dirty: Option<Range<u32>>,
+    // A glyph didn't fit even at the maximum size
+    overflowed: bool,
+}
+
+impl GlyphAtlas {
+    pub const DEFAULT_SIZE: u32 = 256;
+    pub const DEFAULT_MAX_SIZE: u32 = 4096;
+
+    pub fn new(size: u32, max_size: u32) -> Self {
+        let size = size.max(1);
+        Self {
+            allocator: BucketedAtlasAllocator::new(size2(size as i32, size as i32)),
+            size,
+            max_size: max_size.max(size),
+            pixels: vec![0; (size * size) as usize],
+            glyphs: HashMap::new(),
+            dirty: None,
+            overflowed: false,
+        }
+    }
+
+    // Width and height in texels
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    // Row-major coverage, one byte per texel
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // Glyphs rasterized so far, empty ones included
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    // Where `key` is in the atlas, rasterizing it on first use. None when the glyph has no
+    // outline or doesn't fit.
+    pub fn glyph(&mut self, fonts: &FontLibrary, key: GlyphKey) -> Option<AtlasGlyph> {
+        if let Some(entry) = self.glyphs.get(&key) {
+            return *entry;
+        }
+        let font = fonts.font(key.font)?;
+        let glyph = key.id.with_scale_and_position(px_scale(font, key.pixel_size()), point(0.0, 0.0));
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            self.glyphs.insert(key, None);
+            return None;
+        };
+        let bounds = outlined.px_bounds();
+        let size = UVec2::new(bounds.width() as u32, bounds.height() as u32) + 2 * PADDING;
+        let Some(position) = self.allocate(size) else {
+            self.overflowed = true;
+            return None;
+        };
+
+        let stride = self.size as usize;
+        for y in position.y..position.y + size.y {
+            let row = y as usize * stride;
+            self.pixels[row + position.x as usize..row + (position.x + size.x) as usize].fill(0);
+        }
+        let inner = position + PADDING;
+        outlined.draw(|x, y, coverage| {
+            let index = (inner.y + y) as usize * stride + (inner.x + x) as usize;
+            self.pixels[index] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+        self.mark_dirty(position.y..position.y + size.y);
+
+        let entry = AtlasGlyph {
+            position,
+            size,
+            offset: Vec2::new(bounds.min.x, bounds.min.y) - PADDING as f32,
+        };
+        self.glyphs.insert(key, Some(entry));
+        Some(entry)
+    }
+
+    fn allocate(&mut self, size: UVec2) -> Option<UVec2> {
+        loop {
+            if let Some(allocation) = self.allocator.allocate(size2(size.x as i32, size.y as i32)) {
+                let min = allocation.rectangle.min;
+                return Some(UVec2::new(min.x as u32, min.y as u32));
+            }
+            if self.size * 2 > self.max_size {
+                return None;
+            }
+            self.grow(self.size * 2);
+        }
+    }
+
+    fn grow(&mut self, size: u32) {
+        self.allocator.grow(size2(size as i32, size as i32));
+        let mut pixels = vec![0; (size * size) as usize];
+        let (old, new) = (self.size as usize, size as usize);
+        for y in 0..old {
+            pixels[y * new..y * new + old].copy_from_slice(&self.pixels[y * old..(y + 1) * old]);
+        }
+        self.pixels = pixels;
+        self.size = size;
+        self.dirty = Some(0..size);
+    }
+
+    fn mark_dirty(&mut self, rows: Range<u32>) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(rows.start)..dirty.end.max(rows.end),
+            None => rows,
+        });
+    }
+
+    // Rows that changed since the last call, to upload
+    pub fn take_dirty_rows(&mut self) -> Option<Range<u32>> {
+        self.dirty.take()
+    }
+
+    // Whether a glyph didn't fit since the last call
+    pub fn take_overflowed(&mut self) -> bool {
+        std::mem::take(&mut self.overflowed)
+    }
+
+    // Forgets every glyph, keeping the size. Glyphs handed out before are invalid afterwards.
+    pub fn clear(&mut self) {
+        self.allocator.clear();
+        self.glyphs.clear();
+        self.overflowed = false;
+    }
+}
+
+impl Default for GlyphAtlas {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SIZE, Self::DEFAULT_MAX_SIZE)
+    }
+}
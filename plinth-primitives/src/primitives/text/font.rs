@@ -0,0 +1,84 @@
+use std::fmt;
+
+use ab_glyph::{Font as _, FontArc, PxScale};
+
+// A font in a FontLibrary, in load order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FontId(pub usize);
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum FontError {
+    #[error("Invalid font data for family {0}")]
+    InvalidFont(String),
+}
+
+// Fonts loaded from TTF/OTF bytes and looked up by family name, case-insensitively. The
+// first font loaded is the fallback for families nothing was loaded under, which includes
+// the generic ones (sans-serif, monospace...) unless fonts are loaded with those names.
+#[derive(Clone, Default)]
+pub struct FontLibrary {
+    fonts: Vec<(String, FontArc)>,
+}
+
+impl FontLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Loading a family again shadows the earlier font without invalidating its id
+    pub fn load(&mut self, family: impl Into<String>, data: impl Into<Vec<u8>>) -> Result<FontId, FontError> {
+        let family = family.into();
+        let font = FontArc::try_from_vec(data.into()).map_err(|_| FontError::InvalidFont(family.clone()))?;
+        self.fonts.push((family, font));
+        Ok(FontId(self.fonts.len() - 1))
+    }
+
+    pub fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    pub fn font(&self, id: FontId) -> Option<&FontArc> {
+        self.fonts.get(id.0).map(|(_, font)| font)
+    }
+
+    pub fn family(&self, id: FontId) -> Option<&str> {
+        self.fonts.get(id.0).map(|(family, _)| family.as_str())
+    }
+
+    // The font last loaded under `family`
+    pub fn find(&self, family: &str) -> Option<FontId> {
+        self.fonts
+            .iter()
+            .rposition(|(name, _)| name.eq_ignore_ascii_case(family.trim()))
+            .map(FontId)
+    }
+
+    // Fonts to try for a `font-family` list, in order: each family that was loaded, then
+    // the first font loaded. Empty only when nothing was loaded.
+    pub fn resolve(&self, families: &[String]) -> Vec<FontId> {
+        let mut chain: Vec<FontId> = Vec::new();
+        for id in families.iter().filter_map(|family| self.find(family)).chain((!self.is_empty()).then_some(FontId(0))) {
+            if !chain.contains(&id) {
+                chain.push(id);
+            }
+        }
+        chain
+    }
+}
+
+impl fmt::Debug for FontLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.fonts.iter().map(|(family, _)| family)).finish()
+    }
+}
+
+// ab_glyph scales fonts by their height (ascent - descent); font sizes are em sizes, as in CSS
+pub fn px_scale(font: &FontArc, font_size: f32) -> PxScale {
+    let height = font.height_unscaled();
+    let units_per_em = font.units_per_em().unwrap_or(height);
+    PxScale::from(font_size * height / units_per_em)
+}
@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+use ab_glyph::{Font as _, GlyphId, ScaleFont as _};
+use glam::Vec2;
+
+use crate::primitives::{px_scale, FontId, FontLibrary, Text, TextAlign, VerticalAlign};
+
+// A glyph placed by `layout_text`. `position` is the pen position on the baseline, in the
+// text's font size unit from the top-left of its box with y pointing down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub font: FontId,
+    pub id: GlyphId,
+    pub position: Vec2,
+    // Byte offset of the character in the text's content
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub glyphs: Range<usize>,
+    // Left edge after alignment
+    pub x: f32,
+    // Trailing whitespace doesn't count
+    pub width: f32,
+    pub baseline: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<TextLine>,
+    // Extent of the lines, which may overflow the box
+    pub size: Vec2,
+}
+
+// A character mapped to a glyph of the first font in the family chain that has one
+struct ShapedGlyph {
+    font: FontId,
+    id: GlyphId,
+    index: usize,
+    advance: f32,
+    // Kerning with the previous glyph when both come from the same font
+    kern: f32,
+    whitespace: bool,
+}
+
+// Breaks `text` into lines at newlines and, when it has a box, wherever the next word would
+// overflow its width. Words wider than the box break between characters. Returns an empty
+// layout when no font is loaded.
+pub fn layout_text(text: &Text, fonts: &FontLibrary) -> TextLayout {
+    let chain = fonts.resolve(&text.font_family);
+    let Some(primary) = chain.first().and_then(|id| fonts.font(*id)) else {
+        return TextLayout::default();
+    };
+    let font_size = text.font_size.value().max(0.0);
+    let metrics = primary.as_scaled(px_scale(primary, font_size));
+    let (ascent, descent) = (metrics.ascent(), metrics.descent());
+    let line_height = match text.line_height {
+        Some(multiplier) => multiplier * font_size,
+        None => ascent - descent + metrics.line_gap(),
+    };
+    let max_width = text.bounds.map(|bounds| bounds.x);
+
+    let mut lines: Vec<(Vec<ShapedGlyph>, f32)> = Vec::new();
+    let mut offset = 0;
+    for paragraph in text.content.split('\n') {
+        let shaped = shape(paragraph.trim_end_matches('\r'), offset, &chain, fonts, font_size);
+        offset += paragraph.len() + 1;
+        for line in wrap(shaped, max_width) {
+            let width = line_width(&line);
+            lines.push((line, width));
+        }
+    }
+
+    let widest = lines.iter().map(|(_, width)| *width).fold(0.0, f32::max);
+    let content_height = lines.len() as f32 * line_height;
+    let align_width = max_width.unwrap_or(widest);
+    let top = match (text.bounds, text.vertical_align) {
+        (Some(bounds), VerticalAlign::Middle) => (bounds.y - content_height) / 2.0,
+        (Some(bounds), VerticalAlign::Bottom) => bounds.y - content_height,
+        _ => 0.0,
+    };
+    // Space above and below the glyphs' ascent and descent, split evenly as in CSS
+    let half_leading = (line_height - (ascent - descent)) / 2.0;
+
+    let mut layout = TextLayout {
+        size: Vec2::new(widest, content_height),
+        ..TextLayout::default()
+    };
+    for (row, (line, width)) in lines.into_iter().enumerate() {
+        let x = match text.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => (align_width - width) / 2.0,
+            TextAlign::Right => align_width - width,
+        };
+        let baseline = top + row as f32 * line_height + half_leading + ascent;
+        let start = layout.glyphs.len();
+        let mut pen = x;
+        for (column, glyph) in line.iter().enumerate() {
+            if column > 0 {
+                pen += glyph.kern;
+            }
+            layout.glyphs.push(PositionedGlyph {
+                font: glyph.font,
+                id: glyph.id,
+                position: Vec2::new(pen, baseline),
+                index: glyph.index,
+            });
+            pen += glyph.advance;
+        }
+        layout.lines.push(TextLine {
+            glyphs: start..layout.glyphs.len(),
+            x,
+            width,
+            baseline,
+        });
+    }
+    layout
+}
+
+fn shape(paragraph: &str, offset: usize, chain: &[FontId], fonts: &FontLibrary, font_size: f32) -> Vec<ShapedGlyph> {
+    let mut shaped: Vec<ShapedGlyph> = Vec::with_capacity(paragraph.len());
+    for (index, character) in paragraph.char_indices() {
+        let font_id = chain
+            .iter()
+            .copied()
+            .find(|id| fonts.font(*id).is_some_and(|font| font.glyph_id(character).0 != 0))
+            .unwrap_or(chain[0]);
+        let Some(font) = fonts.font(font_id) else {
+            continue;
+        };
+        let scaled = font.as_scaled(px_scale(font, font_size));
+        let id = scaled.glyph_id(character);
+        let kern = match shaped.last() {
+            Some(previous) if previous.font == font_id => scaled.kern(previous.id, id),
+            _ => 0.0,
+        };
+        shaped.push(ShapedGlyph {
+            font: font_id,
+            id,
+            index: offset + index,
+            advance: scaled.h_advance(id),
+            kern,
+            whitespace: character.is_whitespace(),
+        });
+    }
+    shaped
+}
+
+// Greedy line breaking after whitespace. Whitespace itself never wraps, so runs of it hang
+// past the edge instead of starting the next line.
+fn wrap(glyphs: Vec<ShapedGlyph>, max_width: Option<f32>) -> Vec<Vec<ShapedGlyph>> {
+    let Some(max_width) = max_width else {
+        return vec![glyphs];
+    };
+    let mut breaks = Vec::new();
+    let mut start = 0;
+    let mut x = 0.0;
+    // First glyph after the latest whitespace on the current line
+    let mut opportunity = None;
+    for index in 0..glyphs.len() {
+        let glyph = &glyphs[index];
+        let kern = if index > start { glyph.kern } else { 0.0 };
+        if !glyph.whitespace && index > start && x + kern + glyph.advance > max_width {
+            start = match opportunity {
+                Some(opportunity) if opportunity > start => opportunity,
+                _ => index,
+            };
+            breaks.push(start);
+            opportunity = None;
+            x = advance(&glyphs[start..index]);
+        }
+        x += if index > start { glyph.kern } else { 0.0 } + glyph.advance;
+        if glyph.whitespace {
+            opportunity = Some(index + 1);
+        }
+    }
+
+    let mut lines = Vec::with_capacity(breaks.len() + 1);
+    let mut rest = glyphs;
+    for split in breaks.into_iter().rev() {
+        lines.push(rest.split_off(split));
+    }
+    lines.push(rest);
+    lines.reverse();
+    lines
+}
+
+fn advance(glyphs: &[ShapedGlyph]) -> f32 {
+    glyphs
+        .iter()
+        .enumerate()
+        .map(|(index, glyph)| if index > 0 { glyph.kern } else { 0.0 } + glyph.advance)
+        .sum()
+}
+
+fn line_width(glyphs: &[ShapedGlyph]) -> f32 {
+    let end = glyphs.iter().rposition(|glyph| !glyph.whitespace).map_or(0, |last| last + 1);
+    advance(&glyphs[..end])
+}
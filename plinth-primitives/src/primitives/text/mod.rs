@@ -0,0 +1,344 @@
+pub mod atlas;
+pub mod font;
+pub mod layout;
+pub mod text_batch;
+
+pub use atlas::*;
+pub use font::*;
+pub use layout::*;
+pub use text_batch::*;
+
+use glam::Vec2;
+
+use crate::primitives::{Interactive, Styled};
+use crate::types::{Appearance, Color, InteractionState, Style, StyleViewport, Transform};
+
+// Surface size pixel font sizes are measured against when hit testing without one
+const DEFAULT_HIT_SURFACE: Vec2 = Vec2::splat(1000.0);
+
+// How big text is drawn: the em size, as CSS font-size. Pixel sizes stay the same at any
+// transform scale and surface size; world sizes are in clip space units and scale with the
+// transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontSize {
+    Pixels(f32),
+    World(f32),
+}
+
+impl FontSize {
+    pub fn value(self) -> f32 {
+        match self {
+            FontSize::Pixels(size) | FontSize::World(size) => size,
+        }
+    }
+
+    // The same kind of size with another value
+    pub fn with_value(self, value: f32) -> Self {
+        match self {
+            FontSize::Pixels(_) => FontSize::Pixels(value),
+            FontSize::World(_) => FontSize::World(value),
+        }
+    }
+
+    // The same kind of size, `px` CSS pixels large
+    pub fn with_css_length(self, px: f32, viewport: &StyleViewport) -> Self {
+        let value = match self {
+            FontSize::Pixels(_) => viewport.pixels(px),
+            FontSize::World(_) => viewport.clip_length(px),
+        };
+        self.with_value(value.max(0.0))
+    }
+}
+
+impl Default for FontSize {
+    // `medium`, as in browsers
+    fn default() -> Self {
+        FontSize::Pixels(16.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+// Where the lines sit in a box taller than them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+// A run of text drawn from a glyph atlas, laid out down and to the right of `position`.
+// `bounds` is the box it wraps and aligns within, in the font size's unit like `line_height`
+// is a multiple of it; without a box lines only break at newlines and align against the
+// widest one. Fonts come from the renderer's FontLibrary by `font_family`, falling back to
+// the first font loaded.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Text {
+    pub content: String,
+    pub position: Vec2,
+    pub bounds: Option<Vec2>,
+    pub font_size: FontSize,
+    pub font_family: Vec<String>,
+    // Multiple of the font size, None for the font's own line spacing
+    pub line_height: Option<f32>,
+    pub color: Color,
+    pub align: TextAlign,
+    pub vertical_align: VerticalAlign,
+    pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
+    pub css_class: Option<String>,
+}
+
+impl Text {
+    pub fn new(content: impl Into<String>, position: Vec2) -> Self {
+        Self {
+            content: content.into(),
+            position,
+            bounds: None,
+            font_size: FontSize::default(),
+            font_family: Vec::new(),
+            line_height: None,
+            color: Color::default(),
+            align: TextAlign::default(),
+            vertical_align: VerticalAlign::default(),
+            transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
+            css_class: None,
+        }
+    }
+
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Vec2) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: FontSize) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    // Family names in order of preference
+    pub fn with_font_family(mut self, families: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.font_family = families.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.line_height = Some(line_height);
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+
+    // One class or a space-separated list, e.g. "button primary"
+    pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
+        self.css_class = Some(class.into());
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn apply_css_override(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    // Applies every property `style` sets: color (not background-color, which would be the
+    // box's), font-size in the text's current unit and font-family
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        if let Some(color) = style.color {
+            self.apply_css_override(color);
+        }
+        if let Some(size) = style.font_size {
+            self.font_size = self.font_size.with_css_length(size, viewport);
+        }
+        if let Some(ref families) = style.font_family {
+            self.font_family = families.clone();
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+
+    // Maps a point in layout space (font size units, y down from the box's top-left) to
+    // clip space. Pixel sized text is rotated by the transform but not scaled.
+    pub fn layout_to_clip(&self, point: Vec2, surface_size: Vec2) -> Vec2 {
+        let local = Vec2::new(point.x, -point.y);
+        match self.font_size {
+            FontSize::Pixels(_) => {
+                let rotated = Vec2::from_angle(self.transform.rotation).rotate(local);
+                self.transform.transform_point(self.position) + rotated * 2.0 / surface_size
+            }
+            FontSize::World(_) => self.transform.transform_point(self.position + local),
+        }
+    }
+
+    // Inverse of `layout_to_clip`
+    pub fn clip_to_layout(&self, point: Vec2, surface_size: Vec2) -> Vec2 {
+        let local = match self.font_size {
+            FontSize::Pixels(_) => {
+                let offset = (point - self.transform.transform_point(self.position)) * surface_size / 2.0;
+                Vec2::from_angle(-self.transform.rotation).rotate(offset)
+            }
+            FontSize::World(_) => self.transform.inverse_transform_point(point) - self.position,
+        };
+        Vec2::new(local.x, -local.y)
+    }
+
+    // Pixels one layout unit covers on screen
+    pub fn pixels_per_unit(&self, surface_size: Vec2) -> f32 {
+        match self.font_size {
+            FontSize::Pixels(_) => 1.0,
+            FontSize::World(_) => {
+                let scale = self.transform.scale.abs() * surface_size / 2.0;
+                (scale.x * scale.y).sqrt()
+            }
+        }
+    }
+
+    // A quad per visible glyph of `layout`, rasterizing glyphs into `atlas` as needed.
+    // `surface_size` in physical pixels decides the size glyphs are rasterized at.
+    pub fn glyph_instances(
+        &self,
+        layout: &TextLayout,
+        fonts: &FontLibrary,
+        atlas: &mut GlyphAtlas,
+        surface_size: Vec2,
+    ) -> Vec<GlyphInstance> {
+        let font_size = self.font_size.value();
+        let pixel_size = font_size * self.pixels_per_unit(surface_size);
+        if !(pixel_size.is_finite() && pixel_size > 0.0) {
+            return Vec::new();
+        }
+        let color = self.appearance.apply_opacity(self.color);
+        let mut instances = Vec::with_capacity(layout.glyphs.len());
+        for glyph in &layout.glyphs {
+            let key = GlyphKey::new(glyph.font, glyph.id, pixel_size);
+            let Some(entry) = atlas.glyph(fonts, key) else {
+                continue;
+            };
+            // Atlas pixels are at the key's rounded size
+            let units_per_pixel = font_size / key.pixel_size();
+            let top_left = glyph.position + entry.offset * units_per_pixel;
+            let extent = entry.size.as_vec2() * units_per_pixel;
+            let origin = self.layout_to_clip(top_left, surface_size);
+            instances.push(GlyphInstance {
+                origin: origin.into(),
+                axis_x: (self.layout_to_clip(top_left + Vec2::new(extent.x, 0.0), surface_size) - origin).into(),
+                axis_y: (self.layout_to_clip(top_left + Vec2::new(0.0, extent.y), surface_size) - origin).into(),
+                uv_min: entry.position.as_vec2().into(),
+                uv_max: (entry.position + entry.size).as_vec2().into(),
+                color: [color.r, color.g, color.b, color.a],
+            });
+        }
+        instances
+    }
+
+    // Inside the box, in layout space. Text without a box has no area to hit.
+    fn hits(&self, point: Vec2, surface_size: Vec2) -> bool {
+        let Some(bounds) = self.bounds else {
+            return false;
+        };
+        let local = self.clip_to_layout(point, surface_size);
+        self.appearance.visible && local.cmpge(Vec2::ZERO).all() && local.cmple(bounds).all()
+    }
+}
+
+impl Default for Text {
+    fn default() -> Self {
+        Self::new("", Vec2::ZERO)
+    }
+}
+
+impl Styled for Text {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Text::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Text {
+    // Within the box, with pixel font sizes measured on a 1000 pixel surface
+    fn hit_test(&self, point: Vec2) -> bool {
+        self.hits(point, DEFAULT_HIT_SURFACE)
+    }
+
+    fn hit_test_in_surface(&self, point: Vec2, surface_size: (u32, u32)) -> bool {
+        match surface_size {
+            (0, _) | (_, 0) => self.hit_test(point),
+            (width, height) => self.hits(point, Vec2::new(width as f32, height as f32)),
+        }
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
+}
+
+// One glyph quad in clip space: `origin` is its top-left corner and the axes run along its
+// width and down its height, so rotated text stays a parallelogram. UVs are in atlas texels.
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct GlyphInstance {
+    pub origin: [f32; 2],
+    pub axis_x: [f32; 2],
+    pub axis_y: [f32; 2],
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub color: [f32; 4],
+}
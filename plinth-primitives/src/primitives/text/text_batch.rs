@@ -0,0 +1,574 @@
+use glam::Vec2;
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPipeline, ShaderModuleDescriptor};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::primitives::{layout_text, FontError, FontId, FontLibrary, GlyphAtlas, GlyphInstance, StyleResolver, Text};
+use crate::batch::{
+    clip_depth_stencil, render_clip_runs, BatchShader, ClipId, ClipRun, ClipScope, Drawn, InstanceBatch,
+    InstanceSources, StyleCache,
+};
+use crate::shaders::ShaderPreprocessor;
+use crate::types::StyleViewport;
+
+pub const TEXT_SHADER: ShaderSource = shader_source!("src/shaders/text.wgsl");
+
+pub struct TextBatch {
+    // Texts as added, in insertion order, before any class styles are applied
+    texts: Vec<Text>,
+    // The clip each text was added under
+    clips: Vec<ClipId>,
+    // The clip texts added from now on are drawn in
+    clip: ClipId,
+    fonts: FontLibrary,
+    atlas: GlyphAtlas,
+    // One instance per drawn glyph of the visible texts, sorted by z-index with insertion
+    // order kept within the same z-index. Also what the instance buffer currently holds once
+    // uploaded.
+    instances: Vec<GlyphInstance>,
+    // Runs of instances by the clip they are drawn in
+    clip_runs: Vec<ClipRun>,
+    instance_buffer: Option<wgpu::Buffer>,
+    // Instances the buffer has room for
+    buffer_capacity: usize,
+    render_pipeline: Option<RenderPipeline>,
+    bind_group_layout: Option<BindGroupLayout>,
+    sampler: Option<wgpu::Sampler>,
+    // Recreated at the atlas' size whenever it grows
+    atlas_texture: Option<wgpu::Texture>,
+    bind_group: Option<BindGroup>,
+    // In physical pixels, which decides the size glyphs are rasterized at
+    surface_size: Vec2,
+    shader: BatchShader,
+    style_cache: StyleCache<Text>,
+    // Which text each run of glyph instances was built from
+    sources: InstanceSources,
+    // Texts or fonts were added or removed, or the surface resized, since instances were
+    // last built
+    needs_rebuild: bool,
+    // Instances that changed since the last upload
+    dirty: Option<Range<usize>>,
+}
+
+impl TextBatch {
+    pub fn new() -> Self {
+        Self {
+            texts: Vec::new(),
+            clips: Vec::new(),
+            clip: ClipId::NONE,
+            fonts: FontLibrary::new(),
+            atlas: GlyphAtlas::default(),
+            instances: Vec::new(),
+            clip_runs: Vec::new(),
+            instance_buffer: None,
+            buffer_capacity: 0,
+            render_pipeline: None,
+            bind_group_layout: None,
+            sampler: None,
+            atlas_texture: None,
+            bind_group: None,
+            surface_size: Vec2::ONE,
+            shader: BatchShader::new("text", TEXT_SHADER),
+            style_cache: StyleCache::new(),
+            sources: InstanceSources::new(),
+            needs_rebuild: true,
+            dirty: None,
+        }
+    }
+
+    // Texts without classes that are hidden are dropped here; styled ones are kept since
+    // their classes may show them again
+    pub fn add_text(&mut self, text: Text) {
+        if !text.appearance.visible && text.css_class.is_none() {
+            return;
+        }
+        self.texts.push(text);
+        self.clips.push(self.clip);
+        self.needs_rebuild = true;
+    }
+
+    pub fn add_texts(&mut self, texts: impl IntoIterator<Item = Text>) {
+        for text in texts {
+            self.add_text(text);
+        }
+    }
+
+    // Draws texts added from now on in `clip`, see PrimitiveRenderer::push_clip_rect
+    pub fn set_clip(&mut self, clip: ClipId) {
+        self.clip = clip;
+    }
+
+    pub fn clip(&self) -> ClipId {
+        self.clip
+    }
+
+    // The instances split by the clips they are drawn in, as of the last `resolve_styles`
+    pub fn clip_runs(&self) -> &[ClipRun] {
+        &self.clip_runs
+    }
+
+    // Removes the texts; loaded fonts and rasterized glyphs stay
+    pub fn clear(&mut self) {
+        self.texts.clear();
+        self.clips.clear();
+        self.style_cache.clear();
+        self.needs_rebuild = true;
+    }
+
+    // Time style transitions and animations are sampled at on the next `resolve_styles`
+    pub fn set_style_time(&mut self, time: Duration) {
+        self.style_cache.set_time(time);
+    }
+
+    // What CSS lengths in styles are converted against, restyling the batch when it changes
+    pub fn set_style_viewport(&mut self, viewport: StyleViewport) {
+        self.style_cache.set_viewport(viewport);
+    }
+
+    // Whether a style transition or animation is running, so the batch restyles every frame
+    pub fn is_animating(&self) -> bool {
+        self.style_cache.is_animating()
+    }
+
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    // Texts as added, to move them or change their state in place. They are
+    // restyled on the next `resolve_styles`.
+    pub fn texts_mut(&mut self) -> &mut [Text] {
+        self.style_cache.invalidate();
+        self.needs_rebuild = true;
+        &mut self.texts
+    }
+
+    // The texts as last drawn, in draw order, for the renderer's pointer handling, which
+    // only flags a state change so just the texts whose state changed are restyled
+    pub(crate) fn interaction_mut(&mut self) -> (Vec<Drawn<'_, Text>>, &mut bool) {
+        self.style_cache.drawn_mut(&mut self.texts)
+    }
+
+    // Loads a TTF/OTF font from memory under `family`. The first font loaded is used for
+    // texts whose families aren't loaded.
+    pub fn load_font(&mut self, family: impl Into<String>, data: impl Into<Vec<u8>>) -> Result<FontId, FontError> {
+        let id = self.fonts.load(family, data)?;
+        self.needs_rebuild = true;
+        Ok(id)
+    }
+
+    pub fn fonts(&self) -> &FontLibrary {
+        &self.fonts
+    }
+
+    pub fn atlas(&self) -> &GlyphAtlas {
+        &self.atlas
+    }
+
+    // Surface size in physical pixels. Glyphs are rasterized for it.
+    pub fn set_surface_size(&mut self, width: u32, height: u32) {
+        let size = Vec2::new(width.max(1) as f32, height.max(1) as f32);
+        if size != self.surface_size {
+            self.surface_size = size;
+            self.needs_rebuild = true;
+        }
+    }
+
+    // Lays the texts out again and rebuilds the instances, applying the styles of each
+    // text's classes and state from `resolver`. Only texts that were added, changed state or
+    // are animating are restyled, all of them once the resolver's generation changes. While
+    // the layout stays the same only their glyphs are marked for upload, otherwise those
+    // that come out different.
+    pub fn resolve_styles(&mut self, resolver: Option<&dyn StyleResolver>) {
+        if !self.style_cache.sync(resolver) && !self.needs_rebuild {
+            return;
+        }
+        let rebuild = std::mem::take(&mut self.needs_rebuild);
+        let restyled = self.style_cache.restyle(resolver, &self.texts);
+        if restyled.is_empty() && !rebuild {
+            return;
+        }
+
+        let (mut instances, mut runs, mut clip_runs) = self.build_instances();
+        // Fonts and the surface size decide every text's layout
+        let mut relayout = rebuild;
+        if self.atlas.take_overflowed() {
+            // Start over to drop glyphs of sizes and texts no longer drawn
+            self.atlas.clear();
+            (instances, runs, clip_runs) = self.build_instances();
+            if self.atlas.take_overflowed() {
+                log::warn!("Glyph atlas is full, some glyphs are not drawn");
+            }
+            relayout = true;
+        }
+
+        if relayout {
+            self.sources.forget();
+        }
+        self.style_cache.set_drawn(runs.iter().map(|(index, _)| *index));
+        let changed = self.sources.changed(runs, &restyled, &self.instances, &instances);
+        if let Some(changed) = changed {
+            self.dirty = Some(match self.dirty.take() {
+                Some(dirty) => dirty.start.min(changed.start)..dirty.end.max(changed.end),
+                None => changed,
+            });
+        }
+        self.instances = instances;
+        self.clip_runs = clip_runs;
+    }
+
+    // The glyph instances of the texts as last restyled, which text each run of them came
+    // from and the runs by clip
+    #[allow(clippy::type_complexity)]
+    fn build_instances(&mut self) -> (Vec<GlyphInstance>, Vec<(usize, Range<usize>)>, Vec<ClipRun>) {
+        let mut drawn: Vec<(i32, usize, Vec<GlyphInstance>, ClipId)> = Vec::with_capacity(self.texts.len());
+        for (index, (text, clip)) in self.texts.iter().zip(&self.clips).enumerate() {
+            let text = self.style_cache.styled(index, text);
+            if text.appearance.visible {
+                let layout = layout_text(text, &self.fonts);
+                let instances = text.glyph_instances(&layout, &self.fonts, &mut self.atlas, self.surface_size);
+                drawn.push((text.appearance.z_index, index, instances, *clip));
+            }
+        }
+        // Stable, so insertion order is kept within a z-index
+        drawn.sort_by_key(|(z_index, _, _, _)| *z_index);
+        let mut instances = Vec::new();
+        let mut runs = Vec::with_capacity(drawn.len());
+        let mut clip_runs = Vec::new();
+        for (_, index, glyphs, clip) in drawn {
+            let start = instances.len();
+            instances.extend(glyphs);
+            runs.push((index, start..instances.len()));
+            ClipRun::push(&mut clip_runs, start..instances.len(), clip);
+        }
+        (instances, runs, clip_runs)
+    }
+
+    pub fn create_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat) -> RenderPipeline {
+        if self.bind_group_layout.is_none() {
+            self.create_atlas_layout(device);
+        }
+        self.shader.set_targets(PipelineTargets { format: surface_format, sample_count: 1 });
+        self.rebuild_pipelines(device);
+        self.render_pipeline.clone().expect("Failed to build text pipeline")
+    }
+
+    fn create_atlas_layout(&mut self, device: &Device) {
+        self.bind_group_layout = Some(device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Glyph Atlas Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        }));
+        // Linear, since transformed glyphs rarely land on whole pixels
+        self.sampler = Some(device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Atlas Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        }));
+    }
+
+    // A texture at the atlas' current size, with the bind group pointing at it
+    fn create_atlas_texture(&mut self, device: &Device) {
+        let (Some(layout), Some(sampler)) = (&self.bind_group_layout, &self.sampler) else {
+            return;
+        };
+        let size = self.atlas.size();
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Atlas Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        }));
+        self.atlas_texture = Some(texture);
+    }
+
+    pub fn preprocessor(&self) -> &ShaderPreprocessor {
+        self.shader.preprocessor()
+    }
+
+    // Rebuilds the pipelines for color targets of `targets`, e.g. multisampled
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.shader.set_targets(targets) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines with the define toggled, see BatchShader
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        if self.shader.set_define(name, enabled) {
+            self.rebuild_pipelines(device);
+        }
+    }
+
+    // Rebuilds the pipelines if text.wgsl or one of its includes changed on disk
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        self.shader.reload(shaders) && self.rebuild_pipelines(device)
+    }
+
+    fn rebuild_pipelines(&mut self, device: &Device) -> bool {
+        let Some(layout) = &self.bind_group_layout else {
+            return false;
+        };
+        let Some(render_pipeline) = self.shader.build(device, &[], |source, targets, blend| {
+            Self::build_pipeline(device, targets, blend, source, layout)
+        }) else {
+            return false;
+        };
+        self.render_pipeline = Some(render_pipeline);
+        true
+    }
+
+    fn build_pipeline(
+        device: &Device,
+        targets: PipelineTargets,
+        blend: wgpu::BlendState,
+        source: &str,
+        layout: &BindGroupLayout,
+    ) -> RenderPipeline {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(TEXT_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[layout],
+            push_constant_ranges: &[],
+        });
+
+        let offset = |floats: usize| (floats * std::mem::size_of::<f32>()) as wgpu::BufferAddress;
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<GlyphInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2, // origin
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(2),
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x2, // axis_x
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(4),
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x2, // axis_y
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(6),
+                            shader_location: 3,
+                            format: wgpu::VertexFormat::Float32x2, // uv_min
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(8),
+                            shader_location: 4,
+                            format: wgpu::VertexFormat::Float32x2, // uv_max
+                        },
+                        wgpu::VertexAttribute {
+                            offset: offset(10),
+                            shader_location: 5,
+                            format: wgpu::VertexFormat::Float32x4, // color
+                        },
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: targets.format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // Mirroring transforms flip the quad's winding
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(clip_depth_stencil()),
+            multisample: targets.multisample(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // Uploads newly rasterized glyphs and the instances that changed since the last upload,
+    // growing the buffer and recreating the atlas texture when needed. Texts are drawn
+    // unstyled unless `resolve_styles` ran with a resolver first.
+    pub fn update_buffer(&mut self, device: &Device, queue: &wgpu::Queue) {
+        if self.needs_rebuild {
+            self.resolve_styles(None);
+        }
+        self.upload_atlas(device, queue);
+
+        let Some(mut dirty) = self.dirty.take() else {
+            return;
+        };
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instance_buffer.is_none() || self.instances.len() > self.buffer_capacity {
+            // Room to grow without reallocating every time a glyph is added
+            self.buffer_capacity = self.instances.len().next_power_of_two();
+            let buffer_size = (self.buffer_capacity * std::mem::size_of::<GlyphInstance>()) as wgpu::BufferAddress;
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Glyph Instance Buffer"),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            dirty = 0..self.instances.len();
+        }
+
+        if let Some(ref buffer) = self.instance_buffer {
+            let dirty = dirty.start.min(self.instances.len())..dirty.end.min(self.instances.len());
+            let offset = (dirty.start * std::mem::size_of::<GlyphInstance>()) as wgpu::BufferAddress;
+            queue.write_buffer(buffer, offset, bytemuck::cast_slice(&self.instances[dirty]));
+        }
+    }
+
+    fn upload_atlas(&mut self, device: &Device, queue: &wgpu::Queue) {
+        let size = self.atlas.size();
+        let mut rows = self.atlas.take_dirty_rows();
+        if self.atlas_texture.as_ref().is_none_or(|texture| texture.width() != size) {
+            self.create_atlas_texture(device);
+            rows = Some(0..size);
+        }
+        let (Some(texture), Some(rows)) = (&self.atlas_texture, rows) else {
+            return;
+        };
+        let start = (rows.start * size) as usize;
+        let end = (rows.end * size) as usize;
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: 0, y: rows.start, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.atlas.pixels()[start..end],
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size),
+                rows_per_image: Some(rows.len() as u32),
+            },
+            wgpu::Extent3d {
+                width: size,
+                height: rows.len() as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.render_range(render_pass, 0..self.instances.len());
+    }
+
+    // Draws the instances run by run, each in the clip its text was added under
+    pub fn render_clipped(&self, render_pass: &mut wgpu::RenderPass, scope: &mut dyn ClipScope) {
+        render_clip_runs(render_pass, &self.clip_runs, scope, |render_pass, instances| {
+            self.render_range(render_pass, instances)
+        });
+    }
+
+    // Draws some of the instances, e.g. one of `clip_runs`
+    pub fn render_range(&self, render_pass: &mut wgpu::RenderPass, instances: Range<usize>) {
+        if let (Some(pipeline), Some(bind_group), Some(buffer)) = (&self.render_pipeline, &self.bind_group, &self.instance_buffer) {
+            let count = self.instances.len();
+            let instances = instances.start.min(count)..instances.end.min(count);
+            if !instances.is_empty() {
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                let size = (count * std::mem::size_of::<GlyphInstance>()) as wgpu::BufferAddress;
+                render_pass.set_vertex_buffer(0, buffer.slice(..size));
+                // A quad per glyph
+                render_pass.draw(0..6, instances.start as u32..instances.end as u32);
+            }
+        }
+    }
+}
+
+impl Default for TextBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstanceBatch for TextBatch {
+    fn len(&self) -> usize {
+        TextBatch::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        TextBatch::is_empty(self)
+    }
+
+    fn clear(&mut self) {
+        TextBatch::clear(self);
+    }
+}
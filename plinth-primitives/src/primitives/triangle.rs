@@ -1,11 +1,15 @@
 use glam::Vec2;
-use crate::types::{Color, Transform};
+use crate::types::{Appearance, Color, InteractionState, Style, StyleViewport, Transform};
+use crate::primitives::{Interactive, Path, Styled};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Triangle {
     pub vertices: [Vec2; 3],
     pub color: Color,
     pub transform: Transform,
+    pub appearance: Appearance,
+    pub state: InteractionState,
     pub css_class: Option<String>,
 }
 
@@ -15,6 +19,8 @@ impl Triangle {
             vertices,
             color: Color::default(),
             transform: Transform::default(),
+            appearance: Appearance::default(),
+            state: InteractionState::NONE,
             css_class: None,
         }
     }
@@ -24,6 +30,7 @@ impl Triangle {
         self
     }
 
+    // One class or a space-separated list, e.g. "button primary"
     pub fn with_css_class(mut self, class: impl Into<String>) -> Self {
         self.css_class = Some(class.into());
         self
@@ -34,9 +41,67 @@ impl Triangle {
         self
     }
 
+    pub fn with_appearance(mut self, appearance: Appearance) -> Self {
+        self.appearance = appearance;
+        self
+    }
+
+    pub fn with_state(mut self, state: InteractionState) -> Self {
+        self.state = state;
+        self
+    }
+
     pub fn apply_css_override(&mut self, color: Color) {
         self.color = color;
     }
+
+    // Applies every property `style` sets. Triangles have no box, so width, height and
+    // border-radius are ignored.
+    pub fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        if let Some(color) = style.fill_color() {
+            self.color = color;
+        }
+        if let Some(transform) = style.transform {
+            self.transform = self.transform.compose(&viewport.clip_transform(transform));
+        }
+        self.appearance.apply_style(style, viewport);
+    }
+}
+
+impl Styled for Triangle {
+    fn css_class(&self) -> Option<&str> {
+        self.css_class.as_deref()
+    }
+
+    fn state(&self) -> InteractionState {
+        self.state
+    }
+
+    fn apply_css_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        Triangle::apply_css_style(self, style, viewport);
+    }
+}
+
+impl Interactive for Triangle {
+    fn hit_test(&self, point: Vec2) -> bool {
+        if !self.appearance.visible {
+            return false;
+        }
+        let local = self.transform.inverse_transform_point(point);
+        let [a, b, c] = self.vertices;
+        let edge = |from: Vec2, to: Vec2| (to - from).perp_dot(local - from);
+        let (d0, d1, d2) = (edge(a, b), edge(b, c), edge(c, a));
+        // Inside when the point is on the same side of every edge, whatever the winding
+        !((d0 < 0.0 || d1 < 0.0 || d2 < 0.0) && (d0 > 0.0 || d1 > 0.0 || d2 > 0.0))
+    }
+
+    fn state_mut(&mut self) -> &mut InteractionState {
+        &mut self.state
+    }
+
+    fn z_index(&self) -> i32 {
+        self.appearance.z_index
+    }
 }
 
 impl Default for Triangle {
@@ -48,3 +113,16 @@ impl Default for Triangle {
         ])
     }
 }
+
+// A filled 3-point path, which is how triangles are drawn
+impl From<&Triangle> for Path {
+    fn from(triangle: &Triangle) -> Self {
+        Path {
+            transform: triangle.transform,
+            appearance: triangle.appearance,
+            state: triangle.state,
+            css_class: triangle.css_class.clone(),
+            ..Path::polygon(triangle.vertices).with_color(triangle.color)
+        }
+    }
+}
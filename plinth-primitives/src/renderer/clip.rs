@@ -0,0 +1,361 @@
+use glam::Vec2;
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shader_source;
+use plinth_core::shaders::ShaderSource;
+use wgpu::util::DeviceExt;
+use wgpu::{Device, RenderPass, RenderPipeline, TextureView};
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::batch::{ClipId, ClipScope, STENCIL_FORMAT};
+use crate::effects::RenderTarget;
+use crate::primitives::{tessellate_path, MeshBuffers, Path, DEFAULT_TOLERANCE};
+use crate::shaders::ShaderPreprocessor;
+
+pub const CLIP_SHADER: ShaderSource = shader_source!("src/shaders/clip.wgsl");
+
+// What a clip pushed while others are active is cut to: the intersection of its rectangle
+// with theirs, and every rounded or path mask among them
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipRegion {
+    // Clip-space corners of the scissor rectangle
+    pub min: Vec2,
+    pub max: Vec2,
+    // Stencil masks, outermost first, as indices into ClipStack::mask
+    pub masks: Vec<usize>,
+}
+
+impl ClipRegion {
+    pub fn is_empty(&self) -> bool {
+        self.max.x <= self.min.x || self.max.y <= self.min.y
+    }
+
+    // The region's rectangle in pixels of a `width` by `height` target as x, y, width and
+    // height from the top-left, grown to whole pixels. None when nothing of it is on screen.
+    pub fn scissor_rect(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let size = Vec2::new(width as f32, height as f32);
+        let to_pixels = |x: f32, y: f32| Vec2::new((x + 1.0) / 2.0, (1.0 - y) / 2.0) * size;
+        let top_left = to_pixels(self.min.x, self.max.y).floor().clamp(Vec2::ZERO, size);
+        let bottom_right = to_pixels(self.max.x, self.min.y).ceil().clamp(Vec2::ZERO, size);
+        let extent = bottom_right - top_left;
+        if self.is_empty() || extent.x <= 0.0 || extent.y <= 0.0 {
+            return None;
+        }
+        Some((top_left.x as u32, top_left.y as u32, extent.x as u32, extent.y as u32))
+    }
+}
+
+// The clips pushed so far. Rectangles are cut with a scissor rectangle alone; rounded
+// rectangles and paths also draw their shape into the stencil buffer. Every push records a
+// region, which stays valid after it's popped so primitives added under it keep their clip.
+#[derive(Debug, Clone, Default)]
+pub struct ClipStack {
+    // ClipId(n) is regions[n - 1]
+    regions: Vec<ClipRegion>,
+    // Triangles of each stencil mask in clip space, three points per triangle
+    masks: Vec<Vec<Vec2>>,
+    active: Vec<ClipId>,
+    // Bumped whenever masks are added or dropped, so their vertices get uploaded again
+    generation: u64,
+}
+
+impl ClipStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Clips to the axis-aligned rectangle between two corners in clip space
+    pub fn push_rect(&mut self, min: Vec2, max: Vec2) -> ClipId {
+        self.push(min.min(max), min.max(max), None)
+    }
+
+    // Clips to a rectangle with circular corners of `radius`, in clip space like its corners.
+    // Without a radius it's a plain rectangle clip and needs no stencil.
+    pub fn push_rounded_rect(&mut self, min: Vec2, max: Vec2, radius: f32) -> ClipId {
+        let (min, max) = (min.min(max), min.max(max));
+        if radius <= 0.0 {
+            return self.push(min, max, None);
+        }
+        let shape = Path::rounded_rect(min, max - min, Vec2::splat(radius));
+        self.push_shape(&shape)
+    }
+
+    // Clips to the fill of `path` after its transform, following its fill rule. Its stroke,
+    // colors and paint are ignored.
+    pub fn push_path(&mut self, path: &Path) -> ClipId {
+        let mut shape = path.clone();
+        shape.fill = Some(Default::default());
+        shape.paint = None;
+        shape.stroke = None;
+        self.push_shape(&shape)
+    }
+
+    fn push_shape(&mut self, shape: &Path) -> ClipId {
+        let mut mesh = MeshBuffers::new();
+        if let Err(e) = tessellate_path(shape, DEFAULT_TOLERANCE, &mut mesh) {
+            // An empty mask, so the clip hides what's drawn in it rather than nothing
+            log::warn!("Clip path failed to tessellate, clipping everything: {:?}", e);
+            return self.push(Vec2::ZERO, Vec2::ZERO, None);
+        }
+        let triangles: Vec<Vec2> = mesh
+            .indices
+            .iter()
+            .map(|index| Vec2::from(mesh.vertices[*index as usize].position))
+            .collect();
+        let min = triangles.iter().copied().reduce(Vec2::min).unwrap_or(Vec2::ZERO);
+        let max = triangles.iter().copied().reduce(Vec2::max).unwrap_or(Vec2::ZERO);
+        self.masks.push(triangles);
+        self.generation += 1;
+        self.push(min, max, Some(self.masks.len() - 1))
+    }
+
+    fn push(&mut self, min: Vec2, max: Vec2, mask: Option<usize>) -> ClipId {
+        let mut region = ClipRegion { min, max, masks: Vec::new() };
+        if let Some(parent) = self.region(self.current()) {
+            region.min = region.min.max(parent.min);
+            region.max = region.max.min(parent.max);
+            region.masks = parent.masks.clone();
+        }
+        region.masks.extend(mask);
+        self.regions.push(region);
+        let id = ClipId(self.regions.len() as u32);
+        self.active.push(id);
+        id
+    }
+
+    // Ends the innermost clip, returning it. Primitives added afterwards go back to the one
+    // around it.
+    pub fn pop(&mut self) -> Option<ClipId> {
+        self.active.pop()
+    }
+
+    // The innermost active clip, ClipId::NONE when there's none
+    pub fn current(&self) -> ClipId {
+        self.active.last().copied().unwrap_or(ClipId::NONE)
+    }
+
+    // Clips currently pushed and not popped
+    pub fn depth(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn region(&self, clip: ClipId) -> Option<&ClipRegion> {
+        (clip.0 as usize).checked_sub(1).and_then(|index| self.regions.get(index))
+    }
+
+    pub fn mask(&self, index: usize) -> &[Vec2] {
+        &self.masks[index]
+    }
+
+    pub fn mask_count(&self) -> usize {
+        self.masks.len()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // Drops every clip, active or not. Ids are handed out again from the start, so clear the
+    // primitives added under the old ones too.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.masks.clear();
+        self.active.clear();
+        self.generation += 1;
+    }
+}
+
+// The GPU half of clipping: a stencil buffer the size of the surface and the pipelines that
+// draw masks into it. Every pass drawing batches attaches the stencil, cleared to 0.
+pub struct ClipMask {
+    stencil: RenderTarget,
+    // Counts a mask where the stencil already holds every mask before it
+    increment_pipeline: RenderPipeline,
+    // Writes 0 wherever it's drawn, to undo masks after their run
+    reset_pipeline: RenderPipeline,
+    // A triangle covering the target, then every mask of the stack
+    vertex_buffer: Option<wgpu::Buffer>,
+    mask_ranges: Vec<Range<u32>>,
+    // Generation of the ClipStack the vertices came from
+    generation: Option<u64>,
+}
+
+impl ClipMask {
+    // For passes over color targets of `targets`, multisampled or not
+    pub fn new(device: &Device, targets: PipelineTargets, width: u32, height: u32) -> Self {
+        let source = ShaderPreprocessor::new()
+            .process(CLIP_SHADER.embedded)
+            .expect("Failed to preprocess clip shader");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(CLIP_SHADER.label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Clip Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |label: &str, compare: wgpu::CompareFunction, pass_op: wgpu::StencilOperation| {
+            let face = wgpu::StencilFaceState {
+                compare,
+                fail_op: wgpu::StencilOperation::Keep,
+                depth_fail_op: wgpu::StencilOperation::Keep,
+                pass_op,
+            };
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2, // position
+                        }],
+                    }],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: targets.format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::empty(),
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: STENCIL_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState {
+                        front: face,
+                        back: face,
+                        read_mask: 0xff,
+                        write_mask: 0xff,
+                    },
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: targets.multisample(),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        Self {
+            stencil: RenderTarget::multisampled(
+                device,
+                width,
+                height,
+                STENCIL_FORMAT,
+                targets.sample_count,
+                "Clip Stencil",
+            ),
+            increment_pipeline: pipeline(
+                "Clip Mask Pipeline",
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::IncrementClamp,
+            ),
+            reset_pipeline: pipeline(
+                "Clip Reset Pipeline",
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::Replace,
+            ),
+            vertex_buffer: None,
+            mask_ranges: Vec::new(),
+            generation: None,
+        }
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.stencil.resize(device, width, height);
+    }
+
+    pub fn stencil_view(&self) -> &TextureView {
+        self.stencil.view()
+    }
+
+    // Uploads the masks of `clips` if they changed since the last upload
+    pub fn upload(&mut self, device: &Device, clips: &ClipStack) {
+        if self.generation == Some(clips.generation()) {
+            return;
+        }
+        self.generation = Some(clips.generation());
+        let mut vertices: Vec<[f32; 2]> = vec![[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]];
+        self.mask_ranges.clear();
+        for index in 0..clips.mask_count() {
+            let start = vertices.len() as u32;
+            vertices.extend(clips.mask(index).iter().map(|point| point.to_array()));
+            self.mask_ranges.push(start..vertices.len() as u32);
+        }
+        // Rebuilt rather than written in place, since masks only change when clips are pushed
+        self.vertex_buffer = Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Clip Mask Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        }));
+    }
+
+    // What batches clip their runs with in a pass over a `width` by `height` target
+    pub fn scope<'a>(&'a self, clips: &'a ClipStack, width: u32, height: u32) -> ClipPass<'a> {
+        ClipPass { mask: self, clips, width, height }
+    }
+}
+
+// The ClipScope batches draw through, see ClipMask::scope. A run's clip is cut with the
+// scissor rectangle of its region; masks are counted into the stencil, one after another
+// where all the previous ones passed, so the run is drawn where the stencil equals their
+// number. The stencil is back to 0 after every run.
+pub struct ClipPass<'a> {
+    mask: &'a ClipMask,
+    clips: &'a ClipStack,
+    width: u32,
+    height: u32,
+}
+
+impl ClipScope for ClipPass<'_> {
+    fn begin(&mut self, render_pass: &mut RenderPass, clip: ClipId) -> bool {
+        // Clips dropped with ClipStack::clear draw unclipped
+        let Some(region) = self.clips.region(clip) else {
+            return true;
+        };
+        let Some((x, y, width, height)) = region.scissor_rect(self.width, self.height) else {
+            return false;
+        };
+        render_pass.set_scissor_rect(x, y, width, height);
+        let buffer = match &self.mask.vertex_buffer {
+            Some(buffer) if !region.masks.is_empty() => buffer,
+            _ => return true,
+        };
+        render_pass.set_pipeline(&self.mask.increment_pipeline);
+        render_pass.set_vertex_buffer(0, buffer.slice(..));
+        for (depth, index) in region.masks.iter().enumerate() {
+            if let Some(range) = self.mask.mask_ranges.get(*index) {
+                render_pass.set_stencil_reference(depth as u32);
+                render_pass.draw(range.clone(), 0..1);
+            }
+        }
+        render_pass.set_stencil_reference(region.masks.len() as u32);
+        true
+    }
+
+    fn end(&mut self, render_pass: &mut RenderPass, clip: ClipId) {
+        let Some(region) = self.clips.region(clip) else {
+            return;
+        };
+        if let Some(buffer) = self.mask.vertex_buffer.as_ref().filter(|_| !region.masks.is_empty()) {
+            // Still scissored, so this only clears what the masks could have touched
+            render_pass.set_pipeline(&self.mask.reset_pipeline);
+            render_pass.set_vertex_buffer(0, buffer.slice(..));
+            render_pass.set_stencil_reference(0);
+            render_pass.draw(0..3, 0..1);
+        }
+        render_pass.set_scissor_rect(0, 0, self.width, self.height);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod clip;
 pub mod primitive_renderer;
 
+pub use clip::*;
 pub use primitive_renderer::*;
@@ -1,21 +1,215 @@
-use wgpu::{Device, Queue, Surface, SurfaceConfiguration, RenderPassDescriptor, RenderPassColorAttachment, Operations, LoadOp, StoreOp, Color};
+use glam::Vec2;
+use wgpu::{
+    BindGroup, BindGroupLayout, Color, CommandEncoder, Device, LoadOp, Operations, Queue, RenderPass,
+    RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor, StoreOp, Surface,
+    SurfaceConfiguration, TextureView,
+};
 
-use crate::primitives::CircleBatch;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::Duration;
+
+use plinth_core::input::PointerEvent;
+use plinth_core::pipelines::PipelineTargets;
+use plinth_core::shaders::ShaderLibrary;
+use plinth_core::textures::TextureManager;
+
+use crate::batch::{render_clip_runs, ClipId, ClipRun, ClipScope};
+use crate::effects::{EffectLayer, GaussianBlur, RenderTarget};
+use crate::primitives::{
+    CircleBatch, FontError, FontId, Interactive, LineBatch, PathBatch, PointerTracker, RectangleBatch, SpriteBatch,
+    StyleResolver, TextBatch,
+};
+use crate::renderer::{ClipMask, ClipStack};
+use crate::types::StyleViewport;
 
 pub struct PrimitiveRenderer {
+    rectangle_batch: RectangleBatch,
     circle_batch: CircleBatch,
-    surface_format: wgpu::TextureFormat,
+    path_batch: PathBatch,
+    line_batch: LineBatch,
+    sprite_batch: SpriteBatch,
+    text_batch: TextBatch,
+    // Blur filters on rectangles and circles, drawn through offscreen targets
+    blur: GaussianBlur,
+    effect_targets: Option<EffectTargets>,
+    // Clips pushed so far and the stencil buffer rounded and path clips are drawn into
+    clips: ClipStack,
+    clip_mask: ClipMask,
+    // What every pipeline is built for. With a sample count above 1 passes draw into `msaa`,
+    // resolved into the surface or offscreen target they are for.
+    targets: PipelineTargets,
+    msaa: Option<RenderTarget>,
+    // Resolves primitives' classes at upload time, typically a shared ClassMapper
+    class_mapper: Option<Rc<RefCell<dyn StyleResolver>>>,
+    // Physical pixels per CSS pixel in class styles
+    scale_factor: f32,
+    pointer: PointerTracker,
 }
 
 impl PrimitiveRenderer {
     pub fn new(device: &Device, surface_format: wgpu::TextureFormat) -> Self {
+        let mut rectangle_batch = RectangleBatch::new();
+        rectangle_batch.create_pipeline(device, surface_format);
         let mut circle_batch = CircleBatch::new();
         circle_batch.create_pipeline(device, surface_format);
-        
+        let mut path_batch = PathBatch::new();
+        path_batch.create_pipeline(device, surface_format);
+        let mut line_batch = LineBatch::new();
+        line_batch.create_pipeline(device, surface_format);
+        let mut sprite_batch = SpriteBatch::new();
+        sprite_batch.create_pipeline(device, surface_format);
+        let mut text_batch = TextBatch::new();
+        text_batch.create_pipeline(device, surface_format);
+        let targets = PipelineTargets { format: surface_format, sample_count: 1 };
+
         Self {
+            rectangle_batch,
             circle_batch,
-            surface_format,
+            path_batch,
+            line_batch,
+            sprite_batch,
+            text_batch,
+            blur: GaussianBlur::new(device, targets.format),
+            effect_targets: None,
+            clips: ClipStack::new(),
+            clip_mask: ClipMask::new(device, targets, 1, 1),
+            targets,
+            msaa: None,
+            pointer: PointerTracker::new(),
+            class_mapper: None,
+            scale_factor: 1.0,
+        }
+    }
+
+    // Rebuilds every pipeline for `targets`, typically `gfx.pipelines.targets()` after
+    // Graphics::set_sample_count. A surface format change is picked up on the next render
+    // by itself.
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) {
+        if self.targets == targets {
+            return;
         }
+        self.targets = targets;
+        self.rectangle_batch.set_targets(device, targets);
+        self.circle_batch.set_targets(device, targets);
+        self.path_batch.set_targets(device, targets);
+        self.line_batch.set_targets(device, targets);
+        self.sprite_batch.set_targets(device, targets);
+        self.text_batch.set_targets(device, targets);
+        self.blur = GaussianBlur::new(device, targets.format);
+        self.effect_targets = None;
+        self.clip_mask = ClipMask::new(device, targets, 1, 1);
+        self.msaa = None;
+    }
+
+    pub fn targets(&self) -> PipelineTargets {
+        self.targets
+    }
+
+    // Styles primitives from their classes and state using `class_mapper` on every render.
+    // Only instances whose resolved style changed are re-uploaded, so editing the mapper
+    // (theme switches, reloads, media changes) restyles the scene without touching it.
+    pub fn with_class_mapper<R: StyleResolver + 'static>(mut self, class_mapper: Rc<RefCell<R>>) -> Self {
+        self.set_class_mapper(Some(class_mapper));
+        self
+    }
+
+    pub fn set_class_mapper<R: StyleResolver + 'static>(&mut self, class_mapper: Option<Rc<RefCell<R>>>) {
+        self.class_mapper = class_mapper.map(|class_mapper| class_mapper as Rc<RefCell<dyn StyleResolver>>);
+    }
+
+    // Physical pixels per CSS pixel, e.g. `ctx.scale_factor()`. Lengths in class styles are
+    // CSS pixels, converted against it and the surface size on the next render.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    // Time style transitions and keyframe animations are sampled at on the next render,
+    // e.g. `ctx.time().elapsed` from `before_render`
+    pub fn set_time(&mut self, time: Duration) {
+        self.rectangle_batch.set_style_time(time);
+        self.circle_batch.set_style_time(time);
+        self.path_batch.set_style_time(time);
+        self.line_batch.set_style_time(time);
+        self.sprite_batch.set_style_time(time);
+        self.text_batch.set_style_time(time);
+    }
+
+    // Whether a style transition or animation is still running, i.e. whether the app should
+    // keep requesting redraws
+    pub fn is_animating(&self) -> bool {
+        self.rectangle_batch.is_animating()
+            || self.circle_batch.is_animating()
+            || self.path_batch.is_animating()
+            || self.line_batch.is_animating()
+            || self.sprite_batch.is_animating()
+            || self.text_batch.is_animating()
+    }
+
+    // Clips primitives added from now on to the rectangle between two corners, within the
+    // clips already pushed, until the matching `pop_clip`. Drawn with a scissor rectangle.
+    pub fn push_clip_rect(&mut self, min: Vec2, max: Vec2) -> ClipId {
+        let clip = self.clips.push_rect(min, max);
+        self.set_batch_clips(clip);
+        clip
+    }
+
+    // Same as `push_clip_rect` with corners rounded by `radius`, cut through the stencil buffer
+    pub fn push_clip_rounded_rect(&mut self, min: Vec2, max: Vec2, radius: f32) -> ClipId {
+        let clip = self.clips.push_rounded_rect(min, max, radius);
+        self.set_batch_clips(clip);
+        clip
+    }
+
+    // Same as `push_clip_rect` for the fill of an arbitrary path, cut through the stencil
+    // buffer, e.g. a mask
+    pub fn push_clip_path(&mut self, path: &crate::primitives::Path) -> ClipId {
+        let clip = self.clips.push_path(path);
+        self.set_batch_clips(clip);
+        clip
+    }
+
+    // Ends the innermost clip. Primitives already added keep being drawn in it.
+    pub fn pop_clip(&mut self) -> Option<ClipId> {
+        let clip = self.clips.pop();
+        self.set_batch_clips(self.clips.current());
+        clip
+    }
+
+    // Drops every clip, see ClipStack::clear
+    pub fn clear_clips(&mut self) {
+        self.clips.clear();
+        self.set_batch_clips(ClipId::NONE);
+    }
+
+    pub fn clip_stack(&self) -> &ClipStack {
+        &self.clips
+    }
+
+    fn set_batch_clips(&mut self, clip: ClipId) {
+        self.rectangle_batch.set_clip(clip);
+        self.circle_batch.set_clip(clip);
+        self.path_batch.set_clip(clip);
+        self.line_batch.set_clip(clip);
+        self.sprite_batch.set_clip(clip);
+        self.text_batch.set_clip(clip);
+    }
+
+    pub fn add_rectangle(&mut self, rectangle: crate::primitives::Rectangle) {
+        self.rectangle_batch.add_rectangle(rectangle);
+    }
+
+    pub fn add_rectangles(&mut self, rectangles: impl IntoIterator<Item = crate::primitives::Rectangle>) {
+        self.rectangle_batch.add_rectangles(rectangles);
+    }
+
+    pub fn clear_rectangles(&mut self) {
+        self.rectangle_batch.clear();
+    }
+
+    pub fn rectangles_mut(&mut self) -> &mut [crate::primitives::Rectangle] {
+        self.rectangle_batch.rectangles_mut()
     }
 
     pub fn add_circle(&mut self, circle: crate::primitives::Circle) {
@@ -30,41 +224,556 @@ impl PrimitiveRenderer {
         self.circle_batch.clear();
     }
 
-    pub fn render(&mut self, device: &Device, queue: &Queue, surface: &Surface, _surface_config: &SurfaceConfiguration) {
-        // Update instance buffers
+    pub fn circles_mut(&mut self) -> &mut [crate::primitives::Circle] {
+        self.circle_batch.circles_mut()
+    }
+
+    pub fn add_path(&mut self, path: crate::primitives::Path) {
+        self.path_batch.add_path(path);
+    }
+
+    pub fn add_paths(&mut self, paths: impl IntoIterator<Item = crate::primitives::Path>) {
+        self.path_batch.add_paths(paths);
+    }
+
+    pub fn clear_paths(&mut self) {
+        self.path_batch.clear();
+    }
+
+    pub fn paths_mut(&mut self) -> &mut [crate::primitives::Path] {
+        self.path_batch.paths_mut()
+    }
+
+    pub fn add_line(&mut self, line: crate::primitives::Line) {
+        self.line_batch.add_line(line);
+    }
+
+    pub fn add_lines(&mut self, lines: impl IntoIterator<Item = crate::primitives::Line>) {
+        self.line_batch.add_lines(lines);
+    }
+
+    pub fn add_polyline(&mut self, polyline: crate::primitives::Polyline) {
+        self.line_batch.add_polyline(polyline);
+    }
+
+    pub fn add_polylines(&mut self, polylines: impl IntoIterator<Item = crate::primitives::Polyline>) {
+        self.line_batch.add_polylines(polylines);
+    }
+
+    // Removes lines and polylines, which share a batch
+    pub fn clear_lines(&mut self) {
+        self.line_batch.clear();
+    }
+
+    // Lines are kept as polylines
+    pub fn lines_mut(&mut self) -> &mut [crate::primitives::Polyline] {
+        self.line_batch.lines_mut()
+    }
+
+    // Sprites are drawn from the textures passed to `render_with_textures`
+    pub fn add_sprite(&mut self, sprite: crate::primitives::Sprite) {
+        self.sprite_batch.add_sprite(sprite);
+    }
+
+    pub fn add_sprites(&mut self, sprites: impl IntoIterator<Item = crate::primitives::Sprite>) {
+        self.sprite_batch.add_sprites(sprites);
+    }
+
+    pub fn clear_sprites(&mut self) {
+        self.sprite_batch.clear();
+    }
+
+    pub fn sprites_mut(&mut self) -> &mut [crate::primitives::Sprite] {
+        self.sprite_batch.sprites_mut()
+    }
+
+    pub fn add_text(&mut self, text: crate::primitives::Text) {
+        self.text_batch.add_text(text);
+    }
+
+    pub fn add_texts(&mut self, texts: impl IntoIterator<Item = crate::primitives::Text>) {
+        self.text_batch.add_texts(texts);
+    }
+
+    pub fn clear_texts(&mut self) {
+        self.text_batch.clear();
+    }
+
+    pub fn texts_mut(&mut self) -> &mut [crate::primitives::Text] {
+        self.text_batch.texts_mut()
+    }
+
+    // Applies a pointer event to the :hover/:active/:focus state of every primitive, hit-testing
+    // them as and in the order they were last drawn, and restyles the batches if any state
+    // changed. `surface_size` is in
+    // physical pixels, e.g. `ctx.size()`. Returns whether a state changed.
+    pub fn handle_pointer_event(&mut self, event: &PointerEvent, surface_size: (u32, u32)) -> bool {
+        self.update_pointer(|pointer, primitives| pointer.handle_event(event, surface_size, primitives))
+    }
+
+    fn update_pointer(&mut self, update: impl FnOnce(&mut PointerTracker, Vec<&mut dyn Interactive>) -> bool) -> bool {
+        let (mut rectangles, rectangles_changed) = self.rectangle_batch.interaction_mut();
+        let (mut circles, circles_changed) = self.circle_batch.interaction_mut();
+        let (mut paths, paths_changed) = self.path_batch.interaction_mut();
+        let (mut lines, lines_changed) = self.line_batch.interaction_mut();
+        let (mut sprites, sprites_changed) = self.sprite_batch.interaction_mut();
+        let (mut texts, texts_changed) = self.text_batch.interaction_mut();
+        // The batches' draw order, see `render_frame`
+        let primitives = rectangles
+            .iter_mut()
+            .map(|rectangle| rectangle as &mut dyn Interactive)
+            .chain(circles.iter_mut().map(|circle| circle as &mut dyn Interactive))
+            .chain(paths.iter_mut().map(|path| path as &mut dyn Interactive))
+            .chain(lines.iter_mut().map(|line| line as &mut dyn Interactive))
+            .chain(sprites.iter_mut().map(|sprite| sprite as &mut dyn Interactive))
+            .chain(texts.iter_mut().map(|text| text as &mut dyn Interactive))
+            .collect();
+        let changed = update(&mut self.pointer, primitives);
+        if changed {
+            let batches = [rectangles_changed, circles_changed, paths_changed, lines_changed, sprites_changed, texts_changed];
+            for batch_changed in batches {
+                *batch_changed = true;
+            }
+        }
+        changed
+    }
+
+    // Loads a TTF/OTF font from memory for texts to pick by `font-family`. The first one
+    // loaded is the fallback for families that aren't.
+    pub fn load_font(&mut self, family: impl Into<String>, data: impl Into<Vec<u8>>) -> Result<FontId, FontError> {
+        self.text_batch.load_font(family, data)
+    }
+
+    // Replaces what's drawn with the primitives of `scene`. Triangles are drawn as paths.
+    #[cfg(feature = "serde")]
+    pub fn load_scene(&mut self, scene: &crate::scene::Scene) {
+        self.rectangle_batch.clear();
+        self.rectangle_batch.add_rectangles(scene.rectangles.iter().cloned());
+        self.circle_batch.clear();
+        self.circle_batch.add_circles(scene.circles.iter().cloned());
+        self.path_batch.clear();
+        self.path_batch.add_paths(scene.paths.iter().cloned());
+        self.path_batch.add_paths(scene.triangles.iter().map(crate::primitives::Path::from));
+        self.line_batch.clear();
+        self.line_batch.add_lines(scene.lines.iter().cloned());
+        self.line_batch.add_polylines(scene.polylines.iter().cloned());
+        self.text_batch.clear();
+        self.text_batch.add_texts(scene.texts.iter().cloned());
+        self.sprite_batch.clear();
+        self.sprite_batch.add_sprites(scene.sprites.iter().cloned());
+    }
+
+    // Same as `load_scene` for a `.ron` or `.json` file
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn load_scene_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), crate::scene::SceneError> {
+        let scene = crate::scene::Scene::load(path)?;
+        self.load_scene(&scene);
+        Ok(())
+    }
+
+    // Rebuilds batch pipelines whose shaders changed on disk. Pass `gfx.pipelines.shaders`;
+    // it only reports changes once shader hot reload is enabled on Graphics.
+    pub fn reload_shaders(&mut self, device: &Device, shaders: &mut ShaderLibrary) -> bool {
+        // Every batch must get the chance to watch its files
+        let rectangles = self.rectangle_batch.reload_shaders(device, shaders);
+        let circles = self.circle_batch.reload_shaders(device, shaders);
+        let paths = self.path_batch.reload_shaders(device, shaders);
+        let lines = self.line_batch.reload_shaders(device, shaders);
+        let sprites = self.sprite_batch.reload_shaders(device, shaders);
+        let texts = self.text_batch.reload_shaders(device, shaders);
+        rectangles || circles || paths || lines || sprites || texts
+    }
+
+    // Toggles a define in every batch shader (e.g. PREMULTIPLIED_ALPHA, DEBUG_OVERLAY)
+    pub fn set_shader_define(&mut self, device: &Device, name: &str, enabled: bool) {
+        self.rectangle_batch.set_shader_define(device, name, enabled);
+        self.circle_batch.set_shader_define(device, name, enabled);
+        self.path_batch.set_shader_define(device, name, enabled);
+        self.line_batch.set_shader_define(device, name, enabled);
+        self.sprite_batch.set_shader_define(device, name, enabled);
+        self.text_batch.set_shader_define(device, name, enabled);
+    }
+
+    // Draws everything but sprites, which need `render_with_textures`
+    pub fn render(&mut self, device: &Device, queue: &Queue, surface: &Surface, surface_config: &SurfaceConfiguration) {
+        self.render_frame(device, queue, surface, surface_config, None);
+    }
+
+    // Same as `render`, also drawing sprites from `textures` (typically `gfx.textures`).
+    // Textures loaded since the last frame are uploaded first.
+    pub fn render_with_textures(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface: &Surface,
+        surface_config: &SurfaceConfiguration,
+        textures: &mut TextureManager,
+    ) {
+        self.render_frame(device, queue, surface, surface_config, Some(textures));
+    }
+
+    fn render_frame(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        surface: &Surface,
+        surface_config: &SurfaceConfiguration,
+        mut textures: Option<&mut TextureManager>,
+    ) {
+        if surface_config.format != self.targets.format {
+            self.set_targets(device, PipelineTargets { format: surface_config.format, ..self.targets });
+        }
+        self.line_batch.set_surface_size(surface_config.width, surface_config.height);
+        self.text_batch.set_surface_size(surface_config.width, surface_config.height);
+        let viewport =
+            StyleViewport::new(surface_config.width, surface_config.height).with_scale_factor(self.scale_factor);
+        self.rectangle_batch.set_style_viewport(viewport);
+        self.circle_batch.set_style_viewport(viewport);
+        self.path_batch.set_style_viewport(viewport);
+        self.line_batch.set_style_viewport(viewport);
+        self.sprite_batch.set_style_viewport(viewport);
+        self.text_batch.set_style_viewport(viewport);
+
+        // Resolve class styles, then upload whatever changed
+        self.resolve_styles(textures.as_deref());
+        // Primitives may have moved under a still pointer, which restyles those it enters or
+        // leaves
+        if self.pointer.position().is_some() && self.update_pointer(|pointer, primitives| pointer.refresh(primitives)) {
+            self.resolve_styles(textures.as_deref());
+        }
+        self.rectangle_batch.update_buffer(device, queue);
         self.circle_batch.update_buffer(device, queue);
+        self.path_batch.update_buffer(device, queue);
+        self.line_batch.update_buffer(device, queue);
+        if let Some(textures) = textures.as_deref_mut() {
+            textures.upload(device, queue);
+            self.sprite_batch.update_buffer(device, queue, textures);
+        }
+        self.text_batch.update_buffer(device, queue);
+        let (width, height) = (surface_config.width, surface_config.height);
+        self.clip_mask.resize(device, width, height);
+        self.clip_mask.upload(device, &self.clips);
+        if self.targets.sample_count > 1 {
+            let (format, sample_count) = (self.targets.format, self.targets.sample_count);
+            self.msaa
+                .get_or_insert_with(|| RenderTarget::multisampled(device, width, height, format, sample_count, "MSAA Target"))
+                .resize(device, width, height);
+        }
 
         let frame = surface.get_current_texture().expect("Failed to acquire next swap chain texture");
         let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::TRANSPARENT),
-                        store: StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            // Render all primitive batches
-            self.circle_batch.render(&mut render_pass);
+        let sprites = textures.is_some();
+        let effects = self.rectangle_batch.layers().iter().chain(self.circle_batch.layers()).any(EffectLayer::has_effects);
+        if effects {
+            self.render_effects(device, &mut encoder, &view, surface_config, sprites);
+        } else {
+            let passes = self.pass_targets(device);
+            let mut render_pass = passes.begin_clear(&mut encoder, &view);
+            let mut scope = self.clip_mask.scope(&self.clips, width, height);
+            // Render all primitive batches, rectangles first as backgrounds and text last so
+            // labels sit on top of shapes and images
+            self.rectangle_batch.render_clipped(&mut render_pass, &mut scope);
+            self.circle_batch.render_clipped(&mut render_pass, &mut scope);
+            self.render_unlayered(&mut render_pass, &mut scope, sprites);
         }
 
         queue.submit(Some(encoder.finish()));
         frame.present();
     }
 
+    fn resolve_styles(&mut self, textures: Option<&TextureManager>) {
+        let class_mapper = self.class_mapper.as_ref().map(|class_mapper| class_mapper.borrow());
+        let resolver = class_mapper.as_deref();
+        self.rectangle_batch.resolve_styles(resolver);
+        self.circle_batch.resolve_styles(resolver);
+        self.path_batch.resolve_styles(resolver);
+        self.line_batch.resolve_styles(resolver);
+        if let Some(textures) = textures {
+            self.sprite_batch.resolve_styles(resolver, textures);
+        }
+        self.text_batch.resolve_styles(resolver);
+    }
+
+    fn pass_targets<'a>(&'a self, device: &'a Device) -> PassTargets<'a> {
+        PassTargets {
+            device,
+            stencil: self.clip_mask.stencil_view(),
+            msaa: self.msaa.as_ref().map(RenderTarget::view),
+            blur: &self.blur,
+        }
+    }
+
+    fn render_unlayered(&self, render_pass: &mut RenderPass, scope: &mut dyn ClipScope, sprites: bool) {
+        self.path_batch.render_clipped(render_pass, scope);
+        self.line_batch.render_clipped(render_pass, scope);
+        if sprites {
+            self.sprite_batch.render_clipped(render_pass, scope);
+        }
+        self.text_batch.render_clipped(render_pass, scope);
+    }
+
+    // The same frame drawn into an offscreen target so blur filters can read it back.
+    // Rectangles and circles go layer by layer: a blurred layer is drawn on its own, blurred
+    // and laid over the frame; a backdrop layer first gets the frame so far, blurred, under
+    // its shapes. Backdrops only see what is drawn before them, so rectangles' backdrops
+    // don't see circles. Clips cut a blurred layer before it's blurred, so the blur still
+    // spreads past them.
+    fn render_effects(
+        &mut self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        surface_config: &SurfaceConfiguration,
+        sprites: bool,
+    ) {
+        let (width, height) = (surface_config.width, surface_config.height);
+        let format = self.targets.format;
+        let mut targets = self.effect_targets.take().unwrap_or_else(|| EffectTargets::new(device, width, height, format));
+        targets.resize(device, width, height);
+
+        let passes = PassTargets {
+            device,
+            stencil: self.clip_mask.stencil_view(),
+            msaa: self.msaa.as_ref().map(RenderTarget::view),
+            blur: &self.blur,
+        };
+        let mut scope = self.clip_mask.scope(&self.clips, width, height);
+        // An empty pass just to clear the frame, every later pass draws over it
+        passes.begin_clear(encoder, targets.frame.view());
+        render_layers(&mut self.rectangle_batch, encoder, &passes, &targets, &mut scope);
+        render_layers(&mut self.circle_batch, encoder, &passes, &targets, &mut scope);
+        passes.draw_over(encoder, targets.frame.view(), &targets.scratch, |render_pass| {
+            self.render_unlayered(render_pass, &mut scope, sprites)
+        });
+        self.blur.copy(device, encoder, &targets.frame, view);
+        self.effect_targets = Some(targets);
+    }
+
+    pub fn get_rectangle_batch_mut(&mut self) -> &mut RectangleBatch {
+        &mut self.rectangle_batch
+    }
+
     pub fn get_circle_batch_mut(&mut self) -> &mut CircleBatch {
         &mut self.circle_batch
     }
+
+    pub fn get_path_batch_mut(&mut self) -> &mut PathBatch {
+        &mut self.path_batch
+    }
+
+    pub fn get_line_batch_mut(&mut self) -> &mut LineBatch {
+        &mut self.line_batch
+    }
+
+    pub fn get_sprite_batch_mut(&mut self) -> &mut SpriteBatch {
+        &mut self.sprite_batch
+    }
+
+    pub fn get_text_batch_mut(&mut self) -> &mut TextBatch {
+        &mut self.text_batch
+    }
+}
+
+// Offscreen targets for frames with blur filters, all the size of the surface
+struct EffectTargets {
+    // The frame being drawn, copied to the surface at the end
+    frame: RenderTarget,
+    // A blurred layer on its own
+    layer: RenderTarget,
+    // The frame behind a backdrop layer, blurred
+    backdrop: RenderTarget,
+    // Between the two passes of a blur
+    scratch: RenderTarget,
+}
+
+impl EffectTargets {
+    fn new(device: &Device, width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        Self {
+            frame: RenderTarget::new(device, width, height, format, "Effect Frame Target"),
+            layer: RenderTarget::new(device, width, height, format, "Effect Layer Target"),
+            backdrop: RenderTarget::new(device, width, height, format, "Effect Backdrop Target"),
+            scratch: RenderTarget::new(device, width, height, format, "Effect Scratch Target"),
+        }
+    }
+
+    fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        for target in [&mut self.frame, &mut self.layer, &mut self.backdrop, &mut self.scratch] {
+            target.resize(device, width, height);
+        }
+    }
+}
+
+// Batches whose instances come in effect layers
+trait LayeredBatch {
+    fn layers(&self) -> &[EffectLayer];
+    fn render_range(&self, render_pass: &mut RenderPass, instances: Range<usize>);
+    fn render_backdrop(
+        &mut self,
+        device: &Device,
+        render_pass: &mut RenderPass,
+        instances: Range<usize>,
+        layout: &BindGroupLayout,
+        backdrop: &BindGroup,
+    );
+}
+
+impl LayeredBatch for RectangleBatch {
+    fn layers(&self) -> &[EffectLayer] {
+        RectangleBatch::layers(self)
+    }
+
+    fn render_range(&self, render_pass: &mut RenderPass, instances: Range<usize>) {
+        RectangleBatch::render_range(self, render_pass, instances);
+    }
+
+    fn render_backdrop(
+        &mut self,
+        device: &Device,
+        render_pass: &mut RenderPass,
+        instances: Range<usize>,
+        layout: &BindGroupLayout,
+        backdrop: &BindGroup,
+    ) {
+        RectangleBatch::render_backdrop(self, device, render_pass, instances, layout, backdrop);
+    }
+}
+
+impl LayeredBatch for CircleBatch {
+    fn layers(&self) -> &[EffectLayer] {
+        CircleBatch::layers(self)
+    }
+
+    fn render_range(&self, render_pass: &mut RenderPass, instances: Range<usize>) {
+        CircleBatch::render_range(self, render_pass, instances);
+    }
+
+    fn render_backdrop(
+        &mut self,
+        device: &Device,
+        render_pass: &mut RenderPass,
+        instances: Range<usize>,
+        layout: &BindGroupLayout,
+        backdrop: &BindGroup,
+    ) {
+        CircleBatch::render_backdrop(self, device, render_pass, instances, layout, backdrop);
+    }
+}
+
+fn render_layers(
+    batch: &mut dyn LayeredBatch,
+    encoder: &mut CommandEncoder,
+    passes: &PassTargets,
+    targets: &EffectTargets,
+    scope: &mut dyn ClipScope,
+) {
+    let PassTargets { device, blur, .. } = *passes;
+    let layers = batch.layers().to_vec();
+    for layer in layers {
+        let run = [ClipRun { instances: layer.instances.clone(), clip: layer.clip }];
+        if layer.backdrop_blur > 0.0 {
+            blur.blur(device, encoder, &targets.frame, &targets.scratch, &targets.backdrop, layer.backdrop_blur);
+            let backdrop = blur.bind_texture(device, targets.backdrop.view());
+            passes.draw_over(encoder, targets.frame.view(), &targets.scratch, |render_pass| {
+                render_clip_runs(render_pass, &run, scope, |render_pass, instances| {
+                    batch.render_backdrop(device, render_pass, instances, blur.texture_layout(), &backdrop)
+                })
+            });
+        }
+        if layer.blur > 0.0 {
+            {
+                let mut render_pass = passes.begin_clear(encoder, targets.layer.view());
+                render_clip_runs(&mut render_pass, &run, scope, |render_pass, instances| {
+                    batch.render_range(render_pass, instances)
+                });
+            }
+            blur.blur(device, encoder, &targets.layer, &targets.scratch, &targets.layer, layer.blur);
+            blur.composite(device, encoder, &targets.layer, targets.frame.view());
+        } else {
+            passes.draw_over(encoder, targets.frame.view(), &targets.scratch, |render_pass| {
+                render_clip_runs(render_pass, &run, scope, |render_pass, instances| {
+                    batch.render_range(render_pass, instances)
+                })
+            });
+        }
+    }
+}
+
+// What every pass drawing batches attaches: the clip stencil and, under MSAA, the
+// multisampled color target it draws into before resolving
+#[derive(Clone, Copy)]
+struct PassTargets<'a> {
+    device: &'a Device,
+    stencil: &'a TextureView,
+    msaa: Option<&'a TextureView>,
+    blur: &'a GaussianBlur,
+}
+
+impl PassTargets<'_> {
+    // A pass drawing into `view` cleared to transparent
+    fn begin_clear<'e>(&self, encoder: &'e mut CommandEncoder, view: &TextureView) -> RenderPass<'e> {
+        let clear = LoadOp::Clear(Color::TRANSPARENT);
+        match self.msaa {
+            Some(msaa) => begin_pass(encoder, msaa, Some(view), self.stencil, clear),
+            None => begin_pass(encoder, view, None, self.stencil, clear),
+        }
+    }
+
+    // Draws over what `view` holds. A multisampled target can't load a single-sampled one,
+    // so under MSAA the pass draws from transparent, resolves into `scratch` and that is
+    // composited over `view`, the same as blending straight into it.
+    fn draw_over(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        scratch: &RenderTarget,
+        draw: impl FnOnce(&mut RenderPass),
+    ) {
+        match self.msaa {
+            Some(_) => {
+                draw(&mut self.begin_clear(encoder, scratch.view()));
+                self.blur.composite(self.device, encoder, scratch, view);
+            }
+            None => draw(&mut begin_pass(encoder, view, None, self.stencil, LoadOp::Load)),
+        }
+    }
+}
+
+// Every pass drawing batches attaches the clip stencil. Clips leave it at 0 after each run,
+// so it starts cleared and is never kept. A multisampled `view` is resolved into
+// `resolve_target` and dropped.
+fn begin_pass<'a>(
+    encoder: &'a mut CommandEncoder,
+    view: &TextureView,
+    resolve_target: Option<&TextureView>,
+    stencil: &TextureView,
+    load: LoadOp<Color>,
+) -> RenderPass<'a> {
+    encoder.begin_render_pass(&RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: Operations {
+                load,
+                store: if resolve_target.is_some() { StoreOp::Discard } else { StoreOp::Store },
+            },
+            depth_slice: None,
+        })],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+            view: stencil,
+            depth_ops: Some(Operations {
+                load: LoadOp::Clear(1.0),
+                store: StoreOp::Discard,
+            }),
+            stencil_ops: Some(Operations {
+                load: LoadOp::Clear(0),
+                store: StoreOp::Discard,
+            }),
+        }),
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    })
 }
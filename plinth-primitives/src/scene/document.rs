@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::primitives::{Circle, Line, Path, Polyline, Rectangle, Sprite, Text, Triangle};
+
+// A static set of primitives described as data, e.g. a diagram or a test fixture. Every
+// field of a primitive is optional in the document and defaults like its constructor does,
+// so a circle can be as short as `(center: (0.0, 0.0), radius: 0.2, css_class: "node")`
+// in RON (with `#![enable(implicit_some)]`) or the same object in JSON. Colors accept
+// CSS color strings, and a path's commands are written as SVG path data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene {
+    pub circles: Vec<Circle>,
+    pub rectangles: Vec<Rectangle>,
+    pub triangles: Vec<Triangle>,
+    pub paths: Vec<Path>,
+    pub lines: Vec<Line>,
+    pub polylines: Vec<Polyline>,
+    pub texts: Vec<Text>,
+    pub sprites: Vec<Sprite>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneFormat {
+    Ron,
+    Json,
+}
+
+impl SceneFormat {
+    // From a file extension, `.ron` or `.json`
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        if extension.eq_ignore_ascii_case("ron") {
+            Some(Self::Ron)
+        } else if extension.eq_ignore_ascii_case("json") {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_circle(mut self, circle: Circle) -> Self {
+        self.circles.push(circle);
+        self
+    }
+
+    pub fn with_rectangle(mut self, rectangle: Rectangle) -> Self {
+        self.rectangles.push(rectangle);
+        self
+    }
+
+    pub fn with_triangle(mut self, triangle: Triangle) -> Self {
+        self.triangles.push(triangle);
+        self
+    }
+
+    pub fn with_path(mut self, path: Path) -> Self {
+        self.paths.push(path);
+        self
+    }
+
+    pub fn with_line(mut self, line: Line) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    pub fn with_polyline(mut self, polyline: Polyline) -> Self {
+        self.polylines.push(polyline);
+        self
+    }
+
+    pub fn with_text(mut self, text: Text) -> Self {
+        self.texts.push(text);
+        self
+    }
+
+    pub fn with_sprite(mut self, sprite: Sprite) -> Self {
+        self.sprites.push(sprite);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.circles.len()
+            + self.rectangles.len()
+            + self.triangles.len()
+            + self.paths.len()
+            + self.lines.len()
+            + self.polylines.len()
+            + self.texts.len()
+            + self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn parse(text: &str, format: SceneFormat) -> Result<Self, SceneError> {
+        match format {
+            SceneFormat::Ron => Self::from_ron(text),
+            SceneFormat::Json => Self::from_json(text),
+        }
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Self, SceneError> {
+        ron::from_str(ron).map_err(|e| SceneError::Ron(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        serde_json::from_str(json).map_err(|e| SceneError::Json(e.to_string()))
+    }
+
+    pub fn to_text(&self, format: SceneFormat) -> Result<String, SceneError> {
+        match format {
+            SceneFormat::Ron => self.to_ron(),
+            SceneFormat::Json => self.to_json(),
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| SceneError::Ron(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, SceneError> {
+        serde_json::to_string_pretty(self).map_err(|e| SceneError::Json(e.to_string()))
+    }
+
+    // Reads a `.ron` or `.json` scene file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let format = SceneFormat::from_path(path).ok_or_else(|| SceneError::UnknownFormat(path.display().to_string()))?;
+        let text = std::fs::read_to_string(path).map_err(|e| SceneError::Io(path.display().to_string(), e.to_string()))?;
+        Self::parse(&text, format)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> Result<(), SceneError> {
+        let path = path.as_ref();
+        let format = SceneFormat::from_path(path).ok_or_else(|| SceneError::UnknownFormat(path.display().to_string()))?;
+        std::fs::write(path, self.to_text(format)?).map_err(|e| SceneError::Io(path.display().to_string(), e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SceneError {
+    #[error("Invalid RON scene: {0}")]
+    Ron(String),
+    #[error("Invalid JSON scene: {0}")]
+    Json(String),
+    #[error("Failed to access {0}: {1}")]
+    Io(String, String),
+    #[error("Unknown scene format for {0}, expected .ron or .json")]
+    UnknownFormat(String),
+}
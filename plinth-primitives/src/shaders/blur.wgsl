@@ -0,0 +1,56 @@
+// One direction of a separable Gaussian blur over a render target, plus the copies that put
+// targets back into the frame. Targets hold premultiplied color, so blurring never bleeds
+// the color of transparent texels.
+
+struct BlurParams {
+    // One step between taps, in texture coordinates
+    direction: vec2<f32>,
+    // Weights in use, the center's included
+    taps: f32,
+    _padding: f32,
+    // weights[0] is the center, then one per step out on either side
+    weights: array<vec4<f32>, 16>,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+
+// Only bound for fs_blur
+@group(1) @binding(0) var<uniform> params: BlurParams;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One triangle covering the target, uv 0 at the top-left
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+fn blur_weight(index: u32) -> f32 {
+    return params.weights[index / 4u][index % 4u];
+}
+
+@fragment
+fn fs_blur(input: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSampleLevel(source, source_sampler, input.uv, 0.0) * blur_weight(0u);
+    let taps = u32(params.taps);
+    for (var i = 1u; i < taps; i = i + 1u) {
+        let offset = params.direction * f32(i);
+        let pair = textureSampleLevel(source, source_sampler, input.uv + offset, 0.0)
+            + textureSampleLevel(source, source_sampler, input.uv - offset, 0.0);
+        color = color + pair * blur_weight(i);
+    }
+    return color;
+}
+
+@fragment
+fn fs_copy(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSampleLevel(source, source_sampler, input.uv, 0.0);
+}
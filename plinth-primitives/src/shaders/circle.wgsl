@@ -1,9 +1,26 @@
+#include "plinth/quad.wgsl"
+#include "plinth/transform.wgsl"
+#include "plinth/sdf.wgsl"
+#include "plinth/color.wgsl"
+#include "plinth/paint.wgsl"
+#include "plinth/shadow.wgsl"
+
+#ifdef BACKDROP
+// The blurred frame behind the circle, premultiplied
+@group(1) @binding(0) var backdrop: texture_2d<f32>;
+@group(1) @binding(1) var backdrop_sampler: sampler;
+#endif
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
-    @location(0) world_pos: vec2<f32>,
-    @location(1) center: vec2<f32>,
-    @location(2) radius: f32,
-    @location(3) color: vec4<f32>,
+    @location(0) radius: f32,
+    @location(1) color: vec4<f32>,
+    // Around the center before the transform, where the circle and its paint are laid out
+    @location(2) local: vec2<f32>,
+    @location(3) @interpolate(flat) paint_params: vec4<f32>,
+    @location(4) @interpolate(flat) paint_geometry: vec4<f32>,
+    @location(5) @interpolate(flat) shadow_params: vec4<f32>,
+    @location(6) @interpolate(flat) shadow_color: vec4<f32>,
 }
 
 struct CircleInstance {
@@ -13,55 +30,73 @@ struct CircleInstance {
     @location(3) transform_position: vec2<f32>,
     @location(4) transform_scale: vec2<f32>,
     @location(5) transform_rotation: f32,
+    @location(6) paint_params: vec4<f32>,
+    @location(7) paint_geometry: vec4<f32>,
+    @location(8) shadow_params: vec4<f32>,
+    @location(9) shadow_color: vec4<f32>,
 }
 
-// Quad vertices for instanced rendering
-const QUAD_VERTICES: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
-    vec2<f32>(-1.0, -1.0), // Bottom-left
-    vec2<f32>( 1.0, -1.0), // Bottom-right
-    vec2<f32>(-1.0,  1.0), // Top-left
-    vec2<f32>(-1.0,  1.0), // Top-left
-    vec2<f32>( 1.0, -1.0), // Bottom-right
-    vec2<f32>( 1.0,  1.0), // Top-right
-);
-
 @vertex
 fn vs_main(@builtin(vertex_index) vertex_index: u32, instance: CircleInstance) -> VertexOutput {
-    let quad_vertex = QUAD_VERTICES[vertex_index];
-    
-    // Apply rotation
-    let cos_rot = cos(instance.transform_rotation);
-    let sin_rot = sin(instance.transform_rotation);
-    let rotated_vertex = vec2<f32>(
-        quad_vertex.x * cos_rot - quad_vertex.y * sin_rot,
-        quad_vertex.x * sin_rot + quad_vertex.y * cos_rot
+    // Scaled and turned around its center, which the transform's position moves
+    let center = instance.center + instance.transform_position;
+    // Grown to fit the shadow
+    let extent = instance.radius + shadow_margin(instance.shadow_params, instance.shadow_color);
+    let local = QUAD_VERTICES[vertex_index] * extent;
+    let world_pos = apply_transform(
+        local,
+        center,
+        instance.transform_scale,
+        instance.transform_rotation,
     );
-    
-    // Apply scale and position
-    let scaled_vertex = rotated_vertex * instance.transform_scale;
-    let world_pos = scaled_vertex + instance.center + instance.transform_position;
-    
+
     var output: VertexOutput;
     output.position = vec4<f32>(world_pos, 0.0, 1.0);
-    output.world_pos = world_pos;
-    output.center = instance.center + instance.transform_position;
     output.radius = instance.radius;
     output.color = instance.color;
-    
+    output.local = local;
+    output.paint_params = instance.paint_params;
+    output.paint_geometry = instance.paint_geometry;
+    output.shadow_params = instance.shadow_params;
+    output.shadow_color = instance.shadow_color;
+
     return output;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let distance = length(input.world_pos - input.center);
-    
-    if (distance > input.radius) {
-        discard;
-    }
-    
+    let p = input.local;
+    let distance = sd_circle(p, input.radius);
     // Smooth edges for anti-aliasing
     let edge_smoothness = 1.0;
-    let alpha = 1.0 - smoothstep(input.radius - edge_smoothness, input.radius, distance);
-    
-    return vec4<f32>(input.color.rgb, input.color.a * alpha);
+    let coverage = select(0.0, sdf_coverage(distance, edge_smoothness), distance <= 0.0);
+
+#ifdef BACKDROP
+    // Drawn under the circle itself: the blurred frame, cut to the circle
+    if (coverage <= 0.0) {
+        discard;
+    }
+    let behind = textureSampleLevel(backdrop, backdrop_sampler, input.position.xy / vec2<f32>(textureDimensions(backdrop)), 0.0);
+    return output_color(vec4<f32>(behind.rgb / max(behind.a, 1e-6), behind.a), coverage);
+#else
+    // A circle is a rounded box with corners as round as it is
+    let radius = max(input.radius + input.shadow_params.w, 0.0);
+    let shadow_coverage = input.shadow_color.a
+        * rounded_box_shadow(p - input.shadow_params.xy, vec2<f32>(radius), radius, input.shadow_params.z);
+
+#ifdef DEBUG_OVERLAY
+    // Show the instance quad behind the circle and shadow
+    if (coverage <= 0.0 && shadow_coverage <= 0.0) {
+        return output_color(vec4<f32>(1.0, 0.0, 1.0, 0.25), 1.0);
+    }
+#else
+    if (coverage <= 0.0 && shadow_coverage <= 0.0) {
+        discard;
+    }
+#endif
+
+    let color = paint_color(input.color, input.local, input.paint_params, input.paint_geometry);
+    let shadow = vec4<f32>(input.shadow_color.rgb, 1.0);
+    return output_color(over_shadow(color, coverage, shadow, shadow_coverage), 1.0);
+#endif
 }
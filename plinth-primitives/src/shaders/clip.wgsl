@@ -0,0 +1,12 @@
+// Stencil masks of rounded and path clips. Only the stencil is written; positions come in
+// clip space already, tessellated on the CPU.
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(position, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0);
+}
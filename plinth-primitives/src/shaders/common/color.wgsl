@@ -0,0 +1,9 @@
+// Final fragment color. Blending expects straight alpha unless PREMULTIPLIED_ALPHA is defined.
+fn output_color(color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let alpha = color.a * coverage;
+#ifdef PREMULTIPLIED_ALPHA
+    return vec4<f32>(color.rgb * alpha, alpha);
+#else
+    return vec4<f32>(color.rgb, alpha);
+#endif
+}
@@ -0,0 +1,33 @@
+// Gradient ramps of the batch being drawn, one row per gradient, see GradientRamps
+@group(0) @binding(0) var gradient_ramps: texture_2d<f32>;
+@group(0) @binding(1) var gradient_sampler: sampler;
+
+const TAU: f32 = 6.28318530718;
+
+// `color` for solid paint; otherwise the gradient at `local`, tinted by `color`. `params` is
+// the kind (0 solid, 1 linear, 2 radial, 3 conic), ramp row and conic start angle, and
+// `geometry` the gradient's points in the same space as `local`, matching PaintData.
+fn paint_color(color: vec4<f32>, local: vec2<f32>, params: vec4<f32>, geometry: vec4<f32>) -> vec4<f32> {
+    var t = 0.0;
+    switch u32(params.x + 0.5) {
+        case 1u: {
+            let axis = geometry.zw - geometry.xy;
+            t = dot(local - geometry.xy, axis) / max(dot(axis, axis), 1e-12);
+        }
+        case 2u: {
+            t = length((local - geometry.xy) / max(geometry.zw, vec2<f32>(1e-6)));
+        }
+        case 3u: {
+            // Clockwise from up, like CSS angles
+            let offset = local - geometry.xy;
+            t = fract((atan2(offset.x, offset.y) - params.z) / TAU);
+        }
+        default: {
+            return color;
+        }
+    }
+    // Explicit level, since the kind can differ between fragments of one draw
+    let size = vec2<f32>(textureDimensions(gradient_ramps));
+    let uv = vec2<f32>((clamp(t, 0.0, 1.0) * (size.x - 1.0) + 0.5) / size.x, (params.y + 0.5) / size.y);
+    return textureSampleLevel(gradient_ramps, gradient_sampler, uv, 0.0) * color;
+}
@@ -0,0 +1,9 @@
+// Two triangles covering [-1, 1]², indexed by vertex_index for instanced quads
+const QUAD_VERTICES: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0), // Bottom-left
+    vec2<f32>( 1.0, -1.0), // Bottom-right
+    vec2<f32>(-1.0,  1.0), // Top-left
+    vec2<f32>(-1.0,  1.0), // Top-left
+    vec2<f32>( 1.0, -1.0), // Bottom-right
+    vec2<f32>( 1.0,  1.0), // Top-right
+);
@@ -0,0 +1,15 @@
+fn sd_circle(p: vec2<f32>, radius: f32) -> f32 {
+    return length(p) - radius;
+}
+
+// Coverage of a shape from its signed distance, smoothed over `smoothness`
+fn sdf_coverage(distance: f32, smoothness: f32) -> f32 {
+    return 1.0 - smoothstep(-smoothness, 0.0, distance);
+}
+
+// Box of half extents `half_size` around the origin with corners rounded by `radius`
+fn sd_rounded_box(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let r = clamp(radius, 0.0, min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + r;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+}
@@ -0,0 +1,73 @@
+// Analytic Gaussian blur of a rounded box, after Evan Wallace's "Fast Rounded Rectangle
+// Shadows": the blur is separable along x for each row of the box, so x is integrated with
+// erf and y with a few samples of the Gaussian.
+
+fn shadow_gaussian(x: f32, sigma: f32) -> f32 {
+    let pi = 3.141592653589793;
+    return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * pi) * sigma);
+}
+
+// Abramowitz and Stegun's approximation, good to about 5e-4
+fn shadow_erf(x: vec2<f32>) -> vec2<f32> {
+    let s = sign(x);
+    let a = abs(x);
+    var y = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    y = y * y;
+    return s - s / (y * y);
+}
+
+// Blurred coverage of the row at `y` of a box of half extents `half_size` around the origin
+fn shadow_row(x: f32, y: f32, sigma: f32, radius: f32, half_size: vec2<f32>) -> f32 {
+    let delta = min(half_size.y - radius - abs(y), 0.0);
+    let curved = half_size.x - radius + sqrt(max(0.0, radius * radius - delta * delta));
+    let integral = 0.5 + 0.5 * shadow_erf((x + vec2<f32>(-curved, curved)) * (sqrt(0.5) / sigma));
+    return integral.y - integral.x;
+}
+
+// Coverage at `p` of a box of half extents `half_size` around the origin with corners
+// rounded by `radius`, blurred by a Gaussian of standard deviation `sigma`
+fn rounded_box_shadow(p: vec2<f32>, half_size: vec2<f32>, radius: f32, sigma: f32) -> f32 {
+    let r = clamp(radius, 0.0, min(half_size.x, half_size.y));
+    // Without blur it's the box itself, smoothed over a pixel like the shapes. Derivatives
+    // need uniform control flow, so this is worked out before branching.
+    let q = abs(p) - half_size + r;
+    let distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+    let sharp = clamp(0.5 - distance / max(fwidth(distance), 1e-6), 0.0, 1.0);
+    if (sigma <= 1e-4) {
+        return sharp;
+    }
+    // Only rows within three standard deviations contribute
+    let low = p.y - half_size.y;
+    let high = p.y + half_size.y;
+    let start = clamp(-3.0 * sigma, low, high);
+    let end = clamp(3.0 * sigma, low, high);
+    let step = (end - start) / 4.0;
+    var y = start + step * 0.5;
+    var value = 0.0;
+    for (var i = 0; i < 4; i = i + 1) {
+        value = value + shadow_row(p.x, p.y - y, sigma, r, half_size) * shadow_gaussian(y, sigma) * step;
+        y = y + step;
+    }
+    return value;
+}
+
+// A shape of `color` with `coverage` over its shadow, straight alpha. CSS only draws outer
+// shadows outside the shape, so the shadow is cut where the shape covers.
+fn over_shadow(color: vec4<f32>, coverage: f32, shadow: vec4<f32>, shadow_coverage: f32) -> vec4<f32> {
+    let shape_alpha = color.a * coverage;
+    let shadow_alpha = shadow.a * shadow_coverage * (1.0 - coverage);
+    let alpha = shape_alpha + shadow_alpha * (1.0 - shape_alpha);
+    if (alpha <= 0.0) {
+        return vec4<f32>(0.0);
+    }
+    let rgb = (color.rgb * shape_alpha + shadow.rgb * shadow_alpha * (1.0 - shape_alpha)) / alpha;
+    return vec4<f32>(rgb, alpha);
+}
+
+// How far a shadow reaches past its shape, see ShadowData::margin
+fn shadow_margin(params: vec4<f32>, color: vec4<f32>) -> f32 {
+    if (color.a <= 0.0) {
+        return 0.0;
+    }
+    return max(params.w + params.z * 3.0, 0.0) + max(abs(params.x), abs(params.y));
+}
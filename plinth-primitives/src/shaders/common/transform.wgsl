@@ -0,0 +1,10 @@
+fn rotate2d(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2<f32>(v.x * c - v.y * s, v.x * s + v.y * c);
+}
+
+// Rotate, then scale, then translate, matching Transform on the Rust side
+fn apply_transform(v: vec2<f32>, position: vec2<f32>, scale: vec2<f32>, rotation: f32) -> vec2<f32> {
+    return rotate2d(v, rotation) * scale + position;
+}
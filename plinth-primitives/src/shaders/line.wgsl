@@ -0,0 +1,223 @@
+#include "plinth/quad.wgsl"
+#include "plinth/color.wgsl"
+
+// Surface size in physical pixels, for pixel-wide lines and anti-aliasing margins
+struct Viewport {
+    size: vec2<f32>,
+    _padding: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> viewport: Viewport;
+
+const PIXEL_WIDTH: u32 = 1u;
+const CAP_SHIFT: u32 = 1u;
+const JOIN_SHIFT: u32 = 3u;
+const HAS_PREVIOUS: u32 = 32u;
+const HAS_NEXT: u32 = 64u;
+
+const CAP_ROUND: u32 = 1u;
+const CAP_SQUARE: u32 = 2u;
+const JOIN_MITER: u32 = 0u;
+const JOIN_ROUND: u32 = 1u;
+
+struct LineInstance {
+    @location(0) start: vec2<f32>,
+    @location(1) end: vec2<f32>,
+    @location(2) previous: vec2<f32>,
+    @location(3) next: vec2<f32>,
+    @location(4) start_color: vec4<f32>,
+    @location(5) end_color: vec4<f32>,
+    // width, miter limit, distance at start, dash count
+    @location(6) params: vec4<f32>,
+    @location(7) dashes_a: vec4<f32>,
+    @location(8) dashes_b: vec4<f32>,
+    @location(9) flags: u32,
+}
+
+// Everything but `position` is in line space: pixels for pixel-wide lines, clip space otherwise
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) segment: vec4<f32>,
+    @location(2) @interpolate(flat) neighbours: vec4<f32>,
+    @location(3) @interpolate(flat) start_color: vec4<f32>,
+    @location(4) @interpolate(flat) end_color: vec4<f32>,
+    @location(5) @interpolate(flat) params: vec4<f32>,
+    @location(6) @interpolate(flat) dashes_a: vec4<f32>,
+    @location(7) @interpolate(flat) dashes_b: vec4<f32>,
+    @location(8) @interpolate(flat) flags: u32,
+}
+
+fn line_space_scale(flags: u32) -> vec2<f32> {
+    if ((flags & PIXEL_WIDTH) != 0u) {
+        return viewport.size * 0.5;
+    }
+    return vec2<f32>(1.0, 1.0);
+}
+
+fn direction_or(v: vec2<f32>, fallback: vec2<f32>) -> vec2<f32> {
+    let len = length(v);
+    if (len < 1e-6) {
+        return fallback;
+    }
+    return v / len;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, instance: LineInstance) -> VertexOutput {
+    let scale = line_space_scale(instance.flags);
+    let start = instance.start * scale;
+    let end = instance.end * scale;
+    let direction = direction_or(end - start, vec2<f32>(1.0, 0.0));
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // Room for caps and joins past the ends, plus a pixel for anti-aliasing
+    let half_width = instance.params.x * 0.5;
+    let pixel = select(2.0 / min(viewport.size.x, viewport.size.y), 1.0, (instance.flags & PIXEL_WIDTH) != 0u);
+    var reach = half_width;
+    if (((instance.flags >> JOIN_SHIFT) & 3u) == JOIN_MITER) {
+        reach = half_width * max(instance.params.y, 1.0);
+    }
+    reach += pixel;
+
+    let corner = QUAD_VERTICES[vertex_index];
+    let along = select(start - direction * reach, end + direction * reach, corner.x > 0.0);
+    let local = along + normal * corner.y * (half_width + pixel);
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(local / scale, 0.0, 1.0);
+    output.local = local;
+    output.segment = vec4<f32>(start, end);
+    output.neighbours = vec4<f32>(instance.previous * scale, instance.next * scale);
+    output.start_color = instance.start_color;
+    output.end_color = instance.end_color;
+    output.params = instance.params;
+    output.dashes_a = instance.dashes_a;
+    output.dashes_b = instance.dashes_b;
+    output.flags = instance.flags;
+    return output;
+}
+
+// Cuts the distance at an open end. `along` is measured outwards from the end.
+fn cap_end(distance: f32, along: f32, across: f32, half_width: f32, cap: u32) -> f32 {
+    if (cap == CAP_SQUARE) {
+        return max(distance, along - half_width);
+    }
+    let cut = max(distance, along);
+    if (cap == CAP_ROUND) {
+        return min(cut, length(vec2<f32>(along, across)) - half_width);
+    }
+    return cut;
+}
+
+// Cuts the distance at a joint shared with the neighbouring segment. `inward` points from
+// the neighbour into this segment and `outward` along this segment, away from the joint.
+fn join_end(distance: f32, p: vec2<f32>, joint: vec2<f32>, inward: vec2<f32>, outward: vec2<f32>, half_width: f32, join: u32, miter_limit: f32) -> f32 {
+    // Segments meet on the bisector of the joint
+    let tangent = direction_or(inward + outward, outward);
+    var result = max(distance, -dot(p - joint, tangent));
+
+    let corner = inward - outward;
+    if (length(corner) < 1e-4) {
+        return result;
+    }
+    // Towards the outer corner; the bevel cuts it at half_width * cos(half the turn)
+    let outer = normalize(corner);
+    let cos_half = abs(dot(outer, vec2<f32>(-outward.y, outward.x)));
+    if (join == JOIN_MITER && cos_half * miter_limit >= 1.0) {
+        return result;
+    }
+    result = max(result, dot(p - joint, outer) - half_width * cos_half);
+    if (join == JOIN_ROUND) {
+        result = min(result, length(p - joint) - half_width);
+    }
+    return result;
+}
+
+fn dash_length(index: u32, dashes_a: vec4<f32>, dashes_b: vec4<f32>) -> f32 {
+    if (index < 4u) {
+        return dashes_a[index];
+    }
+    return dashes_b[index - 4u];
+}
+
+// Signed distance along the line to the nearest dash: negative inside a dash, positive in a gap
+fn dash_distance(position: f32, count: u32, dashes_a: vec4<f32>, dashes_b: vec4<f32>) -> f32 {
+    var period = 0.0;
+    for (var i = 0u; i < count; i++) {
+        period += dash_length(i, dashes_a, dashes_b);
+    }
+    var offset = position - period * floor(position / period);
+    for (var i = 0u; i < count; i++) {
+        let dash = dash_length(i, dashes_a, dashes_b);
+        if (offset <= dash || i == count - 1u) {
+            let inside = min(offset, dash - offset);
+            return select(inside, -inside, i % 2u == 0u);
+        }
+        offset -= dash;
+    }
+    return -1.0;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let p = input.local;
+    let start = input.segment.xy;
+    let end = input.segment.zw;
+    let half_width = input.params.x * 0.5;
+    let miter_limit = input.params.y;
+    let cap = (input.flags >> CAP_SHIFT) & 3u;
+    let join = (input.flags >> JOIN_SHIFT) & 3u;
+
+    let segment_length = length(end - start);
+    let direction = direction_or(end - start, vec2<f32>(1.0, 0.0));
+    let normal = vec2<f32>(-direction.y, direction.x);
+    let along = dot(p - start, direction);
+    let across = dot(p - start, normal);
+
+    var distance = abs(across) - half_width;
+    if ((input.flags & HAS_PREVIOUS) != 0u) {
+        let inward = direction_or(start - input.neighbours.xy, direction);
+        distance = join_end(distance, p, start, inward, direction, half_width, join, miter_limit);
+    } else {
+        distance = cap_end(distance, -along, across, half_width, cap);
+    }
+    if ((input.flags & HAS_NEXT) != 0u) {
+        let inward = direction_or(end - input.neighbours.zw, -direction);
+        distance = join_end(distance, p, end, inward, -direction, half_width, join, miter_limit);
+    } else {
+        distance = cap_end(distance, along - segment_length, across, half_width, cap);
+    }
+
+    let dash_count = u32(input.params.w);
+    if (dash_count > 0u) {
+        let gap = dash_distance(input.params.z + along, dash_count, input.dashes_a, input.dashes_b);
+        // Dashes get the line's caps at both ends
+        if (cap == CAP_ROUND) {
+            if (gap > 0.0) {
+                distance = max(distance, length(vec2<f32>(gap, across)) - half_width);
+            }
+        } else if (cap == CAP_SQUARE) {
+            distance = max(distance, gap - half_width);
+        } else {
+            distance = max(distance, gap);
+        }
+    }
+
+    // One pixel of smoothing whatever space the distance is in
+    let coverage = clamp(0.5 - distance / max(fwidth(distance), 1e-6), 0.0, 1.0);
+
+#ifdef DEBUG_OVERLAY
+    // Show the instance quad around the segment
+    if (coverage <= 0.0) {
+        return output_color(vec4<f32>(1.0, 0.0, 1.0, 0.25), 1.0);
+    }
+#else
+    if (coverage <= 0.0) {
+        discard;
+    }
+#endif
+
+    let t = clamp(along / max(segment_length, 1e-6), 0.0, 1.0);
+    return output_color(mix(input.start_color, input.end_color, t), coverage);
+}
@@ -0,0 +1,41 @@
+#include "plinth/color.wgsl"
+#include "plinth/paint.wgsl"
+
+// Pre-tessellated triangles with the transform and opacity already applied on the CPU
+struct MeshVertex {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) local: vec2<f32>,
+    @location(3) paint_params: vec4<f32>,
+    @location(4) paint_geometry: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) local: vec2<f32>,
+    @location(2) @interpolate(flat) paint_params: vec4<f32>,
+    @location(3) @interpolate(flat) paint_geometry: vec4<f32>,
+}
+
+@vertex
+fn vs_main(vertex: MeshVertex) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4<f32>(vertex.position, 0.0, 1.0);
+    output.color = vertex.color;
+    output.local = vertex.local;
+    output.paint_params = vertex.paint_params;
+    output.paint_geometry = vertex.paint_geometry;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = paint_color(input.color, input.local, input.paint_params, input.paint_geometry);
+#ifdef DEBUG_OVERLAY
+    // Tint every triangle so overdraw and tessellation density show up
+    return output_color(mix(color, vec4<f32>(1.0, 0.0, 1.0, 1.0), 0.5), 1.0);
+#else
+    return output_color(color, 1.0);
+#endif
+}
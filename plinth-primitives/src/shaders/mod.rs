@@ -1,2 +1,5 @@
 // This module contains the shader compilation logic
 // Individual shaders are included as static strings in the primitive modules
+pub mod preprocessor;
+
+pub use preprocessor::{PreprocessError, ShaderPreprocessor};
@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+
+use plinth_core::shader_source;
+use plinth_core::shaders::{ShaderLibrary, ShaderSource};
+
+// Snippets every preprocessor can include, e.g. `#include "plinth/sdf.wgsl"`
+pub const BUILTIN_INCLUDES: [(&str, ShaderSource); 6] = [
+    ("plinth/quad.wgsl", shader_source!("src/shaders/common/quad.wgsl")),
+    ("plinth/transform.wgsl", shader_source!("src/shaders/common/transform.wgsl")),
+    ("plinth/sdf.wgsl", shader_source!("src/shaders/common/sdf.wgsl")),
+    ("plinth/color.wgsl", shader_source!("src/shaders/common/color.wgsl")),
+    ("plinth/paint.wgsl", shader_source!("src/shaders/common/paint.wgsl")),
+    ("plinth/shadow.wgsl", shader_source!("src/shaders/common/shadow.wgsl")),
+];
+
+// Minimal C-style preprocessor for WGSL. Directives must start a line:
+//   #include "name"      pastes a registered snippet (each snippet at most once)
+//   #define NAME [value] defines a toggle, or a token replaced by `value` in later lines
+//   #undef NAME
+//   #ifdef / #ifndef NAME, #else, #endif
+#[derive(Debug, Clone)]
+pub struct ShaderPreprocessor {
+    includes: HashMap<String, ShaderSource>,
+    defines: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PreprocessError {
+    #[error("Line {line}: unknown include \"{name}\"")]
+    UnknownInclude { name: String, line: usize },
+    #[error("Line {line}: include \"{name}\" includes itself")]
+    RecursiveInclude { name: String, line: usize },
+    #[error("Line {line}: unknown directive #{directive}")]
+    UnknownDirective { directive: String, line: usize },
+    #[error("Line {line}: malformed #{directive}")]
+    MalformedDirective { directive: String, line: usize },
+    #[error("Line {line}: #{directive} without a matching #ifdef")]
+    UnmatchedConditional { directive: String, line: usize },
+    #[error("#ifdef opened on line {line} is never closed")]
+    UnterminatedConditional { line: usize },
+}
+
+struct Conditional {
+    line: usize,
+    // Whether the enclosing block is emitting lines
+    parent_active: bool,
+    condition: bool,
+    seen_else: bool,
+}
+
+impl Conditional {
+    fn active(&self) -> bool {
+        self.parent_active && (self.condition != self.seen_else)
+    }
+}
+
+struct State<'a> {
+    // Text to paste for an include
+    read: &'a dyn Fn(&ShaderSource) -> String,
+    defines: HashMap<String, String>,
+    included: HashSet<String>,
+    // Includes currently being expanded, to report cycles
+    stack: Vec<String>,
+    output: String,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> Self {
+        let includes = BUILTIN_INCLUDES
+            .iter()
+            .map(|(name, source)| (name.to_string(), *source))
+            .collect();
+        Self {
+            includes,
+            defines: HashMap::new(),
+        }
+    }
+
+    pub fn with_include(mut self, name: impl Into<String>, source: &'static str) -> Self {
+        self.add_include(name, ShaderSource::new("include", source));
+        self
+    }
+
+    pub fn with_define(mut self, name: impl Into<String>) -> Self {
+        self.define(name);
+        self
+    }
+
+    pub fn with_define_value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.define_value(name, value);
+        self
+    }
+
+    // Registers a snippet from a ShaderSource so it follows shader hot reload
+    pub fn add_include(&mut self, name: impl Into<String>, source: ShaderSource) {
+        self.includes.insert(name.into(), source);
+    }
+
+    pub fn define(&mut self, name: impl Into<String>) {
+        self.defines.insert(name.into(), String::new());
+    }
+
+    pub fn define_value(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.defines.insert(name.into(), value.into());
+    }
+
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    pub fn set_define(&mut self, name: impl Into<String>, enabled: bool) {
+        let name = name.into();
+        if enabled {
+            self.define(name);
+        } else {
+            self.undefine(&name);
+        }
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    // How pipelines built from this preprocessor's output blend, matching what output_color
+    // in plinth/color.wgsl writes
+    pub fn blend_state(&self) -> wgpu::BlendState {
+        if self.is_defined("PREMULTIPLIED_ALPHA") {
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING
+        } else {
+            wgpu::BlendState::ALPHA_BLENDING
+        }
+    }
+
+    // On-disk paths of every include, for watching them alongside the main shader
+    pub fn include_paths(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.includes.values().filter_map(|source| source.dev_path)
+    }
+
+    pub fn includes(&self) -> impl Iterator<Item = &ShaderSource> + '_ {
+        self.includes.values()
+    }
+
+    // Expands `source` using the embedded copy of every include
+    pub fn process(&self, source: &str) -> Result<String, PreprocessError> {
+        self.process_with(source, &|shader| shader.embedded.to_string())
+    }
+
+    // Expands `source`, reading includes through `shaders` so edited snippets are picked up
+    pub fn process_with_library(&self, source: &str, shaders: &ShaderLibrary) -> Result<String, PreprocessError> {
+        self.process_with(source, &|shader| shaders.source(shader).into_owned())
+    }
+
+    // Expands `source`, pasting whatever `read` returns for each include
+    pub fn process_with(&self, source: &str, read: &dyn Fn(&ShaderSource) -> String) -> Result<String, PreprocessError> {
+        let mut state = State {
+            read,
+            defines: self.defines.clone(),
+            included: HashSet::new(),
+            stack: Vec::new(),
+            output: String::with_capacity(source.len()),
+        };
+        self.expand(source, &mut state)?;
+        Ok(state.output)
+    }
+
+    fn expand(&self, source: &str, state: &mut State) -> Result<(), PreprocessError> {
+        let mut conditionals: Vec<Conditional> = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let number = index + 1;
+            let active = conditionals.last().is_none_or(Conditional::active);
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    state.output.push_str(&substitute(line, &state.defines));
+                    state.output.push('\n');
+                }
+                continue;
+            };
+
+            let mut parts = directive.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+            let argument = parts.next();
+            let malformed = || PreprocessError::MalformedDirective {
+                directive: keyword.to_string(),
+                line: number,
+            };
+
+            match keyword {
+                "ifdef" | "ifndef" => {
+                    let name = argument.ok_or_else(malformed)?;
+                    let defined = state.defines.contains_key(name);
+                    conditionals.push(Conditional {
+                        line: number,
+                        parent_active: active,
+                        condition: if keyword == "ifdef" { defined } else { !defined },
+                        seen_else: false,
+                    });
+                }
+                "else" | "endif" => {
+                    let unmatched = || PreprocessError::UnmatchedConditional {
+                        directive: keyword.to_string(),
+                        line: number,
+                    };
+                    if keyword == "endif" {
+                        conditionals.pop().ok_or_else(unmatched)?;
+                    } else {
+                        let conditional = conditionals.last_mut().ok_or_else(unmatched)?;
+                        if conditional.seen_else {
+                            return Err(unmatched());
+                        }
+                        conditional.seen_else = true;
+                    }
+                }
+                _ if !active => {}
+                "define" => {
+                    let name = argument.ok_or_else(malformed)?;
+                    let value = parts.collect::<Vec<_>>().join(" ");
+                    state.defines.insert(name.to_string(), value);
+                }
+                "undef" => {
+                    state.defines.remove(argument.ok_or_else(malformed)?);
+                }
+                "include" => {
+                    let name = directive["include".len()..]
+                        .trim()
+                        .strip_prefix('"')
+                        .and_then(|rest| rest.strip_suffix('"'))
+                        .ok_or_else(malformed)?;
+                    self.include(name, number, state)?;
+                }
+                _ => {
+                    return Err(PreprocessError::UnknownDirective {
+                        directive: keyword.to_string(),
+                        line: number,
+                    })
+                }
+            }
+        }
+
+        match conditionals.first() {
+            Some(conditional) => Err(PreprocessError::UnterminatedConditional { line: conditional.line }),
+            None => Ok(()),
+        }
+    }
+
+    fn include(&self, name: &str, line: usize, state: &mut State) -> Result<(), PreprocessError> {
+        if state.stack.iter().any(|open| open == name) {
+            return Err(PreprocessError::RecursiveInclude {
+                name: name.to_string(),
+                line,
+            });
+        }
+        // Include-once, so snippets can include their own dependencies
+        if !state.included.insert(name.to_string()) {
+            return Ok(());
+        }
+        let shader = self.includes.get(name).ok_or_else(|| PreprocessError::UnknownInclude {
+            name: name.to_string(),
+            line,
+        })?;
+        let source = (state.read)(shader);
+        state.stack.push(name.to_string());
+        self.expand(&source, state)?;
+        state.stack.pop();
+        Ok(())
+    }
+}
+
+impl Default for ShaderPreprocessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Replaces whole identifiers that name a define with a value
+fn substitute(line: &str, defines: &HashMap<String, String>) -> String {
+    if !defines.values().any(|value| !value.is_empty()) {
+        return line.to_string();
+    }
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(|c: char| c.is_alphabetic() || c == '_') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let identifier = &rest[..end];
+        match defines.get(identifier) {
+            Some(value) if !value.is_empty() => output.push_str(value),
+            _ => output.push_str(identifier),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
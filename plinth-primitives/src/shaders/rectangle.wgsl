@@ -0,0 +1,106 @@
+#include "plinth/quad.wgsl"
+#include "plinth/transform.wgsl"
+#include "plinth/sdf.wgsl"
+#include "plinth/color.wgsl"
+#include "plinth/paint.wgsl"
+#include "plinth/shadow.wgsl"
+
+#ifdef BACKDROP
+// The blurred frame behind the rectangle, premultiplied
+@group(1) @binding(0) var backdrop: texture_2d<f32>;
+@group(1) @binding(1) var backdrop_sampler: sampler;
+#endif
+
+struct RectangleInstance {
+    @location(0) position: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) transform_position: vec2<f32>,
+    @location(4) transform_scale: vec2<f32>,
+    @location(5) transform_rotation: f32,
+    @location(6) corner_radius: f32,
+    @location(7) paint_params: vec4<f32>,
+    @location(8) paint_geometry: vec4<f32>,
+    @location(9) shadow_params: vec4<f32>,
+    @location(10) shadow_color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // Before the transform, where the box and paint are laid out
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) center: vec2<f32>,
+    @location(2) @interpolate(flat) half_size: vec2<f32>,
+    @location(3) @interpolate(flat) corner_radius: f32,
+    @location(4) @interpolate(flat) color: vec4<f32>,
+    @location(5) @interpolate(flat) paint_params: vec4<f32>,
+    @location(6) @interpolate(flat) paint_geometry: vec4<f32>,
+    @location(7) @interpolate(flat) shadow_params: vec4<f32>,
+    @location(8) @interpolate(flat) shadow_color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, instance: RectangleInstance) -> VertexOutput {
+    let half_size = instance.size * 0.5;
+    let center = instance.position + half_size;
+    // Grown to fit the shadow
+    let extent = half_size + shadow_margin(instance.shadow_params, instance.shadow_color);
+    let local = center + QUAD_VERTICES[vertex_index] * extent;
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(
+        apply_transform(local, instance.transform_position, instance.transform_scale, instance.transform_rotation),
+        0.0,
+        1.0,
+    );
+    output.local = local;
+    output.center = center;
+    output.half_size = half_size;
+    output.corner_radius = instance.corner_radius;
+    output.color = instance.color;
+    output.paint_params = instance.paint_params;
+    output.paint_geometry = instance.paint_geometry;
+    output.shadow_params = instance.shadow_params;
+    output.shadow_color = instance.shadow_color;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let p = input.local - input.center;
+    let distance = sd_rounded_box(p, input.half_size, input.corner_radius);
+    // About a pixel of smoothing whatever the transform
+    let coverage = clamp(0.5 - distance / max(fwidth(distance), 1e-6), 0.0, 1.0);
+
+#ifdef BACKDROP
+    // Drawn under the rectangle itself: the blurred frame, cut to the rounded box
+    if (coverage <= 0.0) {
+        discard;
+    }
+    let behind = textureSampleLevel(backdrop, backdrop_sampler, input.position.xy / vec2<f32>(textureDimensions(backdrop)), 0.0);
+    return output_color(vec4<f32>(behind.rgb / max(behind.a, 1e-6), behind.a), coverage);
+#else
+    let spread = input.shadow_params.w;
+    let shadow_coverage = input.shadow_color.a * rounded_box_shadow(
+        p - input.shadow_params.xy,
+        max(input.half_size + spread, vec2<f32>(0.0)),
+        max(input.corner_radius + spread, 0.0),
+        input.shadow_params.z,
+    );
+
+#ifdef DEBUG_OVERLAY
+    // Show the instance quad behind the rounded corners and shadow
+    if (coverage <= 0.0 && shadow_coverage <= 0.0) {
+        return output_color(vec4<f32>(1.0, 0.0, 1.0, 0.25), 1.0);
+    }
+#else
+    if (coverage <= 0.0 && shadow_coverage <= 0.0) {
+        discard;
+    }
+#endif
+
+    let color = paint_color(input.color, input.local, input.paint_params, input.paint_geometry);
+    let shadow = vec4<f32>(input.shadow_color.rgb, 1.0);
+    return output_color(over_shadow(color, coverage, shadow, shadow_coverage), 1.0);
+#endif
+}
@@ -0,0 +1,53 @@
+#include "plinth/quad.wgsl"
+#include "plinth/color.wgsl"
+
+// Glyph coverage, one channel
+@group(0) @binding(0) var atlas: texture_2d<f32>;
+@group(0) @binding(1) var atlas_sampler: sampler;
+
+struct GlyphInstance {
+    @location(0) origin: vec2<f32>,
+    @location(1) axis_x: vec2<f32>,
+    @location(2) axis_y: vec2<f32>,
+    // In atlas texels
+    @location(3) uv_min: vec2<f32>,
+    @location(4) uv_max: vec2<f32>,
+    @location(5) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, instance: GlyphInstance) -> VertexOutput {
+    // From the top-left corner, across and down the glyph
+    let corner = QUAD_VERTICES[vertex_index];
+    let t = vec2<f32>(corner.x * 0.5 + 0.5, 0.5 - corner.y * 0.5);
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(instance.origin + instance.axis_x * t.x + instance.axis_y * t.y, 0.0, 1.0);
+    output.uv = mix(instance.uv_min, instance.uv_max, t);
+    output.color = instance.color;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas, atlas_sampler, input.uv / vec2<f32>(textureDimensions(atlas))).r;
+
+#ifdef DEBUG_OVERLAY
+    // Show the glyph quads
+    if (coverage <= 0.0) {
+        return output_color(vec4<f32>(1.0, 0.0, 1.0, 0.25), 1.0);
+    }
+#else
+    if (coverage <= 0.0) {
+        discard;
+    }
+#endif
+
+    return output_color(input.color, coverage);
+}
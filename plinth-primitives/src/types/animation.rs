@@ -0,0 +1,194 @@
+// `transition` and `animation` settings of the style model. Times are in seconds.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimingFunction {
+    Linear,
+    // Control points (x1, y1, x2, y2); ease, ease-in... are predefined curves
+    CubicBezier(f32, f32, f32, f32),
+    Steps(u32, StepPosition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepPosition {
+    JumpStart,
+    JumpEnd,
+    JumpNone,
+    JumpBoth,
+}
+
+impl TimingFunction {
+    pub const EASE: Self = Self::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    pub const EASE_IN: Self = Self::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    pub const EASE_OUT: Self = Self::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    pub const EASE_IN_OUT: Self = Self::CubicBezier(0.42, 0.0, 0.58, 1.0);
+
+    // Output progress for input progress `t` in [0, 1]
+    pub fn evaluate(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Self::Linear => t,
+            Self::CubicBezier(x1, y1, x2, y2) => {
+                let s = solve_bezier_x(x1, x2, t);
+                bezier(y1, y2, s)
+            }
+            Self::Steps(steps, position) => {
+                let steps = steps.max(1) as f32;
+                let mut step = (t * steps).floor();
+                if matches!(position, StepPosition::JumpStart | StepPosition::JumpBoth) {
+                    step += 1.0;
+                }
+                let jumps = match position {
+                    StepPosition::JumpEnd | StepPosition::JumpStart => steps,
+                    StepPosition::JumpNone => (steps - 1.0).max(1.0),
+                    StepPosition::JumpBoth => steps + 1.0,
+                };
+                if t >= 1.0 {
+                    return 1.0;
+                }
+                (step / jumps).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+impl Default for TimingFunction {
+    fn default() -> Self {
+        Self::EASE
+    }
+}
+
+// One coordinate of a cubic bezier from (0, 0) to (1, 1) at parameter `s`
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+}
+
+// The parameter where the curve's x equals `x`: Newton steps, then bisection if they stall
+fn solve_bezier_x(x1: f32, x2: f32, x: f32) -> f32 {
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-6 {
+            return s;
+        }
+        let inverse = 1.0 - s;
+        let slope = 3.0 * inverse * inverse * x1 + 6.0 * inverse * s * (x2 - x1) + 3.0 * s * s * (1.0 - x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        s -= error / slope;
+    }
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    s
+}
+
+// One entry of a `transition` list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    // A CSS property name, or "all"
+    pub property: String,
+    pub duration: f32,
+    pub delay: f32,
+    pub timing: TimingFunction,
+}
+
+impl Transition {
+    pub fn applies_to(&self, property: &str) -> bool {
+        self.property == "all" || self.property == property
+    }
+}
+
+// One entry of an `animation` list, running the @keyframes rule called `name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub name: String,
+    pub duration: f32,
+    pub delay: f32,
+    pub timing: TimingFunction,
+    // f32::INFINITY for `infinite`
+    pub iterations: f32,
+    pub direction: AnimationDirection,
+    pub fill_mode: FillMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationDirection {
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillMode {
+    None,
+    Forwards,
+    Backwards,
+    Both,
+}
+
+impl Animation {
+    pub fn new(name: impl Into<String>, duration: f32) -> Self {
+        Self {
+            name: name.into(),
+            duration,
+            delay: 0.0,
+            timing: TimingFunction::EASE,
+            iterations: 1.0,
+            direction: AnimationDirection::Normal,
+            fill_mode: FillMode::None,
+        }
+    }
+
+    // Keyframe progress in [0, 1] `elapsed` seconds after the animation started, or None
+    // while it has no effect (in its delay or finished, without a fill mode covering that)
+    pub fn progress(&self, elapsed: f32) -> Option<f32> {
+        let active = elapsed - self.delay;
+        let total = self.duration * self.iterations;
+        let (iteration, fraction) = if active < 0.0 {
+            if !matches!(self.fill_mode, FillMode::Backwards | FillMode::Both) {
+                return None;
+            }
+            (0.0, 0.0)
+        } else if active >= total {
+            if !matches!(self.fill_mode, FillMode::Forwards | FillMode::Both) {
+                return None;
+            }
+            // Hold the end of the last (possibly partial) iteration
+            let last = (self.iterations.ceil() - 1.0).max(0.0);
+            let fraction = if self.iterations.fract() == 0.0 { 1.0 } else { self.iterations.fract() };
+            (last, fraction)
+        } else if self.duration <= 0.0 {
+            (0.0, 1.0)
+        } else {
+            let position = active / self.duration;
+            (position.floor(), position.fract())
+        };
+
+        let reversed = match self.direction {
+            AnimationDirection::Normal => false,
+            AnimationDirection::Reverse => true,
+            AnimationDirection::Alternate => iteration % 2.0 == 1.0,
+            AnimationDirection::AlternateReverse => iteration % 2.0 == 0.0,
+        };
+        Some(if reversed { 1.0 - fraction } else { fraction })
+    }
+
+    // Whether the animation still changes after `elapsed` seconds
+    pub fn is_running(&self, elapsed: f32) -> bool {
+        elapsed < self.delay + self.duration * self.iterations
+    }
+}
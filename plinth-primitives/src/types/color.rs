@@ -1,6 +1,7 @@
 use bytemuck::{Pod, Zeroable};
 
 #[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
 pub struct Color {
     pub r: f32,
@@ -46,19 +47,44 @@ impl Default for Color {
     }
 }
 
-// CSS color parsing will be added in the styles crate
-#[cfg(feature = "web")]
-impl Color {
-    pub fn from_css_color(css_color: &str) -> Result<Self, ColorParseError> {
-        // This will be implemented in the styles crate
-        Err(ColorParseError::UnsupportedFormat)
-    }
-}
-
-#[derive(Debug, thiserror::Error)]
+// Parsing from CSS values lives in css_color.rs
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum ColorParseError {
     #[error("Unsupported color format")]
     UnsupportedFormat,
     #[error("Invalid color value: {0}")]
     InvalidValue(String),
+    #[error("currentColor can't be resolved without a current color")]
+    UnresolvedCurrentColor,
+}
+
+// Written as {r, g, b, a}; read from that, an [r, g, b, a] array or any CSS color string,
+// so scene files can say "#3366ff" or "rebeccapurple"
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        fn opaque() -> f32 {
+            1.0
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Css(String),
+            Components {
+                r: f32,
+                g: f32,
+                b: f32,
+                #[serde(default = "opaque")]
+                a: f32,
+            },
+            Array([f32; 4]),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Css(css) => Color::from_css_color(&css).map_err(serde::de::Error::custom),
+            Repr::Components { r, g, b, a } => Ok(Color::new(r, g, b, a)),
+            Repr::Array([r, g, b, a]) => Ok(Color::new(r, g, b, a)),
+        }
+    }
 }
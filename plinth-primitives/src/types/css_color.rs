@@ -0,0 +1,648 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use crate::types::{Color, ColorParseError};
+
+// A parsed CSS <color>. `currentColor` can only be turned into a Color once the color it
+// refers to is known, so it's kept symbolic until `resolve`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CssColor {
+    Color(Color),
+    CurrentColor,
+}
+
+impl CssColor {
+    // Parses any CSS Color 4 <color>: hex, named colors, transparent, currentColor, rgb(),
+    // hsl(), hwb(), lab(), lch(), oklab(), oklch() and color(). Colors outside sRGB are
+    // converted to sRGB and clipped.
+    pub fn parse(input: &str) -> Result<Self, ColorParseError> {
+        let input = input.trim();
+        let invalid = || ColorParseError::InvalidValue(input.to_string());
+
+        if let Some(hex) = input.strip_prefix('#') {
+            return parse_hex(hex).map(CssColor::Color).ok_or_else(invalid);
+        }
+
+        if let Some(open) = input.find('(') {
+            let name = input[..open].trim().to_ascii_lowercase();
+            let arguments = input[open + 1..].strip_suffix(')').ok_or_else(invalid)?;
+            return parse_function(&name, arguments).map(CssColor::Color).map_err(|e| match e {
+                FunctionError::Unsupported => ColorParseError::UnsupportedFormat,
+                FunctionError::Invalid => invalid(),
+            });
+        }
+
+        let keyword = input.to_ascii_lowercase();
+        match keyword.as_str() {
+            "currentcolor" => Ok(CssColor::CurrentColor),
+            "transparent" => Ok(CssColor::Color(Color::TRANSPARENT)),
+            _ => named_color(&keyword).map(CssColor::Color).ok_or_else(invalid),
+        }
+    }
+
+    pub fn resolve(self, current_color: Color) -> Color {
+        match self {
+            CssColor::Color(color) => color,
+            CssColor::CurrentColor => current_color,
+        }
+    }
+}
+
+impl FromStr for CssColor {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Color {
+    // Parses a CSS color value. `currentColor` is an error here since there is nothing for it
+    // to refer to; use `from_css_color_with_current` where a current color is known.
+    pub fn from_css_color(css_color: &str) -> Result<Self, ColorParseError> {
+        match CssColor::parse(css_color)? {
+            CssColor::Color(color) => Ok(color),
+            CssColor::CurrentColor => Err(ColorParseError::UnresolvedCurrentColor),
+        }
+    }
+
+    pub fn from_css_color_with_current(css_color: &str, current_color: Color) -> Result<Self, ColorParseError> {
+        CssColor::parse(css_color).map(|color| color.resolve(current_color))
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_css_color(s)
+    }
+}
+
+// OKLab, the perceptual space used for interpolating colors
+impl Color {
+    pub fn to_oklab(self) -> [f32; 3] {
+        let [r, g, b] = [self.r, self.g, self.b].map(srgb_to_linear);
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        [
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        ]
+    }
+
+    // Out-of-gamut results are clamped to sRGB
+    pub fn from_oklab(lab: [f32; 3], alpha: f32) -> Self {
+        from_linear_srgb(oklab_to_linear_srgb(lab[0], lab[1], lab[2]), alpha)
+    }
+
+    // Interpolates towards `other` in premultiplied OKLab, as CSS does for `in oklab`
+    pub fn mix_oklab(self, other: Color, t: f32) -> Color {
+        let alpha = self.a + (other.a - self.a) * t;
+        if alpha <= 0.0 {
+            return Color::TRANSPARENT;
+        }
+        let (from, to) = (self.to_oklab(), other.to_oklab());
+        let lab = [0, 1, 2].map(|i| (from[i] * self.a * (1.0 - t) + to[i] * other.a * t) / alpha);
+        Color::from_oklab(lab, alpha)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let [r, g, b, a] = match hex.len() {
+        3 => [digit(0)?, digit(1)?, digit(2)?, 255],
+        4 => [digit(0)?, digit(1)?, digit(2)?, digit(3)?],
+        6 => [pair(0)?, pair(2)?, pair(4)?, 255],
+        8 => [pair(0)?, pair(2)?, pair(4)?, pair(6)?],
+        _ => return None,
+    };
+    Some(Color::from_rgba(r, g, b, a))
+}
+
+enum FunctionError {
+    Unsupported,
+    Invalid,
+}
+
+impl From<()> for FunctionError {
+    fn from(_: ()) -> Self {
+        FunctionError::Invalid
+    }
+}
+
+// One component of a color function
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Component {
+    Number(f32),
+    Percentage(f32),
+    // Angle in degrees
+    Angle(f32),
+    None,
+}
+
+impl Component {
+    fn parse(token: &str) -> Result<Self, ()> {
+        let token = token.to_ascii_lowercase();
+        if token == "none" {
+            return Ok(Component::None);
+        }
+        if let Some(value) = token.strip_suffix('%') {
+            return parse_number(value).map(Component::Percentage);
+        }
+        for (unit, to_degrees) in [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / PI), ("turn", 360.0)] {
+            if let Some(value) = token.strip_suffix(unit) {
+                return parse_number(value).map(|v| Component::Angle(v * to_degrees));
+            }
+        }
+        parse_number(&token).map(Component::Number)
+    }
+
+    // Number or percentage, where 100% maps to `percent_reference`. `none` is 0.
+    fn value(self, percent_reference: f32) -> Result<f32, ()> {
+        match self {
+            Component::Number(v) => Ok(v),
+            Component::Percentage(v) => Ok(v / 100.0 * percent_reference),
+            Component::None => Ok(0.0),
+            Component::Angle(_) => Err(()),
+        }
+    }
+
+    // Hue in degrees, normalized to [0, 360)
+    fn hue(self) -> Result<f32, ()> {
+        let degrees = match self {
+            Component::Number(v) | Component::Angle(v) => v,
+            Component::None => 0.0,
+            Component::Percentage(_) => return Err(()),
+        };
+        Ok(degrees.rem_euclid(360.0))
+    }
+
+    fn alpha(self) -> Result<f32, ()> {
+        self.value(1.0).map(|a| a.clamp(0.0, 1.0))
+    }
+}
+
+fn parse_number(value: &str) -> Result<f32, ()> {
+    // Rust accepts "inf" and "nan", CSS doesn't
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit() || b"+-.e".contains(&b)) {
+        return Err(());
+    }
+    value.parse::<f32>().map_err(|_| ())
+}
+
+// Components of a color function, with the alpha after `/` (or the fourth comma value)
+struct Arguments {
+    components: Vec<Component>,
+    alpha: Option<Component>,
+    legacy: bool,
+}
+
+impl Arguments {
+    fn parse(arguments: &str) -> Result<Self, ()> {
+        if arguments.contains(',') {
+            let mut components = arguments
+                .split(',')
+                .map(|token| Component::parse(token.trim()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if components.contains(&Component::None) || !(3..=4).contains(&components.len()) {
+                return Err(());
+            }
+            let alpha = (components.len() == 4).then(|| components.pop().unwrap());
+            return Ok(Arguments {
+                components,
+                alpha,
+                legacy: true,
+            });
+        }
+
+        let spaced = arguments.replace('/', " / ");
+        let mut parts = spaced.split(" / ");
+        let components = parts
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(Component::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        let alpha = match parts.next() {
+            Some(alpha) => {
+                let mut tokens = alpha.split_whitespace();
+                let alpha = Component::parse(tokens.next().ok_or(())?)?;
+                if tokens.next().is_some() {
+                    return Err(());
+                }
+                Some(alpha)
+            }
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(Arguments {
+            components,
+            alpha,
+            legacy: false,
+        })
+    }
+
+    fn alpha(&self) -> Result<f32, ()> {
+        self.alpha.map_or(Ok(1.0), Component::alpha)
+    }
+
+    fn three(&self) -> Result<[Component; 3], ()> {
+        self.components.as_slice().try_into().map_err(|_| ())
+    }
+}
+
+fn parse_function(name: &str, arguments: &str) -> Result<Color, FunctionError> {
+    if arguments.trim_start().to_ascii_lowercase().starts_with("from ") {
+        // Relative color syntax
+        return Err(FunctionError::Unsupported);
+    }
+    if name == "color" {
+        // color(<space> c0 c1 c2 / alpha), percentages map 100% to 1.0
+        let (space, rest) = arguments.trim().split_once(char::is_whitespace).ok_or(FunctionError::Invalid)?;
+        let args = Arguments::parse(rest)?;
+        if args.legacy {
+            return Err(FunctionError::Invalid);
+        }
+        let [c0, c1, c2] = args.three()?.map(|c| c.value(1.0));
+        return color_function(&space.to_ascii_lowercase(), [c0?, c1?, c2?], args.alpha()?);
+    }
+
+    let args = Arguments::parse(arguments)?;
+    let alpha = args.alpha()?;
+
+    let color = match name {
+        "rgb" | "rgba" => {
+            let components = args.three()?;
+            if args.legacy {
+                // Legacy syntax can't mix numbers and percentages
+                let percentages = components.iter().filter(|c| matches!(c, Component::Percentage(_))).count();
+                if percentages != 0 && percentages != 3 {
+                    return Err(FunctionError::Invalid);
+                }
+            }
+            let [r, g, b] = components.map(|c| c.value(255.0).map(|v| (v / 255.0).clamp(0.0, 1.0)));
+            Color::new(r?, g?, b?, alpha)
+        }
+        "hsl" | "hsla" => {
+            let [h, s, l] = args.three()?;
+            if args.legacy && !(matches!(s, Component::Percentage(_)) && matches!(l, Component::Percentage(_))) {
+                return Err(FunctionError::Invalid);
+            }
+            let s = (s.value(100.0)? / 100.0).clamp(0.0, 1.0);
+            let l = (l.value(100.0)? / 100.0).clamp(0.0, 1.0);
+            let [r, g, b] = hsl_to_srgb(h.hue()?, s, l);
+            Color::new(r, g, b, alpha)
+        }
+        "hwb" if !args.legacy => {
+            let [h, w, b] = args.three()?;
+            let w = (w.value(100.0)? / 100.0).clamp(0.0, 1.0);
+            let b = (b.value(100.0)? / 100.0).clamp(0.0, 1.0);
+            let [r, g, b] = hwb_to_srgb(h.hue()?, w, b);
+            Color::new(r, g, b, alpha)
+        }
+        "lab" if !args.legacy => {
+            let [l, a, b] = args.three()?;
+            let l = l.value(100.0)?.clamp(0.0, 100.0);
+            let xyz = lab_to_xyz_d65(l, a.value(125.0)?, b.value(125.0)?);
+            from_xyz_d65(xyz, alpha)
+        }
+        "lch" if !args.legacy => {
+            let [l, c, h] = args.three()?;
+            let l = l.value(100.0)?.clamp(0.0, 100.0);
+            let [a, b] = polar_to_rectangular(c.value(150.0)?.max(0.0), h.hue()?);
+            from_xyz_d65(lab_to_xyz_d65(l, a, b), alpha)
+        }
+        "oklab" if !args.legacy => {
+            let [l, a, b] = args.three()?;
+            let l = l.value(1.0)?.clamp(0.0, 1.0);
+            from_linear_srgb(oklab_to_linear_srgb(l, a.value(0.4)?, b.value(0.4)?), alpha)
+        }
+        "oklch" if !args.legacy => {
+            let [l, c, h] = args.three()?;
+            let l = l.value(1.0)?.clamp(0.0, 1.0);
+            let [a, b] = polar_to_rectangular(c.value(0.4)?.max(0.0), h.hue()?);
+            from_linear_srgb(oklab_to_linear_srgb(l, a, b), alpha)
+        }
+        // Legacy comma syntax is only valid for rgb() and hsl()
+        "hwb" | "lab" | "lch" | "oklab" | "oklch" => return Err(FunctionError::Invalid),
+        _ => return Err(FunctionError::Unsupported),
+    };
+    Ok(color)
+}
+
+fn color_function(space: &str, c: [f32; 3], alpha: f32) -> Result<Color, FunctionError> {
+    let color = match space {
+        "srgb" => Color::new(c[0].clamp(0.0, 1.0), c[1].clamp(0.0, 1.0), c[2].clamp(0.0, 1.0), alpha),
+        "srgb-linear" => from_linear_srgb(c, alpha),
+        "display-p3" => from_xyz_d65(mul(&P3_TO_XYZ, c.map(srgb_to_linear)), alpha),
+        "a98-rgb" => from_xyz_d65(mul(&A98_TO_XYZ, c.map(a98_to_linear)), alpha),
+        "prophoto-rgb" => from_xyz_d65(mul(&D50_TO_D65, mul(&PROPHOTO_TO_XYZ_D50, c.map(prophoto_to_linear))), alpha),
+        "rec2020" => from_xyz_d65(mul(&REC2020_TO_XYZ, c.map(rec2020_to_linear)), alpha),
+        "xyz" | "xyz-d65" => from_xyz_d65(c, alpha),
+        "xyz-d50" => from_xyz_d65(mul(&D50_TO_D65, c), alpha),
+        _ => return Err(FunctionError::Unsupported),
+    };
+    Ok(color)
+}
+
+fn hsl_to_srgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let f = |n: f32| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+fn hwb_to_srgb(hue: f32, white: f32, black: f32) -> [f32; 3] {
+    if white + black >= 1.0 {
+        let gray = white / (white + black);
+        return [gray; 3];
+    }
+    hsl_to_srgb(hue, 1.0, 0.5).map(|c| c * (1.0 - white - black) + white)
+}
+
+fn polar_to_rectangular(chroma: f32, hue: f32) -> [f32; 2] {
+    let radians = hue.to_radians();
+    [chroma * radians.cos(), chroma * radians.sin()]
+}
+
+fn lab_to_xyz_d65(l: f32, a: f32, b: f32) -> [f32; 3] {
+    const KAPPA: f32 = 24389.0 / 27.0;
+    const EPSILON: f32 = 216.0 / 24389.0;
+    const D50_WHITE: [f32; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+    let f1 = (l + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+    let x = if f0.powi(3) > EPSILON { f0.powi(3) } else { (116.0 * f0 - 16.0) / KAPPA };
+    let y = if l > KAPPA * EPSILON { f1.powi(3) } else { l / KAPPA };
+    let z = if f2.powi(3) > EPSILON { f2.powi(3) } else { (116.0 * f2 - 16.0) / KAPPA };
+    mul(&D50_TO_D65, [x * D50_WHITE[0], y * D50_WHITE[1], z * D50_WHITE[2]])
+}
+
+fn oklab_to_linear_srgb(l: f32, a: f32, b: f32) -> [f32; 3] {
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    [
+        4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+        -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+    ]
+}
+
+fn from_xyz_d65(xyz: [f32; 3], alpha: f32) -> Color {
+    from_linear_srgb(mul(&XYZ_TO_LINEAR_SRGB, xyz), alpha)
+}
+
+fn from_linear_srgb(linear: [f32; 3], alpha: f32) -> Color {
+    let [r, g, b] = linear.map(|c| linear_to_srgb(c).clamp(0.0, 1.0));
+    Color::new(r, g, b, alpha)
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    let abs = c.abs();
+    if abs <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        c.signum() * (1.055 * abs.powf(1.0 / 2.4) - 0.055)
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    let abs = c.abs();
+    if abs <= 0.040_45 {
+        c / 12.92
+    } else {
+        c.signum() * ((abs + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn a98_to_linear(c: f32) -> f32 {
+    c.signum() * c.abs().powf(563.0 / 256.0)
+}
+
+fn prophoto_to_linear(c: f32) -> f32 {
+    if c.abs() <= 16.0 / 512.0 {
+        c / 16.0
+    } else {
+        c.signum() * c.abs().powf(1.8)
+    }
+}
+
+fn rec2020_to_linear(c: f32) -> f32 {
+    const ALPHA: f32 = 1.099_296_8;
+    const BETA: f32 = 0.018_053_97;
+    if c.abs() < BETA * 4.5 {
+        c / 4.5
+    } else {
+        c.signum() * ((c.abs() + ALPHA - 1.0) / ALPHA).powf(1.0 / 0.45)
+    }
+}
+
+type Matrix = [[f32; 3]; 3];
+
+fn mul(m: &Matrix, v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+// Conversion matrices from CSS Color 4
+const XYZ_TO_LINEAR_SRGB: Matrix = [
+    [3.240_97, -1.537_383_2, -0.498_610_76],
+    [-0.969_243_65, 1.875_967_5, 0.041_555_06],
+    [0.055_630_08, -0.203_976_96, 1.056_971_5],
+];
+const D50_TO_D65: Matrix = [
+    [0.955_473_4, -0.023_098_455, 0.063_259_24],
+    [-0.028_369_71, 1.009_995_4, 0.021_041_44],
+    [0.012_314_015, -0.020_507_65, 1.330_365_9],
+];
+const P3_TO_XYZ: Matrix = [
+    [0.486_570_95, 0.265_667_7, 0.198_217_29],
+    [0.228_974_56, 0.691_738_5, 0.079_286_91],
+    [0.0, 0.045_113_38, 1.043_944_4],
+];
+const A98_TO_XYZ: Matrix = [
+    [0.576_669_04, 0.185_558_24, 0.188_228_65],
+    [0.297_344_98, 0.627_363_6, 0.080_291_46],
+    [0.027_031_362, 0.070_688_85, 0.991_337_54],
+];
+const PROPHOTO_TO_XYZ_D50: Matrix = [
+    [0.797_766_6, 0.135_181_3, 0.031_347_734],
+    [0.288_074_83, 0.711_835_2, 0.000_089_936_94],
+    [0.0, 0.0, 0.825_104_6],
+];
+const REC2020_TO_XYZ: Matrix = [
+    [0.636_958_05, 0.144_616_9, 0.168_880_98],
+    [0.262_700_2, 0.677_998_1, 0.059_301_716],
+    [0.0, 0.028_072_692, 1.060_985_1],
+];
+
+fn named_color(name: &str) -> Option<Color> {
+    NAMED_COLORS
+        .binary_search_by(|(candidate, _)| candidate.cmp(&name))
+        .ok()
+        .map(|index| Color::from_hex(NAMED_COLORS[index].1))
+}
+
+// CSS named colors, sorted by name for binary search
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
@@ -1,5 +1,15 @@
+pub mod animation;
 pub mod color;
+pub mod css_color;
+pub mod paint;
+pub mod state;
+pub mod style;
 pub mod transform;
 
+pub use animation::*;
 pub use color::*;
+pub use css_color::*;
+pub use paint::*;
+pub use state::*;
+pub use style::*;
 pub use transform::*;
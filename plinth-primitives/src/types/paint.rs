@@ -0,0 +1,320 @@
+use std::f32::consts::SQRT_2;
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+
+use crate::types::Color;
+
+// A color along a gradient, `offset` 0 at its start and 1 at its end. Stops without an
+// offset are spread evenly between their neighbours, as in CSS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorStop {
+    pub color: Color,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: Option<f32>,
+}
+
+impl ColorStop {
+    pub fn new(color: Color, offset: f32) -> Self {
+        Self { color, offset: Some(offset) }
+    }
+}
+
+impl From<Color> for ColorStop {
+    fn from(color: Color) -> Self {
+        Self { color, offset: None }
+    }
+}
+
+impl From<(Color, f32)> for ColorStop {
+    fn from((color, offset): (Color, f32)) -> Self {
+        Self::new(color, offset)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GradientDirection {
+    // Radians clockwise from up, like a CSS `<angle>`: 0 runs bottom to top
+    Angle(f32),
+    // `to top right` and friends, aimed so the middle of the gradient runs through the
+    // other two corners whatever the box's aspect ratio
+    Corner { right: bool, top: bool },
+}
+
+impl Default for GradientDirection {
+    // CSS's default, `to bottom`
+    fn default() -> Self {
+        Self::Angle(std::f32::consts::PI)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct LinearGradient {
+    pub direction: GradientDirection,
+    pub stops: Vec<ColorStop>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RadialShape {
+    Circle,
+    #[default]
+    Ellipse,
+}
+
+// How far a radial gradient reaches from its center, by the CSS keywords or as explicit
+// radii (a circle only uses x)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RadialSize {
+    ClosestSide,
+    FarthestSide,
+    ClosestCorner,
+    #[default]
+    FarthestCorner,
+    Extent(Vec2),
+}
+
+// `center` is a fraction of the box from its top-left corner, y down like CSS's `at`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct RadialGradient {
+    pub shape: RadialShape,
+    pub size: RadialSize,
+    pub center: Vec2,
+    pub stops: Vec<ColorStop>,
+}
+
+impl Default for RadialGradient {
+    fn default() -> Self {
+        Self {
+            shape: RadialShape::default(),
+            size: RadialSize::default(),
+            center: Vec2::splat(0.5),
+            stops: Vec::new(),
+        }
+    }
+}
+
+// Sweeps clockwise around `center`, starting at `angle` radians clockwise from up
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct ConicGradient {
+    pub angle: f32,
+    pub center: Vec2,
+    pub stops: Vec<ColorStop>,
+}
+
+impl Default for ConicGradient {
+    fn default() -> Self {
+        Self {
+            angle: 0.0,
+            center: Vec2::splat(0.5),
+            stops: Vec::new(),
+        }
+    }
+}
+
+// How a shape is filled. Gradients are laid out over the shape's box before its transform
+// and looked up per fragment from a ramp texture owned by the batch drawing the shape.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Paint {
+    Solid(Color),
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+    Conic(ConicGradient),
+}
+
+impl Paint {
+    pub fn linear(angle: f32, stops: impl IntoIterator<Item = impl Into<ColorStop>>) -> Self {
+        Self::Linear(LinearGradient {
+            direction: GradientDirection::Angle(angle),
+            stops: stops.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    // A circle reaching the farthest corner of the box from its middle
+    pub fn radial(stops: impl IntoIterator<Item = impl Into<ColorStop>>) -> Self {
+        Self::Radial(RadialGradient {
+            shape: RadialShape::Circle,
+            stops: stops.into_iter().map(Into::into).collect(),
+            ..RadialGradient::default()
+        })
+    }
+
+    pub fn conic(angle: f32, stops: impl IntoIterator<Item = impl Into<ColorStop>>) -> Self {
+        Self::Conic(ConicGradient {
+            angle,
+            stops: stops.into_iter().map(Into::into).collect(),
+            ..ConicGradient::default()
+        })
+    }
+
+    // None for a solid color
+    pub fn stops(&self) -> Option<&[ColorStop]> {
+        match self {
+            Paint::Solid(_) => None,
+            Paint::Linear(gradient) => Some(&gradient.stops),
+            Paint::Radial(gradient) => Some(&gradient.stops),
+            Paint::Conic(gradient) => Some(&gradient.stops),
+        }
+    }
+
+    // What the shader multiplies the paint by: the color itself when solid, white when the
+    // ramp carries the colors. Opacity is folded into this afterwards.
+    pub fn base_color(&self) -> Color {
+        match self {
+            Paint::Solid(color) => *color,
+            _ => Color::WHITE,
+        }
+    }
+
+    // The gradient laid out over the box from `min` to `max` (y up), sampling ramp row 0
+    pub fn data(&self, min: Vec2, max: Vec2) -> PaintData {
+        let size = max - min;
+        // CSS positions are from the top-left, y down
+        let point = |fraction: Vec2| Vec2::new(min.x + fraction.x * size.x, max.y - fraction.y * size.y);
+        match self {
+            Paint::Solid(_) => PaintData::SOLID,
+            Paint::Linear(gradient) => {
+                let direction = match gradient.direction {
+                    GradientDirection::Angle(angle) => Vec2::new(angle.sin(), angle.cos()),
+                    GradientDirection::Corner { right, top } => {
+                        let sign = |positive: bool| if positive { 1.0 } else { -1.0 };
+                        Vec2::new(sign(right) * size.y, sign(top) * size.x).normalize_or_zero()
+                    }
+                };
+                // Long enough for the ends to reach the corners, as in CSS
+                let length = size.x.abs() * direction.x.abs() + size.y.abs() * direction.y.abs();
+                let center = point(Vec2::splat(0.5));
+                let start = center - direction * length / 2.0;
+                let end = center + direction * length / 2.0;
+                PaintData::new(PaintData::KIND_LINEAR, 0.0, [start.x, start.y, end.x, end.y])
+            }
+            Paint::Radial(gradient) => {
+                let center = point(gradient.center);
+                let radii = radial_radii(gradient.shape, gradient.size, center, min.min(max), min.max(max));
+                PaintData::new(PaintData::KIND_RADIAL, 0.0, [center.x, center.y, radii.x, radii.y])
+            }
+            Paint::Conic(gradient) => {
+                let center = point(gradient.center);
+                PaintData::new(PaintData::KIND_CONIC, gradient.angle, [center.x, center.y, 0.0, 0.0])
+            }
+        }
+    }
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Paint::Solid(Color::default())
+    }
+}
+
+fn radial_radii(shape: RadialShape, size: RadialSize, center: Vec2, min: Vec2, max: Vec2) -> Vec2 {
+    let near = (center - min).abs().min((max - center).abs());
+    let far = (center - min).abs().max((max - center).abs());
+    let radii = match (shape, size) {
+        (RadialShape::Circle, RadialSize::ClosestSide) => Vec2::splat(near.min_element()),
+        (RadialShape::Circle, RadialSize::FarthestSide) => Vec2::splat(far.max_element()),
+        (RadialShape::Circle, RadialSize::ClosestCorner) => Vec2::splat(near.length()),
+        (RadialShape::Circle, RadialSize::FarthestCorner) => Vec2::splat(far.length()),
+        (RadialShape::Circle, RadialSize::Extent(radii)) => Vec2::splat(radii.x),
+        (RadialShape::Ellipse, RadialSize::ClosestSide) => near,
+        (RadialShape::Ellipse, RadialSize::FarthestSide) => far,
+        // The closest-side/farthest-side ellipse grown until it passes through the corner
+        (RadialShape::Ellipse, RadialSize::ClosestCorner) => near * SQRT_2,
+        (RadialShape::Ellipse, RadialSize::FarthestCorner) => far * SQRT_2,
+        (RadialShape::Ellipse, RadialSize::Extent(radii)) => radii,
+    };
+    radii.abs()
+}
+
+// Stops with their offsets filled in and made non-decreasing, following CSS: a missing first
+// or last offset is 0 or 1, missing ones in between are spread evenly, and an offset before
+// an earlier one is moved up to it.
+pub fn resolve_stops(stops: &[ColorStop]) -> Vec<(f32, Color)> {
+    let mut offsets: Vec<Option<f32>> = stops.iter().map(|stop| stop.offset).collect();
+    if let Some(first) = offsets.first_mut() {
+        first.get_or_insert(0.0);
+    }
+    if let Some(last) = offsets.last_mut() {
+        last.get_or_insert(1.0);
+    }
+    let mut highest = f32::NEG_INFINITY;
+    for offset in offsets.iter_mut().flatten() {
+        highest = highest.max(*offset);
+        *offset = highest;
+    }
+    let mut index = 0;
+    while index < offsets.len() {
+        if offsets[index].is_some() {
+            index += 1;
+            continue;
+        }
+        // Runs of missing offsets always sit between two known ones
+        let before = offsets[index - 1].unwrap_or(0.0);
+        let end = (index..offsets.len()).find(|&i| offsets[i].is_some()).unwrap_or(offsets.len() - 1);
+        let after = offsets[end].unwrap_or(1.0);
+        let steps = (end - index + 1) as f32;
+        for (step, offset) in offsets[index..end].iter_mut().enumerate() {
+            *offset = Some(before + (after - before) * (step + 1) as f32 / steps);
+        }
+        index = end;
+    }
+    offsets.into_iter().zip(stops).map(|(offset, stop)| (offset.unwrap_or(0.0), stop.color)).collect()
+}
+
+// Per-instance (or per-vertex) paint for the shaders, see plinth/paint.wgsl. `params` is
+// the kind, ramp row and conic start angle; `geometry` the gradient's points: start and
+// end for linear, center and radii for radial, center for conic.
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct PaintData {
+    pub params: [f32; 4],
+    pub geometry: [f32; 4],
+}
+
+impl PaintData {
+    pub const KIND_SOLID: f32 = 0.0;
+    pub const KIND_LINEAR: f32 = 1.0;
+    pub const KIND_RADIAL: f32 = 2.0;
+    pub const KIND_CONIC: f32 = 3.0;
+    pub const SOLID: PaintData = PaintData { params: [0.0; 4], geometry: [0.0; 4] };
+
+    pub fn new(kind: f32, angle: f32, geometry: [f32; 4]) -> Self {
+        Self { params: [kind, 0.0, angle, 0.0], geometry }
+    }
+
+    pub fn kind(&self) -> f32 {
+        self.params[0]
+    }
+
+    pub fn is_gradient(&self) -> bool {
+        self.kind() != Self::KIND_SOLID
+    }
+
+    pub fn ramp_row(&self) -> u32 {
+        self.params[1] as u32
+    }
+
+    pub fn with_ramp_row(mut self, row: u32) -> Self {
+        self.params[1] = row as f32;
+        self
+    }
+}
+
+impl Default for PaintData {
+    fn default() -> Self {
+        Self::SOLID
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::{BitOr, BitOrAssign};
+
+// Interaction state of a primitive as a bitset of CSS pseudo-classes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct InteractionState(u8);
+
+impl InteractionState {
+    pub const NONE: Self = Self(0);
+    pub const HOVER: Self = Self(1 << 0);
+    pub const ACTIVE: Self = Self(1 << 1);
+    pub const FOCUS: Self = Self(1 << 2);
+    pub const DISABLED: Self = Self(1 << 3);
+
+    const NAMES: [(&'static str, Self); 4] = [
+        ("hover", Self::HOVER),
+        ("active", Self::ACTIVE),
+        ("focus", Self::FOCUS),
+        ("disabled", Self::DISABLED),
+    ];
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    pub fn set(&mut self, other: Self, enabled: bool) {
+        if enabled {
+            self.insert(other);
+        } else {
+            self.remove(other);
+        }
+    }
+
+    // Number of states set, which is what they add to a selector's specificity
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    // The state for a pseudo-class name without the colon, e.g. "hover"
+    pub fn from_pseudo_class(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(pseudo_class, _)| pseudo_class.eq_ignore_ascii_case(name))
+            .map(|(_, state)| *state)
+    }
+
+    pub fn pseudo_classes(self) -> impl Iterator<Item = &'static str> {
+        Self::NAMES
+            .into_iter()
+            .filter(move |(_, state)| self.contains(*state))
+            .map(|(name, _)| name)
+    }
+}
+
+impl BitOr for InteractionState {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for InteractionState {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.insert(rhs);
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+
+use crate::types::{Animation, Color, CssColor, Paint, Transform, Transition};
+
+// Computed style of a CSS class, as far as primitives care about it. Every field is
+// optional so styles can be layered: unset fields leave the primitive's own value alone.
+// Lengths are in CSS pixels. Primitives convert them against a StyleViewport when the
+// style is applied: shape sizes, radii, border widths, shadows and translations to the clip
+// space the shapes are placed in, a line's width and a text's font size to their Pixels or
+// World unit, and blurs to physical pixels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Color>,
+    // Colors that can be currentColor, resolved against `color` once the cascade is done
+    pub background_color: Option<CssColor>,
+    // A gradient from `background-image` or `background`, drawn over background-color
+    pub background_image: Option<Paint>,
+    pub border_color: Option<CssColor>,
+    pub opacity: Option<f32>,
+    pub border_width: Option<f32>,
+    pub border_radius: Option<f32>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    // Composed with the primitive's own transform, inside the space that one places it in.
+    // Apply styles to the primitive as authored, as the batches do, not to a styled copy.
+    pub transform: Option<Transform>,
+    pub z_index: Option<i32>,
+    pub visibility: Option<Visibility>,
+    pub display: Option<Display>,
+    pub box_shadow: Option<Vec<BoxShadow>>,
+    // Radius of `filter: blur()`
+    pub filter_blur: Option<f32>,
+    // Radius of `backdrop-filter: blur()`, blurring what is drawn behind the shape
+    pub backdrop_blur: Option<f32>,
+    pub font_size: Option<f32>,
+    // Family names in order of preference, unquoted
+    pub font_family: Option<Vec<String>>,
+    pub transitions: Option<Vec<Transition>>,
+    pub animations: Option<Vec<Animation>>,
+    // `--name: value` declarations, unparsed
+    pub custom_properties: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+    Collapse,
+}
+
+// Primitives only distinguish rendered from `display: none`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Display {
+    Shown,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    pub offset: Vec2,
+    pub blur: f32,
+    pub spread: f32,
+    // Defaults to currentColor, resolved against the style's `color`
+    pub color: CssColor,
+    pub inset: bool,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Copies every property set on `other` over this style
+    pub fn merge(&mut self, other: &Style) {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field.clone();
+                })*
+            };
+        }
+        take!(
+            color, background_color, background_image, border_color, opacity, border_width, border_radius, width, height,
+            transform, z_index, visibility, display, box_shadow, filter_blur, backdrop_blur, font_size, font_family,
+            transitions, animations
+        );
+        self.custom_properties
+            .extend(other.custom_properties.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    // The properties a child inherits from this style: color, visibility, fonts and custom
+    // properties, as in CSS. Everything else starts unset on the child.
+    pub fn inherited(&self) -> Style {
+        Style {
+            color: self.color,
+            visibility: self.visibility,
+            font_size: self.font_size,
+            font_family: self.font_family.clone(),
+            custom_properties: self.custom_properties.clone(),
+            ..Style::default()
+        }
+    }
+
+    pub fn custom_property(&self, name: &str) -> Option<&str> {
+        self.custom_properties.get(name).map(String::as_str)
+    }
+
+    // `color` with currentColor resolved against this style's color, None if that isn't set
+    pub fn resolve_color(&self, color: CssColor) -> Option<Color> {
+        match color {
+            CssColor::Color(color) => Some(color),
+            CssColor::CurrentColor => self.color,
+        }
+    }
+
+    // Replaces currentColor in background-color and border-color with the final color. Call
+    // it once the cascade is done; currentColor is unset without a color.
+    pub fn resolve_current_color(&mut self) {
+        self.background_color = self.background_color.and_then(|color| self.resolve_color(color)).map(CssColor::Color);
+        self.border_color = self.border_color.and_then(|color| self.resolve_color(color)).map(CssColor::Color);
+    }
+
+    // Shape fill: background-color, falling back to color
+    pub fn fill_color(&self) -> Option<Color> {
+        self.background_color.and_then(|color| self.resolve_color(color)).or(self.color)
+    }
+
+    // border-color with currentColor resolved
+    pub fn stroke_color(&self) -> Option<Color> {
+        self.border_color.and_then(|color| self.resolve_color(color))
+    }
+
+    // Shape paint: the background gradient, falling back to fill_color as a solid paint
+    pub fn fill_paint(&self) -> Option<Paint> {
+        self.background_image.clone().or_else(|| self.fill_color().map(Paint::Solid))
+    }
+
+    // Whether anything gets drawn: not `visibility: hidden/collapse` and not `display: none`
+    pub fn is_visible(&self) -> bool {
+        !matches!(self.visibility, Some(Visibility::Hidden | Visibility::Collapse))
+            && self.display != Some(Display::None)
+    }
+
+    // First outer shadow with its color resolved, in CSS pixels
+    pub fn outer_shadow(&self) -> Option<Shadow> {
+        let current_color = self.color.unwrap_or(Color::BLACK);
+        self.box_shadow
+            .as_ref()?
+            .iter()
+            .find(|shadow| !shadow.inset)
+            .map(|shadow| Shadow {
+                offset: shadow.offset,
+                blur: shadow.blur,
+                spread: shadow.spread,
+                color: shadow.color.resolve(current_color),
+            })
+    }
+}
+
+// What CSS lengths are measured against when a style is applied: a surface `size` physical
+// pixels large, with `scale_factor` physical pixels to a CSS pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StyleViewport {
+    pub size: Vec2,
+    pub scale_factor: f32,
+}
+
+impl StyleViewport {
+    // `width` and `height` in physical pixels
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            size: Vec2::new(width.max(1) as f32, height.max(1) as f32),
+            scale_factor: 1.0,
+        }
+    }
+
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    // `px` CSS pixels in physical pixels
+    pub fn pixels(&self, px: f32) -> f32 {
+        px * self.scale_factor
+    }
+
+    // A size or offset in CSS pixels in clip space, which is 2 units across either axis
+    pub fn clip(&self, px: Vec2) -> Vec2 {
+        px * self.scale_factor * 2.0 / self.size
+    }
+
+    // A length without a direction, like a radius or border width, in clip space. Measured
+    // along the surface's height.
+    pub fn clip_length(&self, px: f32) -> f32 {
+        px * self.scale_factor * 2.0 / self.size.y
+    }
+
+    // A CSS transform with its translation in clip space
+    pub fn clip_transform(&self, transform: Transform) -> Transform {
+        transform.with_position(self.clip(transform.position))
+    }
+}
+
+impl Default for StyleViewport {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+// A drop shadow with a concrete color, as stored on primitives. `blur` is the CSS blur
+// radius, twice the standard deviation of the Gaussian; `spread` grows the shape first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shadow {
+    pub offset: Vec2,
+    pub blur: f32,
+    pub spread: f32,
+    pub color: Color,
+}
+
+impl Shadow {
+    pub fn new(offset: Vec2, blur: f32, spread: f32, color: Color) -> Self {
+        Self { offset, blur, spread, color }
+    }
+
+    // The shadow for the shaders, faded by the primitive's opacity
+    pub fn data(&self, opacity: f32) -> ShadowData {
+        let color = self.color;
+        ShadowData {
+            params: [self.offset.x, self.offset.y, self.blur.max(0.0) / 2.0, self.spread],
+            color: [color.r, color.g, color.b, color.a * opacity],
+        }
+    }
+}
+
+// Per-instance shadow for the shaders, see plinth/shadow.wgsl. `params` is the offset,
+// the Gaussian's standard deviation and the spread; a transparent color draws nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct ShadowData {
+    pub params: [f32; 4],
+    pub color: [f32; 4],
+}
+
+impl ShadowData {
+    pub const NONE: ShadowData = ShadowData { params: [0.0; 4], color: [0.0; 4] };
+
+    pub fn is_visible(&self) -> bool {
+        self.color[3] > 0.0
+    }
+
+    // How far the shadow reaches past the shape, which is how much its quad has to grow
+    pub fn margin(&self) -> f32 {
+        if !self.is_visible() {
+            return 0.0;
+        }
+        let [x, y, sigma, spread] = self.params;
+        (spread + sigma * 3.0).max(0.0) + x.abs().max(y.abs())
+    }
+}
+
+impl Default for ShadowData {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+// Style-driven state shared by every primitive
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Appearance {
+    pub opacity: f32,
+    pub border_width: f32,
+    pub border_color: Color,
+    pub z_index: i32,
+    pub visible: bool,
+    pub shadow: Option<Shadow>,
+    // Standard deviation of `filter: blur()` in pixels, applied as a post-process
+    pub blur: f32,
+    // Same for `backdrop-filter: blur()`, blurring what is behind the primitive
+    pub backdrop_blur: f32,
+}
+
+impl Appearance {
+    // Applies the parts of `style` every primitive shares, its lengths measured against `viewport`
+    pub fn apply_style(&mut self, style: &Style, viewport: &StyleViewport) {
+        if let Some(opacity) = style.opacity {
+            self.opacity = opacity.clamp(0.0, 1.0);
+        }
+        if let Some(width) = style.border_width {
+            self.border_width = viewport.clip_length(width).max(0.0);
+        }
+        if let Some(color) = style.stroke_color() {
+            self.border_color = color;
+        }
+        if let Some(z_index) = style.z_index {
+            self.z_index = z_index;
+        }
+        if style.visibility.is_some() || style.display.is_some() {
+            self.visible = style.is_visible();
+        }
+        if style.box_shadow.is_some() {
+            self.shadow = style.outer_shadow().map(|shadow| Shadow {
+                offset: viewport.clip(shadow.offset),
+                blur: viewport.clip_length(shadow.blur),
+                spread: viewport.clip_length(shadow.spread),
+                ..shadow
+            });
+        }
+        if let Some(blur) = style.filter_blur {
+            self.blur = viewport.pixels(blur).max(0.0);
+        }
+        if let Some(blur) = style.backdrop_blur {
+            self.backdrop_blur = viewport.pixels(blur).max(0.0);
+        }
+    }
+
+    // The shadow for the shaders, NONE without one
+    pub fn shadow_data(&self) -> ShadowData {
+        self.shadow.map_or(ShadowData::NONE, |shadow| shadow.data(self.opacity))
+    }
+
+    // `color` with opacity folded into its alpha
+    pub fn apply_opacity(&self, color: Color) -> Color {
+        Color::new(color.r, color.g, color.b, color.a * self.opacity)
+    }
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            opacity: 1.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+            z_index: 0,
+            visible: true,
+            shadow: None,
+            blur: 0.0,
+            backdrop_blur: 0.0,
+        }
+    }
+}
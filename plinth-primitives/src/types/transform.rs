@@ -1,6 +1,7 @@
 use glam::Vec2;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Transform {
     pub position: Vec2,
     pub scale: Vec2,
@@ -38,6 +39,32 @@ impl Transform {
         self.rotation = rotation;
         self
     }
+
+    // `inner` applied first, then this transform, e.g. a CSS transform in the space a
+    // primitive's own transform places. Exact unless a non-uniform scale meets a rotation,
+    // which a single rotate-scale-translate can't express.
+    pub fn compose(&self, inner: &Transform) -> Transform {
+        Transform {
+            position: self.transform_point(inner.position),
+            scale: self.scale * inner.scale,
+            rotation: self.rotation + inner.rotation,
+        }
+    }
+
+    // Rotate, then scale, then translate, matching apply_transform in the shaders
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        Vec2::from_angle(self.rotation).rotate(point) * self.scale + self.position
+    }
+
+    // Inverse of `transform_point`; a zero scale collapses everything to the origin
+    pub fn inverse_transform_point(&self, point: Vec2) -> Vec2 {
+        let scaled = point - self.position;
+        let unscaled = Vec2::new(
+            if self.scale.x != 0.0 { scaled.x / self.scale.x } else { 0.0 },
+            if self.scale.y != 0.0 { scaled.y / self.scale.y } else { 0.0 },
+        );
+        Vec2::from_angle(-self.rotation).rotate(unscaled)
+    }
 }
 
 impl Default for Transform {
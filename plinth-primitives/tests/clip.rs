@@ -0,0 +1,127 @@
+use glam::Vec2;
+use plinth_primitives::batch::{ClipId, ClipRun};
+use plinth_primitives::renderer::ClipStack;
+use plinth_primitives::{Appearance, Line, LineBatch, Path, PathBatch, Rectangle, RectangleBatch};
+
+fn rectangle(z_index: i32) -> Rectangle {
+    Rectangle::new(Vec2::ZERO, Vec2::splat(0.1)).with_appearance(Appearance { z_index, ..Appearance::default() })
+}
+
+#[test]
+fn nested_rect_clips_intersect_and_pop_back_out() {
+    let mut clips = ClipStack::new();
+    assert_eq!(clips.current(), ClipId::NONE);
+
+    // Corners may come in any order
+    let outer = clips.push_rect(Vec2::new(0.5, 0.5), Vec2::new(-0.5, -0.5));
+    let inner = clips.push_rect(Vec2::new(0.0, -1.0), Vec2::new(1.0, 0.25));
+    assert_eq!((outer, inner, clips.depth()), (ClipId(1), ClipId(2), 2));
+    let region = clips.region(inner).unwrap();
+    assert_eq!((region.min, region.max), (Vec2::new(0.0, -0.5), Vec2::new(0.5, 0.25)));
+    assert!(region.masks.is_empty());
+
+    assert_eq!(clips.pop(), Some(inner));
+    assert_eq!(clips.current(), outer);
+    assert_eq!(clips.pop(), Some(outer));
+    assert_eq!(clips.pop(), None);
+    // Popped clips stay valid for what was drawn in them
+    assert!(clips.region(inner).is_some());
+    assert!(clips.region(ClipId::NONE).is_none());
+
+    clips.clear();
+    assert!(clips.region(outer).is_none());
+}
+
+#[test]
+fn rounded_and_path_clips_add_stencil_masks() {
+    let mut clips = ClipStack::new();
+    let square = clips.push_rounded_rect(Vec2::splat(-0.5), Vec2::splat(0.5), 0.0);
+    assert!(clips.region(square).unwrap().masks.is_empty());
+    assert_eq!(clips.mask_count(), 0);
+
+    let generation = clips.generation();
+    let rounded = clips.push_rounded_rect(Vec2::new(-1.0, -0.25), Vec2::new(1.0, 0.25), 0.1);
+    assert_eq!(clips.region(rounded).unwrap().masks, [0]);
+    assert_ne!(clips.generation(), generation);
+    let triangles = clips.mask(0);
+    assert_eq!(triangles.len() % 3, 0);
+    assert!(triangles.iter().all(|point| point.x.abs() <= 1.0 + 1e-5 && point.y.abs() <= 0.25 + 1e-5));
+
+    // Nested masks keep the outer ones, and the scissor shrinks to the path's bounds
+    let circle = clips.push_path(&Path::circle(Vec2::new(0.25, 0.0), 0.5));
+    let region = clips.region(circle).unwrap();
+    assert_eq!(region.masks, [0, 1]);
+    assert!((region.min - Vec2::new(-0.25, -0.25)).abs().max_element() < 1e-3);
+    assert!((region.max - Vec2::new(0.5, 0.25)).abs().max_element() < 1e-3);
+}
+
+#[test]
+fn scissor_rects_cover_whole_pixels_on_screen() {
+    let mut clips = ClipStack::new();
+    let clip = clips.push_rect(Vec2::new(-0.5, -1.0), Vec2::new(0.501, 0.5));
+    // y is up in clip space and down in pixels
+    assert_eq!(clips.region(clip).unwrap().scissor_rect(800, 600), Some((200, 150, 401, 450)));
+
+    let clip = clips.push_rect(Vec2::new(-4.0, -4.0), Vec2::new(4.0, 4.0));
+    assert_eq!(clips.region(clip).unwrap().scissor_rect(800, 600), Some((200, 150, 401, 450)));
+    clips.pop();
+    clips.pop();
+
+    let off_screen = clips.push_rect(Vec2::new(2.0, 0.0), Vec2::new(3.0, 1.0));
+    assert_eq!(clips.region(off_screen).unwrap().scissor_rect(800, 600), None);
+    clips.pop();
+    clips.push_rect(Vec2::new(-1.0, -1.0), Vec2::ZERO);
+    let disjoint = clips.push_rect(Vec2::new(0.5, 0.5), Vec2::ONE);
+    assert!(clips.region(disjoint).unwrap().is_empty());
+}
+
+#[test]
+fn batches_record_clips_when_added_and_split_runs_at_them() {
+    let mut batch = RectangleBatch::new();
+    batch.add_rectangle(rectangle(0));
+    batch.set_clip(ClipId(1));
+    batch.add_rectangles([rectangle(0), rectangle(0)]);
+    // Sorting by z-index moves this one after the others, next to the unclipped one
+    batch.add_rectangle(rectangle(1));
+    batch.set_clip(ClipId::NONE);
+    batch.add_rectangle(rectangle(1));
+    assert_eq!(batch.clip(), ClipId::NONE);
+
+    batch.resolve_styles(None);
+    let runs: Vec<_> = batch.clip_runs().iter().map(|run| (run.instances.clone(), run.clip)).collect();
+    assert_eq!(runs, [(0..1, ClipId::NONE), (1..4, ClipId(1)), (4..5, ClipId::NONE)]);
+    let layers: Vec<_> = batch.layers().iter().map(|layer| (layer.instances.clone(), layer.clip)).collect();
+    assert_eq!(layers, runs);
+
+    batch.clear();
+    batch.add_rectangle(rectangle(0));
+    batch.resolve_styles(None);
+    assert_eq!(batch.clip_runs(), [ClipRun { instances: 0..1, clip: ClipId::NONE }]);
+}
+
+#[test]
+fn mesh_and_line_runs_cover_their_indices_and_segments() {
+    let mut paths = PathBatch::new();
+    paths.set_clip(ClipId(2));
+    paths.add_paths([Path::rect(Vec2::ZERO, Vec2::ONE), Path::circle(Vec2::ZERO, 0.5)]);
+    paths.set_clip(ClipId(3));
+    paths.add_path(Path::rect(Vec2::ONE, Vec2::ONE));
+    paths.resolve_styles(None);
+    let runs = paths.clip_runs();
+    assert_eq!(runs.len(), 2);
+    assert_eq!((runs[0].clip, runs[1].clip), (ClipId(2), ClipId(3)));
+    assert_eq!(runs[0].instances.end, runs[1].instances.start);
+    assert_eq!(runs[1].instances.end, paths.geometry().indices.len());
+    // The last square is two triangles
+    assert_eq!(runs[1].instances.len(), 6);
+
+    let mut lines = LineBatch::new();
+    lines.add_line(Line::new(Vec2::ZERO, Vec2::ONE));
+    lines.set_clip(ClipId(1));
+    lines.add_line(Line::new(Vec2::ZERO, Vec2::X));
+    lines.resolve_styles(None);
+    assert_eq!(
+        lines.clip_runs(),
+        [ClipRun { instances: 0..1, clip: ClipId::NONE }, ClipRun { instances: 1..2, clip: ClipId(1) }]
+    );
+}
@@ -0,0 +1,172 @@
+use plinth_primitives::{Color, ColorParseError, CssColor};
+
+// Expected 8-bit sRGB + alpha, or None when the input must be rejected
+const CASES: &[(&str, Option<[u8; 4]>)] = &[
+    // Hex, every length
+    ("#f00", Some([255, 0, 0, 255])),
+    ("#F0A", Some([255, 0, 170, 255])),
+    ("#f008", Some([255, 0, 0, 136])),
+    ("#ff8000", Some([255, 128, 0, 255])),
+    ("#FF800080", Some([255, 128, 0, 128])),
+    ("#0000", Some([0, 0, 0, 0])),
+    ("  #abc  ", Some([170, 187, 204, 255])),
+    ("#12345", None),
+    ("#1234567", None),
+    ("#ggg", None),
+    ("#", None),
+    ("#+ff", None),
+    // Keywords and named colors
+    ("transparent", Some([0, 0, 0, 0])),
+    ("TRANSPARENT", Some([0, 0, 0, 0])),
+    ("red", Some([255, 0, 0, 255])),
+    ("Red", Some([255, 0, 0, 255])),
+    ("aliceblue", Some([240, 248, 255, 255])),
+    ("rebeccapurple", Some([102, 51, 153, 255])),
+    ("yellowgreen", Some([154, 205, 50, 255])),
+    ("gray", Some([128, 128, 128, 255])),
+    ("grey", Some([128, 128, 128, 255])),
+    ("lightgoldenrodyellow", Some([250, 250, 210, 255])),
+    ("notacolor", None),
+    ("", None),
+    // rgb() legacy comma syntax
+    ("rgb(255, 0, 0)", Some([255, 0, 0, 255])),
+    ("rgba(255, 0, 0, 0.5)", Some([255, 0, 0, 128])),
+    ("rgb(255,128,0,50%)", Some([255, 128, 0, 128])),
+    ("rgb(100%, 50%, 0%)", Some([255, 128, 0, 255])),
+    ("rgba(0, 0, 0, .25)", Some([0, 0, 0, 64])),
+    ("rgb(255, 50%, 0)", None),
+    ("rgb(none, 0, 0)", None),
+    ("rgb(255, 0)", None),
+    ("rgb(1, 2, 3, 4, 5)", None),
+    ("rgb(255 0, 0)", None),
+    // rgb() space syntax
+    ("rgb(255 0 0)", Some([255, 0, 0, 255])),
+    ("rgb(255 0 0 / 0.5)", Some([255, 0, 0, 128])),
+    ("rgb(255 0 0/50%)", Some([255, 0, 0, 128])),
+    ("rgba(255 0 0 / 25%)", Some([255, 0, 0, 64])),
+    ("rgb(100% 50% 0)", Some([255, 128, 0, 255])),
+    ("rgb(none 255 none)", Some([0, 255, 0, 255])),
+    ("RGB(255 0 0)", Some([255, 0, 0, 255])),
+    ("rgb(300 -10 0)", Some([255, 0, 0, 255])),
+    ("rgb(0 0 0 / 2)", Some([0, 0, 0, 255])),
+    ("rgb(0 0 0 / -1)", Some([0, 0, 0, 0])),
+    ("rgb(1e2 0 0)", Some([100, 0, 0, 255])),
+    ("rgb(255 0)", None),
+    ("rgb(1 2 3 4)", None),
+    ("rgb(0 0 0 / 1 2)", None),
+    ("rgb(0 0 0 / 1 / 2)", None),
+    ("rgb(0 0 0 /)", None),
+    ("rgb(0 0 0", None),
+    ("rgb(inf 0 0)", None),
+    ("rgb(10deg 0 0)", None),
+    // hsl()
+    ("hsl(120, 100%, 50%)", Some([0, 255, 0, 255])),
+    ("hsla(120, 100%, 25%, 0.5)", Some([0, 128, 0, 128])),
+    ("hsl(0 100% 50%)", Some([255, 0, 0, 255])),
+    ("hsl(120deg 100% 25%)", Some([0, 128, 0, 255])),
+    ("hsl(0.5turn 100% 50%)", Some([0, 255, 255, 255])),
+    ("hsl(200grad 100% 50%)", Some([0, 255, 255, 255])),
+    ("hsl(3.14159265rad 100% 50%)", Some([0, 255, 255, 255])),
+    ("hsl(-240 100% 50%)", Some([0, 255, 0, 255])),
+    ("hsl(480 100% 50%)", Some([0, 255, 0, 255])),
+    ("hsl(240 100 50)", Some([0, 0, 255, 255])),
+    ("hsl(none 0% 100%)", Some([255, 255, 255, 255])),
+    ("hsl(0 0% 50% / 10%)", Some([128, 128, 128, 26])),
+    ("hsl(120, 100, 50)", None),
+    ("hsl(120%, 100%, 50%)", None),
+    ("hsl(120 100%)", None),
+    // hwb()
+    ("hwb(0 0% 0%)", Some([255, 0, 0, 255])),
+    ("hwb(120 0% 50%)", Some([0, 128, 0, 255])),
+    ("hwb(240 20% 20%)", Some([51, 51, 204, 255])),
+    ("hwb(0 60% 60%)", Some([128, 128, 128, 255])),
+    ("hwb(0 100% 0% / 0.5)", Some([255, 255, 255, 128])),
+    ("hwb(0, 0%, 0%)", None),
+    // lab() / lch(), reference values from CSS Color 4
+    ("lab(29.2345% 39.3825 20.0664)", Some([125, 35, 41, 255])),
+    ("lab(52.2345% 40.1645 59.9971)", Some([198, 93, 6, 255])),
+    ("lab(100 0 0)", Some([255, 255, 255, 255])),
+    ("lab(0 0 0)", Some([0, 0, 0, 255])),
+    ("lab(50% 0 0 / 0.5)", Some([119, 119, 119, 128])),
+    ("lch(29.2345% 44.2 27)", Some([125, 35, 41, 255])),
+    ("lch(52.2345% 72.2 56.2)", Some([198, 93, 6, 255])),
+    ("lch(100% 0 0)", Some([255, 255, 255, 255])),
+    ("lab(50%, 0, 0)", None),
+    ("lch(50% 10)", None),
+    // oklab() / oklch()
+    ("oklab(40.101% 0.1147 0.0453)", Some([125, 35, 41, 255])),
+    ("oklab(0.62796 0.22486 0.12585)", Some([255, 0, 0, 255])),
+    ("oklab(1 0 0)", Some([255, 255, 255, 255])),
+    ("oklab(0 0 0)", Some([0, 0, 0, 255])),
+    ("oklch(40.101% 0.12332 21.555)", Some([125, 35, 41, 255])),
+    ("oklch(0.62796 0.25768 29.2339)", Some([255, 0, 0, 255])),
+    ("oklch(100% 0 none)", Some([255, 255, 255, 255])),
+    ("oklch(1 0 0 / 50%)", Some([255, 255, 255, 128])),
+    ("oklab(0.5, 0, 0)", None),
+    // color()
+    ("color(srgb 1 0.5 0)", Some([255, 128, 0, 255])),
+    ("color(srgb 100% 50% 0%)", Some([255, 128, 0, 255])),
+    ("color(srgb 1 0 0 / 0.5)", Some([255, 0, 0, 128])),
+    ("color(srgb-linear 0.5 0.5 0.5)", Some([188, 188, 188, 255])),
+    ("color(srgb-linear 1 0 0)", Some([255, 0, 0, 255])),
+    ("color(display-p3 1 1 1)", Some([255, 255, 255, 255])),
+    ("color(display-p3 1 0 0)", Some([255, 0, 0, 255])),
+    ("color(display-p3 0.5 0.5 0.5)", Some([128, 128, 128, 255])),
+    ("color(a98-rgb 1 1 1)", Some([255, 255, 255, 255])),
+    ("color(a98-rgb 0 0 0)", Some([0, 0, 0, 255])),
+    ("color(prophoto-rgb 1 1 1)", Some([255, 255, 255, 255])),
+    ("color(rec2020 1 1 1)", Some([255, 255, 255, 255])),
+    ("color(xyz 0.9505 1 1.089)", Some([255, 255, 255, 255])),
+    ("color(xyz-d65 0.9505 1 1.089)", Some([255, 255, 255, 255])),
+    ("color(xyz-d50 0.9642 1 0.8252)", Some([255, 255, 255, 255])),
+    ("COLOR(SRGB 0 0 1)", Some([0, 0, 255, 255])),
+    ("color(srgb 1 0)", None),
+    ("color(srgb, 1, 0, 0)", None),
+    ("color(srgb)", None),
+];
+
+fn to_u8(color: Color) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a].map(|c| (c * 255.0).round() as u8)
+}
+
+#[test]
+fn css_color_table() {
+    let mut failures = Vec::new();
+    for (input, expected) in CASES {
+        let parsed = Color::from_css_color(input).map(to_u8);
+        let ok = match (expected, &parsed) {
+            // Allow one step of rounding difference from the reference values
+            (Some(expected), Ok(actual)) => expected.iter().zip(actual).all(|(e, a)| e.abs_diff(*a) <= 1),
+            (None, Err(_)) => true,
+            _ => false,
+        };
+        if !ok {
+            failures.push(format!("{:?}: expected {:?}, got {:?}", input, expected, parsed));
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn current_color_needs_resolving() {
+    assert_eq!(CssColor::parse("currentColor"), Ok(CssColor::CurrentColor));
+    assert_eq!(CssColor::parse("CURRENTCOLOR"), Ok(CssColor::CurrentColor));
+    assert_eq!(Color::from_css_color("currentcolor"), Err(ColorParseError::UnresolvedCurrentColor));
+    assert_eq!(Color::from_css_color_with_current("currentColor", Color::BLUE), Ok(Color::BLUE));
+    assert_eq!(Color::from_css_color_with_current("red", Color::BLUE), Ok(Color::RED));
+    assert_eq!(CssColor::CurrentColor.resolve(Color::GREEN), Color::GREEN);
+}
+
+#[test]
+fn unsupported_syntax_is_distinguished_from_invalid_values() {
+    assert_eq!(Color::from_css_color("foo(1 2 3)"), Err(ColorParseError::UnsupportedFormat));
+    assert_eq!(Color::from_css_color("color(foo 1 1 1)"), Err(ColorParseError::UnsupportedFormat));
+    assert_eq!(Color::from_css_color("rgb(from red r g b)"), Err(ColorParseError::UnsupportedFormat));
+    assert!(matches!(Color::from_css_color("rgb(1 2)"), Err(ColorParseError::InvalidValue(_))));
+}
+
+#[test]
+fn from_str_matches_from_css_color() {
+    assert_eq!("#336699".parse::<Color>(), Color::from_css_color("#336699"));
+    assert_eq!("currentColor".parse::<CssColor>(), Ok(CssColor::CurrentColor));
+}
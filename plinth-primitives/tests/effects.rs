@@ -0,0 +1,101 @@
+use glam::Vec2;
+use plinth_primitives::batch::ClipId;
+use plinth_primitives::effects::{EffectLayer, GaussianKernel, MAX_BLUR_TAPS};
+use plinth_primitives::{
+    Appearance, BoxShadow, Circle, CircleInstance, Color, CssColor, Rectangle, RectangleBatch, RectangleInstance,
+    Shadow, ShadowData, Style, StyleViewport, Styled,
+};
+
+fn kernel_total(kernel: &GaussianKernel) -> f32 {
+    kernel.weights[0] + 2.0 * kernel.weights[1..].iter().sum::<f32>()
+}
+
+#[test]
+fn gaussian_kernels_are_normalized_and_reach_three_sigma() {
+    assert_eq!(GaussianKernel::new(0.0), GaussianKernel { weights: vec![1.0], spacing: 1.0 });
+
+    let kernel = GaussianKernel::new(2.0);
+    assert_eq!((kernel.weights.len(), kernel.spacing), (7, 1.0));
+    assert!((kernel_total(&kernel) - 1.0).abs() < 1e-5);
+    assert!(kernel.weights.windows(2).all(|pair| pair[0] > pair[1]));
+
+    // Wide blurs keep the tap count and space the taps out instead
+    let wide = GaussianKernel::new(40.0);
+    assert_eq!(wide.weights.len(), MAX_BLUR_TAPS);
+    assert!((wide.spacing * (MAX_BLUR_TAPS - 1) as f32 - 120.0).abs() < 1e-3);
+    assert!((kernel_total(&wide) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn effect_layers_group_runs_and_keep_backdrops_apart() {
+    let appearance = |blur: f32, backdrop_blur: f32| Appearance { blur, backdrop_blur, ..Appearance::default() };
+    let mut layers = Vec::new();
+    for (index, (blur, backdrop)) in [(0.0, 0.0), (0.0, 0.0), (4.0, 0.0), (4.0, 0.0), (0.0, 8.0), (0.0, 8.0), (0.0, 0.0)]
+        .into_iter()
+        .enumerate()
+    {
+        EffectLayer::push(&mut layers, index, &appearance(blur, backdrop), ClipId::NONE);
+    }
+    let runs: Vec<_> = layers.iter().map(|layer| (layer.instances.clone(), layer.blur, layer.backdrop_blur)).collect();
+    assert_eq!(
+        runs,
+        [(0..2, 0.0, 0.0), (2..4, 4.0, 0.0), (4..5, 0.0, 8.0), (5..6, 0.0, 8.0), (6..7, 0.0, 0.0)]
+    );
+    assert!(!layers[0].has_effects() && layers[1].has_effects() && layers[2].has_effects());
+}
+
+#[test]
+fn shadows_reach_the_instances() {
+    let shadow = Shadow::new(Vec2::new(2.0, -3.0), 8.0, 1.0, Color::new(0.0, 0.0, 0.0, 0.5));
+    let mut rectangle = Rectangle::new(Vec2::ZERO, Vec2::new(10.0, 5.0)).with_shadow(shadow);
+    rectangle.appearance.opacity = 0.5;
+    let instance = RectangleInstance::from(&rectangle);
+    // The blur radius is twice the standard deviation; opacity fades the shadow too
+    assert_eq!(instance.shadow.params, [2.0, -3.0, 4.0, 1.0]);
+    assert_eq!(instance.shadow.color, [0.0, 0.0, 0.0, 0.25]);
+    assert_eq!(instance.shadow.margin(), 1.0 + 12.0 + 3.0);
+
+    let plain = CircleInstance::from(&Circle::new(Vec2::ZERO, 1.0));
+    assert_eq!(plain.shadow, ShadowData::NONE);
+    assert!(!plain.shadow.is_visible());
+    assert_eq!(plain.shadow.margin(), 0.0);
+    let circle = CircleInstance::from(&Circle::new(Vec2::ZERO, 1.0).with_shadow(shadow));
+    assert!(circle.shadow.is_visible());
+}
+
+#[test]
+fn styles_drive_shadows_and_blurs() {
+    let style = Style {
+        color: Some(Color::RED),
+        box_shadow: Some(vec![BoxShadow {
+            offset: Vec2::new(0.0, 4.0),
+            blur: 6.0,
+            spread: 0.0,
+            color: CssColor::CurrentColor,
+            inset: false,
+        }]),
+        filter_blur: Some(3.0),
+        backdrop_blur: Some(12.0),
+        ..Style::default()
+    };
+    let mut merged = Style::default();
+    merged.merge(&style);
+    assert_eq!(merged.backdrop_blur, Some(12.0));
+
+    // Shadows are drawn in clip space, where 1px is 0.005 here, and blurs in physical pixels
+    let mut rectangle = Rectangle::new(Vec2::ZERO, Vec2::ONE);
+    Styled::apply_css_style(&mut rectangle, &style, &StyleViewport::new(800, 800).with_scale_factor(2.0));
+    assert_eq!(rectangle.appearance.shadow, Some(Shadow::new(Vec2::new(0.0, 0.02), 0.03, 0.0, Color::RED)));
+    assert_eq!((rectangle.appearance.blur, rectangle.appearance.backdrop_blur), (6.0, 24.0));
+
+    // Batches split their instances where the effects change
+    let mut batch = RectangleBatch::new();
+    batch.add_rectangle(Rectangle::new(Vec2::ZERO, Vec2::ONE));
+    batch.add_rectangle(rectangle);
+    batch.add_rectangle(Rectangle::new(Vec2::ONE, Vec2::ONE));
+    batch.resolve_styles(None);
+    let layers = batch.layers();
+    assert_eq!(layers.len(), 3);
+    assert_eq!((layers[1].instances.clone(), layers[1].blur, layers[1].backdrop_blur), (1..2, 6.0, 24.0));
+    assert!(!layers[0].has_effects() && !layers[2].has_effects());
+}
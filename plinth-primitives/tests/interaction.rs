@@ -0,0 +1,173 @@
+use glam::Vec2;
+use plinth_core::input::{PointerButton, PointerEvent, PointerEventKind};
+use plinth_primitives::batch::{InstanceSources, StyleCache};
+use plinth_primitives::{
+    Circle, CircleBatch, CircleInstance, Color, InteractionState, Interactive, PointerTracker, Style, StyleResolver,
+    Transform,
+};
+
+const SURFACE: (u32, u32) = (200, 200);
+
+fn interactive(circles: &mut [Circle]) -> Vec<&mut dyn Interactive> {
+    circles.iter_mut().map(|circle| circle as &mut dyn Interactive).collect()
+}
+
+// Red while hovered, blue otherwise
+struct HoverResolver;
+
+impl StyleResolver for HoverResolver {
+    fn generation(&self) -> u64 {
+        0
+    }
+
+    fn resolve_style(&self, _classes: &[&str], state: InteractionState) -> Style {
+        let color = if state.contains(InteractionState::HOVER) { Color::RED } else { Color::BLUE };
+        Style { color: Some(color), ..Style::default() }
+    }
+}
+
+// Twice as large and raised for "raised", nothing else
+struct RaisedResolver;
+
+impl StyleResolver for RaisedResolver {
+    fn generation(&self) -> u64 {
+        0
+    }
+
+    fn resolve_style(&self, classes: &[&str], _state: InteractionState) -> Style {
+        if !classes.contains(&"raised") {
+            return Style::default();
+        }
+        Style {
+            transform: Some(Transform::identity().with_scale(Vec2::splat(2.0))),
+            z_index: Some(1),
+            ..Style::default()
+        }
+    }
+}
+
+fn event(kind: PointerEventKind, x: f32, y: f32) -> PointerEvent {
+    PointerEvent::new(kind, (x, y))
+}
+
+#[test]
+fn the_topmost_primitive_under_the_pointer_is_hovered_and_pressed() {
+    let mut circles = [Circle::new(Vec2::ZERO, 0.5), Circle::new(Vec2::new(0.25, 0.0), 0.5)];
+    let mut pointer = PointerTracker::new();
+
+    // The surface center, inside both circles; the later one is on top
+    assert!(pointer.handle_event(&event(PointerEventKind::Moved, 100.0, 100.0), SURFACE, interactive(&mut circles)));
+    assert_eq!([circles[0].state, circles[1].state], [InteractionState::NONE, InteractionState::HOVER]);
+    assert_eq!(pointer.position(), Some(Vec2::ZERO));
+
+    let press = event(PointerEventKind::Pressed(PointerButton::Primary), 100.0, 100.0);
+    pointer.handle_event(&press, SURFACE, interactive(&mut circles));
+    let pressed = InteractionState::HOVER | InteractionState::ACTIVE | InteractionState::FOCUS;
+    assert_eq!(circles[1].state, pressed);
+
+    // The pointer stays put while the top circle moves away
+    circles[1].center = Vec2::new(5.0, 0.0);
+    assert!(pointer.refresh(interactive(&mut circles)));
+    assert!(circles[0].state.contains(InteractionState::HOVER));
+    assert!(!circles[1].state.contains(InteractionState::HOVER));
+
+    pointer.handle_event(&event(PointerEventKind::Left, 0.0, 0.0), SURFACE, interactive(&mut circles));
+    assert_eq!(pointer.position(), None);
+    assert_eq!(circles[0].state, InteractionState::NONE);
+    assert_eq!(circles[1].state, InteractionState::FOCUS);
+}
+
+#[test]
+fn states_changed_in_place_are_restyled() {
+    let mut batch = CircleBatch::new();
+    batch.add_circle(Circle::new(Vec2::ZERO, 0.5).with_css_class("button"));
+    batch.resolve_styles(Some(&HoverResolver));
+    assert_eq!(batch.instances()[0].color, [0.0, 0.0, 1.0, 1.0]);
+
+    batch.circles_mut()[0].state = InteractionState::HOVER;
+    batch.resolve_styles(Some(&HoverResolver));
+    assert_eq!(batch.instances()[0].color, [1.0, 0.0, 0.0, 1.0]);
+
+    batch.circles_mut()[0].center = Vec2::new(0.5, 0.0);
+    batch.resolve_styles(Some(&HoverResolver));
+    assert_eq!(batch.instances()[0].center, [0.5, 0.0]);
+}
+
+#[test]
+fn only_circles_whose_state_changed_are_restyled_and_uploaded() {
+    let mut circles = vec![Circle::new(Vec2::ZERO, 0.5).with_css_class("button"); 3];
+    let mut cache = StyleCache::new();
+    assert!(cache.sync(Some(&HoverResolver)));
+    assert_eq!(cache.restyle(Some(&HoverResolver), &circles), [0, 1, 2]);
+    assert!(!cache.sync(Some(&HoverResolver)));
+
+    circles[1].state = InteractionState::HOVER;
+    *cache.states_changed_mut() = true;
+    assert!(cache.sync(Some(&HoverResolver)));
+    assert_eq!(cache.restyle(Some(&HoverResolver), &circles), [1]);
+    assert_eq!(cache.styled(1, &circles[1]).color, Color::RED);
+    assert_eq!(cache.styled(0, &circles[0]).color, Color::BLUE);
+
+    // Laid out the same way, only the restyled circle's instance is compared and uploaded
+    let instances = |circles: &[Circle]| -> Vec<CircleInstance> { circles.iter().map(CircleInstance::from).collect() };
+    let runs = || vec![(0, 0..1), (1, 1..2), (2, 2..3)];
+    let mut sources = InstanceSources::new();
+    let before = instances(&[circles[0].clone(), circles[0].clone(), circles[0].clone()]);
+    assert_eq!(sources.changed(runs(), &[0, 1, 2], &[], &before), Some(0..3));
+    let after = instances(&[circles[0].clone(), cache.styled(1, &circles[1]).clone(), circles[0].clone()]);
+    assert_eq!(sources.changed(runs(), &[1], &before, &after), Some(1..2));
+    assert_eq!(sources.changed(runs(), &[], &before, &after), None);
+    // A new draw order compares every instance
+    let reordered = vec![(1, 0..1), (0, 1..2), (2, 2..3)];
+    assert_eq!(sources.changed(reordered, &[], &before, &after), Some(1..2));
+}
+
+#[test]
+fn scaled_circles_are_hit_where_they_are_drawn() {
+    let scaled =
+        |scale: Vec2| Circle::new(Vec2::new(0.5, 0.0), 0.25).with_transform(Transform::identity().with_scale(scale));
+
+    // Scaled around the center like the shader does, so the radius grows with the scale
+    let circle = scaled(Vec2::splat(2.0));
+    assert!(circle.hit_test(Vec2::new(0.95, 0.0)));
+    assert!(circle.hit_test(Vec2::new(0.5, -0.45)));
+    assert!(!circle.hit_test(Vec2::new(1.05, 0.0)));
+
+    // A non-uniform scale makes an ellipse
+    let ellipse = scaled(Vec2::new(2.0, 0.5));
+    assert!(ellipse.hit_test(Vec2::new(0.95, 0.0)));
+    assert!(!ellipse.hit_test(Vec2::new(0.5, 0.2)));
+
+    let shrunk = scaled(Vec2::splat(0.5));
+    assert!(!shrunk.hit_test(Vec2::new(0.7, 0.0)));
+}
+
+#[test]
+fn primitives_are_hit_as_styled_and_in_the_order_they_were_drawn() {
+    // The second circle only reaches the pointer with its class styles applied, which also
+    // sort it above the first one
+    let mut circles = [Circle::new(Vec2::new(0.5, 0.0), 0.1), Circle::new(Vec2::ZERO, 0.3).with_css_class("raised")];
+    let mut cache = StyleCache::new();
+    cache.sync(Some(&RaisedResolver));
+    cache.restyle(Some(&RaisedResolver), &circles);
+    cache.set_drawn([0, 1]);
+
+    let mut pointer = PointerTracker::new();
+    let moved = event(PointerEventKind::Moved, 150.0, 100.0);
+    let (mut drawn, _) = cache.drawn_mut(&mut circles);
+    let primitives = drawn.iter_mut().map(|circle| circle as &mut dyn Interactive);
+    assert!(pointer.handle_event(&moved, SURFACE, primitives));
+    assert_eq!([circles[0].state, circles[1].state], [InteractionState::NONE, InteractionState::HOVER]);
+
+    // Drawn the other way around the first circle is on top
+    cache.set_drawn([1, 0]);
+    let (mut drawn, _) = cache.drawn_mut(&mut circles);
+    assert!(pointer.refresh(drawn.iter_mut().map(|circle| circle as &mut dyn Interactive)));
+    assert_eq!([circles[0].state, circles[1].state], [InteractionState::HOVER, InteractionState::NONE]);
+
+    // Primitives that weren't drawn can't be hit, and stop being hovered
+    cache.set_drawn([1]);
+    let (mut drawn, _) = cache.drawn_mut(&mut circles);
+    assert!(pointer.refresh(drawn.iter_mut().map(|circle| circle as &mut dyn Interactive)));
+    assert_eq!([circles[0].state, circles[1].state], [InteractionState::NONE, InteractionState::HOVER]);
+}
@@ -0,0 +1,103 @@
+use glam::Vec2;
+use plinth_primitives::{
+    Color, Interactive, Line, LineCap, LineInstance, LineJoin, LineWidth, Polyline, Style, StyleViewport, Styled,
+    Transform,
+};
+
+const SURFACE: Vec2 = Vec2::new(800.0, 600.0);
+
+#[test]
+fn polylines_become_one_instance_per_segment_with_neighbours() {
+    let points = [Vec2::ZERO, Vec2::new(0.5, 0.0), Vec2::new(0.5, 0.5)];
+    let polyline = Polyline::new(points).with_join(LineJoin::Bevel).with_cap(LineCap::Round);
+    let instances = polyline.instances(SURFACE);
+    assert_eq!(instances.len(), 2);
+
+    let first = instances[0];
+    assert_eq!(first.previous, first.start);
+    assert_eq!(first.next, [0.5, 0.5]);
+    assert_eq!(first.flags & LineInstance::HAS_PREVIOUS, 0);
+    assert_ne!(first.flags & LineInstance::HAS_NEXT, 0);
+    assert_ne!(first.flags & LineInstance::PIXEL_WIDTH, 0);
+    assert_eq!((first.flags >> LineInstance::CAP_SHIFT) & 3, 1);
+    assert_eq!((first.flags >> LineInstance::JOIN_SHIFT) & 3, 2);
+
+    let closed = polyline.with_closed(true).instances(SURFACE);
+    assert_eq!(closed.len(), 3);
+    assert!(closed.iter().all(|instance| instance.flags & LineInstance::HAS_PREVIOUS != 0));
+    assert_eq!(closed[2].end, [0.0, 0.0]);
+}
+
+#[test]
+fn repeated_points_are_skipped() {
+    let polyline = Polyline::new([Vec2::ZERO, Vec2::ZERO, Vec2::X, Vec2::X]);
+    assert_eq!(polyline.instances(SURFACE).len(), 1);
+    assert!(Polyline::new([Vec2::ONE]).instances(SURFACE).is_empty());
+}
+
+#[test]
+fn dashes_continue_across_segments_in_pixels() {
+    // 0.5 clip units across an 800 pixel wide surface is 200 pixels
+    let polyline = Polyline::new([Vec2::ZERO, Vec2::new(0.5, 0.0), Vec2::new(0.5, 0.5)]).with_dashes([5.0, 5.0, 2.0], 3.0);
+    let instances = polyline.instances(SURFACE);
+    assert_eq!(instances[0].params[2], 3.0);
+    assert_eq!(instances[1].params[2], 203.0);
+    // Odd patterns repeat, like stroke-dasharray
+    assert_eq!(instances[0].params[3], 6.0);
+    assert_eq!(instances[0].dashes[..6], [5.0, 5.0, 2.0, 5.0, 5.0, 2.0]);
+
+    let world = polyline.with_width(LineWidth::World(0.01)).instances(SURFACE);
+    assert_eq!(world[1].params[2], 3.5);
+    assert_eq!(world[0].flags & LineInstance::PIXEL_WIDTH, 0);
+}
+
+#[test]
+fn transform_and_opacity_are_baked_into_instances() {
+    let line = Line::new(Vec2::ZERO, Vec2::X)
+        .with_color(Color::RED)
+        .with_end_color(Color::BLUE)
+        .with_width(LineWidth::World(0.1))
+        .with_transform(Transform::identity().with_position(Vec2::new(0.0, 0.5)).with_scale(Vec2::splat(0.5)));
+    let mut polyline = Polyline::from(&line);
+    polyline.appearance.opacity = 0.5;
+    let instance = polyline.instances(SURFACE)[0];
+    assert_eq!(instance.start, [0.0, 0.5]);
+    assert_eq!(instance.end, [0.5, 0.5]);
+    assert_eq!(instance.start_color, [1.0, 0.0, 0.0, 0.5]);
+    assert_eq!(instance.end_color, [0.0, 0.0, 1.0, 0.5]);
+    assert_eq!(instance.params[0], 0.05);
+}
+
+#[test]
+fn hit_tests_against_the_width() {
+    let line = Line::new(Vec2::new(-0.5, 0.0), Vec2::new(0.5, 0.0)).with_width(LineWidth::World(0.1));
+    assert!(line.hit_test(Vec2::new(0.0, 0.04)));
+    assert!(!line.hit_test(Vec2::new(0.0, 0.06)));
+
+    // 10 pixels wide on a 600 pixel tall surface reaches 5 / 300 clip units either side
+    let pixels = line.with_width(LineWidth::Pixels(10.0));
+    assert!(pixels.hit_test_in_surface(Vec2::new(0.0, 0.016), (800, 600)));
+    assert!(!pixels.hit_test_in_surface(Vec2::new(0.0, 0.018), (800, 600)));
+}
+
+#[test]
+fn class_styles_set_color_and_width() {
+    let mut polyline = Polyline::new([Vec2::ZERO, Vec2::X])
+        .with_colors([Color::RED, Color::BLUE])
+        .with_width(LineWidth::World(0.1));
+    let style = Style {
+        color: Some(Color::GREEN),
+        border_width: Some(40.0),
+        ..Style::default()
+    };
+    // World widths are in clip space, pixel widths in physical pixels
+    let viewport = StyleViewport::new(800, 800).with_scale_factor(2.0);
+    Styled::apply_css_style(&mut polyline, &style, &viewport);
+    assert!(polyline.colors.is_empty());
+    assert_eq!(polyline.color, Color::GREEN);
+    assert_eq!(polyline.width, LineWidth::World(0.2));
+
+    let mut line = Line::new(Vec2::ZERO, Vec2::X).with_width(LineWidth::Pixels(1.0));
+    Styled::apply_css_style(&mut line, &Style { border_width: Some(3.0), ..Style::default() }, &viewport);
+    assert_eq!(line.width, LineWidth::Pixels(6.0));
+}
@@ -0,0 +1,193 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use glam::Vec2;
+use plinth_primitives::batch::{GradientRamps, RAMP_WIDTH};
+use plinth_primitives::{
+    resolve_stops, tessellate_path, Circle, CircleBatch, CircleInstance, Color, ColorStop, CssColor, GradientDirection,
+    LinearGradient, MeshBuffers, MeshVertex, Paint, PaintData, Path, RadialGradient, RadialShape, RadialSize, Rectangle,
+    RectangleBatch, RectangleInstance, Style, StyleViewport, Styled,
+};
+
+fn close(a: Vec2, b: Vec2) -> bool {
+    (a - b).length() < 1e-4
+}
+
+#[test]
+fn stop_offsets_are_filled_in_like_css() {
+    let stops = [
+        ColorStop::from(Color::RED),
+        ColorStop::from(Color::GREEN),
+        ColorStop::new(Color::BLUE, 0.8),
+        ColorStop::from(Color::WHITE),
+        ColorStop::from(Color::BLACK),
+    ];
+    let offsets: Vec<f32> = resolve_stops(&stops).iter().map(|(offset, _)| *offset).collect();
+    assert_eq!(offsets.len(), 5);
+    for (offset, expected) in offsets.iter().zip([0.0, 0.4, 0.8, 0.9, 1.0]) {
+        assert!((offset - expected).abs() < 1e-6, "{:?}", offsets);
+    }
+
+    // An offset before an earlier one moves up to it
+    let backwards = resolve_stops(&[ColorStop::new(Color::RED, 0.6), ColorStop::new(Color::BLUE, 0.2)]);
+    assert_eq!(backwards[1].0, 0.6);
+}
+
+#[test]
+fn ramps_interpolate_between_stops_and_share_rows() {
+    let mut ramps = GradientRamps::new();
+    let stops = [ColorStop::from(Color::RED), ColorStop::from(Color::BLUE)];
+    assert_eq!(ramps.row(&stops), 0);
+    assert_eq!(ramps.row(&[Color::RED.into(), (Color::BLUE, 1.0).into()]), 0);
+    assert_eq!(ramps.row(&[Color::GREEN.into(), Color::WHITE.into()]), 1);
+    assert_eq!(ramps.len(), 2);
+    assert_eq!(ramps.pixels().len(), 2 * RAMP_WIDTH as usize * 4);
+
+    let texel = |x: u32| &ramps.pixels()[x as usize * 4..x as usize * 4 + 4];
+    assert_eq!(texel(0), [255, 0, 0, 255]);
+    assert_eq!(texel(RAMP_WIDTH - 1), [0, 0, 255, 255]);
+    let middle = texel(RAMP_WIDTH / 2);
+    assert!(middle[0] > 100 && middle[2] > 100, "{:?}", middle);
+
+    // Fading out keeps the color instead of darkening towards transparent black
+    let mut fade = GradientRamps::new();
+    fade.row(&[Color::WHITE.into(), Color::TRANSPARENT.into()]);
+    let texel = &fade.pixels()[(RAMP_WIDTH / 2) as usize * 4..][..4];
+    assert_eq!(&texel[..3], [255, 255, 255]);
+    assert!(texel[3] > 100 && texel[3] < 155);
+
+    ramps.clear();
+    assert!(ramps.is_empty() && ramps.pixels().is_empty());
+}
+
+#[test]
+fn linear_gradients_reach_the_corners() {
+    let (min, max) = (Vec2::new(0.0, 0.0), Vec2::new(2.0, 1.0));
+    // `to right` runs across the box
+    let data = Paint::linear(FRAC_PI_2, [Color::RED, Color::BLUE]).data(min, max);
+    assert_eq!(data.kind(), PaintData::KIND_LINEAR);
+    assert!(close(Vec2::new(data.geometry[0], data.geometry[1]), Vec2::new(0.0, 0.5)));
+    assert!(close(Vec2::new(data.geometry[2], data.geometry[3]), Vec2::new(2.0, 0.5)));
+
+    // The default, `to bottom`, runs from top to bottom with y up
+    let down = Paint::Linear(LinearGradient { stops: vec![Color::RED.into(), Color::BLUE.into()], ..Default::default() });
+    let data = down.data(min, max);
+    assert!(close(Vec2::new(data.geometry[0], data.geometry[1]), Vec2::new(1.0, 1.0)));
+    assert!(close(Vec2::new(data.geometry[2], data.geometry[3]), Vec2::new(1.0, 0.0)));
+
+    // `to top right` puts the middle of the gradient through the other two corners
+    let corner = Paint::Linear(LinearGradient {
+        direction: GradientDirection::Corner { right: true, top: true },
+        stops: vec![Color::RED.into(), Color::BLUE.into()],
+    });
+    let data = corner.data(min, max);
+    let (start, end) = (Vec2::new(data.geometry[0], data.geometry[1]), Vec2::new(data.geometry[2], data.geometry[3]));
+    let along = |point: Vec2| (point - start).dot(end - start) / (end - start).length_squared();
+    assert!((along(Vec2::new(0.0, 1.0)) - 0.5).abs() < 1e-4);
+    assert!((along(Vec2::new(2.0, 0.0)) - 0.5).abs() < 1e-4);
+    assert!(along(Vec2::new(0.0, 0.0)).abs() < 1e-4);
+    assert!((along(Vec2::new(2.0, 1.0)) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn radial_gradients_size_to_the_box() {
+    let (min, max) = (Vec2::new(0.0, 0.0), Vec2::new(4.0, 2.0));
+    let radii = |shape, size| {
+        let paint = Paint::Radial(RadialGradient { shape, size, ..Default::default() });
+        let data = paint.data(min, max);
+        assert!(close(Vec2::new(data.geometry[0], data.geometry[1]), Vec2::new(2.0, 1.0)));
+        Vec2::new(data.geometry[2], data.geometry[3])
+    };
+    assert!(close(radii(RadialShape::Circle, RadialSize::ClosestSide), Vec2::splat(1.0)));
+    assert!(close(radii(RadialShape::Circle, RadialSize::FarthestSide), Vec2::splat(2.0)));
+    assert!(close(radii(RadialShape::Circle, RadialSize::FarthestCorner), Vec2::splat(5f32.sqrt())));
+    assert!(close(radii(RadialShape::Ellipse, RadialSize::ClosestSide), Vec2::new(2.0, 1.0)));
+    assert!(close(radii(RadialShape::Ellipse, RadialSize::FarthestCorner), Vec2::new(2.0, 1.0) * 2f32.sqrt()));
+    assert!(close(radii(RadialShape::Ellipse, RadialSize::Extent(Vec2::new(3.0, 0.5))), Vec2::new(3.0, 0.5)));
+
+    // Centers are placed from the top-left, y down
+    let corner = Paint::Radial(RadialGradient { center: Vec2::ZERO, ..Default::default() }).data(min, max);
+    assert!(close(Vec2::new(corner.geometry[0], corner.geometry[1]), Vec2::new(0.0, 2.0)));
+
+    let conic = Paint::conic(PI, [Color::RED, Color::BLUE]).data(min, max);
+    assert_eq!(conic.kind(), PaintData::KIND_CONIC);
+    assert_eq!(conic.params[2], PI);
+    assert_eq!(Paint::Solid(Color::RED).data(min, max), PaintData::SOLID);
+}
+
+#[test]
+fn primitives_carry_their_paint_to_the_gpu() {
+    let paint = Paint::linear(0.0, [Color::RED, Color::BLUE]);
+    let mut circle = Circle::new(Vec2::ZERO, 0.5).with_paint(paint.clone());
+    circle.appearance.opacity = 0.5;
+    let instance = CircleInstance::from(&circle);
+    // The ramp carries the colors; the instance color only dims them
+    assert_eq!(instance.color, [1.0, 1.0, 1.0, 0.5]);
+    assert_eq!(instance.paint.kind(), PaintData::KIND_LINEAR);
+    assert_eq!(instance.paint.geometry, [0.0, -0.5, 0.0, 0.5]);
+    let solid = CircleInstance::from(&Circle::new(Vec2::ZERO, 0.5).with_color(Color::GREEN));
+    assert_eq!(solid.paint, PaintData::SOLID);
+
+    let rectangle = Rectangle::new(Vec2::new(1.0, 1.0), Vec2::new(-1.0, 2.0)).with_paint(Color::RED);
+    let instance = RectangleInstance::from(&rectangle);
+    assert_eq!(instance.position, [0.0, 1.0]);
+    assert_eq!(instance.size, [1.0, 2.0]);
+    assert_eq!(instance.color, [1.0, 0.0, 0.0, 1.0]);
+    assert!(!instance.paint.is_gradient());
+
+    // Batches give each distinct gradient its own ramp row
+    let mut batch = CircleBatch::new();
+    batch.add_circle(circle.clone());
+    batch.add_circle(Circle::new(Vec2::ONE, 0.5).with_paint(Paint::radial([Color::WHITE, Color::BLACK])));
+    batch.add_circle(circle);
+    batch.resolve_styles(None);
+    let rows: Vec<u32> = batch.instances().iter().map(|instance| instance.paint.ramp_row()).collect();
+    assert_eq!(rows, [0, 1, 0]);
+    assert_eq!(batch.gradients().len(), 2);
+
+    let mut rectangles = RectangleBatch::new();
+    rectangles.add_rectangle(Rectangle::new(Vec2::ZERO, Vec2::ONE).with_paint(paint));
+    rectangles.resolve_styles(None);
+    assert_eq!(rectangles.instances().len(), 1);
+    assert_eq!(rectangles.gradients().len(), 1);
+}
+
+#[test]
+fn background_styles_set_or_clear_the_paint() {
+    let gradient = Paint::linear(0.0, [Color::RED, Color::BLUE]);
+    let with_image = Style {
+        background_color: Some(CssColor::Color(Color::GREEN)),
+        background_image: Some(gradient.clone()),
+        ..Style::default()
+    };
+    assert_eq!(with_image.fill_paint(), Some(gradient.clone()));
+
+    let mut rectangle = Rectangle::new(Vec2::ZERO, Vec2::ONE);
+    Styled::apply_css_style(&mut rectangle, &with_image, &StyleViewport::default());
+    assert_eq!(rectangle.paint, Some(gradient.clone()));
+    let solid = Style { background_color: Some(CssColor::Color(Color::GREEN)), ..Style::default() };
+    Styled::apply_css_style(&mut rectangle, &solid, &StyleViewport::default());
+    assert_eq!((rectangle.paint, rectangle.color), (None, Color::GREEN));
+
+    // Unfilled paths get a fill for the gradient
+    let mut outline = Path::rect(Vec2::ZERO, Vec2::ONE).with_fill(None);
+    Styled::apply_css_style(&mut outline, &with_image, &StyleViewport::default());
+    assert!(outline.fill.is_some());
+    assert_eq!(outline.paint, Some(gradient));
+
+    let mut merged = Style::default();
+    merged.merge(&with_image);
+    assert_eq!(merged.background_image, with_image.background_image);
+}
+
+#[test]
+fn path_fills_lay_gradients_over_their_bounds() {
+    let path = Path::rect(Vec2::new(1.0, 1.0), Vec2::new(2.0, 2.0))
+        .with_paint(Paint::linear(FRAC_PI_2, [Color::RED, Color::BLUE]))
+        .with_stroke(Some(plinth_primitives::Stroke::new(Color::BLACK, 0.1)));
+    let mut mesh = MeshBuffers::new();
+    tessellate_path(&path, 0.01, &mut mesh).unwrap();
+    let (fill, stroke): (Vec<&MeshVertex>, Vec<&MeshVertex>) = mesh.vertices.iter().partition(|vertex| vertex.paint.is_gradient());
+    assert!(!fill.is_empty() && !stroke.is_empty());
+    assert!(fill.iter().all(|vertex| vertex.paint.geometry == [1.0, 2.0, 3.0, 2.0] && vertex.color == [1.0; 4]));
+    assert!(fill.iter().all(|vertex| vertex.local == vertex.position));
+}
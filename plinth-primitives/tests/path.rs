@@ -0,0 +1,188 @@
+use glam::Vec2;
+use plinth_primitives::{
+    dash_path, parse_path_data, tessellate_path, write_path_data, Color, FillRule, Interactive, MeshBuffers, Path,
+    PathCommand, PathDataError, Stroke, Transform,
+};
+
+#[test]
+fn parses_relative_implicit_and_compact_path_data() {
+    let commands = parse_path_data("m10 20 5 0h-5v5z M1.5.5-2e1 1").unwrap();
+    assert_eq!(
+        commands,
+        [
+            PathCommand::MoveTo(Vec2::new(10.0, 20.0)),
+            // Coordinates after a moveto are linetos
+            PathCommand::LineTo(Vec2::new(15.0, 20.0)),
+            PathCommand::LineTo(Vec2::new(10.0, 20.0)),
+            PathCommand::LineTo(Vec2::new(10.0, 25.0)),
+            PathCommand::Close,
+            PathCommand::MoveTo(Vec2::new(1.5, 0.5)),
+            PathCommand::LineTo(Vec2::new(-20.0, 1.0)),
+        ]
+    );
+}
+
+#[test]
+fn reflects_smooth_curve_control_points() {
+    let commands = parse_path_data("M0 0 C0 1 1 1 1 0 S2 -1 2 0 Q3 1 4 0 T6 0").unwrap();
+    assert_eq!(
+        commands[2],
+        PathCommand::CubicTo {
+            ctrl1: Vec2::new(1.0, -1.0),
+            ctrl2: Vec2::new(2.0, -1.0),
+            to: Vec2::new(2.0, 0.0)
+        }
+    );
+    assert_eq!(
+        commands[4],
+        PathCommand::QuadTo {
+            ctrl: Vec2::new(5.0, -1.0),
+            to: Vec2::new(6.0, 0.0)
+        }
+    );
+}
+
+#[test]
+fn arc_flags_need_no_separators() {
+    let commands = parse_path_data("M0 0a5 5 30 1110 0").unwrap();
+    match commands[1] {
+        PathCommand::ArcTo {
+            radii,
+            x_rotation,
+            large_arc,
+            sweep,
+            to,
+        } => {
+            assert_eq!(radii, Vec2::splat(5.0));
+            assert!((x_rotation - 30f32.to_radians()).abs() < 1e-6);
+            assert!(large_arc && sweep);
+            assert_eq!(to, Vec2::new(10.0, 0.0));
+        }
+        other => panic!("expected an arc, got {:?}", other),
+    }
+}
+
+#[test]
+fn reports_errors_with_offsets() {
+    assert_eq!(parse_path_data("L 1 1"), Err(PathDataError::MissingMoveTo { position: 0 }));
+    assert_eq!(parse_path_data("M 1 x"), Err(PathDataError::ExpectedNumber { position: 4 }));
+    assert_eq!(parse_path_data("M0 0 A1 1 0 2 0 1 1"), Err(PathDataError::ExpectedFlag { position: 12 }));
+    assert!(matches!(parse_path_data("M0 0 Z 1"), Err(PathDataError::UnexpectedCharacter { position: 7, .. })));
+}
+
+#[test]
+fn written_path_data_parses_back() {
+    let data = "M0 0 L1 0 Q1 1 0.5 1 C0 1 0 0.5 0.25 0.25 A1 2 0 0 1 3 3 Z";
+    let commands = parse_path_data(data).unwrap();
+    assert_eq!(write_path_data(&commands), data);
+    assert_eq!(parse_path_data(&write_path_data(&commands)).unwrap(), commands);
+}
+
+#[test]
+fn tessellates_fill_and_stroke_with_the_transform_applied() {
+    let square = Path::rect(Vec2::ZERO, Vec2::ONE).with_color(Color::RED);
+    let mut mesh = MeshBuffers::new();
+    tessellate_path(&square, 0.01, &mut mesh).unwrap();
+    assert_eq!(mesh.indices.len(), 6);
+    assert!(mesh.vertices.iter().all(|vertex| vertex.color == [1.0, 0.0, 0.0, 1.0]));
+
+    let moved = square
+        .clone()
+        .with_stroke(Some(Stroke::new(Color::BLACK, 0.1)))
+        .with_transform(Transform::identity().with_position(Vec2::new(10.0, 0.0)));
+    let mut mesh = MeshBuffers::new();
+    tessellate_path(&moved, 0.01, &mut mesh).unwrap();
+    assert!(mesh.indices.len() > 6);
+    let min_x = mesh.vertices.iter().map(|vertex| vertex.position[0]).fold(f32::INFINITY, f32::min);
+    assert!((min_x - 9.95).abs() < 1e-4, "{}", min_x);
+}
+
+#[test]
+fn dashes_split_subpaths_into_pieces() {
+    let line = Path::polyline([Vec2::ZERO, Vec2::new(10.0, 0.0)]).to_lyon();
+    // 3 on, 1 off: dashes at 0-3, 4-7 and 8-10
+    let dashed = dash_path(&line, &[3.0, 1.0], 0.0, 0.01).unwrap();
+    assert_eq!(dashed.iter().filter(|event| matches!(event, lyon::path::Event::Begin { .. })).count(), 3);
+    // Starting 2 into the pattern: 0-1, 2-5, 6-9
+    let offset = dash_path(&line, &[3.0, 1.0], 2.0, 0.01).unwrap();
+    assert_eq!(offset.iter().filter(|event| matches!(event, lyon::path::Event::Begin { .. })).count(), 3);
+    assert!(dash_path(&line, &[], 0.0, 0.01).is_none());
+    assert!(dash_path(&line, &[0.0, 0.0], 0.0, 0.01).is_none());
+}
+
+#[test]
+fn hit_test_follows_fill_rule_and_stroke() {
+    // A square with a square hole when filled even-odd
+    let frame = Path::from_svg_path_data("M0 0 H4 V4 H0 Z M1 1 H3 V3 H1 Z").unwrap();
+    assert!(frame.hit_test(Vec2::new(2.0, 2.0)));
+    let frame = frame.with_fill_rule(FillRule::EvenOdd);
+    assert!(!frame.hit_test(Vec2::new(2.0, 2.0)));
+    assert!(frame.hit_test(Vec2::new(0.5, 2.0)));
+
+    let outline = Path::circle(Vec2::ZERO, 1.0).with_fill(None).with_stroke(Some(Stroke::new(Color::BLACK, 0.2)));
+    assert!(outline.hit_test(Vec2::new(1.05, 0.0)));
+    assert!(!outline.hit_test(Vec2::ZERO));
+    let scaled = outline.with_transform(Transform::identity().with_scale(Vec2::splat(2.0)));
+    assert!(scaled.hit_test(Vec2::new(0.0, 2.0)));
+}
+
+#[cfg(feature = "svg")]
+mod svg {
+    use glam::Vec2;
+    use plinth_primitives::{Color, FillRule, PathCommand, SvgDocument};
+
+    const DOCUMENT: &str = r##"
+        <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 50" width="200" height="100">
+            <g fill="#3366ff" stroke="black" stroke-width="2" opacity="0.5">
+                <rect class="node primary" x="10" y="10" width="20" height="10" rx="2"/>
+                <circle cx="50" cy="25" r="10" style="fill: none; stroke-dasharray: 4 2"/>
+                <path d="M60 10 L90 10 L75 40 Z" fill-rule="evenodd" stroke="none" transform="translate(5)"/>
+            </g>
+            <line x1="0" y1="0" x2="100" y2="50" stroke="red"/>
+            <polygon points="0,0 1,0 1,1" display="none"/>
+            <text>ignored</text>
+        </svg>
+    "##;
+
+    #[test]
+    fn imports_shapes_with_inherited_presentation() {
+        let svg = SvgDocument::parse(DOCUMENT).unwrap();
+        assert_eq!(svg.view_box, Some([0.0, 0.0, 100.0, 50.0]));
+        assert_eq!(svg.paths.len(), 4);
+
+        let rect = &svg.paths[0];
+        assert_eq!(rect.css_class.as_deref(), Some("node primary"));
+        assert_eq!(rect.fill, Some(Color::from_hex(0x3366ff)));
+        assert_eq!(rect.stroke.as_ref().map(|stroke| stroke.width), Some(2.0));
+        assert_eq!(rect.appearance.opacity, 0.5);
+
+        let circle = &svg.paths[1];
+        assert_eq!(circle.fill, None);
+        assert_eq!(circle.stroke.as_ref().unwrap().dashes, [4.0, 2.0]);
+
+        let triangle = &svg.paths[2];
+        assert!(triangle.stroke.is_none());
+        assert_eq!(triangle.fill_rule, FillRule::EvenOdd);
+        assert_eq!(triangle.commands[0], PathCommand::MoveTo(Vec2::new(65.0, 10.0)));
+
+        let line = &svg.paths[3];
+        assert_eq!(line.stroke.as_ref().unwrap().color, Color::RED);
+        assert_eq!(line.stroke.as_ref().unwrap().width, 1.0);
+    }
+
+    #[test]
+    fn fits_the_view_box_with_y_up() {
+        let svg = SvgDocument::parse(DOCUMENT).unwrap();
+        let fitted = svg.fitted(Vec2::ZERO, Vec2::splat(2.0));
+        // 100x50 into 2x2 scales by 0.02; the line runs from the top left to the bottom right
+        assert_eq!(fitted[3].commands[0], PathCommand::MoveTo(Vec2::new(-1.0, 0.5)));
+        assert_eq!(fitted[3].commands[1], PathCommand::LineTo(Vec2::new(1.0, -0.5)));
+        assert!((fitted[0].stroke.as_ref().unwrap().width - 0.04).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(SvgDocument::parse("<html/>").is_err());
+        assert!(SvgDocument::parse("<svg").is_err());
+    }
+}
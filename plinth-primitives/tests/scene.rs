@@ -0,0 +1,138 @@
+use glam::Vec2;
+use plinth_primitives::{Color, Interactive, Path, PathCommand, Transform, Triangle};
+#[cfg(feature = "serde")]
+use plinth_primitives::{
+    Circle, Line, LineWidth, Paint, Polyline, Rectangle, Scene, SceneFormat, Sprite, Stroke, Text, TextAlign, UvRect,
+};
+
+#[cfg(feature = "serde")]
+const RON: &str = r##"
+#![enable(implicit_some)]
+(
+    circles: [
+        (center: (0.0, 0.25), radius: 0.2, color: "#3366ff", css_class: "node primary"),
+        (center: (-0.5, 0.0), radius: 0.1, transform: (rotation: 1.5)),
+    ],
+    rectangles: [
+        (position: (-0.25, -0.5), size: (0.5, 0.25), corner_radius: 0.05, color: (r: 1.0, g: 0.5, b: 0.0)),
+    ],
+)
+"##;
+
+#[cfg(feature = "serde")]
+const JSON: &str = r##"
+{
+    "circles": [
+        { "center": [0.0, 0.25], "radius": 0.2, "color": "#3366ff", "css_class": "node primary" },
+        { "center": [-0.5, 0.0], "radius": 0.1, "transform": { "rotation": 1.5 } }
+    ],
+    "rectangles": [
+        { "position": [-0.25, -0.5], "size": [0.5, 0.25], "corner_radius": 0.05, "color": [1.0, 0.5, 0.0, 1.0] }
+    ]
+}
+"##;
+
+#[cfg(feature = "serde")]
+fn check(scene: &Scene) {
+    assert_eq!(scene.circles.len(), 2);
+    assert_eq!(scene.rectangles.len(), 1);
+    assert!(scene.triangles.is_empty());
+
+    let node = &scene.circles[0];
+    assert_eq!(node.center, Vec2::new(0.0, 0.25));
+    assert_eq!(node.color, Color::from_hex(0x3366ff));
+    assert_eq!(node.css_class.as_deref(), Some("node primary"));
+    assert!(node.appearance.visible);
+    assert_eq!(node.transform, Transform::identity());
+
+    // Unset fields keep their defaults
+    let rotated = &scene.circles[1];
+    assert_eq!(rotated.transform, Transform::identity().with_rotation(1.5));
+    assert_eq!(rotated.color, Color::WHITE);
+    assert_eq!(rotated.css_class, None);
+
+    let rectangle = &scene.rectangles[0];
+    assert_eq!(rectangle.size, Vec2::new(0.5, 0.25));
+    assert_eq!(rectangle.color, Color::new(1.0, 0.5, 0.0, 1.0));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn parses_ron_and_json() {
+    check(&Scene::from_ron(RON).unwrap());
+    check(&Scene::from_json(JSON).unwrap());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn round_trips() {
+    let scene = Scene::new()
+        .with_circle(Circle::new(Vec2::new(0.1, 0.2), 0.3).with_css_class("a b").with_color(Color::RED))
+        .with_rectangle(
+            Rectangle::new(Vec2::ZERO, Vec2::ONE)
+                .with_corner_radius(0.1)
+                .with_paint(Paint::linear(0.5, [(Color::RED, 0.25), (Color::BLUE, 1.0)])),
+        )
+        .with_triangle(Triangle::default().with_transform(Transform::identity().with_scale(Vec2::splat(2.0))))
+        .with_path(Path::from_svg_path_data("M0 0 L1 0 Q1 1 0 1 Z").unwrap().with_stroke(Some(Stroke::new(Color::BLUE, 0.1))))
+        .with_line(Line::new(Vec2::ZERO, Vec2::ONE).with_width(LineWidth::World(0.05)))
+        .with_polyline(Polyline::new([Vec2::ZERO, Vec2::X, Vec2::ONE]).with_dashes([4.0, 2.0], 1.0))
+        .with_text(Text::new("Hello", Vec2::ZERO).with_font_family(["Inter", "sans-serif"]).with_align(TextAlign::Center))
+        .with_sprite(Sprite::new("icons.png", Vec2::ZERO, Vec2::splat(0.1)).with_uv(UvRect::frame(3, 4, 4)));
+
+    for format in [SceneFormat::Ron, SceneFormat::Json] {
+        let text = scene.to_text(format).unwrap();
+        let parsed = Scene::parse(&text, format).unwrap();
+        assert_eq!(parsed.len(), 8, "{:?}", format);
+        assert_eq!(parsed.circles[0].css_class.as_deref(), Some("a b"));
+        assert_eq!(parsed.circles[0].color, Color::RED);
+        assert_eq!(parsed.rectangles[0].corner_radius, 0.1);
+        assert_eq!(parsed.rectangles[0].paint, scene.rectangles[0].paint);
+        assert_eq!(parsed.triangles[0].vertices, scene.triangles[0].vertices);
+        assert_eq!(parsed.triangles[0].transform.scale, Vec2::splat(2.0));
+        assert_eq!(parsed.paths[0].commands, scene.paths[0].commands);
+        assert_eq!(parsed.paths[0].stroke, scene.paths[0].stroke);
+        assert_eq!(parsed.lines[0].width, LineWidth::World(0.05));
+        assert_eq!(parsed.polylines[0].points, scene.polylines[0].points);
+        assert_eq!(parsed.polylines[0].dashes, [4.0, 2.0]);
+        assert_eq!(parsed.texts[0].content, "Hello");
+        assert_eq!(parsed.texts[0].font_family, ["Inter", "sans-serif"]);
+        assert_eq!(parsed.texts[0].align, TextAlign::Center);
+        assert_eq!(parsed.sprites[0].texture, "icons.png");
+        assert_eq!(parsed.sprites[0].uv, UvRect::frame(3, 4, 4));
+    }
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn rejects_bad_colors() {
+    assert!(Scene::from_json(r#"{ "circles": [{ "color": "notacolor" }] }"#).is_err());
+    assert_eq!(SceneFormat::from_path("fixtures/diagram.RON"), Some(SceneFormat::Ron));
+    assert_eq!(SceneFormat::from_path("diagram.yaml"), None);
+}
+
+// Scenes draw their triangles as paths
+#[test]
+fn triangles_convert_to_filled_paths() {
+    let vertices = [Vec2::new(0.0, 0.5), Vec2::new(-0.5, -0.5), Vec2::new(0.5, -0.5)];
+    let triangle = Triangle::new(vertices)
+        .with_color(Color::RED)
+        .with_css_class("warning")
+        .with_transform(Transform::identity().with_position(Vec2::new(1.0, 0.0)));
+    let path = Path::from(&triangle);
+    assert_eq!(
+        path.commands,
+        [
+            PathCommand::MoveTo(vertices[0]),
+            PathCommand::LineTo(vertices[1]),
+            PathCommand::LineTo(vertices[2]),
+            PathCommand::Close,
+        ]
+    );
+    assert_eq!(path.fill, Some(Color::RED));
+    assert!(path.stroke.is_none());
+    assert_eq!(path.css_class.as_deref(), Some("warning"));
+    for point in [Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0), Vec2::new(1.4, -0.4)] {
+        assert_eq!(path.hit_test(point), triangle.hit_test(point), "{:?}", point);
+    }
+}
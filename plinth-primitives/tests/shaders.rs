@@ -0,0 +1,145 @@
+use plinth_core::shaders::ShaderSource;
+use plinth_primitives::shaders::{PreprocessError, ShaderPreprocessor};
+
+// Every shader with the defines it can be built with
+const SHADERS: &[(&str, &str, &[&str])] = &[
+    ("circle", include_str!("../src/shaders/circle.wgsl"), &["PREMULTIPLIED_ALPHA", "DEBUG_OVERLAY", "BACKDROP"]),
+    ("rectangle", include_str!("../src/shaders/rectangle.wgsl"), &["PREMULTIPLIED_ALPHA", "DEBUG_OVERLAY", "BACKDROP"]),
+    ("mesh", include_str!("../src/shaders/mesh.wgsl"), &["PREMULTIPLIED_ALPHA", "DEBUG_OVERLAY"]),
+    ("line", include_str!("../src/shaders/line.wgsl"), &["PREMULTIPLIED_ALPHA", "DEBUG_OVERLAY"]),
+    ("text", include_str!("../src/shaders/text.wgsl"), &["PREMULTIPLIED_ALPHA", "DEBUG_OVERLAY"]),
+    ("sprite", include_str!("../src/shaders/sprite.wgsl"), &["PREMULTIPLIED_ALPHA", "DEBUG_OVERLAY"]),
+    ("blur", include_str!("../src/shaders/blur.wgsl"), &[]),
+    ("clip", include_str!("../src/shaders/clip.wgsl"), &[]),
+];
+
+// Parses and validates preprocessed WGSL with naga, the same front end wgpu uses
+fn validate(name: &str, source: &str) {
+    let module = naga::front::wgsl::parse_str(source)
+        .unwrap_or_else(|e| panic!("{}: {}\n{}", name, e.emit_to_string(source), source));
+    naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+        .validate(&module)
+        .unwrap_or_else(|e| panic!("{}: {:?}\n{}", name, e, source));
+}
+
+#[test]
+fn shaders_validate_for_every_define_combination() {
+    for (name, source, toggles) in SHADERS {
+        for mask in 0..(1 << toggles.len()) {
+            let mut preprocessor = ShaderPreprocessor::new();
+            let mut defined = Vec::new();
+            for (bit, define) in toggles.iter().enumerate() {
+                let enabled = mask & (1 << bit) != 0;
+                preprocessor.set_define(*define, enabled);
+                if enabled {
+                    defined.push(*define);
+                }
+            }
+            validate(&format!("{} {:?}", name, defined), &preprocessor.process(source).unwrap());
+        }
+    }
+}
+
+#[test]
+fn builtin_snippets_validate_on_their_own() {
+    let preprocessor = ShaderPreprocessor::new();
+    let snippets = [
+        "plinth/quad.wgsl",
+        "plinth/transform.wgsl",
+        "plinth/sdf.wgsl",
+        "plinth/color.wgsl",
+        "plinth/paint.wgsl",
+        "plinth/shadow.wgsl",
+    ];
+    for name in snippets {
+        validate(name, &preprocessor.process(&format!("#include \"{}\"", name)).unwrap());
+    }
+}
+
+#[test]
+fn ifdef_else_selects_branch() {
+    let source = "#ifdef A\na\n#else\nnot_a\n#endif\n#ifndef B\nnot_b\n#endif\n";
+    let plain = ShaderPreprocessor::new().process(source).unwrap();
+    assert_eq!(plain, "not_a\nnot_b\n");
+    let defined = ShaderPreprocessor::new().with_define("A").with_define("B").process(source).unwrap();
+    assert_eq!(defined, "a\n");
+}
+
+#[test]
+fn nested_conditionals_inside_inactive_block_stay_inactive() {
+    let source = "#ifdef A\n#ifndef B\nhidden\n#endif\n#else\nshown\n#endif\n";
+    assert_eq!(ShaderPreprocessor::new().process(source).unwrap(), "shown\n");
+}
+
+#[test]
+fn define_in_source_controls_later_lines_and_substitutes_values() {
+    let source = "#define SAMPLES 4\n#define DEBUG\n#ifdef DEBUG\nconst N: u32 = SAMPLES;\n#endif\n#undef DEBUG\n#ifdef DEBUG\nnope\n#endif\n";
+    let output = ShaderPreprocessor::new().process(source).unwrap();
+    assert_eq!(output, "const N: u32 = 4;\n");
+}
+
+#[test]
+fn value_substitution_matches_whole_identifiers_only() {
+    let output = ShaderPreprocessor::new()
+        .with_define_value("N", "8")
+        .process("let a = N + N_MAX + MIN;")
+        .unwrap();
+    assert_eq!(output, "let a = 8 + N_MAX + MIN;\n");
+}
+
+#[test]
+fn includes_are_pasted_once() {
+    let preprocessor = ShaderPreprocessor::new()
+        .with_include("a", "#include \"b\"\nfn a() {}")
+        .with_include("b", "fn b() {}");
+    let output = preprocessor.process("#include \"a\"\n#include \"b\"\n").unwrap();
+    assert_eq!(output, "fn b() {}\nfn a() {}\n");
+}
+
+#[test]
+fn process_with_reads_includes_through_the_callback() {
+    let preprocessor = ShaderPreprocessor::new().with_include("a", "fn a() {}");
+    let edited = |shader: &ShaderSource| shader.embedded.replace("fn a()", "fn edited()");
+    let output = preprocessor.process_with("#include \"a\"\nfn main() {}", &edited).unwrap();
+    assert_eq!(output, "fn edited() {}\nfn main() {}\n");
+}
+
+#[test]
+fn include_errors() {
+    let preprocessor = ShaderPreprocessor::new()
+        .with_include("loop_a", "#include \"loop_b\"")
+        .with_include("loop_b", "#include \"loop_a\"");
+    assert_eq!(
+        preprocessor.process("#include \"loop_a\""),
+        Err(PreprocessError::RecursiveInclude { name: "loop_a".into(), line: 1 })
+    );
+    assert_eq!(
+        preprocessor.process("\n#include \"missing\""),
+        Err(PreprocessError::UnknownInclude { name: "missing".into(), line: 2 })
+    );
+    assert!(matches!(
+        preprocessor.process("#include missing"),
+        Err(PreprocessError::MalformedDirective { .. })
+    ));
+}
+
+#[test]
+fn conditional_errors() {
+    let preprocessor = ShaderPreprocessor::new();
+    assert_eq!(
+        preprocessor.process("#ifdef A\n"),
+        Err(PreprocessError::UnterminatedConditional { line: 1 })
+    );
+    assert!(matches!(
+        preprocessor.process("#endif"),
+        Err(PreprocessError::UnmatchedConditional { line: 1, .. })
+    ));
+    assert!(matches!(
+        preprocessor.process("#ifdef A\n#else\n#else\n#endif"),
+        Err(PreprocessError::UnmatchedConditional { line: 3, .. })
+    ));
+    assert!(matches!(
+        preprocessor.process("#pragma once"),
+        Err(PreprocessError::UnknownDirective { .. })
+    ));
+}
@@ -0,0 +1,98 @@
+use glam::Vec2;
+use plinth_core::textures::{TextureFilter, TextureManager, TextureRegion};
+use plinth_primitives::batch::ClipId;
+use plinth_primitives::{
+    Color, Interactive, Sprite, SpriteBatch, SpriteGroup, Style, StyleViewport, Styled, Transform, UvRect,
+};
+
+fn solid(size: u32) -> Vec<u8> {
+    vec![255; (size * size * 4) as usize]
+}
+
+#[test]
+fn uv_rects_pick_frames_and_mirror() {
+    let frame = UvRect::frame(5, 4, 2);
+    assert_eq!(frame, UvRect::new(Vec2::new(0.25, 0.5), Vec2::new(0.5, 1.0)));
+    assert_eq!(UvRect::from_pixels(Vec2::new(16.0, 0.0), Vec2::splat(16.0), Vec2::new(64.0, 32.0)), UvRect::frame(1, 4, 2));
+    let flipped = frame.flip_x();
+    assert_eq!((flipped.min.x, flipped.max.x), (0.5, 0.25));
+    assert_eq!(flipped.flip_x(), frame);
+    assert_eq!(frame.flip_y().min.y, 1.0);
+    assert_eq!(UvRect::default(), UvRect::FULL);
+}
+
+#[test]
+fn instances_map_the_quad_and_uv_rect_into_page_texels() {
+    let region = TextureRegion { page: 0, position: [10, 20], size: [4, 8] };
+    let mut sprite = Sprite::new("sheet", Vec2::new(-1.0, -1.0), Vec2::splat(2.0))
+        .with_uv(UvRect::frame(1, 2, 1))
+        .with_tint(Color::RED);
+    sprite.appearance.opacity = 0.5;
+    let instance = sprite.instance(region);
+    // The top-left corner, right across and down the sprite
+    assert_eq!(instance.origin, [-1.0, 1.0]);
+    assert_eq!(instance.axis_x, [2.0, 0.0]);
+    assert_eq!(instance.axis_y, [0.0, -2.0]);
+    assert_eq!(instance.uv_min, [12.0, 20.0]);
+    assert_eq!(instance.uv_max, [14.0, 28.0]);
+    assert_eq!(instance.color, [1.0, 0.0, 0.0, 0.5]);
+
+    let turned = sprite.with_transform(Transform::identity().with_rotation(std::f32::consts::FRAC_PI_2));
+    let instance = turned.instance(region);
+    assert!(instance.axis_x[0].abs() < 1e-6 && (instance.axis_x[1] - 2.0).abs() < 1e-6);
+}
+
+#[test]
+fn batch_groups_draws_by_page_within_a_z_index() {
+    let mut textures = TextureManager::new();
+    textures.insert_rgba("smooth", 1, 1, &solid(1), TextureFilter::Linear).unwrap();
+    textures.insert_rgba("pixel", 1, 1, &solid(1), TextureFilter::Nearest).unwrap();
+
+    let sprite = |texture: &str| Sprite::new(texture, Vec2::ZERO, Vec2::ONE);
+    let mut top = sprite("pixel");
+    top.appearance.z_index = 1;
+    let mut batch = SpriteBatch::new();
+    batch.add_sprites([sprite("smooth"), sprite("pixel"), sprite("smooth"), top, sprite("later")]);
+    batch.resolve_styles(None, &textures);
+    assert_eq!(batch.instances().len(), 4);
+    assert_eq!(
+        batch.groups(),
+        [
+            SpriteGroup { page: 0, instances: 0..2, clip: ClipId::NONE },
+            SpriteGroup { page: 1, instances: 2..4, clip: ClipId::NONE },
+        ]
+    );
+
+    // Sprites waiting on a texture show up once it's loaded
+    textures.insert_rgba("later", 1, 1, &solid(1), TextureFilter::Linear).unwrap();
+    batch.resolve_styles(None, &textures);
+    assert_eq!(batch.instances().len(), 5);
+    assert_eq!(batch.groups()[0], SpriteGroup { page: 0, instances: 0..3, clip: ClipId::NONE });
+}
+
+#[test]
+fn class_styles_tint_and_resize() {
+    let mut sprite = Sprite::new("icon", Vec2::ZERO, Vec2::ONE);
+    let style = Style {
+        color: Some(Color::GREEN),
+        width: Some(100.0),
+        ..Style::default()
+    };
+    Styled::apply_css_style(&mut sprite, &style, &StyleViewport::new(400, 400));
+    assert_eq!(sprite.tint, Color::GREEN);
+    assert_eq!(sprite.size, Vec2::new(0.5, 1.0));
+}
+
+#[test]
+fn hit_tests_against_the_quad() {
+    let sprite = Sprite::new("icon", Vec2::new(-0.5, -0.5), Vec2::ONE)
+        .with_transform(Transform::identity().with_position(Vec2::new(1.0, 0.0)));
+    assert!(sprite.hit_test(Vec2::new(1.4, 0.4)));
+    assert!(!sprite.hit_test(Vec2::new(0.4, 0.4)));
+    // Mirrored by a negative size
+    let mirrored = Sprite::new("icon", Vec2::ZERO, Vec2::new(-1.0, 1.0));
+    assert!(mirrored.hit_test(Vec2::new(-0.5, 0.5)));
+    let mut hidden = sprite.clone();
+    hidden.appearance.visible = false;
+    assert!(!hidden.hit_test(Vec2::new(1.0, 0.0)));
+}
@@ -0,0 +1,206 @@
+use glam::{UVec2, Vec2};
+use plinth_primitives::{
+    layout_text, Color, CssColor, FontError, FontId, FontLibrary, FontSize, GlyphAtlas, GlyphKey, Interactive, Style,
+    StyleViewport, Styled, Text, TextAlign, Transform, VerticalAlign,
+};
+
+const CANTARELL: &[u8] = include_bytes!("fonts/Cantarell-Regular.ttf");
+const SURFACE: Vec2 = Vec2::new(800.0, 600.0);
+
+fn fonts() -> FontLibrary {
+    let mut fonts = FontLibrary::new();
+    fonts.load("Cantarell", CANTARELL).unwrap();
+    fonts
+}
+
+#[test]
+fn families_resolve_case_insensitively_with_the_first_font_as_fallback() {
+    let mut fonts = fonts();
+    let serif = fonts.load("Serif Test", CANTARELL).unwrap();
+    assert_eq!(fonts.find("serif test"), Some(serif));
+    assert_eq!(fonts.resolve(&["Missing".into(), "SERIF TEST".into()]), [serif, FontId(0)]);
+    assert_eq!(fonts.resolve(&["Cantarell".into()]), [FontId(0)]);
+    assert!(FontLibrary::new().resolve(&["Cantarell".into()]).is_empty());
+    assert_eq!(fonts.load("Broken", vec![0u8; 16]), Err(FontError::InvalidFont("Broken".into())));
+}
+
+#[test]
+fn lays_out_one_line_along_the_baseline() {
+    let text = Text::new("Hello", Vec2::ZERO).with_font_size(FontSize::Pixels(20.0));
+    let layout = layout_text(&text, &fonts());
+    assert_eq!(layout.glyphs.len(), 5);
+    assert_eq!(layout.lines.len(), 1);
+    let baseline = layout.lines[0].baseline;
+    assert!(baseline > 10.0 && baseline < 25.0, "{}", baseline);
+    assert!(layout.glyphs.windows(2).all(|pair| pair[1].position.x > pair[0].position.x));
+    assert!(layout.glyphs.iter().all(|glyph| glyph.position.y == baseline));
+    assert_eq!(layout.glyphs[4].index, 4);
+    assert!((layout.size.x - layout.lines[0].width).abs() < 1e-4);
+
+    // Nothing to lay out with, nothing laid out
+    assert!(layout_text(&text, &FontLibrary::new()).glyphs.is_empty());
+}
+
+#[test]
+fn wraps_words_within_the_box() {
+    let text = Text::new("one two three four", Vec2::ZERO)
+        .with_font_size(FontSize::Pixels(20.0))
+        .with_bounds(Vec2::new(90.0, 200.0));
+    let layout = layout_text(&text, &fonts());
+    assert!(layout.lines.len() > 1);
+    for line in &layout.lines {
+        assert!(line.width <= 90.0, "{}", line.width);
+        // Every line starts at a word
+        let first = layout.glyphs[line.glyphs.start].index;
+        assert!(first == 0 || text.content.as_bytes()[first - 1] == b' ', "{}", first);
+    }
+    assert_eq!(layout.glyphs.len(), text.content.len());
+
+    // Words wider than the box break between characters
+    let narrow = text.with_content("abcdefghijklmnop").with_bounds(Vec2::new(30.0, 200.0));
+    let layout = layout_text(&narrow, &fonts());
+    assert!(layout.lines.len() > 2);
+    assert!(layout.lines.iter().all(|line| line.width <= 30.0 && !line.glyphs.is_empty()));
+}
+
+#[test]
+fn newlines_start_lines_one_line_height_apart() {
+    let text = Text::new("a\n\r\nb", Vec2::ZERO)
+        .with_font_size(FontSize::Pixels(10.0))
+        .with_line_height(1.5);
+    let layout = layout_text(&text, &fonts());
+    assert_eq!(layout.lines.len(), 3);
+    assert!(layout.lines[1].glyphs.is_empty());
+    assert!((layout.lines[2].baseline - layout.lines[0].baseline - 30.0).abs() < 1e-4);
+    assert!((layout.size.y - 45.0).abs() < 1e-4);
+    assert_eq!(layout.glyphs[1].index, 4);
+}
+
+#[test]
+fn aligns_lines_in_the_box() {
+    let fonts = fonts();
+    let text = Text::new("ab", Vec2::ZERO).with_font_size(FontSize::Pixels(10.0)).with_bounds(Vec2::new(100.0, 60.0));
+    let left = layout_text(&text, &fonts);
+    let width = left.lines[0].width;
+    assert_eq!(left.lines[0].x, 0.0);
+
+    let center = layout_text(&text.clone().with_align(TextAlign::Center), &fonts);
+    assert!((center.lines[0].x - (100.0 - width) / 2.0).abs() < 1e-4);
+    assert!((center.glyphs[0].position.x - center.lines[0].x).abs() < 1e-4);
+    let right = layout_text(&text.clone().with_align(TextAlign::Right), &fonts);
+    assert!((right.lines[0].x + width - 100.0).abs() < 1e-4);
+
+    let height = left.size.y;
+    let middle = layout_text(&text.clone().with_vertical_align(VerticalAlign::Middle), &fonts);
+    assert!((middle.lines[0].baseline - left.lines[0].baseline - (60.0 - height) / 2.0).abs() < 1e-4);
+    let bottom = layout_text(&text.with_vertical_align(VerticalAlign::Bottom), &fonts);
+    assert!((bottom.lines[0].baseline - left.lines[0].baseline - (60.0 - height)).abs() < 1e-4);
+}
+
+#[test]
+fn atlas_rasterizes_each_glyph_once_and_grows_when_full() {
+    let fonts = fonts();
+    let font = fonts.font(FontId(0)).unwrap();
+    let a = ab_glyph::Font::glyph_id(font, 'A');
+    let space = ab_glyph::Font::glyph_id(font, ' ');
+
+    let mut atlas = GlyphAtlas::new(32, 128);
+    let glyph = atlas.glyph(&fonts, GlyphKey::new(FontId(0), a, 20.0)).unwrap();
+    assert_eq!(atlas.glyph(&fonts, GlyphKey::new(FontId(0), a, 20.1)), Some(glyph));
+    assert!(atlas.glyph(&fonts, GlyphKey::new(FontId(0), space, 20.0)).is_none());
+    assert_eq!(atlas.len(), 2);
+    // Padding stays empty, the glyph's own texels don't
+    let texel = |position: UVec2| atlas.pixels()[(position.y * atlas.size() + position.x) as usize];
+    assert_eq!(texel(glyph.position), 0);
+    assert!((1..glyph.size.y - 1).any(|y| (1..glyph.size.x - 1).any(|x| texel(glyph.position + UVec2::new(x, y)) > 0)));
+    assert!(atlas.take_dirty_rows().is_some());
+    assert!(atlas.take_dirty_rows().is_none());
+
+    // A glyph bigger than the atlas doubles it, keeping what was there
+    let big = atlas.glyph(&fonts, GlyphKey::new(FontId(0), a, 60.0)).unwrap();
+    assert!(atlas.size() > 32);
+    assert_eq!(atlas.take_dirty_rows(), Some(0..atlas.size()));
+    assert_ne!(big.position, glyph.position);
+    assert!(!atlas.take_overflowed());
+
+    assert!(atlas.glyph(&fonts, GlyphKey::new(FontId(0), a, 400.0)).is_none());
+    assert!(atlas.take_overflowed());
+    atlas.clear();
+    assert!(atlas.is_empty());
+}
+
+#[test]
+fn pixel_sized_glyphs_map_to_clip_space_with_opacity_baked_in() {
+    let fonts = fonts();
+    let mut text = Text::new("I", Vec2::new(-1.0, 1.0)).with_font_size(FontSize::Pixels(20.0)).with_color(Color::RED);
+    text.appearance.opacity = 0.5;
+    let layout = layout_text(&text, &fonts);
+    let mut atlas = GlyphAtlas::default();
+    let glyph = text.glyph_instances(&layout, &fonts, &mut atlas, SURFACE)[0];
+    assert_eq!(glyph.color, [1.0, 0.0, 0.0, 0.5]);
+    // From the top-left corner of the surface, right and down, one pixel is 2 / 800 wide
+    assert!(glyph.origin[0] > -1.0 && glyph.origin[1] < 1.0);
+    assert!(glyph.axis_x[0] > 0.0 && glyph.axis_x[1] == 0.0);
+    assert!(glyph.axis_y[0] == 0.0 && glyph.axis_y[1] < 0.0);
+    let texels = Vec2::from(glyph.uv_max) - Vec2::from(glyph.uv_min);
+    assert!((glyph.axis_x[0] - texels.x * 2.0 / SURFACE.x).abs() < 1e-5);
+    assert!((glyph.axis_y[1] + texels.y * 2.0 / SURFACE.y).abs() < 1e-5);
+
+    // Rotation turns the quad; scale leaves pixel sizes alone
+    let turned = text
+        .clone()
+        .with_transform(Transform::identity().with_rotation(std::f32::consts::FRAC_PI_2).with_scale(Vec2::splat(3.0)));
+    let turned = turned.glyph_instances(&layout, &fonts, &mut atlas, SURFACE)[0];
+    assert!(turned.axis_x[0].abs() < 1e-6 && turned.axis_x[1] > 0.0);
+    assert!((turned.axis_x[1] - texels.x * 2.0 / SURFACE.y).abs() < 1e-5);
+}
+
+#[test]
+fn world_sized_text_is_rasterized_for_its_size_on_screen() {
+    let text = Text::new("I", Vec2::ZERO).with_font_size(FontSize::World(0.1));
+    // One clip space unit is half of a 600 pixel surface
+    assert_eq!(text.pixels_per_unit(Vec2::splat(600.0)), 300.0);
+    let scaled = text.with_transform(Transform::identity().with_scale(Vec2::splat(2.0)));
+    assert_eq!(scaled.pixels_per_unit(Vec2::splat(600.0)), 600.0);
+    let point = Vec2::new(0.05, 0.02);
+    let round_trip = scaled.layout_to_clip(scaled.clip_to_layout(point, SURFACE), SURFACE);
+    assert!((round_trip - point).length() < 1e-6);
+}
+
+#[test]
+fn class_styles_set_color_and_font() {
+    let mut text = Text::new("label", Vec2::ZERO).with_font_size(FontSize::World(0.1));
+    let style = Style {
+        color: Some(Color::GREEN),
+        background_color: Some(CssColor::Color(Color::BLUE)),
+        font_size: Some(40.0),
+        font_family: Some(vec!["Inter".into(), "sans-serif".into()]),
+        ..Style::default()
+    };
+    // World sizes are in clip space, pixel sizes in physical pixels
+    let viewport = StyleViewport::new(800, 800).with_scale_factor(2.0);
+    Styled::apply_css_style(&mut text, &style, &viewport);
+    assert_eq!(text.color, Color::GREEN);
+    assert_eq!(text.font_size, FontSize::World(0.2));
+    assert_eq!(text.font_family, ["Inter", "sans-serif"]);
+
+    let mut text = Text::new("label", Vec2::ZERO).with_font_size(FontSize::Pixels(12.0));
+    Styled::apply_css_style(&mut text, &Style { font_size: Some(16.0), ..Style::default() }, &viewport);
+    assert_eq!(text.font_size, FontSize::Pixels(32.0));
+}
+
+#[test]
+fn hit_tests_against_the_box() {
+    let text = Text::new("button", Vec2::new(-0.5, 0.5)).with_font_size(FontSize::World(0.1)).with_bounds(Vec2::new(1.0, 0.5));
+    assert!(text.hit_test(Vec2::new(0.0, 0.25)));
+    assert!(!text.hit_test(Vec2::new(0.0, 0.75)));
+    let mut hidden = text.clone();
+    hidden.appearance.visible = false;
+    assert!(!hidden.hit_test(Vec2::new(0.0, 0.25)));
+
+    // 100 by 20 pixels from the top-left corner of an 800x600 surface
+    let pixels = Text::new("button", Vec2::new(-1.0, 1.0)).with_bounds(Vec2::new(100.0, 20.0));
+    assert!(pixels.hit_test_in_surface(Vec2::new(-0.9, 0.95), (800, 600)));
+    assert!(!pixels.hit_test_in_surface(Vec2::new(-0.7, 0.95), (800, 600)));
+    assert!(!Text::new("no box", Vec2::ZERO).hit_test(Vec2::ZERO));
+}
@@ -0,0 +1,68 @@
+use glam::Vec2;
+use plinth_primitives::{Circle, Rectangle, Style, StyleViewport, Transform};
+
+fn close(a: Vec2, b: Vec2) -> bool {
+    a.distance(b) < 1e-5
+}
+
+#[test]
+fn composing_applies_the_inner_transform_first() {
+    let outer = Transform::new(Vec2::new(1.0, 2.0), Vec2::splat(2.0), std::f32::consts::FRAC_PI_2);
+    let inner = Transform::new(Vec2::new(0.5, 0.0), Vec2::splat(3.0), 0.25);
+    let composed = outer.compose(&inner);
+    for point in [Vec2::ZERO, Vec2::X, Vec2::new(-0.3, 0.7)] {
+        let expected = outer.transform_point(inner.transform_point(point));
+        assert!(close(composed.transform_point(point), expected), "{:?}", point);
+    }
+    assert_eq!(Transform::identity().compose(&inner), inner);
+    assert_eq!(inner.compose(&Transform::identity()), inner);
+}
+
+#[test]
+fn css_transforms_compose_with_the_primitive_transform() {
+    let own = Transform::identity().with_position(Vec2::new(0.5, 0.0)).with_scale(Vec2::splat(2.0));
+    let style = Style {
+        transform: Some(Transform::identity().with_position(Vec2::new(0.0, 50.0)).with_rotation(0.5)),
+        ..Style::default()
+    };
+    // 400 pixels across, so 50px is an eighth of clip space's 2 units
+    let viewport = StyleViewport::new(400, 400);
+
+    let mut circle = Circle::new(Vec2::ZERO, 0.1).with_transform(own);
+    circle.apply_css_style(&style, &viewport);
+    assert!(close(circle.transform.position, Vec2::new(0.5, 0.5)));
+    assert_eq!(circle.transform.scale, Vec2::splat(2.0));
+    assert_eq!(circle.transform.rotation, 0.5);
+
+    let mut rectangle = Rectangle::new(Vec2::ZERO, Vec2::ONE).with_transform(own);
+    rectangle.apply_css_style(&style, &viewport);
+    assert_eq!(rectangle.transform, circle.transform);
+}
+
+#[test]
+fn css_lengths_are_converted_to_clip_space_against_the_viewport() {
+    // 2 physical pixels per CSS pixel on a 800x400 surface: 1px is 0.005 across, 0.01 up
+    let viewport = StyleViewport::new(800, 400).with_scale_factor(2.0);
+    assert_eq!(viewport.pixels(3.0), 6.0);
+    assert!(close(viewport.clip(Vec2::new(100.0, 50.0)), Vec2::new(0.5, 0.5)));
+    let style = Style {
+        width: Some(100.0),
+        height: Some(20.0),
+        border_width: Some(2.0),
+        border_radius: Some(5.0),
+        ..Style::default()
+    };
+
+    let mut rectangle = Rectangle::new(Vec2::ZERO, Vec2::ONE);
+    rectangle.apply_css_style(&style, &viewport);
+    assert!(close(rectangle.size, Vec2::new(0.5, 0.2)));
+    assert!((rectangle.corner_radius - 0.05).abs() < 1e-6);
+    assert!((rectangle.appearance.border_width - 0.02).abs() < 1e-6);
+
+    // The diameter is the smaller of both sides
+    let mut circle = Circle::new(Vec2::ZERO, 0.5);
+    circle.apply_css_style(&style, &viewport);
+    assert!((circle.radius - 0.1).abs() < 1e-6);
+    circle.apply_css_style(&Style { width: Some(40.0), ..Style::default() }, &viewport);
+    assert!((circle.radius - 0.1).abs() < 1e-6);
+}
@@ -0,0 +1,189 @@
+use std::any::Any;
+use std::time::Duration;
+
+use plinth_primitives::{Animation, Style, StyleAnimation, TimingFunction};
+
+use crate::animation::{animated_value, set_animated_value, AnimatedValue, Keyframes, ANIMATABLE_PROPERTIES};
+use crate::mapping::ClassMapper;
+
+// Per-primitive transition and animation state. Feed it the primitive's computed style every
+// frame with the frame clock's time; it returns the style to draw, with transitions started
+// whenever a property's computed value changes and @keyframes animations applied on top.
+#[derive(Debug, Default)]
+pub struct StyleAnimator {
+    // Computed (un-animated) values seen on the previous update
+    targets: [Option<AnimatedValue>; ANIMATABLE_PROPERTIES.len()],
+    // Values returned by the previous update, where interrupted transitions restart from
+    current: [Option<AnimatedValue>; ANIMATABLE_PROPERTIES.len()],
+    transitions: Vec<RunningTransition>,
+    animations: Vec<RunningAnimation>,
+    initialized: bool,
+    time: f32,
+}
+
+#[derive(Debug)]
+struct RunningTransition {
+    property: usize,
+    from: AnimatedValue,
+    to: AnimatedValue,
+    start: f32,
+    delay: f32,
+    duration: f32,
+    timing: TimingFunction,
+}
+
+#[derive(Debug)]
+struct RunningAnimation {
+    animation: Animation,
+    start: f32,
+}
+
+impl StyleAnimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Whether another frame would look different, i.e. a redraw should be requested
+    pub fn is_animating(&self) -> bool {
+        !self.transitions.is_empty()
+            || self
+                .animations
+                .iter()
+                .any(|running| running.animation.is_running(self.time - running.start))
+    }
+
+    // Drops all running transitions and animations; the next update starts from scratch
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // `target` is the computed style, `time` the frame clock's elapsed time. Keyframes are
+    // looked up in `class_mapper`.
+    pub fn update(&mut self, target: &Style, class_mapper: &ClassMapper, time: Duration) -> Style {
+        let now = time.as_secs_f32();
+        self.time = now;
+        self.sync_animations(target.animations.as_deref().unwrap_or_default(), now);
+
+        let mut output = target.clone();
+        for (index, property) in ANIMATABLE_PROPERTIES.iter().enumerate() {
+            let new_target = animated_value(target, property);
+            if self.initialized && new_target != self.targets[index] {
+                self.transitions.retain(|transition| transition.property != index);
+                let transition = target
+                    .transitions
+                    .iter()
+                    .flatten()
+                    .rev()
+                    .find(|transition| transition.applies_to(property.css_name()));
+                let from = self.current[index].or(self.targets[index]);
+                if let (Some(transition), Some(from), Some(to)) = (transition, from, new_target) {
+                    if transition.duration > 0.0 {
+                        self.transitions.push(RunningTransition {
+                            property: index,
+                            from,
+                            to,
+                            start: now,
+                            delay: transition.delay,
+                            duration: transition.duration,
+                            timing: transition.timing,
+                        });
+                    }
+                }
+            }
+            self.targets[index] = new_target;
+        }
+        self.initialized = true;
+
+        self.transitions.retain(|transition| now < transition.start + transition.delay + transition.duration);
+        for transition in &self.transitions {
+            let progress = ((now - transition.start - transition.delay) / transition.duration).max(0.0);
+            let value = transition.from.interpolate(transition.to, transition.timing.evaluate(progress));
+            set_animated_value(&mut output, &ANIMATABLE_PROPERTIES[transition.property], value);
+        }
+
+        // Animations override transitions, later animations override earlier ones
+        for running in &self.animations {
+            let Some(progress) = running.animation.progress(now - running.start) else {
+                continue;
+            };
+            let Some(keyframes) = class_mapper.keyframes(&running.animation.name) else {
+                continue;
+            };
+            for property in &ANIMATABLE_PROPERTIES {
+                let underlying = animated_value(&output, property);
+                if let Some(value) = keyframe_value(keyframes, property, underlying, progress, running.animation.timing) {
+                    set_animated_value(&mut output, property, value);
+                }
+            }
+        }
+
+        for (index, property) in ANIMATABLE_PROPERTIES.iter().enumerate() {
+            self.current[index] = animated_value(&output, property);
+        }
+        output
+    }
+
+    // Keeps the start time of animations that stay listed (by name), starts new ones now
+    fn sync_animations(&mut self, animations: &[Animation], now: f32) {
+        let mut previous = std::mem::take(&mut self.animations);
+        for animation in animations {
+            let start = match previous.iter().position(|running| running.animation.name == animation.name) {
+                Some(index) => previous.remove(index).start,
+                None => now,
+            };
+            self.animations.push(RunningAnimation {
+                animation: animation.clone(),
+                start,
+            });
+        }
+    }
+}
+
+impl StyleAnimation for StyleAnimator {
+    fn is_animating(&self) -> bool {
+        StyleAnimator::is_animating(self)
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Value of `property` at `progress` through `keyframes`. Missing `from`/`to` frames fall back
+// to the underlying value; the timing function applies within each pair of keyframes.
+fn keyframe_value(
+    keyframes: &Keyframes,
+    property: &crate::types::StyleProperty,
+    underlying: Option<AnimatedValue>,
+    progress: f32,
+    timing: TimingFunction,
+) -> Option<AnimatedValue> {
+    let mut stops: Vec<(f32, AnimatedValue)> = keyframes
+        .frames
+        .iter()
+        .filter_map(|frame| animated_value(&frame.style, property).map(|value| (frame.offset, value)))
+        .collect();
+    if stops.is_empty() {
+        return None;
+    }
+    if let Some(underlying) = underlying {
+        if stops[0].0 > 0.0 {
+            stops.insert(0, (0.0, underlying));
+        }
+        if stops[stops.len() - 1].0 < 1.0 {
+            stops.push((1.0, underlying));
+        }
+    }
+
+    let next = stops.iter().position(|(offset, _)| *offset > progress);
+    match next {
+        None => stops.last().map(|(_, value)| *value),
+        Some(0) => Some(stops[0].1),
+        Some(next) => {
+            let (from_offset, from) = stops[next - 1];
+            let (to_offset, to) = stops[next];
+            let local = (progress - from_offset) / (to_offset - from_offset);
+            Some(from.interpolate(to, timing.evaluate(local)))
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use plinth_primitives::{Color, CssColor, Style, Transform};
+
+use crate::types::StyleProperty;
+
+// Properties transitions and animations interpolate. The rest (z-index, visibility,
+// display...) switch at the end of a transition, like discretely animated CSS properties.
+pub const ANIMATABLE_PROPERTIES: [StyleProperty; 12] = [
+    StyleProperty::Color,
+    StyleProperty::BackgroundColor,
+    StyleProperty::BorderColor,
+    StyleProperty::Opacity,
+    StyleProperty::BorderWidth,
+    StyleProperty::BorderRadius,
+    StyleProperty::Width,
+    StyleProperty::Height,
+    StyleProperty::Transform,
+    StyleProperty::FilterBlur,
+    StyleProperty::BackdropBlur,
+    StyleProperty::FontSize,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimatedValue {
+    Color(Color),
+    Number(f32),
+    Transform(Transform),
+}
+
+impl AnimatedValue {
+    // Colors mix in OKLab; transforms interpolate position, scale and rotation separately
+    pub fn interpolate(self, to: AnimatedValue, t: f32) -> AnimatedValue {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        match (self, to) {
+            (Self::Color(a), Self::Color(b)) => Self::Color(a.mix_oklab(b, t)),
+            (Self::Number(a), Self::Number(b)) => Self::Number(lerp(a, b)),
+            (Self::Transform(a), Self::Transform(b)) => Self::Transform(Transform::new(
+                a.position.lerp(b.position, t),
+                a.scale.lerp(b.scale, t),
+                lerp(a.rotation, b.rotation),
+            )),
+            // Mismatched kinds can't come out of one property; jump halfway like CSS does
+            _ if t < 0.5 => self,
+            _ => to,
+        }
+    }
+}
+
+// Value of an animatable property, None if it's unset or not animatable
+pub fn animated_value(style: &Style, property: &StyleProperty) -> Option<AnimatedValue> {
+    use AnimatedValue::*;
+    match property {
+        StyleProperty::Color => style.color.map(Color),
+        StyleProperty::BackgroundColor => style.background_color.and_then(|color| style.resolve_color(color)).map(Color),
+        StyleProperty::BorderColor => style.stroke_color().map(Color),
+        StyleProperty::Opacity => style.opacity.map(Number),
+        StyleProperty::BorderWidth => style.border_width.map(Number),
+        StyleProperty::BorderRadius => style.border_radius.map(Number),
+        StyleProperty::Width => style.width.map(Number),
+        StyleProperty::Height => style.height.map(Number),
+        StyleProperty::Transform => style.transform.map(Transform),
+        StyleProperty::FilterBlur => style.filter_blur.map(Number),
+        StyleProperty::BackdropBlur => style.backdrop_blur.map(Number),
+        StyleProperty::FontSize => style.font_size.map(Number),
+        _ => None,
+    }
+}
+
+pub fn set_animated_value(style: &mut Style, property: &StyleProperty, value: AnimatedValue) {
+    match (property, value) {
+        (StyleProperty::Color, AnimatedValue::Color(c)) => style.color = Some(c),
+        (StyleProperty::BackgroundColor, AnimatedValue::Color(c)) => style.background_color = Some(CssColor::Color(c)),
+        (StyleProperty::BorderColor, AnimatedValue::Color(c)) => style.border_color = Some(CssColor::Color(c)),
+        (StyleProperty::Opacity, AnimatedValue::Number(n)) => style.opacity = Some(n.clamp(0.0, 1.0)),
+        (StyleProperty::BorderWidth, AnimatedValue::Number(n)) => style.border_width = Some(n.max(0.0)),
+        (StyleProperty::BorderRadius, AnimatedValue::Number(n)) => style.border_radius = Some(n.max(0.0)),
+        (StyleProperty::Width, AnimatedValue::Number(n)) => style.width = Some(n.max(0.0)),
+        (StyleProperty::Height, AnimatedValue::Number(n)) => style.height = Some(n.max(0.0)),
+        (StyleProperty::Transform, AnimatedValue::Transform(t)) => style.transform = Some(t),
+        (StyleProperty::FilterBlur, AnimatedValue::Number(n)) => style.filter_blur = Some(n.max(0.0)),
+        (StyleProperty::BackdropBlur, AnimatedValue::Number(n)) => style.backdrop_blur = Some(n.max(0.0)),
+        (StyleProperty::FontSize, AnimatedValue::Number(n)) => style.font_size = Some(n.max(0.0)),
+        _ => {}
+    }
+}
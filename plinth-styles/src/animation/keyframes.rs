@@ -0,0 +1,66 @@
+use plinth_primitives::Style;
+
+use crate::parser::{split_top_level, AtRule, CssParser, Stylesheet};
+
+// A parsed `@keyframes name { ... }` rule. Frames are sorted by offset; each holds only
+// the properties its block declares.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframes {
+    pub name: String,
+    pub frames: Vec<Keyframe>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    // Position in the animation, 0.0 (`from`) to 1.0 (`to`)
+    pub offset: f32,
+    pub style: Style,
+}
+
+impl Keyframes {
+    pub fn from_at_rule(rule: &AtRule) -> Option<Self> {
+        let name = rule.prelude.trim().trim_matches(|c| c == '"' || c == '\'');
+        if name.is_empty() {
+            return None;
+        }
+        let block = Stylesheet::parse(rule.block.as_deref()?).ok()?;
+
+        let mut frames = Vec::new();
+        for frame_rule in block.style_rules() {
+            let mut style = Style::default();
+            for declaration in &frame_rule.declarations {
+                // !important is ignored inside keyframes, as in CSS
+                if declaration.important {
+                    continue;
+                }
+                if let Err(e) = CssParser::apply_property(&mut style, &declaration.property, &declaration.value) {
+                    log::warn!("Ignoring '{}' in @keyframes {}: {}", declaration.property, name, e);
+                }
+            }
+            for selector in frame_rule.selectors.iter().flat_map(|selector| split_top_level(selector, ',')) {
+                match parse_offset(selector) {
+                    Some(offset) => frames.push(Keyframe { offset, style: style.clone() }),
+                    None => log::warn!("Ignoring keyframe selector '{}' in @keyframes {}", selector.trim(), name),
+                }
+            }
+        }
+        // Stable, so frames at the same offset keep source order and the later one wins
+        frames.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        Some(Self {
+            name: name.to_string(),
+            frames,
+        })
+    }
+}
+
+fn parse_offset(selector: &str) -> Option<f32> {
+    let selector = selector.trim().to_ascii_lowercase();
+    match selector.as_str() {
+        "from" => Some(0.0),
+        "to" => Some(1.0),
+        _ => {
+            let percent: f32 = selector.strip_suffix('%')?.trim().parse().ok()?;
+            (0.0..=100.0).contains(&percent).then_some(percent / 100.0)
+        }
+    }
+}
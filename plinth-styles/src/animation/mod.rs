@@ -0,0 +1,7 @@
+pub mod animator;
+pub mod interpolate;
+pub mod keyframes;
+
+pub use animator::*;
+pub use interpolate::*;
+pub use keyframes::*;
@@ -1,12 +1,17 @@
+pub mod animation;
 pub mod parser;
 pub mod mapping;
 pub mod types;
 
-#[cfg(feature = "web")]
+#[cfg(any(feature = "web", all(feature = "hot-reload", not(target_arch = "wasm32"))))]
 pub mod watcher;
 
+pub use animation::{Keyframes, StyleAnimator};
 pub use mapping::ClassMapper;
 pub use types::*;
 
+#[cfg(any(feature = "web", all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+pub use watcher::{CssWatcher, StyleChange, WatchedProperty};
+
 #[cfg(feature = "web")]
-pub use watcher::CssWatcher;
+pub use watcher::WatcherMetrics;
@@ -1,19 +1,74 @@
 use std::collections::HashMap;
-use plinth_primitives::Color;
-use crate::types::CssClass;
+use std::time::Duration;
+use plinth_primitives::{
+    BoxShadow, Color, Display, InteractionState, Paint, Style, StyleAnimation, StyleResolver, Styled, Transform,
+    Visibility,
+};
+use crate::animation::{Keyframes, StyleAnimator};
+use crate::parser::{CssParser, CssRule, Declaration, MediaQueryList, Selector, Stylesheet};
+use crate::types::{CssClass, DesignTokens, MediaEnvironment};
 
+// Source `load_tokens` replaces
+const TOKENS_SOURCE: &str = "design tokens";
+
+// Rules in source order, resolved with the CSS cascade: for each property the matching rule
+// with the highest specificity wins, later rules break ties, and `!important` declarations
+// beat all normal ones. Rules inside @media blocks only take part while their queries match
+// the current MediaEnvironment. Custom properties declared in `:root` rules are design
+// tokens that var() references in any rule resolve against.
 pub struct ClassMapper {
+    rules: Vec<MappedRule>,
+    // Merged single-class rules outside of @media blocks, kept for `get_class`
     classes: HashMap<String, CssClass>,
+    // @keyframes rules with the source they were loaded from, in load order. The last one
+    // with a name wins.
+    keyframes: Vec<(Option<String>, Keyframes)>,
+    environment: MediaEnvironment,
+    next_order: u32,
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MappedRule {
+    pub selector: Selector,
+    pub order: u32,
+    pub normal: Style,
+    pub important: Style,
+    // Every declaration of a rule using var() or `color: currentColor`, in source order. Its
+    // style is parsed from them when computed, since those values depend on the tokens in
+    // scope and the parent; `normal` and `important` then only hold the other declarations.
+    pub deferred: Vec<Declaration>,
+    // Queries of the enclosing @media blocks, outermost first; all of them have to match
+    pub media: Vec<MediaQueryList>,
+    // Where the rule was loaded from (e.g. a file path) if it can be reloaded
+    pub source: Option<String>,
+    active: bool,
+}
+
+impl MappedRule {
+    // Whether the rule's @media conditions match the mapper's current environment
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
 }
 
 impl ClassMapper {
     pub fn new() -> Self {
         Self {
+            rules: Vec::new(),
             classes: HashMap::new(),
+            keyframes: Vec::new(),
+            environment: MediaEnvironment::default(),
+            next_order: 0,
+            generation: 0,
         }
     }
 
+    // Defines `.name` as exactly `class`, replacing earlier `.name` rules
     pub fn add_class(&mut self, class: CssClass) {
+        let selector = Selector::class(&class.name);
+        self.rules.retain(|rule| rule.selector != selector);
+        self.push_rule(selector, class.style.clone(), Style::default(), Vec::new());
         self.classes.insert(class.name.clone(), class);
     }
 
@@ -21,19 +76,441 @@ impl ClassMapper {
         self.classes.get(class_name)
     }
 
+    pub fn rules(&self) -> &[MappedRule] {
+        &self.rules
+    }
+
+    // Bumped by every change that can affect a computed style: rules added, merged or
+    // reloaded, keyframes replaced, or the environment switching the active @media rules
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn environment(&self) -> &MediaEnvironment {
+        &self.environment
+    }
+
+    // Re-evaluates every @media rule against `environment`. Returns whether the set of active
+    // rules changed, i.e. computed styles may have too. Call it on resize and theme changes.
+    pub fn set_environment(&mut self, environment: MediaEnvironment) -> bool {
+        self.environment = environment;
+        let mut changed = false;
+        for rule in &mut self.rules {
+            let active = rule.media.iter().all(|media| media.matches(&environment));
+            changed |= rule.active != active;
+            rule.active = active;
+        }
+        if changed {
+            self.generation += 1;
+        }
+        changed
+    }
+
+    // Every distinct @media query list the loaded rules depend on
+    pub fn media_queries(&self) -> Vec<&MediaQueryList> {
+        let mut queries: Vec<&MediaQueryList> = Vec::new();
+        for media in self.rules.iter().flat_map(|rule| &rule.media) {
+            if !queries.iter().any(|query| query.text == media.text) {
+                queries.push(media);
+            }
+        }
+        queries
+    }
+
+    // Computed value of one color for a class list like "button primary"
     pub fn get_color_for_class(&self, class_name: &str, property: ColorProperty) -> Option<Color> {
-        self.classes.get(class_name).and_then(|class| match property {
-            ColorProperty::Color => class.color,
-            ColorProperty::BackgroundColor => class.background_color,
-            ColorProperty::BorderColor => class.border_color,
+        self.get_style(class_name).and_then(|style| match property {
+            ColorProperty::Color => style.color,
+            ColorProperty::BackgroundColor => style.background_color.and_then(|color| style.resolve_color(color)),
+            ColorProperty::BorderColor => style.stroke_color(),
         })
     }
 
+    // Computed style of a space-separated class list, or None if no rule matches it. Pass
+    // it to a primitive's `apply_css_style` with the viewport its lengths are measured against.
+    pub fn get_style(&self, class_name: &str) -> Option<Style> {
+        self.get_style_for_state(class_name, InteractionState::NONE)
+    }
+
+    // Same as `get_style` with pseudo-class rules for `state` applied, e.g. `.button:hover`
+    pub fn get_style_for_state(&self, class_name: &str, state: InteractionState) -> Option<Style> {
+        let classes: Vec<&str> = class_name.split_whitespace().collect();
+        if !self.rules.iter().any(|rule| rule.active && rule.selector.matches(&classes, state)) {
+            return None;
+        }
+        Some(self.compute_style(&classes, state, None))
+    }
+
+    // Cascades every rule matching `classes` in `state` on top of what `parent` passes down
+    pub fn compute_style(&self, classes: &[&str], state: InteractionState, parent: Option<&Style>) -> Style {
+        let mut matching: Vec<&MappedRule> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.active && rule.selector.matches(classes, state))
+            .collect();
+        matching.sort_by_key(|rule| (rule.selector.specificity(), rule.order));
+
+        // var() sees the element's final custom properties over the inherited and root ones
+        let scope = matching.iter().any(|rule| !rule.deferred.is_empty()).then(|| {
+            let mut scope = self.tokens();
+            for (name, value) in parent.into_iter().flat_map(|parent| &parent.custom_properties) {
+                scope.insert(name, value.as_str());
+            }
+            for style in matching.iter().map(|rule| &rule.normal).chain(matching.iter().map(|rule| &rule.important)) {
+                for (name, value) in &style.custom_properties {
+                    scope.insert(name, value.as_str());
+                }
+            }
+            scope
+        });
+
+        let mut style = parent.map(Style::inherited).unwrap_or_default();
+        let inherited_color = style.color;
+        for important in [false, true] {
+            for rule in &matching {
+                match &scope {
+                    Some(scope) if !rule.deferred.is_empty() => {
+                        style.merge(&Self::resolve_rule(rule, scope, inherited_color, important))
+                    }
+                    _ => style.merge(if important { &rule.important } else { &rule.normal }),
+                }
+            }
+        }
+        // Only now is the color that currentColor refers to known
+        style.resolve_current_color();
+        style
+    }
+
+    // The `important` or normal declarations of a rule using var() or `color: currentColor`,
+    // applied in source order so a later declaration of the same property wins whether or not
+    // either is deferred
+    fn resolve_rule(rule: &MappedRule, scope: &DesignTokens, inherited_color: Option<Color>, important: bool) -> Style {
+        let mut style = Style::default();
+        for declaration in rule.deferred.iter().filter(|d| d.important == important) {
+            // Custom properties keep their var() references for whatever reads them later
+            let value = if declaration.property.starts_with("--") {
+                Some(declaration.value.clone())
+            } else {
+                scope.resolve(&declaration.value)
+            };
+            // currentColor as the color itself is the inherited color
+            if declaration.property == "color" && value.as_deref().is_some_and(is_current_color) {
+                style.color = inherited_color;
+                continue;
+            }
+            let applied =
+                value.is_some_and(|value| CssParser::apply_property(&mut style, &declaration.property, &value).is_ok());
+            if !applied {
+                log::debug!("Ignoring '{}: {}' in {}", declaration.property, declaration.value, rule.selector);
+            }
+        }
+        style
+    }
+
+    // Custom properties of the active `:root` rules, in cascade order
+    pub fn tokens(&self) -> DesignTokens {
+        let root_rules = || self.rules.iter().filter(|rule| rule.active && rule.selector.is_root());
+        let mut tokens = DesignTokens::new();
+        for style in root_rules().map(|rule| &rule.normal).chain(root_rules().map(|rule| &rule.important)) {
+            let mut properties: Vec<(&String, &String)> = style.custom_properties.iter().collect();
+            properties.sort();
+            for (name, value) in properties {
+                tokens.insert(name, value.as_str());
+            }
+        }
+        tokens
+    }
+
+    // Declares `tokens` in a `:root` rule, replacing the tokens of an earlier call. They win
+    // over tokens from stylesheets loaded before the first call.
+    pub fn load_tokens(&mut self, tokens: &DesignTokens) -> Result<usize, StyleError> {
+        self.reload_css(TOKENS_SOURCE, &tokens.to_css(":root"))
+    }
+
+    // A token's value with var() references resolved, parsed as a color
+    pub fn token_color(&self, name: &str) -> Option<Color> {
+        self.tokens().color(name)
+    }
+
+    // A copy of the authored `primitive` with the style for its classes and state applied, its
+    // lengths converted against the environment's viewport, and the computed style to pass as
+    // `parent` for primitives nested under this one. The primitive itself is left as
+    // authored, so styling it again doesn't stack transforms.
+    pub fn styled<T: Styled + Clone>(&self, primitive: &T, parent: Option<&Style>) -> (T, Style) {
+        let classes: Vec<&str> = primitive.css_classes().collect();
+        let style = self.compute_style(&classes, primitive.state(), parent);
+        let mut styled = primitive.clone();
+        styled.apply_css_style(&style, &self.environment.viewport());
+        (styled, style)
+    }
+
+    // Same as `styled`, running the primitive's transitions and animations through
+    // `animator`. Call it every frame with the frame clock's elapsed time, and keep
+    // requesting redraws while `animator.is_animating()`.
+    pub fn animated<T: Styled + Clone>(
+        &self,
+        primitive: &T,
+        animator: &mut StyleAnimator,
+        parent: Option<&Style>,
+        time: Duration,
+    ) -> (T, Style) {
+        let classes: Vec<&str> = primitive.css_classes().collect();
+        let computed = self.compute_style(&classes, primitive.state(), parent);
+        let style = animator.update(&computed, self, time);
+        let mut styled = primitive.clone();
+        styled.apply_css_style(&style, &self.environment.viewport());
+        (styled, style)
+    }
+
+    pub fn keyframes(&self, name: &str) -> Option<&Keyframes> {
+        self.keyframes.iter().rev().map(|(_, keyframes)| keyframes).find(|keyframes| keyframes.name == name)
+    }
+
+    // Replaces any @keyframes rule with the same name
+    pub fn add_keyframes(&mut self, keyframes: Keyframes) {
+        // Ones from a reloadable source are only shadowed, so they come back if this is dropped
+        self.keyframes.retain(|(source, existing)| source.is_some() || existing.name != keyframes.name);
+        self.keyframes.push((None, keyframes));
+        self.generation += 1;
+    }
+
+    // Gradient from background-image or background
+    pub fn get_background_image(&self, class_name: &str) -> Option<Paint> {
+        self.get_style(class_name)?.background_image
+    }
+
+    pub fn get_opacity(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.opacity
+    }
+
+    pub fn get_border_width(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.border_width
+    }
+
+    pub fn get_border_radius(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.border_radius
+    }
+
+    pub fn get_width(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.width
+    }
+
+    pub fn get_height(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.height
+    }
+
+    pub fn get_transform(&self, class_name: &str) -> Option<Transform> {
+        self.get_style(class_name)?.transform
+    }
+
+    pub fn get_z_index(&self, class_name: &str) -> Option<i32> {
+        self.get_style(class_name)?.z_index
+    }
+
+    pub fn get_visibility(&self, class_name: &str) -> Option<Visibility> {
+        self.get_style(class_name)?.visibility
+    }
+
+    pub fn get_display(&self, class_name: &str) -> Option<Display> {
+        self.get_style(class_name)?.display
+    }
+
+    pub fn get_box_shadow(&self, class_name: &str) -> Option<Vec<BoxShadow>> {
+        self.get_style(class_name)?.box_shadow
+    }
+
+    pub fn get_filter_blur(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.filter_blur
+    }
+
+    pub fn get_backdrop_blur(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.backdrop_blur
+    }
+
+    pub fn get_font_size(&self, class_name: &str) -> Option<f32> {
+        self.get_style(class_name)?.font_size
+    }
+
+    pub fn get_font_family(&self, class_name: &str) -> Option<Vec<String>> {
+        self.get_style(class_name)?.font_family
+    }
+
+    // `name` includes the leading `--`
+    pub fn get_custom_property(&self, class_name: &str, name: &str) -> Option<String> {
+        self.get_style(class_name)?.custom_property(name).map(str::to_string)
+    }
+
+    // Merges `class` into the last `.name` rule outside of @media, or adds one if there is none
+    pub fn merge_class(&mut self, class: CssClass) {
+        let selector = Selector::class(&class.name);
+        match self.rules.iter_mut().rev().find(|rule| rule.selector == selector && rule.media.is_empty()) {
+            Some(rule) => {
+                rule.normal.merge(&class.style);
+                self.generation += 1;
+            }
+            None => self.push_rule(selector, class.style.clone(), Style::default(), Vec::new()),
+        }
+        self.merge_view(&class.name, &class.style);
+    }
+
+    // Loads every rule with a supported selector. Returns how many rules it added.
+    pub fn load_css(&mut self, css: &str) -> Result<usize, StyleError> {
+        let stylesheet = Stylesheet::parse(css).map_err(|e| StyleError::CssParseError(e.to_string()))?;
+        Ok(self.load_stylesheet(&stylesheet))
+    }
+
+    pub fn load_stylesheet(&mut self, stylesheet: &Stylesheet) -> usize {
+        for keyframes in stylesheet_keyframes(stylesheet) {
+            self.add_keyframes(keyframes);
+        }
+        self.load_rules(stylesheet, &[])
+    }
+
+    // Replaces the rules and @keyframes previously loaded from `source` with those of
+    // `stylesheet`. They take the old ones' place in the cascade, so a reloaded file still
+    // loses ties to files loaded after it; a new source is appended. Returns how many rules it
+    // added.
+    pub fn reload_stylesheet(&mut self, source: &str, stylesheet: &Stylesheet) -> usize {
+        let from_source = |rule: &MappedRule| rule.source.as_deref() == Some(source);
+        let position = self.rules.iter().position(from_source).unwrap_or(self.rules.len());
+        self.rules.retain(|rule| !from_source(rule));
+        let following = self.rules.split_off(position);
+        let keyframes_from_source = |(from, _): &(Option<String>, Keyframes)| from.as_deref() == Some(source);
+        let keyframes_position = self.keyframes.iter().position(keyframes_from_source).unwrap_or(self.keyframes.len());
+        self.keyframes.retain(|entry| !keyframes_from_source(entry));
+        let following_keyframes = self.keyframes.split_off(keyframes_position);
+
+        let count = self.load_rules(stylesheet, &[]);
+        for rule in &mut self.rules[position..] {
+            rule.source = Some(source.to_string());
+        }
+        self.rules.extend(following);
+        let keyframes = stylesheet_keyframes(stylesheet).map(|keyframes| (Some(source.to_string()), keyframes));
+        self.keyframes.extend(keyframes);
+        self.keyframes.extend(following_keyframes);
+
+        for (order, rule) in self.rules.iter_mut().enumerate() {
+            rule.order = order as u32;
+        }
+        self.next_order = self.rules.len() as u32;
+        self.generation += 1;
+        self.classes.clear();
+        for index in 0..self.rules.len() {
+            let rule = &self.rules[index];
+            if let Some(name) = rule.selector.single_class().filter(|_| rule.media.is_empty()) {
+                let (name, normal, important) = (name.to_string(), rule.normal.clone(), rule.important.clone());
+                self.merge_view(&name, &normal);
+                self.merge_view(&name, &important);
+            }
+        }
+        count
+    }
+
+    // Same as `load_css` for a reloadable `source`, see `reload_stylesheet`
+    pub fn reload_css(&mut self, source: &str, css: &str) -> Result<usize, StyleError> {
+        let stylesheet = Stylesheet::parse(css).map_err(|e| StyleError::CssParseError(e.to_string()))?;
+        Ok(self.reload_stylesheet(source, &stylesheet))
+    }
+
+    // Adds the style rules of `stylesheet` in source order, descending into @media blocks
+    // with their queries added to `media`
+    fn load_rules(&mut self, stylesheet: &Stylesheet, media: &[MediaQueryList]) -> usize {
+        let mut count = 0;
+        for rule in &stylesheet.rules {
+            let rule = match rule {
+                CssRule::Style(rule) => rule,
+                CssRule::At(at) if at.name == "media" => {
+                    let Some(block) = &at.block else {
+                        continue;
+                    };
+                    match Stylesheet::parse(block) {
+                        Ok(nested) => {
+                            let mut nested_media = media.to_vec();
+                            nested_media.push(MediaQueryList::parse(&at.prelude));
+                            count += self.load_rules(&nested, &nested_media);
+                        }
+                        Err(e) => log::warn!("Ignoring @media {}: {}", at.prelude, e),
+                    }
+                    continue;
+                }
+                CssRule::At(_) => continue,
+            };
+            let mut normal = Style::default();
+            let mut important = Style::default();
+            // var() depends on the tokens in scope and `color: currentColor` on the parent, so
+            // both are parsed when a style is computed
+            let is_deferred = |declaration: &Declaration| {
+                let uses_var =
+                    !declaration.property.starts_with("--") && declaration.value.to_ascii_lowercase().contains("var(");
+                uses_var || declaration.property == "color" && is_current_color(&declaration.value)
+            };
+            let deferred = if rule.declarations.iter().any(is_deferred) { rule.declarations.clone() } else { Vec::new() };
+            for declaration in &rule.declarations {
+                if is_deferred(declaration) {
+                    continue;
+                }
+                let target = if declaration.important { &mut important } else { &mut normal };
+                if let Err(e) = CssParser::apply_property(target, &declaration.property, &declaration.value) {
+                    log::warn!("Ignoring '{}' in {}: {}", declaration.property, rule.selectors.join(", "), e);
+                }
+            }
+            for text in &rule.selectors {
+                let Some(selector) = Selector::parse(text) else {
+                    log::debug!("Skipping unsupported selector '{}'", text);
+                    continue;
+                };
+                if let Some(name) = selector.single_class().filter(|_| media.is_empty()) {
+                    let name = name.to_string();
+                    self.merge_view(&name, &normal);
+                    self.merge_view(&name, &important);
+                }
+                self.push_rule(selector, normal.clone(), important.clone(), media.to_vec());
+                if let Some(rule) = self.rules.last_mut() {
+                    rule.deferred = deferred.clone();
+                }
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // Same as `load_css`, reading the stylesheet from disk, e.g. a theme shared with the web
+    // build. Loading the same path again replaces its rules instead of adding them twice.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_css_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<usize, StyleError> {
+        let path = path.as_ref();
+        let css = std::fs::read_to_string(path).map_err(|e| StyleError::Io(path.display().to_string(), e.to_string()))?;
+        self.reload_css(&path.display().to_string(), &css)
+    }
+
     #[cfg(feature = "web")]
     pub fn load_from_dom(&mut self) -> Result<(), StyleError> {
-        // This will parse CSS from the DOM and extract class definitions
-        // For now, we'll implement a simple version
-        Ok(())
+        let css = CssParser::stylesheet_text_from_dom().map_err(|e| StyleError::CssParseError(e.to_string()))?;
+        self.load_css(&css).map(|_| ())
+    }
+
+    fn push_rule(&mut self, selector: Selector, normal: Style, important: Style, media: Vec<MediaQueryList>) {
+        let active = media.iter().all(|media| media.matches(&self.environment));
+        self.rules.push(MappedRule {
+            selector,
+            order: self.next_order,
+            normal,
+            important,
+            deferred: Vec::new(),
+            media,
+            source: None,
+            active,
+        });
+        self.next_order += 1;
+        self.generation += 1;
+    }
+
+    fn merge_view(&mut self, name: &str, style: &Style) {
+        self.classes
+            .entry(name.to_string())
+            .or_insert_with(|| CssClass::new(name.to_string()))
+            .style
+            .merge(style);
     }
 }
 
@@ -52,6 +529,33 @@ pub enum StyleError {
     ColorNotFound(String),
     #[error("DOM access failed")]
     DomAccessFailed,
+    #[error("Failed to read {0}: {1}")]
+    Io(String, String),
+    #[error("Failed to watch {0}: {1}")]
+    Watch(String, String),
+    #[error("Invalid design tokens: {0}")]
+    InvalidTokens(String),
+}
+
+impl StyleResolver for ClassMapper {
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn resolve_style(&self, classes: &[&str], state: InteractionState) -> Style {
+        self.compute_style(classes, state, None)
+    }
+
+    fn animation(&self) -> Option<Box<dyn StyleAnimation>> {
+        Some(Box::new(StyleAnimator::new()))
+    }
+
+    fn animate(&self, animation: &mut dyn StyleAnimation, style: &Style, time: Duration) -> Style {
+        match animation.as_any_mut().downcast_mut::<StyleAnimator>() {
+            Some(animator) => animator.update(style, self, time),
+            None => style.clone(),
+        }
+    }
 }
 
 impl Default for ClassMapper {
@@ -59,3 +563,19 @@ impl Default for ClassMapper {
         Self::new()
     }
 }
+
+fn is_current_color(value: &str) -> bool {
+    value.trim().eq_ignore_ascii_case("currentcolor")
+}
+
+// The well-formed @keyframes rules of `stylesheet`
+fn stylesheet_keyframes(stylesheet: &Stylesheet) -> impl Iterator<Item = Keyframes> + '_ {
+    let rules = stylesheet.at_rules("keyframes").chain(stylesheet.at_rules("-webkit-keyframes"));
+    rules.filter_map(|rule| {
+        let keyframes = Keyframes::from_at_rule(rule);
+        if keyframes.is_none() {
+            log::warn!("Ignoring malformed @keyframes {}", rule.prelude);
+        }
+        keyframes
+    })
+}
@@ -1,36 +1,99 @@
-// These imports are used in the web feature implementation
-#[cfg(feature = "web")]
-use plinth_primitives::Color;
-#[cfg(feature = "web")]
+use std::collections::HashMap;
+
+use plinth_primitives::{Color, ColorParseError, CssColor};
+use crate::parser::{Declaration, Stylesheet, StyleRule};
 use crate::types::CssClass;
 
 #[cfg(feature = "web")]
-use web_sys::{window, Document};
+use wasm_bindgen::JsCast;
+#[cfg(feature = "web")]
+use web_sys::{window, CssStyleSheet};
 
 pub struct CssParser;
 
 impl CssParser {
-    #[cfg(feature = "web")]
+    // Any CSS color value; see plinth_primitives::CssColor for the supported syntax
     pub fn parse_color_from_css(css_value: &str) -> Result<Color, CssParseError> {
-        // Simple hex color parser for now
-        if css_value.starts_with('#') {
-            let hex_str = &css_value[1..];
-            let hex = u32::from_str_radix(hex_str, 16)
-                .map_err(|_| CssParseError::InvalidColor(css_value.to_string()))?;
-            Ok(Color::from_hex(hex))
-        } else {
-            Err(CssParseError::UnsupportedFormat(css_value.to_string()))
+        Color::from_css_color(css_value).map_err(|e| Self::color_error(css_value, e))
+    }
+
+    // Same as `parse_color_from_css`, also accepting currentColor
+    pub fn parse_css_color(css_value: &str) -> Result<CssColor, CssParseError> {
+        CssColor::parse(css_value).map_err(|e| Self::color_error(css_value, e))
+    }
+
+    fn color_error(css_value: &str, error: ColorParseError) -> CssParseError {
+        match error {
+            ColorParseError::UnsupportedFormat => CssParseError::UnsupportedFormat(css_value.to_string()),
+            _ => CssParseError::InvalidColor(css_value.to_string()),
+        }
+    }
+
+    // Every class defined by a single-class selector (`.name { ... }`), in source order.
+    // A class defined by several rules gets their declarations merged, later rules winning.
+    pub fn parse_classes(css: &str) -> Result<Vec<CssClass>, CssParseError> {
+        Ok(Self::classes_from_stylesheet(&Stylesheet::parse(css)?))
+    }
+
+    pub fn classes_from_stylesheet(stylesheet: &Stylesheet) -> Vec<CssClass> {
+        let mut classes: Vec<CssClass> = Vec::new();
+        let mut by_name: HashMap<String, usize> = HashMap::new();
+
+        for rule in stylesheet.style_rules() {
+            for selector in &rule.selectors {
+                let Some(name) = StyleRule::class_name(selector) else {
+                    continue;
+                };
+                let index = *by_name.entry(name.to_string()).or_insert_with(|| {
+                    classes.push(CssClass::new(name.to_string()));
+                    classes.len() - 1
+                });
+                for declaration in &rule.declarations {
+                    if let Err(e) = Self::apply_declaration(&mut classes[index], declaration) {
+                        log::warn!("Ignoring '{}' in .{}: {}", declaration.property, name, e);
+                    }
+                }
+            }
         }
+        classes
+    }
+
+    // Applies one declaration to `class`. Properties the style model doesn't know are ignored.
+    pub fn apply_declaration(class: &mut CssClass, declaration: &Declaration) -> Result<(), CssParseError> {
+        Self::apply_property(&mut class.style, &declaration.property, &declaration.value).map(|_| ())
     }
 
+    // Parses every same-origin stylesheet in the document. Cross-origin sheets can't be read
+    // from script and are skipped.
     #[cfg(feature = "web")]
     pub fn extract_classes_from_dom() -> Result<Vec<CssClass>, CssParseError> {
+        Self::parse_classes(&Self::stylesheet_text_from_dom()?)
+    }
+
+    // Source text of every same-origin stylesheet in the document, in document order
+    #[cfg(feature = "web")]
+    pub fn stylesheet_text_from_dom() -> Result<String, CssParseError> {
         let window = window().ok_or(CssParseError::DomAccessFailed)?;
-        let _document = window.document().ok_or(CssParseError::DomAccessFailed)?;
-        
-        // This is a simplified implementation
-        // In a real implementation, you'd parse all stylesheets and extract class definitions
-        Ok(vec![])
+        let document = window.document().ok_or(CssParseError::DomAccessFailed)?;
+
+        let sheets = document.style_sheets();
+        let mut css = String::new();
+        for i in 0..sheets.length() {
+            let Some(sheet) = sheets.item(i).and_then(|sheet| sheet.dyn_into::<CssStyleSheet>().ok()) else {
+                continue;
+            };
+            let Ok(rules) = sheet.css_rules() else {
+                log::warn!("Skipping stylesheet that can't be read (cross-origin?)");
+                continue;
+            };
+            for j in 0..rules.length() {
+                if let Some(rule) = rules.item(j) {
+                    css.push_str(&rule.css_text());
+                    css.push('\n');
+                }
+            }
+        }
+        Ok(css)
     }
 }
 
@@ -40,6 +103,10 @@ pub enum CssParseError {
     InvalidColor(String),
     #[error("Unsupported format: {0}")]
     UnsupportedFormat(String),
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(String, String),
+    #[error("Syntax error: {0}")]
+    Syntax(String),
     #[error("DOM access failed")]
     DomAccessFailed,
 }
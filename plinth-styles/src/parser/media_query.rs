@@ -0,0 +1,361 @@
+use std::fmt;
+
+use plinth_core::context::Theme;
+
+use crate::parser::split_top_level;
+use crate::types::MediaEnvironment;
+
+// The prelude of an `@media` rule: comma-separated queries, any of which may match.
+// Supports media types, `not`/`only`, `and`, and the width, height, aspect-ratio,
+// orientation, resolution, prefers-color-scheme and prefers-reduced-motion features in
+// both `min-`/`max-` and range (`400px <= width < 800px`) syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQueryList {
+    pub queries: Vec<MediaQuery>,
+    // Source text, e.g. for handing to the browser's matchMedia
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    pub negated: bool,
+    pub media_type: MediaType,
+    pub features: Vec<MediaFeature>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+    // Types we never render to (speech, tv...), or misspelled ones
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaFeature {
+    // CSS pixels
+    Width(Comparison, f32),
+    Height(Comparison, f32),
+    // Width over height
+    AspectRatio(Comparison, f32),
+    // Dots per CSS pixel (dppx)
+    Resolution(Comparison, f32),
+    Orientation(Orientation),
+    PrefersColorScheme(Theme),
+    PrefersReducedMotion(bool),
+    // A feature or value this parser doesn't support; never matches
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl MediaQueryList {
+    // Invalid queries become ones that never match, like `not all` in a browser, so one bad
+    // query doesn't take the others in the list down with it
+    pub fn parse(text: &str) -> Self {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let queries = if text.is_empty() {
+            vec![MediaQuery::all()]
+        } else {
+            split_top_level(&text, ',')
+                .into_iter()
+                .map(|query| {
+                    MediaQuery::parse(query).unwrap_or_else(|| {
+                        log::warn!("Ignoring unsupported media query: {}", query.trim());
+                        MediaQuery::never()
+                    })
+                })
+                .collect()
+        };
+        Self { queries, text }
+    }
+
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        self.queries.iter().any(|query| query.matches(environment))
+    }
+}
+
+impl fmt::Display for MediaQueryList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl MediaQuery {
+    fn all() -> Self {
+        Self {
+            negated: false,
+            media_type: MediaType::All,
+            features: Vec::new(),
+        }
+    }
+
+    fn never() -> Self {
+        Self {
+            negated: true,
+            ..Self::all()
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut query = Self::all();
+        let mut words = text.trim();
+
+        if let Some(rest) = strip_keyword(words, "not") {
+            query.negated = true;
+            words = rest;
+        } else if let Some(rest) = strip_keyword(words, "only") {
+            words = rest;
+        }
+        if !words.starts_with('(') {
+            let (media_type, rest) = words.split_once(' ').unwrap_or((words, ""));
+            query.media_type = match media_type.to_ascii_lowercase().as_str() {
+                "all" => MediaType::All,
+                "screen" => MediaType::Screen,
+                "print" => MediaType::Print,
+                "" => return None,
+                _ => MediaType::Unknown,
+            };
+            words = rest.trim_start();
+            if words.is_empty() {
+                return Some(query);
+            }
+            words = strip_keyword(words, "and")?;
+        }
+
+        loop {
+            let (feature, rest) = parenthesized(words)?;
+            query.features.extend(parse_feature(feature));
+            words = rest.trim_start();
+            if words.is_empty() {
+                return Some(query);
+            }
+            words = strip_keyword(words, "and")?;
+        }
+    }
+
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        let type_matches = matches!(self.media_type, MediaType::All | MediaType::Screen);
+        let matches = type_matches && self.features.iter().all(|feature| feature.matches(environment));
+        matches != self.negated
+    }
+}
+
+impl MediaFeature {
+    pub fn matches(&self, environment: &MediaEnvironment) -> bool {
+        match *self {
+            Self::Width(comparison, value) => comparison.holds(environment.width, value),
+            Self::Height(comparison, value) => comparison.holds(environment.height, value),
+            Self::AspectRatio(comparison, value) => {
+                comparison.holds(environment.width / environment.height.max(f32::EPSILON), value)
+            }
+            Self::Resolution(comparison, value) => comparison.holds(environment.resolution, value),
+            Self::Orientation(orientation) => {
+                let portrait = environment.height >= environment.width;
+                portrait == (orientation == Orientation::Portrait)
+            }
+            Self::PrefersColorScheme(theme) => environment.theme == theme,
+            Self::PrefersReducedMotion(reduce) => environment.reduced_motion == reduce,
+            Self::Unknown => false,
+        }
+    }
+}
+
+impl Comparison {
+    // `actual <op> value`
+    pub fn holds(self, actual: f32, value: f32) -> bool {
+        match self {
+            Self::Less => actual < value,
+            Self::LessOrEqual => actual <= value,
+            Self::Equal => (actual - value).abs() < 1e-3,
+            Self::GreaterOrEqual => actual >= value,
+            Self::Greater => actual > value,
+        }
+    }
+
+    // The same comparison with its operands swapped, for `value <op> name`
+    fn flipped(self) -> Self {
+        match self {
+            Self::Less => Self::Greater,
+            Self::LessOrEqual => Self::GreaterOrEqual,
+            Self::Equal => Self::Equal,
+            Self::GreaterOrEqual => Self::LessOrEqual,
+            Self::Greater => Self::Less,
+        }
+    }
+}
+
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let (word, rest) = text.split_once(' ')?;
+    word.eq_ignore_ascii_case(keyword).then(|| rest.trim_start())
+}
+
+// Splits `(inside) rest` into its parts
+fn parenthesized(text: &str) -> Option<(&str, &str)> {
+    let inside = text.strip_prefix('(')?;
+    let end = inside.find(')')?;
+    Some((inside[..end].trim(), &inside[end + 1..]))
+}
+
+// One `( )` condition; a double range yields two features
+fn parse_feature(text: &str) -> Vec<MediaFeature> {
+    if let Some((name, value)) = text.split_once(':') {
+        return vec![plain_feature(name.trim(), value.trim()).unwrap_or(MediaFeature::Unknown)];
+    }
+    if !text.contains(['<', '>', '=']) {
+        // Boolean context: true when the feature isn't zero / none / no-preference
+        return vec![match text.to_ascii_lowercase().as_str() {
+            "width" => MediaFeature::Width(Comparison::Greater, 0.0),
+            "height" => MediaFeature::Height(Comparison::Greater, 0.0),
+            "prefers-reduced-motion" => MediaFeature::PrefersReducedMotion(true),
+            "orientation" | "resolution" | "aspect-ratio" | "prefers-color-scheme" => return Vec::new(),
+            _ => MediaFeature::Unknown,
+        }];
+    }
+    range_feature(text).unwrap_or_else(|| vec![MediaFeature::Unknown])
+}
+
+fn plain_feature(name: &str, value: &str) -> Option<MediaFeature> {
+    let name = name.to_ascii_lowercase();
+    let value_lower = value.to_ascii_lowercase();
+    let (comparison, base) = if let Some(base) = name.strip_prefix("min-") {
+        (Comparison::GreaterOrEqual, base)
+    } else if let Some(base) = name.strip_prefix("max-") {
+        (Comparison::LessOrEqual, base)
+    } else {
+        (Comparison::Equal, name.as_str())
+    };
+    Some(match base {
+        "orientation" if comparison == Comparison::Equal => MediaFeature::Orientation(match value_lower.as_str() {
+            "portrait" => Orientation::Portrait,
+            "landscape" => Orientation::Landscape,
+            _ => return None,
+        }),
+        "prefers-color-scheme" if comparison == Comparison::Equal => {
+            MediaFeature::PrefersColorScheme(match value_lower.as_str() {
+                "light" => Theme::Light,
+                "dark" => Theme::Dark,
+                _ => return None,
+            })
+        }
+        "prefers-reduced-motion" if comparison == Comparison::Equal => {
+            MediaFeature::PrefersReducedMotion(match value_lower.as_str() {
+                "reduce" => true,
+                "no-preference" => false,
+                _ => return None,
+            })
+        }
+        _ => numeric_feature(base, comparison, value)?,
+    })
+}
+
+fn numeric_feature(name: &str, comparison: Comparison, value: &str) -> Option<MediaFeature> {
+    Some(match name {
+        "width" => MediaFeature::Width(comparison, parse_media_length(value)?),
+        "height" => MediaFeature::Height(comparison, parse_media_length(value)?),
+        "aspect-ratio" => MediaFeature::AspectRatio(comparison, parse_ratio(value)?),
+        "resolution" => MediaFeature::Resolution(comparison, parse_resolution(value)?),
+        _ => return None,
+    })
+}
+
+// `name <op> value`, `value <op> name` or `value <op> name <op> value`
+fn range_feature(text: &str) -> Option<Vec<MediaFeature>> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    loop {
+        let Some(index) = rest.find(['<', '>', '=']) else {
+            parts.push(rest.trim());
+            break;
+        };
+        parts.push(rest[..index].trim());
+        let operator_length = if rest[index + 1..].starts_with('=') { 2 } else { 1 };
+        parts.push(&rest[index..index + operator_length]);
+        rest = &rest[index + operator_length..];
+    }
+
+    let operator = |text: &str| {
+        Some(match text {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            "=" => Comparison::Equal,
+            ">=" => Comparison::GreaterOrEqual,
+            ">" => Comparison::Greater,
+            _ => return None,
+        })
+    };
+    let is_name = |text: &str| text.chars().next().is_some_and(|c| c.is_ascii_alphabetic());
+
+    match parts.as_slice() {
+        [name, op, value] if is_name(name) => Some(vec![numeric_feature(&name.to_ascii_lowercase(), operator(op)?, value)?]),
+        [value, op, name] if is_name(name) => {
+            Some(vec![numeric_feature(&name.to_ascii_lowercase(), operator(op)?.flipped(), value)?])
+        }
+        [low, low_op, name, high_op, high] if is_name(name) => {
+            let (low_op, high_op) = (operator(low_op)?, operator(high_op)?);
+            // Both operators have to point the same way: `a < x < b` or `a > x > b`
+            let less = |op| matches!(op, Comparison::Less | Comparison::LessOrEqual);
+            let greater = |op| matches!(op, Comparison::Greater | Comparison::GreaterOrEqual);
+            if !(less(low_op) && less(high_op) || greater(low_op) && greater(high_op)) {
+                return None;
+            }
+            let name = name.to_ascii_lowercase();
+            Some(vec![
+                numeric_feature(&name, low_op.flipped(), low)?,
+                numeric_feature(&name, high_op, high)?,
+            ])
+        }
+        _ => None,
+    }
+}
+
+// `px`, `em`/`rem` (16px, as media queries don't see any font-size) or a bare 0
+pub fn parse_media_length(value: &str) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    let number = |text: &str| text.trim().parse::<f32>().ok().filter(|v| v.is_finite());
+    if let Some(px) = value.strip_suffix("px") {
+        return number(px);
+    }
+    if let Some(em) = value.strip_suffix("rem").or_else(|| value.strip_suffix("em")) {
+        return number(em).map(|em| em * 16.0);
+    }
+    number(&value).filter(|v| *v == 0.0)
+}
+
+// `16/9` or a plain number
+pub fn parse_ratio(value: &str) -> Option<f32> {
+    let number = |text: &str| text.trim().parse::<f32>().ok().filter(|v| v.is_finite() && *v >= 0.0);
+    match value.split_once('/') {
+        Some((width, height)) => Some(number(width)? / number(height).filter(|h| *h > 0.0)?),
+        None => number(value),
+    }
+}
+
+// `dppx`/`x`, `dpi` or `dpcm`, returned as dppx
+pub fn parse_resolution(value: &str) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    let number = |text: &str| text.trim().parse::<f32>().ok().filter(|v| v.is_finite() && *v >= 0.0);
+    if let Some(dppx) = value.strip_suffix("dppx") {
+        number(dppx)
+    } else if let Some(dpi) = value.strip_suffix("dpi") {
+        number(dpi).map(|dpi| dpi / 96.0)
+    } else if let Some(dpcm) = value.strip_suffix("dpcm") {
+        number(dpcm).map(|dpcm| dpcm * 2.54 / 96.0)
+    } else {
+        number(value.strip_suffix('x')?)
+    }
+}
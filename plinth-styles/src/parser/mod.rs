@@ -1,3 +1,11 @@
 pub mod css_parser;
+pub mod media_query;
+pub mod properties;
+pub mod selector;
+pub mod stylesheet;
 
 pub use css_parser::*;
+pub use media_query::*;
+pub use properties::*;
+pub use selector::*;
+pub use stylesheet::*;
@@ -0,0 +1,618 @@
+use glam::Vec2;
+use plinth_primitives::{
+    Animation, AnimationDirection, BoxShadow, ColorStop, ConicGradient, CssColor, Display, FillMode, GradientDirection,
+    LinearGradient, Paint, RadialGradient, RadialShape, RadialSize, StepPosition, Style, TimingFunction, Transform,
+    Transition, Visibility,
+};
+
+use crate::parser::{is_identifier, split_top_level, CssParser, CssParseError};
+
+// Parsers for the non-color properties of the style model. Lengths are `px` (or a bare 0)
+// since primitives have no font or viewport to resolve relative units against; the number
+// is kept as is, see Style for the unit each primitive reads it in.
+impl CssParser {
+    // Applies one property to `style`. Returns false for properties the style model doesn't know.
+    pub fn apply_property(style: &mut Style, property: &str, value: &str) -> Result<bool, CssParseError> {
+        let invalid = || CssParseError::InvalidValue(property.to_string(), value.to_string());
+        if property.starts_with("--") {
+            style.custom_properties.insert(property.to_string(), value.to_string());
+            return Ok(true);
+        }
+        match property {
+            "color" => style.color = Some(Self::parse_color_from_css(value)?),
+            "background-color" => style.background_color = Some(Self::parse_css_color(value)?),
+            "background-image" => style.background_image = Some(parse_gradient(value).ok_or_else(invalid)?),
+            "background" => {
+                let (color, image) = parse_background(value).ok_or_else(invalid)?;
+                if color.is_some() {
+                    style.background_color = color;
+                }
+                if image.is_some() {
+                    style.background_image = image;
+                }
+            }
+            "border-color" => style.border_color = Some(Self::parse_css_color(value)?),
+            "opacity" => style.opacity = Some(parse_opacity(value).ok_or_else(invalid)?),
+            "border-width" => style.border_width = Some(parse_length(value).filter(|w| *w >= 0.0).ok_or_else(invalid)?),
+            "border-radius" => style.border_radius = Some(parse_length(value).filter(|r| *r >= 0.0).ok_or_else(invalid)?),
+            "width" => style.width = Some(parse_length(value).filter(|w| *w >= 0.0).ok_or_else(invalid)?),
+            "height" => style.height = Some(parse_length(value).filter(|h| *h >= 0.0).ok_or_else(invalid)?),
+            "transform" => style.transform = Some(parse_transform(value).ok_or_else(invalid)?),
+            "z-index" => style.z_index = Some(parse_z_index(value).ok_or_else(invalid)?),
+            "visibility" => style.visibility = Some(parse_visibility(value).ok_or_else(invalid)?),
+            "display" => style.display = Some(parse_display(value)),
+            "box-shadow" => style.box_shadow = Some(parse_box_shadows(value).ok_or_else(invalid)?),
+            "filter" => style.filter_blur = Some(parse_filter_blur(value).ok_or_else(invalid)?),
+            "backdrop-filter" => style.backdrop_blur = Some(parse_filter_blur(value).ok_or_else(invalid)?),
+            "font-size" => style.font_size = Some(parse_font_size(value).ok_or_else(invalid)?),
+            "font-family" => style.font_family = Some(parse_font_family(value).ok_or_else(invalid)?),
+            "transition" => style.transitions = Some(parse_transitions(value).ok_or_else(invalid)?),
+            "animation" => style.animations = Some(parse_animations(value).ok_or_else(invalid)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+// Space-separated components of a value, keeping function arguments together
+pub(crate) fn components(value: &str) -> Vec<String> {
+    let normalized = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    split_top_level(&normalized, ' ')
+        .into_iter()
+        .filter(|component| !component.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// `name(args)` split into its lowercased name and argument text
+pub(crate) fn function(component: &str) -> Option<(String, &str)> {
+    let open = component.find('(')?;
+    let args = component[open + 1..].strip_suffix(')')?;
+    Some((component[..open].trim().to_ascii_lowercase(), args))
+}
+
+fn number(value: &str) -> Option<f32> {
+    value.trim().parse::<f32>().ok().filter(|v| v.is_finite())
+}
+
+pub fn parse_length(value: &str) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    match value.strip_suffix("px") {
+        Some(px) => number(px),
+        None => number(&value).filter(|v| *v == 0.0),
+    }
+}
+
+// Angle in radians, from deg, rad, grad or turn
+pub fn parse_angle(value: &str) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    for (unit, to_radians) in [
+        ("deg", std::f32::consts::PI / 180.0),
+        ("grad", std::f32::consts::PI / 200.0),
+        ("rad", 1.0),
+        ("turn", std::f32::consts::TAU),
+    ] {
+        if let Some(amount) = value.strip_suffix(unit) {
+            return number(amount).map(|v| v * to_radians);
+        }
+    }
+    number(&value).filter(|v| *v == 0.0)
+}
+
+// A number or percentage, clamped to [0, 1]
+pub fn parse_opacity(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let opacity = match value.strip_suffix('%') {
+        Some(percent) => number(percent)? / 100.0,
+        None => number(value)?,
+    };
+    Some(opacity.clamp(0.0, 1.0))
+}
+
+// `auto` maps to 0, the stacking order primitives get by default
+pub fn parse_z_index(value: &str) -> Option<i32> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("auto") {
+        return Some(0);
+    }
+    value.parse().ok()
+}
+
+pub fn parse_visibility(value: &str) -> Option<Visibility> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "visible" => Some(Visibility::Visible),
+        "hidden" => Some(Visibility::Hidden),
+        "collapse" => Some(Visibility::Collapse),
+        _ => None,
+    }
+}
+
+// Every value other than `none` just means "rendered" for a primitive
+pub fn parse_display(value: &str) -> Display {
+    if value.trim().eq_ignore_ascii_case("none") {
+        Display::None
+    } else {
+        Display::Shown
+    }
+}
+
+// translate/scale/rotate/matrix functions folded into one Transform. Transform has no order,
+// so translations and rotations add up and scales multiply; a list that relies on order
+// (e.g. rotate then translate) is approximated. matrix() is what getComputedStyle returns.
+pub fn parse_transform(value: &str) -> Option<Transform> {
+    let mut transform = Transform::identity();
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(transform);
+    }
+    for component in components(value) {
+        let (name, args) = function(&component)?;
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        match (name.as_str(), args.as_slice()) {
+            ("translate", [x]) => transform.position.x += parse_length(x)?,
+            ("translate", [x, y]) => transform.position += Vec2::new(parse_length(x)?, parse_length(y)?),
+            ("translatex", [x]) => transform.position.x += parse_length(x)?,
+            ("translatey", [y]) => transform.position.y += parse_length(y)?,
+            ("scale", [s]) => transform.scale *= parse_scale(s)?,
+            ("scale", [x, y]) => transform.scale *= Vec2::new(parse_scale(x)?, parse_scale(y)?),
+            ("scalex", [x]) => transform.scale.x *= parse_scale(x)?,
+            ("scaley", [y]) => transform.scale.y *= parse_scale(y)?,
+            ("rotate", [angle]) => transform.rotation += parse_angle(angle)?,
+            ("matrix", [a, b, c, d, e, f]) => {
+                let [a, b, c, d, e, f] = [a, b, c, d, e, f].map(|v| number(v));
+                let (a, b, c, d, e, f) = (a?, b?, c?, d?, e?, f?);
+                // Decompose into rotation, then non-uniform scale; skew is dropped
+                let scale_x = (a * a + b * b).sqrt();
+                let scale_y = if scale_x > 0.0 { (a * d - b * c) / scale_x } else { (c * c + d * d).sqrt() };
+                transform.position += Vec2::new(e, f);
+                transform.scale *= Vec2::new(scale_x, scale_y);
+                transform.rotation += b.atan2(a);
+            }
+            _ => return None,
+        }
+    }
+    Some(transform)
+}
+
+fn parse_scale(value: &str) -> Option<f32> {
+    match value.strip_suffix('%') {
+        Some(percent) => number(percent).map(|v| v / 100.0),
+        None => number(value),
+    }
+}
+
+// Comma-separated `[inset] <x> <y> [<blur> [<spread>]] [<color>]` in any order. `none` is
+// an empty list.
+pub fn parse_box_shadows(value: &str) -> Option<Vec<BoxShadow>> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(Vec::new());
+    }
+    split_top_level(value, ',').into_iter().map(parse_box_shadow).collect()
+}
+
+fn parse_box_shadow(value: &str) -> Option<BoxShadow> {
+    let mut lengths = Vec::new();
+    let mut color = None;
+    let mut inset = false;
+    for component in components(value) {
+        if component.eq_ignore_ascii_case("inset") && !inset {
+            inset = true;
+        } else if let Some(length) = parse_length(&component) {
+            lengths.push(length);
+        } else if color.is_none() {
+            color = Some(CssColor::parse(&component).ok()?);
+        } else {
+            return None;
+        }
+    }
+    let (offset, blur, spread) = match lengths.as_slice() {
+        [x, y] => (Vec2::new(*x, *y), 0.0, 0.0),
+        [x, y, blur] => (Vec2::new(*x, *y), *blur, 0.0),
+        [x, y, blur, spread] => (Vec2::new(*x, *y), *blur, *spread),
+        _ => return None,
+    };
+    if blur < 0.0 {
+        return None;
+    }
+    Some(BoxShadow {
+        offset,
+        blur,
+        spread,
+        color: color.unwrap_or(CssColor::CurrentColor),
+        inset,
+    })
+}
+
+// The color and gradient of a `background` shorthand, e.g. `#222 linear-gradient(...)`.
+// Only one layer is supported, and images other than gradients aren't.
+pub fn parse_background(value: &str) -> Option<(Option<CssColor>, Option<Paint>)> {
+    let mut color = None;
+    let mut image = None;
+    for component in components(value) {
+        let is_gradient = function(&component).is_some_and(|(name, _)| name.ends_with("-gradient"));
+        if is_gradient && image.is_none() {
+            image = Some(parse_gradient(&component)?);
+        } else if color.is_none() && !is_gradient {
+            color = Some(CssParser::parse_css_color(&component).ok()?);
+        } else {
+            return None;
+        }
+    }
+    (color.is_some() || image.is_some()).then_some((color, image))
+}
+
+// `linear-gradient()`, `radial-gradient()` or `conic-gradient()` with color stops at
+// optional percentages (angles too for conic). Repeating gradients, stop positions in
+// lengths and interpolation hints aren't supported.
+pub fn parse_gradient(value: &str) -> Option<Paint> {
+    let (name, args) = function(value.trim())?;
+    let args: Vec<&str> = split_top_level(args, ',').into_iter().map(str::trim).collect();
+    let (first, rest) = args.split_first()?;
+    match name.as_str() {
+        "linear-gradient" => {
+            let (direction, stops) = match parse_linear_direction(first) {
+                Some(direction) => (direction, rest),
+                None => (GradientDirection::default(), args.as_slice()),
+            };
+            Some(Paint::Linear(LinearGradient {
+                direction,
+                stops: parse_color_stops(stops, parse_percentage)?,
+            }))
+        }
+        "radial-gradient" => {
+            let (mut gradient, stops) = match parse_radial_shape(first) {
+                Some(gradient) => (gradient, rest),
+                None => (RadialGradient::default(), args.as_slice()),
+            };
+            gradient.stops = parse_color_stops(stops, parse_percentage)?;
+            Some(Paint::Radial(gradient))
+        }
+        "conic-gradient" => {
+            let (mut gradient, stops) = match parse_conic_start(first) {
+                Some(gradient) => (gradient, rest),
+                None => (ConicGradient::default(), args.as_slice()),
+            };
+            let offset = |value: &str| parse_percentage(value).or_else(|| Some(parse_angle(value)? / std::f32::consts::TAU));
+            gradient.stops = parse_color_stops(stops, offset)?;
+            Some(Paint::Conic(gradient))
+        }
+        _ => None,
+    }
+}
+
+// `<angle>` or `to <side> [<side>]`
+fn parse_linear_direction(value: &str) -> Option<GradientDirection> {
+    if let Some(angle) = parse_angle(value) {
+        return Some(GradientDirection::Angle(angle));
+    }
+    let components = components(value);
+    let (to, sides) = components.split_first()?;
+    if !to.eq_ignore_ascii_case("to") {
+        return None;
+    }
+    let (mut horizontal, mut vertical) = (None, None);
+    for side in sides {
+        match side.to_ascii_lowercase().as_str() {
+            "left" if horizontal.is_none() => horizontal = Some(false),
+            "right" if horizontal.is_none() => horizontal = Some(true),
+            "top" if vertical.is_none() => vertical = Some(true),
+            "bottom" if vertical.is_none() => vertical = Some(false),
+            _ => return None,
+        }
+    }
+    let quarter = std::f32::consts::FRAC_PI_2;
+    Some(match (horizontal, vertical) {
+        (Some(right), Some(top)) => GradientDirection::Corner { right, top },
+        (Some(right), None) => GradientDirection::Angle(if right { quarter } else { 3.0 * quarter }),
+        (None, Some(top)) => GradientDirection::Angle(if top { 0.0 } else { 2.0 * quarter }),
+        (None, None) => return None,
+    })
+}
+
+// `[circle | ellipse] [<size keyword> | <length>{1,2}] [at <position>]` in CSS order
+fn parse_radial_shape(value: &str) -> Option<RadialGradient> {
+    let components = components(value);
+    let at = components.iter().position(|c| c.eq_ignore_ascii_case("at")).unwrap_or(components.len());
+    let mut gradient = RadialGradient::default();
+    if at < components.len() {
+        gradient.center = parse_position(&components[at + 1..])?;
+    }
+    let mut shape = None;
+    let mut lengths = Vec::new();
+    for component in &components[..at] {
+        match component.to_ascii_lowercase().as_str() {
+            "circle" if shape.is_none() => shape = Some(RadialShape::Circle),
+            "ellipse" if shape.is_none() => shape = Some(RadialShape::Ellipse),
+            "closest-side" => gradient.size = RadialSize::ClosestSide,
+            "farthest-side" => gradient.size = RadialSize::FarthestSide,
+            "closest-corner" => gradient.size = RadialSize::ClosestCorner,
+            "farthest-corner" => gradient.size = RadialSize::FarthestCorner,
+            _ => lengths.push(parse_length(component).filter(|length| *length >= 0.0)?),
+        }
+    }
+    // A single length means a circle, two an ellipse
+    match (lengths.as_slice(), shape) {
+        ([], _) => {}
+        ([radius], None | Some(RadialShape::Circle)) => {
+            shape = Some(RadialShape::Circle);
+            gradient.size = RadialSize::Extent(Vec2::splat(*radius));
+        }
+        ([x, y], None | Some(RadialShape::Ellipse)) => gradient.size = RadialSize::Extent(Vec2::new(*x, *y)),
+        _ => return None,
+    }
+    gradient.shape = shape.unwrap_or_default();
+    Some(gradient)
+}
+
+// `[from <angle>] [at <position>]`
+fn parse_conic_start(value: &str) -> Option<ConicGradient> {
+    let components = components(value);
+    let mut gradient = ConicGradient::default();
+    let mut rest = components.as_slice();
+    if rest.first().is_some_and(|c| c.eq_ignore_ascii_case("from")) {
+        gradient.angle = parse_angle(rest.get(1)?)?;
+        rest = &rest[2..];
+    }
+    if rest.first().is_some_and(|c| c.eq_ignore_ascii_case("at")) {
+        gradient.center = parse_position(&rest[1..])?;
+        rest = &[];
+    }
+    (rest.is_empty() && !components.is_empty()).then_some(gradient)
+}
+
+// One or two of keywords and percentages, as fractions of the box from its top-left
+fn parse_position(components: &[String]) -> Option<Vec2> {
+    let keyword = |value: &str| match value.to_ascii_lowercase().as_str() {
+        "left" | "top" => Some(0.0),
+        "center" => Some(0.5),
+        "right" | "bottom" => Some(1.0),
+        _ => parse_percentage(value),
+    };
+    let vertical = |value: &str| value.eq_ignore_ascii_case("top") || value.eq_ignore_ascii_case("bottom");
+    let horizontal = |value: &str| value.eq_ignore_ascii_case("left") || value.eq_ignore_ascii_case("right");
+    match components {
+        [only] if vertical(only) => Some(Vec2::new(0.5, keyword(only)?)),
+        [only] => Some(Vec2::new(keyword(only)?, 0.5)),
+        [first, second] if vertical(first) || horizontal(second) => Some(Vec2::new(keyword(second)?, keyword(first)?)),
+        [first, second] => Some(Vec2::new(keyword(first)?, keyword(second)?)),
+        _ => None,
+    }
+}
+
+// `<color> [<offset> [<offset>]]` for each stop; two offsets make two stops of the same color
+fn parse_color_stops(stops: &[&str], offset: impl Fn(&str) -> Option<f32>) -> Option<Vec<ColorStop>> {
+    let mut parsed = Vec::new();
+    for stop in stops {
+        let components = components(stop);
+        let (color, offsets) = components.split_first()?;
+        let color = CssParser::parse_color_from_css(color).ok()?;
+        match offsets {
+            [] => parsed.push(ColorStop::from(color)),
+            [at] => parsed.push(ColorStop::new(color, offset(at)?)),
+            [from, to] => {
+                parsed.push(ColorStop::new(color, offset(from)?));
+                parsed.push(ColorStop::new(color, offset(to)?));
+            }
+            _ => return None,
+        }
+    }
+    (parsed.len() >= 2).then_some(parsed)
+}
+
+fn parse_percentage(value: &str) -> Option<f32> {
+    let value = value.trim();
+    match value.strip_suffix('%') {
+        Some(percent) => number(percent).map(|v| v / 100.0),
+        None => number(value).filter(|v| *v == 0.0),
+    }
+}
+
+// Blur radius of a filter list, for `filter` and `backdrop-filter`; other filter functions
+// are ignored with a warning
+pub fn parse_filter_blur(value: &str) -> Option<f32> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(0.0);
+    }
+    let mut blur = 0.0;
+    for component in components(value) {
+        let (name, args) = function(&component)?;
+        if name == "blur" {
+            blur = if args.trim().is_empty() { 0.0 } else { parse_length(args).filter(|b| *b >= 0.0)? };
+        } else {
+            log::warn!("Ignoring unsupported filter function {}()", name);
+        }
+    }
+    Some(blur)
+}
+
+// A positive length or an absolute size keyword, `medium` being 16px as in browsers
+pub fn parse_font_size(value: &str) -> Option<f32> {
+    let keyword = match value.trim().to_ascii_lowercase().as_str() {
+        "xx-small" => Some(9.0),
+        "x-small" => Some(10.0),
+        "small" => Some(13.0),
+        "medium" => Some(16.0),
+        "large" => Some(18.0),
+        "x-large" => Some(24.0),
+        "xx-large" => Some(32.0),
+        "xxx-large" => Some(48.0),
+        _ => None,
+    };
+    keyword.or_else(|| parse_length(value).filter(|size| *size > 0.0))
+}
+
+// Comma-separated family names, quoted or not. Unquoted names may span several words.
+pub fn parse_font_family(value: &str) -> Option<Vec<String>> {
+    split_top_level(value, ',')
+        .into_iter()
+        .map(|family| {
+            let family = family.trim();
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|quote| family.strip_prefix(*quote)?.strip_suffix(*quote))
+                .map(str::to_string)
+                .unwrap_or_else(|| family.split_whitespace().collect::<Vec<_>>().join(" "));
+            Some(unquoted).filter(|name| !name.is_empty())
+        })
+        .collect()
+}
+
+// Seconds from `s` or `ms`
+pub fn parse_time(value: &str) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(ms) = value.strip_suffix("ms") {
+        return number(ms).map(|v| v / 1000.0);
+    }
+    number(value.strip_suffix('s')?)
+}
+
+pub fn parse_timing_function(value: &str) -> Option<TimingFunction> {
+    let value = value.trim().to_ascii_lowercase();
+    Some(match value.as_str() {
+        "linear" => TimingFunction::Linear,
+        "ease" => TimingFunction::EASE,
+        "ease-in" => TimingFunction::EASE_IN,
+        "ease-out" => TimingFunction::EASE_OUT,
+        "ease-in-out" => TimingFunction::EASE_IN_OUT,
+        "step-start" => TimingFunction::Steps(1, StepPosition::JumpStart),
+        "step-end" => TimingFunction::Steps(1, StepPosition::JumpEnd),
+        _ => {
+            let (name, args) = function(&value)?;
+            let args: Vec<&str> = args.split(',').map(str::trim).collect();
+            match (name.as_str(), args.as_slice()) {
+                ("cubic-bezier", [x1, y1, x2, y2]) => {
+                    let (x1, y1, x2, y2) = (number(x1)?, number(y1)?, number(x2)?, number(y2)?);
+                    // x must stay in [0, 1] for the curve to be a function of time
+                    if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                        return None;
+                    }
+                    TimingFunction::CubicBezier(x1, y1, x2, y2)
+                }
+                ("steps", [steps, position @ ..]) if position.len() <= 1 => {
+                    let steps: u32 = steps.parse().ok().filter(|steps| *steps > 0)?;
+                    let position = match position.first().copied().unwrap_or("jump-end") {
+                        "jump-start" | "start" => StepPosition::JumpStart,
+                        "jump-end" | "end" => StepPosition::JumpEnd,
+                        "jump-none" if steps > 1 => StepPosition::JumpNone,
+                        "jump-both" => StepPosition::JumpBoth,
+                        _ => return None,
+                    };
+                    TimingFunction::Steps(steps, position)
+                }
+                _ => return None,
+            }
+        }
+    })
+}
+
+// Comma-separated `<property> <duration> <timing-function> <delay>`, parts in any order
+// (the first time is the duration, the second the delay). `none` is an empty list.
+pub fn parse_transitions(value: &str) -> Option<Vec<Transition>> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(Vec::new());
+    }
+    split_top_level(value, ',')
+        .into_iter()
+        .map(|transition| {
+            let mut property = None;
+            let mut times = Vec::new();
+            let mut timing = None;
+            for component in components(transition) {
+                if let Some(time) = parse_time(&component) {
+                    times.push(time);
+                } else if let Some(function) = parse_timing_function(&component).filter(|_| timing.is_none()) {
+                    timing = Some(function);
+                } else if property.is_none() && (component.starts_with("--") || is_identifier(&component)) {
+                    property = Some(if component.starts_with("--") { component } else { component.to_ascii_lowercase() });
+                } else {
+                    return None;
+                }
+            }
+            let (duration, delay) = match times.as_slice() {
+                [] => (0.0, 0.0),
+                [duration] => (*duration, 0.0),
+                [duration, delay] => (*duration, *delay),
+                _ => return None,
+            };
+            if duration < 0.0 {
+                return None;
+            }
+            Some(Transition {
+                property: property.unwrap_or_else(|| "all".to_string()),
+                duration,
+                delay,
+                timing: timing.unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+// Comma-separated `animation` shorthands: name, duration, timing function, delay, iteration
+// count, direction and fill mode in any order. `none` is an empty list.
+pub fn parse_animations(value: &str) -> Option<Vec<Animation>> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Some(Vec::new());
+    }
+    split_top_level(value, ',')
+        .into_iter()
+        .map(|animation| {
+            let mut parsed = Animation::new("", 0.0);
+            let (mut name, mut times, mut timing, mut iterations, mut direction, mut fill_mode) =
+                (None, Vec::new(), None, None, None, None);
+            for component in components(animation) {
+                let keyword = component.to_ascii_lowercase();
+                if let Some(time) = parse_time(&component) {
+                    times.push(time);
+                } else if timing.is_none() && parse_timing_function(&component).is_some() {
+                    timing = parse_timing_function(&component);
+                } else if iterations.is_none() && (keyword == "infinite" || number(&keyword).is_some()) {
+                    iterations = Some(if keyword == "infinite" { f32::INFINITY } else { number(&keyword)?.max(0.0) });
+                } else if direction.is_none() && parse_direction(&keyword).is_some() {
+                    direction = parse_direction(&keyword);
+                } else if fill_mode.is_none() && parse_fill_mode(&keyword).is_some() {
+                    fill_mode = parse_fill_mode(&keyword);
+                } else if matches!(keyword.as_str(), "running" | "paused") {
+                    log::warn!("Ignoring animation-play-state '{}'", keyword);
+                } else if name.is_none() && is_identifier(&component) {
+                    name = Some(component);
+                } else {
+                    return None;
+                }
+            }
+            match times.as_slice() {
+                [] => {}
+                [duration] => parsed.duration = *duration,
+                [duration, delay] => (parsed.duration, parsed.delay) = (*duration, *delay),
+                _ => return None,
+            }
+            if parsed.duration < 0.0 {
+                return None;
+            }
+            parsed.name = name?;
+            parsed.timing = timing.unwrap_or_default();
+            parsed.iterations = iterations.unwrap_or(1.0);
+            parsed.direction = direction.unwrap_or(AnimationDirection::Normal);
+            parsed.fill_mode = fill_mode.unwrap_or(FillMode::None);
+            Some(parsed)
+        })
+        .collect()
+}
+
+fn parse_direction(keyword: &str) -> Option<AnimationDirection> {
+    match keyword {
+        "normal" => Some(AnimationDirection::Normal),
+        "reverse" => Some(AnimationDirection::Reverse),
+        "alternate" => Some(AnimationDirection::Alternate),
+        "alternate-reverse" => Some(AnimationDirection::AlternateReverse),
+        _ => None,
+    }
+}
+
+fn parse_fill_mode(keyword: &str) -> Option<FillMode> {
+    match keyword {
+        "none" => Some(FillMode::None),
+        "forwards" => Some(FillMode::Forwards),
+        "backwards" => Some(FillMode::Backwards),
+        "both" => Some(FillMode::Both),
+        _ => None,
+    }
+}
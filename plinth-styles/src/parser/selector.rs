@@ -0,0 +1,92 @@
+use std::fmt;
+
+use plinth_primitives::InteractionState;
+
+use crate::parser::is_identifier;
+
+// A compound class selector such as `.button.primary:hover`. Primitives have no tree to
+// match combinators against, so descendant/child selectors and type or id selectors aren't
+// supported; the only pseudo-classes are the InteractionState ones. `:root` (or `html`) is
+// kept too since it's where design tokens are declared, but it matches no primitive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Selector {
+    pub classes: Vec<String>,
+    pub states: InteractionState,
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Option<Self> {
+        let selector = selector.trim();
+        if selector == ":root" || selector.eq_ignore_ascii_case("html") {
+            return Some(Self::root());
+        }
+        let rest = selector.strip_prefix('.')?;
+        let mut parts = rest.split(':');
+        let classes: Vec<String> = parts.next()?.split('.').map(str::to_string).collect();
+        if !classes.iter().all(|class| is_identifier(class)) {
+            return None;
+        }
+        let mut states = InteractionState::NONE;
+        for pseudo_class in parts {
+            states |= InteractionState::from_pseudo_class(pseudo_class)?;
+        }
+        Some(Self { classes, states })
+    }
+
+    pub fn class(name: &str) -> Self {
+        Self {
+            classes: vec![name.to_string()],
+            states: InteractionState::NONE,
+        }
+    }
+
+    pub fn root() -> Self {
+        Self {
+            classes: Vec::new(),
+            states: InteractionState::NONE,
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn with_states(mut self, states: InteractionState) -> Self {
+        self.states = states;
+        self
+    }
+
+    // The class name if this is a plain single-class selector like `.button`
+    pub fn single_class(&self) -> Option<&str> {
+        match self.classes.as_slice() {
+            [class] if self.states.is_empty() => Some(class),
+            _ => None,
+        }
+    }
+
+    // Classes plus pseudo-classes; with no ids or type selectors it is the whole specificity
+    pub fn specificity(&self) -> u32 {
+        self.classes.len() as u32 + self.states.count()
+    }
+
+    pub fn matches(&self, classes: &[&str], state: InteractionState) -> bool {
+        !self.is_root()
+            && state.contains(self.states)
+            && self.classes.iter().all(|required| classes.contains(&required.as_str()))
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return write!(f, ":root");
+        }
+        for class in &self.classes {
+            write!(f, ".{}", class)?;
+        }
+        for pseudo_class in self.states.pseudo_classes() {
+            write!(f, ":{}", pseudo_class)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use crate::parser::CssParseError;
+
+// A parsed stylesheet. Parsing follows CSS error recovery: malformed declarations and
+// rules are dropped with a warning instead of failing the whole sheet, and blocks and
+// comments still open at the end of the sheet are closed there.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stylesheet {
+    pub rules: Vec<CssRule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CssRule {
+    Style(StyleRule),
+    At(AtRule),
+}
+
+// `selector, selector { declarations }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRule {
+    pub selectors: Vec<String>,
+    pub declarations: Vec<Declaration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub property: String,
+    pub value: String,
+    pub important: bool,
+}
+
+// `@name prelude;` or `@name prelude { block }`. The block is kept as source text since
+// its grammar depends on the at-rule (rules for @media, keyframes for @keyframes...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: String,
+    pub block: Option<String>,
+}
+
+impl Stylesheet {
+    pub fn parse(css: &str) -> Result<Self, CssParseError> {
+        let css = strip_comments(css);
+        let mut rules = Vec::new();
+        let mut rest = css.as_str();
+
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            let Some((end, terminator)) = find_top_level(rest, &['{', ';', '}']) else {
+                log::warn!("Ignoring trailing CSS without a block: {}", rest.trim());
+                break;
+            };
+            let prelude = rest[..end].trim();
+            match terminator {
+                '}' => {
+                    // Stray closing brace; skip it like a browser would
+                    log::warn!("Ignoring unexpected '}}' in CSS");
+                    rest = &rest[end + 1..];
+                }
+                ';' => {
+                    if let Some(at) = prelude.strip_prefix('@') {
+                        rules.push(CssRule::At(at_rule(at, None)));
+                    } else {
+                        log::warn!("Ignoring CSS statement without a block: {}", prelude);
+                    }
+                    rest = &rest[end + 1..];
+                }
+                _ => {
+                    let block_start = end + 1;
+                    let block_end = matching_brace(rest, end).unwrap_or_else(|| {
+                        log::warn!("Closing CSS block after '{}' at the end of the sheet", prelude);
+                        rest.len()
+                    });
+                    let block = &rest[block_start..block_end];
+                    if let Some(at) = prelude.strip_prefix('@') {
+                        rules.push(CssRule::At(at_rule(at, Some(block))));
+                    } else if prelude.is_empty() {
+                        log::warn!("Ignoring CSS block without a selector");
+                    } else {
+                        rules.push(CssRule::Style(StyleRule {
+                            selectors: split_top_level(prelude, ',')
+                                .into_iter()
+                                .map(|selector| selector.split_whitespace().collect::<Vec<_>>().join(" "))
+                                .filter(|selector| !selector.is_empty())
+                                .collect(),
+                            declarations: parse_declarations(block),
+                        }));
+                    }
+                    rest = rest.get(block_end + 1..).unwrap_or("");
+                }
+            }
+        }
+
+        Ok(Self { rules })
+    }
+
+    pub fn style_rules(&self) -> impl Iterator<Item = &StyleRule> {
+        self.rules.iter().filter_map(|rule| match rule {
+            CssRule::Style(rule) => Some(rule),
+            CssRule::At(_) => None,
+        })
+    }
+
+    pub fn at_rules<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a AtRule> {
+        self.rules.iter().filter_map(move |rule| match rule {
+            CssRule::At(rule) if rule.name.eq_ignore_ascii_case(name) => Some(rule),
+            _ => None,
+        })
+    }
+}
+
+impl StyleRule {
+    // Class name of a selector that is exactly one class, e.g. `.button`
+    pub fn class_name(selector: &str) -> Option<&str> {
+        let name = selector.strip_prefix('.')?;
+        is_identifier(name).then_some(name)
+    }
+}
+
+// Parses the inside of a `{ }` block as `property: value [!important]` pairs
+pub fn parse_declarations(block: &str) -> Vec<Declaration> {
+    split_top_level(block, ';')
+        .into_iter()
+        .filter(|declaration| !declaration.trim().is_empty())
+        .filter_map(|declaration| {
+            let parsed = parse_declaration(declaration);
+            if parsed.is_none() {
+                log::warn!("Ignoring malformed CSS declaration: {}", declaration.trim());
+            }
+            parsed
+        })
+        .collect()
+}
+
+fn parse_declaration(declaration: &str) -> Option<Declaration> {
+    let (property, value) = declaration.split_once(':')?;
+    let property = property.trim();
+    if !is_identifier(property) && !property.starts_with("--") {
+        return None;
+    }
+    let mut value = value.trim();
+    let mut important = false;
+    if let Some(index) = value.rfind('!') {
+        if value[index + 1..].trim().eq_ignore_ascii_case("important") {
+            important = true;
+            value = value[..index].trim_end();
+        }
+    }
+    // Custom properties keep their case and may be empty; everything else is case-insensitive
+    let property = if property.starts_with("--") {
+        property.to_string()
+    } else {
+        property.to_ascii_lowercase()
+    };
+    if value.is_empty() && !property.starts_with("--") {
+        return None;
+    }
+    Some(Declaration {
+        property,
+        value: value.to_string(),
+        important,
+    })
+}
+
+fn at_rule(at: &str, block: Option<&str>) -> AtRule {
+    let (name, prelude) = at.split_once(char::is_whitespace).unwrap_or((at, ""));
+    AtRule {
+        name: name.to_ascii_lowercase(),
+        prelude: prelude.trim().to_string(),
+        block: block.map(str::to_string),
+    }
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '-' || !c.is_ascii() => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-' || !c.is_ascii())
+}
+
+// A comment still open at the end of `css` runs to the end
+fn strip_comments(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = next_outside_strings(rest, "/*") {
+        output.push_str(&rest[..start]);
+        // A comment separates tokens like whitespace does
+        output.push(' ');
+        let Some(end) = rest[start + 2..].find("*/") else {
+            log::warn!("Closing CSS comment at the end of the sheet");
+            return output;
+        };
+        rest = &rest[start + 2 + end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+// Walks `text` outside of quoted strings, calling `visit` with each byte index and char
+// together with the current (), [] nesting depth. Stops when `visit` returns true.
+fn scan(text: &str, mut visit: impl FnMut(usize, char, usize) -> bool) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if escaped {
+            // Escaped characters never have structural meaning
+            escaped = false;
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '\\' => escaped = true,
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if visit(index, c, depth) {
+            return Some(index);
+        }
+    }
+    None
+}
+
+fn next_outside_strings(text: &str, pattern: &str) -> Option<usize> {
+    scan(text, |index, _, _| text[index..].starts_with(pattern))
+}
+
+fn find_top_level(text: &str, targets: &[char]) -> Option<(usize, char)> {
+    let mut found = None;
+    scan(text, |index, c, depth| {
+        if depth == 0 && targets.contains(&c) {
+            found = Some((index, c));
+            true
+        } else {
+            false
+        }
+    });
+    found
+}
+
+// Index of the `}` closing the `{` at `open`
+fn matching_brace(text: &str, open: usize) -> Option<usize> {
+    let mut braces = 0usize;
+    scan(&text[open..], |_, c, _| {
+        match c {
+            '{' => braces += 1,
+            '}' => braces -= 1,
+            _ => {}
+        }
+        braces == 0
+    })
+    .map(|index| open + index)
+}
+
+pub(crate) fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut braces = 0usize;
+    scan(text, |index, c, depth| {
+        match c {
+            '{' => braces += 1,
+            '}' => braces = braces.saturating_sub(1),
+            _ if c == separator && depth == 0 && braces == 0 => {
+                parts.push(&text[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+        false
+    });
+    parts.push(&text[start..]);
+    parts
+}
@@ -1,35 +1,41 @@
-use plinth_primitives::Color;
+use plinth_primitives::{Color, CssColor, Style};
 
 #[derive(Debug, Clone)]
 pub struct CssClass {
     pub name: String,
-    pub color: Option<Color>,
-    pub background_color: Option<Color>,
-    pub border_color: Option<Color>,
+    pub style: Style,
 }
 
 impl CssClass {
     pub fn new(name: String) -> Self {
         Self {
             name,
-            color: None,
-            background_color: None,
-            border_color: None,
+            style: Style::default(),
         }
     }
 
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
     pub fn with_color(mut self, color: Color) -> Self {
-        self.color = Some(color);
+        self.style.color = Some(color);
         self
     }
 
     pub fn with_background_color(mut self, color: Color) -> Self {
-        self.background_color = Some(color);
+        self.style.background_color = Some(CssColor::Color(color));
         self
     }
 
     pub fn with_border_color(mut self, color: Color) -> Self {
-        self.border_color = Some(color);
+        self.style.border_color = Some(CssColor::Color(color));
         self
     }
+
+    // Copies every property set on `other` over this class
+    pub fn merge(&mut self, other: &CssClass) {
+        self.style.merge(&other.style);
+    }
 }
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use plinth_primitives::Color;
+
+use crate::mapping::StyleError;
+use crate::parser::{CssParser, CssRule, Selector, Stylesheet};
+
+// Nesting limit for var() references, which also stops reference cycles
+const MAX_VAR_DEPTH: usize = 32;
+
+// CSS custom properties (`--name: value`) shared by the page and the canvas, e.g. the
+// `:root` block of a theme. Names always include the leading `--`; values are kept as
+// written and may reference other tokens with var().
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesignTokens {
+    tokens: BTreeMap<String, String>,
+}
+
+impl DesignTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    // `name` may be given with or without the leading `--`
+    pub fn insert(&mut self, name: &str, value: impl Into<String>) {
+        self.tokens.insert(property_name(name), value.into().trim().to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.tokens.remove(&property_name(name))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tokens.get(&property_name(name)).map(String::as_str)
+    }
+
+    // The token's value with var() references resolved, parsed as a color
+    pub fn color(&self, name: &str) -> Option<Color> {
+        let value = self.resolve(self.get(name)?)?;
+        CssParser::parse_color_from_css(&value).ok()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tokens.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    // Adds every token of `other`, replacing tokens with the same name
+    pub fn merge(&mut self, other: &DesignTokens) {
+        self.tokens.extend(other.tokens.iter().map(|(name, value)| (name.clone(), value.clone())));
+    }
+
+    // Substitutes every `var(--name, fallback)` in `value`. Returns None when a referenced
+    // token is missing and has no fallback, or references loop, in which case CSS treats the
+    // declaration as invalid.
+    pub fn resolve(&self, value: &str) -> Option<String> {
+        self.resolve_depth(value, 0)
+    }
+
+    fn resolve_depth(&self, value: &str, depth: usize) -> Option<String> {
+        if depth > MAX_VAR_DEPTH {
+            return None;
+        }
+        let mut resolved = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = find_var(rest) {
+            resolved.push_str(&rest[..start]);
+            let arguments_start = start + "var(".len();
+            let arguments_end = arguments_start + closing_paren(&rest[arguments_start..])?;
+            let arguments = &rest[arguments_start..arguments_end];
+            let (name, fallback) = match arguments.split_once(',') {
+                Some((name, fallback)) => (name.trim(), Some(fallback.trim())),
+                None => (arguments.trim(), None),
+            };
+            let substitution = match (self.tokens.get(name), fallback) {
+                (Some(token), _) => self.resolve_depth(token, depth + 1)?,
+                (None, Some(fallback)) => self.resolve_depth(fallback, depth + 1)?,
+                (None, None) => return None,
+            };
+            resolved.push_str(&substitution);
+            rest = &rest[arguments_end + 1..];
+        }
+        resolved.push_str(rest);
+        Some(resolved)
+    }
+
+    // Tokens declared in `:root`/`html` rules outside of @media blocks, later ones winning
+    pub fn from_css(css: &str) -> Result<Self, StyleError> {
+        let stylesheet = Stylesheet::parse(css).map_err(|e| StyleError::CssParseError(e.to_string()))?;
+        let mut tokens = Self::new();
+        for rule in &stylesheet.rules {
+            let CssRule::Style(rule) = rule else {
+                continue;
+            };
+            if !rule.selectors.iter().any(|selector| Selector::parse(selector).is_some_and(|s| s.is_root())) {
+                continue;
+            }
+            for declaration in rule.declarations.iter().filter(|d| d.property.starts_with("--")) {
+                tokens.insert(&declaration.property, declaration.value.as_str());
+            }
+        }
+        Ok(tokens)
+    }
+
+    // One rule declaring every token, e.g. `:root { --primary: #3366ff; }`
+    pub fn to_css(&self, selector: &str) -> String {
+        let mut css = format!("{} {{\n", selector);
+        for (name, value) in &self.tokens {
+            let _ = writeln!(css, "  {}: {};", name, value);
+        }
+        css.push_str("}\n");
+        css
+    }
+
+    // Accepts a flat object (`{"--primary": "#3366ff"}`) or nested groups in the design
+    // tokens format (`{"color": {"primary": {"$value": "#3366ff"}}}` becomes
+    // `--color-primary`). `{color.primary}` aliases become var(--color-primary).
+    pub fn from_json(json: &str) -> Result<Self, StyleError> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| StyleError::InvalidTokens(e.to_string()))?;
+        let serde_json::Value::Object(object) = value else {
+            return Err(StyleError::InvalidTokens("expected an object of tokens".to_string()));
+        };
+        let mut tokens = Self::new();
+        flatten_json(&object, "", &mut tokens)?;
+        Ok(tokens)
+    }
+
+    // Flat object of `--name` to value, which `from_json` reads back
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.tokens).unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_json_file(path: impl AsRef<std::path::Path>) -> Result<Self, StyleError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| StyleError::Io(path.display().to_string(), e.to_string()))?;
+        Self::from_json(&json)
+    }
+
+    // Writes the tokens into a `<style id="{element_id}">` in the document head, creating it
+    // the first time, so page CSS using var() picks them up
+    #[cfg(feature = "web")]
+    pub fn inject_into_document(&self, element_id: &str) -> Result<(), StyleError> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or(StyleError::DomAccessFailed)?;
+        let element = match document.get_element_by_id(element_id) {
+            Some(element) => element,
+            None => {
+                let element = document.create_element("style").map_err(|_| StyleError::DomAccessFailed)?;
+                element.set_id(element_id);
+                let head = document.head().ok_or(StyleError::DomAccessFailed)?;
+                head.append_child(&element).map_err(|_| StyleError::DomAccessFailed)?;
+                element
+            }
+        };
+        element.set_text_content(Some(&self.to_css(":root")));
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DesignTokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_css(":root"))
+    }
+}
+
+impl<N: AsRef<str>, V: Into<String>> FromIterator<(N, V)> for DesignTokens {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        let mut tokens = Self::new();
+        for (name, value) in iter {
+            tokens.insert(name.as_ref(), value);
+        }
+        tokens
+    }
+}
+
+fn property_name(name: &str) -> String {
+    let name = name.trim();
+    if name.starts_with("--") {
+        name.to_string()
+    } else {
+        format!("--{}", name)
+    }
+}
+
+fn find_var(value: &str) -> Option<usize> {
+    let lowercase = value.to_ascii_lowercase();
+    let mut offset = 0;
+    while let Some(index) = lowercase[offset..].find("var(") {
+        let start = offset + index;
+        // Not the tail of another function name such as `somevar(`
+        let preceded_by_name = lowercase[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !preceded_by_name {
+            return Some(start);
+        }
+        offset = start + "var(".len();
+    }
+    None
+}
+
+// Index of the `)` closing a group whose `(` was just before `value`
+fn closing_paren(value: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn flatten_json(
+    object: &serde_json::Map<String, serde_json::Value>,
+    prefix: &str,
+    tokens: &mut DesignTokens,
+) -> Result<(), StyleError> {
+    for (key, value) in object {
+        // $type, $description and other metadata
+        if key.starts_with('$') {
+            continue;
+        }
+        let name = if prefix.is_empty() {
+            key.trim_start_matches("--").to_string()
+        } else {
+            format!("{}-{}", prefix, key)
+        };
+        match value {
+            serde_json::Value::Object(group) => match group.get("$value") {
+                Some(value) => tokens.insert(&name, json_value(&name, value)?),
+                None => flatten_json(group, &name, tokens)?,
+            },
+            value => tokens.insert(&name, json_value(&name, value)?),
+        }
+    }
+    Ok(())
+}
+
+fn json_value(name: &str, value: &serde_json::Value) -> Result<String, StyleError> {
+    match value {
+        serde_json::Value::String(value) => Ok(expand_aliases(value)),
+        serde_json::Value::Number(value) => Ok(value.to_string()),
+        serde_json::Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(StyleError::InvalidTokens(format!("--{} must be a string or number", name))),
+    }
+}
+
+// `{color.primary}` -> `var(--color-primary)`
+fn expand_aliases(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        expanded.push_str(&rest[..start]);
+        let path = rest[start + 1..end].trim();
+        let _ = write!(expanded, "var(--{})", path.replace('.', "-"));
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
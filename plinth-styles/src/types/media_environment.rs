@@ -0,0 +1,65 @@
+use plinth_core::context::{Context, Theme};
+use plinth_primitives::StyleViewport;
+
+// What @media rules are evaluated against: the surface, not the window or screen, since
+// that's what primitives are drawn into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MediaEnvironment {
+    // Surface size in CSS pixels (physical pixels over `resolution`)
+    pub width: f32,
+    pub height: f32,
+    // Device pixels per CSS pixel, i.e. the scale factor
+    pub resolution: f32,
+    pub theme: Theme,
+    pub reduced_motion: bool,
+}
+
+impl MediaEnvironment {
+    // `width` and `height` in CSS pixels
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            resolution: 1.0,
+            theme: Theme::Light,
+            reduced_motion: false,
+        }
+    }
+
+    // The current surface size, scale factor, theme and motion preference
+    pub fn from_context(ctx: &Context) -> Self {
+        let (width, height) = ctx.size();
+        let resolution = ctx.scale_factor().max(f64::EPSILON) as f32;
+        Self::new(width as f32 / resolution, height as f32 / resolution)
+            .with_resolution(resolution)
+            .with_theme(ctx.theme())
+            .with_reduced_motion(ctx.prefers_reduced_motion())
+    }
+
+    // The surface CSS lengths in styles are converted against when applied to primitives
+    pub fn viewport(&self) -> StyleViewport {
+        let physical = |css: f32| (css * self.resolution).round() as u32;
+        StyleViewport::new(physical(self.width), physical(self.height)).with_scale_factor(self.resolution)
+    }
+
+    pub fn with_resolution(mut self, resolution: f32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn with_reduced_motion(mut self, reduced_motion: bool) -> Self {
+        self.reduced_motion = reduced_motion;
+        self
+    }
+}
+
+impl Default for MediaEnvironment {
+    fn default() -> Self {
+        Self::new(0.0, 0.0)
+    }
+}
@@ -1,3 +1,9 @@
 pub mod css_class;
+pub mod design_tokens;
+pub mod media_environment;
+pub mod style_property;
 
 pub use css_class::*;
+pub use design_tokens::*;
+pub use media_environment::*;
+pub use style_property::*;
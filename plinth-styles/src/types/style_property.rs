@@ -0,0 +1,94 @@
+use std::fmt;
+
+// A property of the style model (plinth_primitives::Style). Used to say where a watched
+// or resolved CSS value ends up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StyleProperty {
+    Color,
+    BackgroundColor,
+    BackgroundImage,
+    BorderColor,
+    Opacity,
+    BorderWidth,
+    BorderRadius,
+    Width,
+    Height,
+    Transform,
+    ZIndex,
+    Visibility,
+    Display,
+    BoxShadow,
+    FilterBlur,
+    BackdropBlur,
+    FontSize,
+    FontFamily,
+    Transition,
+    Animation,
+    // A `--name` custom property, name including the dashes
+    Custom(String),
+}
+
+impl StyleProperty {
+    // The style property a CSS property name maps to, if the style model has one
+    pub fn from_css_name(name: &str) -> Option<Self> {
+        if name.starts_with("--") {
+            return Some(Self::Custom(name.to_string()));
+        }
+        Some(match name.to_ascii_lowercase().as_str() {
+            "color" => Self::Color,
+            "background-color" | "background" => Self::BackgroundColor,
+            "background-image" => Self::BackgroundImage,
+            "border-color" => Self::BorderColor,
+            "opacity" => Self::Opacity,
+            "border-width" => Self::BorderWidth,
+            "border-radius" => Self::BorderRadius,
+            "width" => Self::Width,
+            "height" => Self::Height,
+            "transform" => Self::Transform,
+            "z-index" => Self::ZIndex,
+            "visibility" => Self::Visibility,
+            "display" => Self::Display,
+            "box-shadow" => Self::BoxShadow,
+            "filter" => Self::FilterBlur,
+            "backdrop-filter" => Self::BackdropBlur,
+            "font-size" => Self::FontSize,
+            "font-family" => Self::FontFamily,
+            "transition" => Self::Transition,
+            "animation" => Self::Animation,
+            _ => return None,
+        })
+    }
+
+    // The CSS property whose value syntax this target takes
+    pub fn css_name(&self) -> &str {
+        match self {
+            Self::Color => "color",
+            Self::BackgroundColor => "background-color",
+            Self::BackgroundImage => "background-image",
+            Self::BorderColor => "border-color",
+            Self::Opacity => "opacity",
+            Self::BorderWidth => "border-width",
+            Self::BorderRadius => "border-radius",
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::Transform => "transform",
+            Self::ZIndex => "z-index",
+            Self::Visibility => "visibility",
+            Self::Display => "display",
+            Self::BoxShadow => "box-shadow",
+            Self::FilterBlur => "filter",
+            Self::BackdropBlur => "backdrop-filter",
+            Self::FontSize => "font-size",
+            Self::FontFamily => "font-family",
+            Self::Transition => "transition",
+            Self::Animation => "animation",
+            Self::Custom(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for StyleProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.css_name())
+    }
+}
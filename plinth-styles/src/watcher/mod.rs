@@ -0,0 +1,32 @@
+use crate::types::StyleProperty;
+
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+mod native;
+#[cfg(feature = "web")]
+mod web;
+
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+pub use native::CssWatcher;
+#[cfg(feature = "web")]
+pub use web::{CssWatcher, WatcherMetrics};
+
+// A CSS property read from elements carrying `class_name`, stored in the class's style
+// under `target`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchedProperty {
+    pub class_name: String,
+    pub property: String,
+    pub target: StyleProperty,
+}
+
+// A property of a class whose value changed and was written to the ClassMapper. On native,
+// `class_name` is the rule's class list (e.g. "button primary" for `.button.primary:hover`)
+// and `value` is empty when the declaration was removed. Design tokens changed in `:root`
+// have an empty `class_name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleChange {
+    pub class_name: String,
+    pub property: String,
+    pub target: StyleProperty,
+    pub value: String,
+}
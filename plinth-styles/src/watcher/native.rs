@@ -0,0 +1,277 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::mapping::{ClassMapper, StyleError};
+use crate::parser::{CssRule, MediaQueryList, Selector, Stylesheet};
+use crate::types::StyleProperty;
+use crate::watcher::StyleChange;
+
+type ChangeCallback = Rc<dyn Fn(&[StyleChange])>;
+
+// Reloads .css files into a ClassMapper when they change on disk, for tweaking a theme while
+// the app runs. Events arrive on a background thread; call `poll` once per frame (e.g. from
+// `before_render`) to apply them and fire the callback. Parent directories are watched so
+// editors that save by renaming a temp file are picked up too.
+pub struct CssWatcher {
+    class_mapper: Rc<RefCell<ClassMapper>>,
+    watcher: Option<RecommendedWatcher>,
+    events: Option<Receiver<notify::Result<notify::Event>>>,
+    // Canonical path -> file as registered
+    files: HashMap<PathBuf, WatchedFile>,
+    directories: HashSet<PathBuf>,
+    callback: Option<ChangeCallback>,
+}
+
+struct WatchedFile {
+    path: PathBuf,
+    // Last successfully loaded contents
+    css: String,
+}
+
+impl CssWatcher {
+    pub fn new(class_mapper: Rc<RefCell<ClassMapper>>) -> Self {
+        Self {
+            class_mapper,
+            watcher: None,
+            events: None,
+            files: HashMap::new(),
+            directories: HashSet::new(),
+            callback: None,
+        }
+    }
+
+    // Loads `path` into the mapper and reloads it whenever it changes once the watcher is
+    // started. Returns how many rules were loaded.
+    pub fn watch_file(&mut self, path: impl AsRef<Path>) -> Result<usize, StyleError> {
+        let path = path.as_ref();
+        let css = std::fs::read_to_string(path).map_err(|e| StyleError::Io(path.display().to_string(), e.to_string()))?;
+        let count = self.class_mapper.borrow_mut().reload_css(&source_name(path), &css)?;
+        self.files.insert(
+            normalize(path),
+            WatchedFile {
+                path: path.to_path_buf(),
+                css,
+            },
+        );
+        if self.watcher.is_some() {
+            self.watch_directory(path)?;
+        }
+        Ok(count)
+    }
+
+    pub fn watched_files(&self) -> impl Iterator<Item = &Path> {
+        self.files.values().map(|file| file.path.as_path())
+    }
+
+    // Called with every class property that changed in a reloaded file
+    pub fn set_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&[StyleChange]) + 'static,
+    {
+        self.callback = Some(Rc::new(callback));
+    }
+
+    pub fn start(&mut self) -> Result<(), StyleError> {
+        if self.watcher.is_some() {
+            return Ok(());
+        }
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })
+        .map_err(|e| StyleError::Watch("stylesheets".to_string(), e.to_string()))?;
+        self.watcher = Some(watcher);
+        self.events = Some(events);
+
+        let paths: Vec<PathBuf> = self.files.values().map(|file| file.path.clone()).collect();
+        for path in paths {
+            self.watch_directory(&path)?;
+        }
+        log::info!("CSS Watcher: watching {} stylesheet(s)", self.files.len());
+        Ok(())
+    }
+
+    // Reloads every watched file that changed since the last call and returns what changed.
+    // A file that fails to read or parse keeps its previous rules.
+    pub fn poll(&mut self) -> Vec<StyleChange> {
+        let Some(events) = &self.events else {
+            return Vec::new();
+        };
+        let mut changed_files: Vec<PathBuf> = Vec::new();
+        for event in events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::error!("CSS Watcher error: {}", e);
+                    continue;
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+            for path in event.paths {
+                let canonical = normalize(&path);
+                if self.files.contains_key(&canonical) && !changed_files.contains(&canonical) {
+                    changed_files.push(canonical);
+                }
+            }
+        }
+
+        let mut changes = Vec::new();
+        for canonical in changed_files {
+            let Some(file) = self.files.get_mut(&canonical) else {
+                continue;
+            };
+            let css = match std::fs::read_to_string(&file.path) {
+                Ok(css) if css != file.css => css,
+                Ok(_) => continue,
+                Err(e) => {
+                    log::error!("CSS Watcher: failed to read {}: {}", file.path.display(), e);
+                    continue;
+                }
+            };
+            let stylesheet = match Stylesheet::parse(&css) {
+                Ok(stylesheet) => stylesheet,
+                Err(e) => {
+                    log::error!("CSS Watcher: keeping previous {}: {}", file.path.display(), e);
+                    continue;
+                }
+            };
+            let previous = Stylesheet::parse(&file.css).unwrap_or_default();
+            self.class_mapper
+                .borrow_mut()
+                .reload_stylesheet(&source_name(&file.path), &stylesheet);
+            log::info!("CSS Watcher: reloaded {}", file.path.display());
+            file.css = css;
+
+            for change in diff(&previous, &stylesheet) {
+                match changes
+                    .iter_mut()
+                    .find(|c: &&mut StyleChange| c.class_name == change.class_name && c.property == change.property)
+                {
+                    Some(existing) => *existing = change,
+                    None => changes.push(change),
+                }
+            }
+        }
+
+        if !changes.is_empty() {
+            if let Some(callback) = &self.callback {
+                callback(&changes);
+            }
+        }
+        changes
+    }
+
+    pub fn stop(&mut self) {
+        self.watcher = None;
+        self.events = None;
+        self.directories.clear();
+    }
+
+    fn watch_directory(&mut self, path: &Path) -> Result<(), StyleError> {
+        let Some(watcher) = &mut self.watcher else {
+            return Ok(());
+        };
+        let canonical = normalize(path);
+        let Some(directory) = canonical.parent() else {
+            return Ok(());
+        };
+        if self.directories.contains(directory) {
+            return Ok(());
+        }
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(|e| StyleError::Watch(path.display().to_string(), e.to_string()))?;
+        self.directories.insert(directory.to_path_buf());
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for CssWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CssWatcher")
+            .field("files", &self.watched_files().collect::<Vec<_>>())
+            .field("started", &self.watcher.is_some())
+            .finish()
+    }
+}
+
+// Declared value of each (media, selector, property) in a stylesheet, `!important` included
+type Declarations = HashMap<(String, String, String), String>;
+
+// Properties whose declared value differs between two versions of a file, per class list
+fn diff(previous: &Stylesheet, current: &Stylesheet) -> Vec<StyleChange> {
+    let (before, after) = (declarations(previous), declarations(current));
+    let mut keys: Vec<&(String, String, String)> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut changes: Vec<StyleChange> = Vec::new();
+    for key in keys {
+        let value = after.get(key);
+        if before.get(key) == value {
+            continue;
+        }
+        let (_, selector, property) = key;
+        let (Some(selector), Some(target)) = (Selector::parse(selector), StyleProperty::from_css_name(property)) else {
+            continue;
+        };
+        let change = StyleChange {
+            class_name: selector.classes.join(" "),
+            property: property.clone(),
+            target,
+            value: value.cloned().unwrap_or_default(),
+        };
+        if !changes.iter().any(|c| c.class_name == change.class_name && c.property == change.property) {
+            changes.push(change);
+        }
+    }
+    changes
+}
+
+fn declarations(stylesheet: &Stylesheet) -> Declarations {
+    let mut declarations = Declarations::new();
+    collect_declarations(stylesheet, "", &mut declarations);
+    declarations
+}
+
+fn collect_declarations(stylesheet: &Stylesheet, media: &str, declarations: &mut Declarations) {
+    for rule in &stylesheet.rules {
+        match rule {
+            CssRule::Style(rule) => {
+                for selector in &rule.selectors {
+                    for declaration in &rule.declarations {
+                        let value = if declaration.important {
+                            format!("{} !important", declaration.value)
+                        } else {
+                            declaration.value.clone()
+                        };
+                        let key = (media.to_string(), selector.clone(), declaration.property.clone());
+                        declarations.insert(key, value);
+                    }
+                }
+            }
+            CssRule::At(at) if at.name == "media" => {
+                if let Some(nested) = at.block.as_deref().and_then(|block| Stylesheet::parse(block).ok()) {
+                    let query = MediaQueryList::parse(&at.prelude);
+                    collect_declarations(&nested, &format!("{}@media {};", media, query), declarations);
+                }
+            }
+            CssRule::At(_) => {}
+        }
+    }
+}
+
+fn source_name(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,438 @@
+use wasm_bindgen::prelude::*;
+use web_sys::{Element, MutationObserver, MutationObserverInit, MutationRecord, Node};
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use crate::types::StyleProperty;
+use crate::watcher::{StyleChange, WatchedProperty};
+use crate::mapping::{ClassMapper, StyleError};
+use crate::parser::CssParser;
+use crate::types::CssClass;
+use js_sys::Array;
+use plinth_core::context::Theme;
+
+const DARK_SCHEME_QUERY: &str = "(prefers-color-scheme: dark)";
+const REDUCED_MOTION_QUERY: &str = "(prefers-reduced-motion: reduce)";
+// Hidden container holding one probe element per watched class
+const PROBE_ROOT_ID: &str = "plinth-css-probes";
+
+type ChangeCallback = Rc<dyn Fn(&[StyleChange])>;
+type FrameCallback = Closure<dyn FnMut(f64)>;
+
+// Counters for judging what the watcher costs. Rates cover the last second.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WatcherMetrics {
+    // Style checks run so far; each reads one computed style per watched class
+    pub checks: u64,
+    pub checks_per_second: f64,
+    // DOM mutations delivered to the watcher, and how many of them could affect styles
+    pub mutations: u64,
+    pub relevant_mutations: u64,
+    // Duration of the last check in milliseconds
+    pub last_check_ms: f64,
+}
+
+// Keeps watched properties in a ClassMapper in sync with the page's stylesheets. Instead of
+// sampling every element carrying a class, it reads the computed style of one hidden probe
+// element per class, so a check costs one `getComputedStyle` per watched class however big
+// the document is. Checks are triggered by stylesheets being added, removed or edited, by
+// class/style changes on <html> and <body> (theme switches), and by media query flips, and
+// run at most once per animation frame.
+pub struct CssWatcher {
+    shared: Rc<Shared>,
+    observers: Vec<MutationObserver>,
+}
+
+struct Shared {
+    class_mapper: Rc<RefCell<ClassMapper>>,
+    watched: RefCell<Vec<WatchedProperty>>,
+    callback: RefCell<Option<ChangeCallback>>,
+    // Last value per (class, property)
+    cached_values: RefCell<HashMap<(String, String), String>>,
+    probes: RefCell<HashMap<String, Element>>,
+    // requestAnimationFrame callback running the debounced check
+    frame_callback: RefCell<Option<FrameCallback>>,
+    // Pending requestAnimationFrame id
+    frame_id: Cell<Option<i32>>,
+    running: Cell<bool>,
+    metrics: RefCell<MetricsState>,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    totals: WatcherMetrics,
+    // Start times (ms) of checks in the last second
+    recent_checks: VecDeque<f64>,
+}
+
+impl CssWatcher {
+    pub fn new(class_mapper: Rc<RefCell<ClassMapper>>) -> Self {
+        Self {
+            shared: Rc::new(Shared {
+                class_mapper,
+                watched: RefCell::new(Vec::new()),
+                callback: RefCell::new(None),
+                cached_values: RefCell::new(HashMap::new()),
+                probes: RefCell::new(HashMap::new()),
+                frame_callback: RefCell::new(None),
+                frame_id: Cell::new(None),
+                running: Cell::new(false),
+                metrics: RefCell::new(MetricsState::default()),
+            }),
+            observers: Vec::new(),
+        }
+    }
+
+    // Watches `--color` on `class_name`, stored as the class's color
+    pub fn watch_class(&mut self, class_name: &str) {
+        self.watch_property_as(class_name, "--color", StyleProperty::Color);
+    }
+
+    // Watches a standard property (stored under the same property) or a custom one (stored
+    // as a custom property). Unknown standard properties are ignored with a warning.
+    pub fn watch_property(&mut self, class_name: &str, property: &str) {
+        match StyleProperty::from_css_name(property) {
+            Some(target) => self.watch_property_as(class_name, property, target),
+            None => log::warn!("CSS Watcher: '{}' has no place in the style model, not watching it", property),
+        }
+    }
+
+    // Watches `property` and stores its value under `target`, e.g. `--radius` as Width. The
+    // value must use the target's syntax.
+    pub fn watch_property_as(&mut self, class_name: &str, property: &str, target: StyleProperty) {
+        let watched = WatchedProperty {
+            class_name: class_name.to_string(),
+            property: property.to_string(),
+            target,
+        };
+        let mut list = self.shared.watched.borrow_mut();
+        if !list.contains(&watched) {
+            list.push(watched);
+            drop(list);
+            // Picked up by the next check, without firing the callback for its first value
+            if self.shared.running.get() {
+                self.shared.check(false);
+            }
+        }
+    }
+
+    pub fn watched(&self) -> Vec<WatchedProperty> {
+        self.shared.watched.borrow().clone()
+    }
+
+    // Called with every watched property that changed since the last check
+    pub fn set_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&[StyleChange]) + 'static,
+    {
+        *self.shared.callback.borrow_mut() = Some(Rc::new(callback));
+    }
+
+    pub fn metrics(&self) -> WatcherMetrics {
+        self.shared.metrics.borrow_mut().snapshot(js_sys::Date::now())
+    }
+
+    // Schedules a check for the next animation frame, e.g. after editing a stylesheet through
+    // the CSSOM (`insertRule`), which no mutation observer sees
+    pub fn request_check(&self) {
+        Shared::schedule(&self.shared);
+    }
+
+    pub fn start(&mut self) -> Result<(), StyleError> {
+        if self.shared.running.replace(true) {
+            return Ok(());
+        }
+        let window = web_sys::window().ok_or(StyleError::DomAccessFailed)?;
+        let document = window.document().ok_or(StyleError::DomAccessFailed)?;
+
+        let weak = Rc::downgrade(&self.shared);
+        *self.shared.frame_callback.borrow_mut() = Some(Closure::wrap(Box::new(move |_time: f64| {
+            if let Some(shared) = weak.upgrade() {
+                shared.frame_id.set(None);
+                if shared.running.get() {
+                    shared.check(true);
+                }
+            }
+        }) as Box<dyn FnMut(f64)>));
+
+        // Initial values go into the mapper without firing the callback
+        self.shared.check(false);
+
+        // Stylesheets can live in <head> or <body>; the theme is usually a class on the root
+        let stylesheets = MutationObserverInit::new();
+        stylesheets.set_child_list(true);
+        stylesheets.set_subtree(true);
+        stylesheets.set_character_data(true);
+        stylesheets.set_attributes(true);
+        let attribute_filter = Array::new();
+        for attribute in ["class", "style", "href", "media", "disabled"] {
+            attribute_filter.push(&attribute.into());
+        }
+        stylesheets.set_attribute_filter(&attribute_filter);
+        let observer = self.observer()?;
+        if let Some(root) = document.document_element() {
+            observer.observe_with_options(&root, &stylesheets).map_err(|_| StyleError::DomAccessFailed)?;
+        }
+        self.observers.push(observer);
+
+        // Media query flips (dark mode, breakpoints...)
+        self.setup_media_query_listeners()?;
+
+        log::info!("CSS Watcher: watching {} properties", self.shared.watched.borrow().len());
+        Ok(())
+    }
+
+    // Observer scheduling a check when a batch of mutations contains a relevant one
+    fn observer(&self) -> Result<MutationObserver, StyleError> {
+        let weak = Rc::downgrade(&self.shared);
+        let closure = Closure::wrap(Box::new(move |mutations: Array, _observer: MutationObserver| {
+            let Some(shared) = weak.upgrade() else {
+                return;
+            };
+            let total = mutations.length() as u64;
+            let relevant = mutations
+                .iter()
+                .filter_map(|mutation| mutation.dyn_into::<MutationRecord>().ok())
+                .filter(is_relevant)
+                .count() as u64;
+            {
+                let mut metrics = shared.metrics.borrow_mut();
+                metrics.totals.mutations += total;
+                metrics.totals.relevant_mutations += relevant;
+            }
+            if relevant > 0 {
+                Shared::schedule(&shared);
+            }
+        }) as Box<dyn FnMut(Array, MutationObserver)>);
+
+        let observer = MutationObserver::new(closure.as_ref().unchecked_ref())
+            .map_err(|_| StyleError::DomAccessFailed)?;
+        // Lives as long as the page; `stop` disconnects the observer
+        closure.forget();
+        Ok(observer)
+    }
+
+    // Listens to every @media query the mapper's rules use, plus the color scheme and motion
+    // preferences. Queries from CSS loaded after `start` aren't listened to.
+    fn setup_media_query_listeners(&mut self) -> Result<(), StyleError> {
+        let window = web_sys::window().ok_or(StyleError::DomAccessFailed)?;
+        let mut queries = vec![DARK_SCHEME_QUERY.to_string(), REDUCED_MOTION_QUERY.to_string()];
+        for media in self.shared.class_mapper.borrow().media_queries() {
+            if !queries.contains(&media.text) {
+                queries.push(media.text.clone());
+            }
+        }
+
+        for query in queries {
+            let Some(mql) = window.match_media(&query).map_err(|_| StyleError::DomAccessFailed)? else {
+                continue;
+            };
+            let weak = Rc::downgrade(&self.shared);
+            let cb = Closure::wrap(Box::new(move |_evt: web_sys::Event| {
+                if let Some(shared) = weak.upgrade() {
+                    Self::sync_media_preferences(&shared.class_mapper);
+                    Shared::schedule(&shared);
+                }
+            }) as Box<dyn FnMut(web_sys::Event)>);
+            mql.add_event_listener_with_callback("change", cb.as_ref().unchecked_ref())
+                .map_err(|_| StyleError::DomAccessFailed)?;
+            cb.forget();
+        }
+        Ok(())
+    }
+
+    // Copies the browser's color scheme and motion preferences into the mapper's environment;
+    // sizes stay whatever the app last set from its surface
+    fn sync_media_preferences(class_mapper: &Rc<RefCell<ClassMapper>>) {
+        let media_matches = |query: &str| {
+            web_sys::window()
+                .and_then(|w| w.match_media(query).ok().flatten())
+                .is_some_and(|list| list.matches())
+        };
+        let theme = if media_matches(DARK_SCHEME_QUERY) { Theme::Dark } else { Theme::Light };
+        let mut class_mapper = class_mapper.borrow_mut();
+        let environment = class_mapper
+            .environment()
+            .with_theme(theme)
+            .with_reduced_motion(media_matches(REDUCED_MOTION_QUERY));
+        class_mapper.set_environment(environment);
+    }
+
+    pub fn stop(&mut self) {
+        for observer in self.observers.drain(..) {
+            observer.disconnect();
+        }
+        self.shared.running.set(false);
+        if let (Some(id), Some(window)) = (self.shared.frame_id.take(), web_sys::window()) {
+            let _ = window.cancel_animation_frame(id);
+        }
+        for (_, probe) in self.shared.probes.borrow_mut().drain() {
+            probe.remove();
+        }
+    }
+}
+
+impl Drop for CssWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Shared {
+    // Runs a check on the next animation frame unless one is already pending
+    fn schedule(shared: &Rc<Shared>) {
+        if !shared.running.get() || shared.frame_id.get().is_some() {
+            return;
+        }
+        let id = web_sys::window().and_then(|window| {
+            let callback = shared.frame_callback.borrow();
+            window.request_animation_frame(callback.as_ref()?.as_ref().unchecked_ref()).ok()
+        });
+        shared.frame_id.set(id);
+    }
+
+    // Reads every watched property from its class's probe, writes changed values into the
+    // mapper and reports them (one per class/property) if `notify` is set
+    fn check(&self, notify: bool) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        let started = js_sys::Date::now();
+        let watched = self.watched.borrow().clone();
+        let mut styles: HashMap<&str, web_sys::CssStyleDeclaration> = HashMap::new();
+        let mut changes: Vec<StyleChange> = Vec::new();
+
+        for watch in &watched {
+            if !styles.contains_key(watch.class_name.as_str()) {
+                let Some(probe) = self.probe(&watch.class_name) else {
+                    continue;
+                };
+                let Ok(Some(style)) = window.get_computed_style(&probe) else {
+                    continue;
+                };
+                styles.insert(&watch.class_name, style);
+            }
+            let value = match styles[watch.class_name.as_str()].get_property_value(&watch.property) {
+                Ok(value) if !value.trim().is_empty() => value.trim().to_string(),
+                _ => continue,
+            };
+
+            let key = (watch.class_name.clone(), watch.property.clone());
+            if self.cached_values.borrow().get(&key) == Some(&value) {
+                continue;
+            }
+            self.cached_values.borrow_mut().insert(key, value.clone());
+
+            let mut class = CssClass::new(watch.class_name.clone());
+            match CssParser::apply_property(&mut class.style, watch.target.css_name(), &value) {
+                Ok(_) => self.class_mapper.borrow_mut().merge_class(class),
+                Err(e) => {
+                    log::warn!("CSS Watcher: ignoring {} on .{}: {}", watch.property, watch.class_name, e);
+                    continue;
+                }
+            }
+            changes.push(StyleChange {
+                class_name: watch.class_name.clone(),
+                property: watch.property.clone(),
+                target: watch.target.clone(),
+                value,
+            });
+        }
+
+        let finished = js_sys::Date::now();
+        self.metrics.borrow_mut().record_check(started, finished);
+
+        if notify && !changes.is_empty() {
+            let callback = self.callback.borrow().clone();
+            if let Some(callback) = callback {
+                callback(&changes);
+            }
+        }
+    }
+
+    // The hidden element standing in for `class_name`, created on first use. Probes sit in a
+    // `display: none` container under <body>, so they match class rules (and rules scoped
+    // under <html>/<body> classes) without being rendered or laid out.
+    fn probe(&self, class_name: &str) -> Option<Element> {
+        if let Some(probe) = self.probes.borrow().get(class_name) {
+            return Some(probe.clone());
+        }
+        let document = web_sys::window()?.document()?;
+        let root = match document.get_element_by_id(PROBE_ROOT_ID) {
+            Some(root) => root,
+            None => {
+                let root = document.create_element("div").ok()?;
+                root.set_id(PROBE_ROOT_ID);
+                root.set_attribute("style", "display: none").ok()?;
+                root.set_attribute("aria-hidden", "true").ok()?;
+                document.body()?.append_child(&root).ok()?;
+                root
+            }
+        };
+        let probe = document.create_element("div").ok()?;
+        probe.set_class_name(class_name);
+        root.append_child(&probe).ok()?;
+        self.probes.borrow_mut().insert(class_name.to_string(), probe.clone());
+        Some(probe)
+    }
+}
+
+impl MetricsState {
+    fn record_check(&mut self, started: f64, finished: f64) {
+        self.totals.checks += 1;
+        self.totals.last_check_ms = finished - started;
+        self.recent_checks.push_back(started);
+    }
+
+    fn snapshot(&mut self, now: f64) -> WatcherMetrics {
+        while self.recent_checks.front().is_some_and(|time| now - time > 1000.0) {
+            self.recent_checks.pop_front();
+        }
+        WatcherMetrics {
+            checks_per_second: self.recent_checks.len() as f64,
+            ..self.totals
+        }
+    }
+}
+
+// Whether a mutation can change what the probes compute: stylesheets added, removed or
+// edited, or class/style changes on <html> and <body>. The probes' own container is ignored.
+fn is_relevant(mutation: &MutationRecord) -> bool {
+    let Some(target) = mutation.target() else {
+        return false;
+    };
+    match mutation.type_().as_str() {
+        "attributes" => target.dyn_ref::<Element>().is_some_and(|element| {
+            matches!(element.tag_name().to_ascii_lowercase().as_str(), "html" | "body" | "style" | "link")
+        }),
+        // Text edited inside a <style>
+        "characterData" => target.parent_element().is_some_and(|parent| is_stylesheet_node(&parent)),
+        "childList" => {
+            if target.dyn_ref::<Element>().is_some_and(|element| element.id() == PROBE_ROOT_ID) {
+                return false;
+            }
+            if target.dyn_ref::<Element>().is_some_and(is_stylesheet_node) {
+                return true;
+            }
+            let touches_stylesheet = |nodes: web_sys::NodeList| {
+                (0..nodes.length()).filter_map(|index| nodes.get(index)).any(|node: Node| {
+                    node.dyn_ref::<Element>().is_some_and(|element| {
+                        is_stylesheet_node(element) || element.query_selector("style, link[rel~='stylesheet']").ok().flatten().is_some()
+                    })
+                })
+            };
+            touches_stylesheet(mutation.added_nodes()) || touches_stylesheet(mutation.removed_nodes())
+        }
+        _ => false,
+    }
+}
+
+fn is_stylesheet_node(element: &Element) -> bool {
+    match element.tag_name().to_ascii_lowercase().as_str() {
+        "style" => true,
+        "link" => element
+            .get_attribute("rel")
+            .is_some_and(|rel| rel.split_whitespace().any(|rel| rel.eq_ignore_ascii_case("stylesheet"))),
+        _ => false,
+    }
+}
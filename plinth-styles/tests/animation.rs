@@ -0,0 +1,284 @@
+use std::time::Duration;
+
+use glam::Vec2;
+use plinth_primitives::{
+    Animation, AnimationDirection, Circle, CircleBatch, Color, FillMode, InteractionState, Style, TimingFunction,
+    Transform,
+};
+use plinth_primitives::batch::StyleCache;
+use plinth_styles::{ClassMapper, StyleAnimator};
+
+fn mapper(css: &str) -> ClassMapper {
+    let mut mapper = ClassMapper::new();
+    mapper.load_css(css).unwrap();
+    mapper
+}
+
+fn seconds(seconds: f32) -> Duration {
+    Duration::from_secs_f32(seconds)
+}
+
+fn assert_near(actual: Option<f32>, expected: f32) {
+    let actual = actual.unwrap();
+    assert!((actual - expected).abs() < 1e-4, "{} is not {}", actual, expected);
+}
+
+fn assert_color(actual: [f32; 4], expected: [f32; 4]) {
+    let close = actual.iter().zip(expected).all(|(actual, expected)| (actual - expected).abs() < 0.01);
+    assert!(close, "{:?} is not {:?}", actual, expected);
+}
+
+fn rgba(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+#[test]
+fn batches_run_transitions_per_circle_until_they_end() {
+    let mapper = mapper(
+        ".button { color: #0000ff; transition: color 1s linear; }
+        .button:hover { color: #ff0000; }",
+    );
+    let mut batch = CircleBatch::new();
+    batch.add_circles([
+        Circle::new(Vec2::ZERO, 0.5).with_css_class("button"),
+        Circle::new(Vec2::new(0.5, 0.0), 0.5).with_css_class("button"),
+    ]);
+    batch.resolve_styles(Some(&mapper));
+    assert!(!batch.is_animating());
+    assert_eq!(batch.instances()[0].color, [0.0, 0.0, 1.0, 1.0]);
+
+    batch.circles_mut()[0].state = InteractionState::HOVER;
+    batch.set_style_time(seconds(2.0));
+    batch.resolve_styles(Some(&mapper));
+    assert!(batch.is_animating());
+    assert_color(batch.instances()[0].color, [0.0, 0.0, 1.0, 1.0]);
+
+    // Restyled every frame while animating, without anything else changing
+    batch.set_style_time(seconds(2.5));
+    batch.resolve_styles(Some(&mapper));
+    let halfway = Color::BLUE.mix_oklab(Color::RED, 0.5);
+    assert_color(batch.instances()[0].color, [halfway.r, halfway.g, halfway.b, halfway.a]);
+    assert_eq!(batch.instances()[1].color, [0.0, 0.0, 1.0, 1.0]);
+
+    batch.set_style_time(seconds(3.0));
+    batch.resolve_styles(Some(&mapper));
+    assert!(!batch.is_animating());
+    assert_color(batch.instances()[0].color, [1.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn transitions_stay_with_their_circle_until_the_batch_is_cleared() {
+    let mapper = mapper(
+        ".button { color: #0000ff; transition: color 1s linear; }
+        .button:hover { color: #ff0000; }",
+    );
+    let mut circles = vec![Circle::new(Vec2::ZERO, 0.5).with_css_class("button"); 2];
+    let mut cache = StyleCache::new();
+    cache.sync(Some(&mapper));
+    cache.restyle(Some(&mapper), &circles);
+
+    circles[1].state = InteractionState::HOVER;
+    *cache.states_changed_mut() = true;
+    cache.set_time(seconds(1.0));
+    cache.sync(Some(&mapper));
+    assert_eq!(cache.restyle(Some(&mapper), &circles), [1]);
+    assert!(cache.is_animating());
+
+    // Only the animating circle is restyled each frame, and one added meanwhile starts fresh
+    circles.push(Circle::new(Vec2::ZERO, 0.5).with_css_class("button"));
+    cache.set_time(seconds(1.5));
+    assert!(cache.sync(Some(&mapper)));
+    assert_eq!(cache.restyle(Some(&mapper), &circles), [1, 2]);
+    let halfway = Color::BLUE.mix_oklab(Color::RED, 0.5);
+    assert_color(rgba(cache.styled(1, &circles[1]).color), rgba(halfway));
+    assert_color(rgba(cache.styled(2, &circles[2]).color), [0.0, 0.0, 1.0, 1.0]);
+
+    // Cleared and added again in another order, no circle picks up the running transition
+    cache.clear();
+    circles.swap(0, 1);
+    cache.restyle(Some(&mapper), &circles);
+    assert!(!cache.is_animating());
+    assert_color(rgba(cache.styled(0, &circles[0]).color), [1.0, 0.0, 0.0, 1.0]);
+    assert_color(rgba(cache.styled(1, &circles[1]).color), [0.0, 0.0, 1.0, 1.0]);
+}
+
+#[test]
+fn styling_the_same_primitive_again_gives_the_same_transform() {
+    let mapper = mapper(".spin { transform: rotate(90deg); transition: opacity 1s; }");
+    let circle = Circle::new(Vec2::ZERO, 0.5)
+        .with_css_class("spin")
+        .with_transform(Transform::identity().with_scale(Vec2::splat(2.0)));
+    let (first, _) = mapper.styled(&circle, None);
+    let (second, _) = mapper.styled(&circle, None);
+    assert_eq!(first.transform, second.transform);
+    assert_near(Some(first.transform.rotation), std::f32::consts::FRAC_PI_2);
+    assert_eq!(first.transform.scale, Vec2::splat(2.0));
+    assert_eq!(circle.transform.rotation, 0.0);
+
+    let mut animator = StyleAnimator::new();
+    let (first, _) = mapper.animated(&circle, &mut animator, None, seconds(0.0));
+    let (second, _) = mapper.animated(&circle, &mut animator, None, seconds(1.0));
+    assert_eq!(first.transform, second.transform);
+    assert_near(Some(second.transform.rotation), std::f32::consts::FRAC_PI_2);
+}
+
+#[test]
+fn transitions_interpolate_after_their_delay_and_restart_from_where_they_were() {
+    let mapper = mapper(
+        ".box { opacity: 0; transform: translate(0px, 0px); transition: opacity 1s linear 0.5s, transform 2s linear; }
+        .box.open { opacity: 1; transform: translate(4px, 0px) scale(3); }",
+    );
+    let closed = mapper.get_style("box").unwrap();
+    let open = mapper.get_style("box open").unwrap();
+    let mut animator = StyleAnimator::new();
+
+    // The first update only records where the style starts
+    assert_eq!(animator.update(&closed, &mapper, seconds(0.0)), closed);
+    assert!(!animator.is_animating());
+
+    let style = animator.update(&open, &mapper, seconds(1.0));
+    assert!(animator.is_animating());
+    assert_near(style.opacity, 0.0);
+    assert_eq!(style.transform.unwrap().position, Vec2::ZERO);
+
+    let style = animator.update(&open, &mapper, seconds(2.0));
+    assert_near(style.opacity, 0.5);
+    let transform = style.transform.unwrap();
+    assert_near(Some(transform.position.x), 2.0);
+    assert_near(Some(transform.scale.x), 2.0);
+
+    // Going back starts from the current opacity, after the delay again
+    let style = animator.update(&closed, &mapper, seconds(2.0));
+    assert_near(style.opacity, 0.5);
+    assert_near(animator.update(&closed, &mapper, seconds(2.25)).opacity, 0.5);
+    assert_near(animator.update(&closed, &mapper, seconds(3.0)).opacity, 0.25);
+
+    assert_eq!(animator.update(&closed, &mapper, seconds(4.0)), closed);
+    assert!(!animator.is_animating());
+}
+
+#[test]
+fn keyframes_interpolate_between_frames_over_the_underlying_value() {
+    let mapper = mapper(
+        "@keyframes pulse { 50% { opacity: 0.2; } to { opacity: 1; width: 4px; } }
+        .dot { opacity: 0.6; width: 2px; animation: pulse 2s linear forwards; }",
+    );
+    let offsets: Vec<f32> = mapper.keyframes("pulse").unwrap().frames.iter().map(|frame| frame.offset).collect();
+    assert_eq!(offsets, [0.5, 1.0]);
+
+    let dot = mapper.get_style("dot").unwrap();
+    let mut animator = StyleAnimator::new();
+    let at = |animator: &mut StyleAnimator, time: f32| {
+        let style = animator.update(&dot, &mapper, seconds(time));
+        (style.opacity, style.width)
+    };
+    // Without a `from` frame the animation starts from the style's own value
+    let (opacity, width) = at(&mut animator, 1.0);
+    assert_near(opacity, 0.6);
+    assert_near(width, 2.0);
+    let (opacity, width) = at(&mut animator, 1.5);
+    assert_near(opacity, 0.4);
+    assert_near(width, 2.5);
+    let (opacity, width) = at(&mut animator, 2.0);
+    assert_near(opacity, 0.2);
+    assert_near(width, 3.0);
+    assert!(animator.is_animating());
+
+    // `forwards` holds the last frame
+    let (opacity, width) = at(&mut animator, 5.0);
+    assert_near(opacity, 1.0);
+    assert_near(width, 4.0);
+    assert!(!animator.is_animating());
+
+    // Dropping the animation from the style drops its effect
+    let style = animator.update(&Style { animations: None, ..dot.clone() }, &mapper, seconds(6.0));
+    assert_near(style.opacity, 0.6);
+}
+
+#[test]
+fn reloading_a_stylesheet_drops_its_keyframes_and_tokens() {
+    let frames = |mapper: &ClassMapper, name: &str| mapper.keyframes(name).map(|keyframes| keyframes.frames.len());
+    let mut mapper = mapper("@keyframes pulse { to { opacity: 1; } }");
+    mapper
+        .reload_css(
+            "theme.css",
+            "@keyframes pulse { 50% { opacity: 0.2; } to { opacity: 1; } }
+            @keyframes spin { to { opacity: 0; } }
+            :root { --accent: red; }",
+        )
+        .unwrap();
+    assert_eq!(frames(&mapper, "pulse"), Some(2));
+    assert_eq!(frames(&mapper, "spin"), Some(1));
+    assert_eq!(mapper.tokens().get("--accent"), Some("red"));
+
+    // The reloaded source's keyframes go away, and the ones they shadowed come back
+    mapper.reload_css("theme.css", ".dot { opacity: 0.5; }").unwrap();
+    assert_eq!(frames(&mapper, "pulse"), Some(1));
+    assert_eq!(frames(&mapper, "spin"), None);
+    assert_eq!(mapper.tokens().get("--accent"), None);
+
+    // Keyframes loaded after the source still win over it when it's reloaded
+    mapper.reload_css("theme.css", "@keyframes spin { to { opacity: 0; } }").unwrap();
+    mapper.load_css("@keyframes spin { 50% { opacity: 0.5; } to { opacity: 0; } }").unwrap();
+    mapper.reload_css("theme.css", "@keyframes spin { to { opacity: 0; } }").unwrap();
+    assert_eq!(frames(&mapper, "spin"), Some(2));
+}
+
+#[test]
+fn animation_shorthands_parse_every_setting() {
+    let mapper = mapper(".spin { animation: spin 2s ease-in 1s infinite alternate both, fade 0.5s; }");
+    let animations = mapper.get_style("spin").unwrap().animations.unwrap();
+    let spin = &animations[0];
+    assert_eq!((spin.name.as_str(), spin.duration, spin.delay), ("spin", 2.0, 1.0));
+    assert_eq!(spin.timing, TimingFunction::EASE_IN);
+    assert_eq!(spin.iterations, f32::INFINITY);
+    assert_eq!(spin.direction, AnimationDirection::Alternate);
+    assert_eq!(spin.fill_mode, FillMode::Both);
+    assert_eq!(animations[1], Animation::new("fade", 0.5));
+}
+
+// Two 2s iterations after a 1s delay, ending at 5s
+fn animation(direction: AnimationDirection, fill_mode: FillMode) -> Animation {
+    Animation { delay: 1.0, iterations: 2.0, direction, fill_mode, ..Animation::new("a", 2.0) }
+}
+
+#[test]
+fn progress_follows_the_fill_mode_outside_the_active_time() {
+    let cases = [
+        (FillMode::None, None, None),
+        (FillMode::Forwards, None, Some(1.0)),
+        (FillMode::Backwards, Some(0.0), None),
+        (FillMode::Both, Some(0.0), Some(1.0)),
+    ];
+    for (fill_mode, during_delay, after_end) in cases {
+        let animation = animation(AnimationDirection::Normal, fill_mode);
+        assert_eq!(animation.progress(0.5), during_delay, "{:?}", fill_mode);
+        assert_eq!(animation.progress(6.0), after_end, "{:?}", fill_mode);
+        assert_eq!(animation.progress(2.0), Some(0.5), "{:?}", fill_mode);
+        assert!(animation.is_running(4.9) && !animation.is_running(5.0));
+    }
+
+    // A partial last iteration is held where it stopped
+    let partial = Animation { iterations: 1.5, ..animation(AnimationDirection::Normal, FillMode::Forwards) };
+    assert_eq!(partial.progress(10.0), Some(0.5));
+    let infinite = Animation { iterations: f32::INFINITY, ..animation(AnimationDirection::Normal, FillMode::Both) };
+    assert_eq!(infinite.progress(1000.5), Some(0.75));
+    assert!(infinite.is_running(1e6));
+}
+
+#[test]
+fn progress_runs_each_iteration_in_its_direction() {
+    let cases = [
+        (AnimationDirection::Normal, [0.25, 0.25], [0.0, 1.0]),
+        (AnimationDirection::Reverse, [0.75, 0.75], [1.0, 0.0]),
+        (AnimationDirection::Alternate, [0.25, 0.75], [0.0, 0.0]),
+        (AnimationDirection::AlternateReverse, [0.75, 0.25], [1.0, 1.0]),
+    ];
+    for (direction, [first, second], [before, after]) in cases {
+        let animation = animation(direction, FillMode::Both);
+        assert_eq!(animation.progress(1.5), Some(first), "{:?}", direction);
+        assert_eq!(animation.progress(3.5), Some(second), "{:?}", direction);
+        assert_eq!(animation.progress(0.0), Some(before), "{:?}", direction);
+        assert_eq!(animation.progress(9.0), Some(after), "{:?}", direction);
+    }
+}
@@ -0,0 +1,135 @@
+use plinth_primitives::{Color, CssColor, InteractionState, Style};
+use plinth_styles::mapping::ColorProperty;
+use plinth_styles::ClassMapper;
+
+fn mapper(css: &str) -> ClassMapper {
+    let mut mapper = ClassMapper::new();
+    mapper.load_css(css).unwrap();
+    mapper
+}
+
+#[test]
+fn var_declarations_keep_their_place_in_the_rule() {
+    let css = ":root { --x: #0000ff; }
+        .later { color: var(--x); color: red; }
+        .earlier { color: red; color: var(--x); }
+        .important { color: var(--x) !important; color: red; }";
+    let mapper = mapper(css);
+    assert_eq!(mapper.get_style("later").unwrap().color, Some(Color::RED));
+    assert_eq!(mapper.get_style("earlier").unwrap().color, Some(Color::BLUE));
+    assert_eq!(mapper.get_style("important").unwrap().color, Some(Color::BLUE));
+}
+
+#[test]
+fn more_specific_selectors_win_then_later_rules() {
+    let css = ".button.primary { color: #00ff00; }
+        .button { color: red; opacity: 0.5; }
+        .primary { color: #0000ff; }
+        .button { opacity: 0.25; }";
+    let mapper = mapper(css);
+    let style = mapper.compute_style(&["button", "primary"], InteractionState::NONE, None);
+    assert_eq!(style.color, Some(Color::GREEN));
+    assert_eq!(style.opacity, Some(0.25));
+    // Equal specificity: the later rule wins whatever the order of the class list
+    assert_eq!(mapper.get_style("primary button").unwrap().color, Some(Color::GREEN));
+    assert_eq!(mapper.compute_style(&["primary"], InteractionState::NONE, None).color, Some(Color::BLUE));
+    assert_eq!(mapper.get_style("unknown"), None);
+}
+
+#[test]
+fn important_declarations_beat_specificity_and_order() {
+    let css = ".a { color: red !important; opacity: 0.5 !important; }
+        .a.b:hover { color: #0000ff; }
+        .b { opacity: 0.75 !important; }
+        .a { opacity: 1; }";
+    let mapper = mapper(css);
+    let style = mapper.compute_style(&["a", "b"], InteractionState::HOVER, None);
+    assert_eq!(style.color, Some(Color::RED));
+    // Among important declarations specificity and order apply again
+    assert_eq!(style.opacity, Some(0.75));
+}
+
+#[test]
+fn hover_and_active_rules_apply_in_their_states() {
+    let css = ".button { color: red; }
+        .button:hover { color: #00ff00; opacity: 0.5; }
+        .button:active { color: #0000ff; }
+        .button:hover:active { opacity: 0.25; }";
+    let mapper = mapper(css);
+    let color = |state| mapper.compute_style(&["button"], state, None).color;
+    assert_eq!(color(InteractionState::NONE), Some(Color::RED));
+    assert_eq!(color(InteractionState::HOVER), Some(Color::GREEN));
+    assert_eq!(color(InteractionState::ACTIVE), Some(Color::BLUE));
+
+    let pressed = mapper.get_style_for_state("button", InteractionState::HOVER | InteractionState::ACTIVE).unwrap();
+    assert_eq!(pressed.color, Some(Color::BLUE));
+    assert_eq!(pressed.opacity, Some(0.25));
+    // Pseudo-class rules stay out of the plain class view
+    assert_eq!(mapper.get_class("button").unwrap().style.color, Some(Color::RED));
+}
+
+#[test]
+fn children_inherit_inherited_properties_only() {
+    let css = ".panel { color: red; opacity: 0.5; font-size: 20px; --accent: #0000ff; }
+        .label { width: 4px; }
+        .icon { color: var(--accent); }";
+    let mapper = mapper(css);
+    let panel = mapper.compute_style(&["panel"], InteractionState::NONE, None);
+    let label = mapper.compute_style(&["label"], InteractionState::NONE, Some(&panel));
+    assert_eq!(label.color, Some(Color::RED));
+    assert_eq!(label.font_size, Some(20.0));
+    assert_eq!(label.opacity, None);
+    assert_eq!(label.width, Some(4.0));
+
+    // Custom properties inherit too, so var() sees the parent's
+    let icon = mapper.compute_style(&["icon"], InteractionState::NONE, Some(&panel));
+    assert_eq!(icon.color, Some(Color::BLUE));
+    assert_eq!(mapper.compute_style(&["icon"], InteractionState::NONE, None).color, None);
+}
+
+#[test]
+fn var_falls_back_when_the_token_is_missing() {
+    let css = ":root { --primary: #0000ff; --alias: var(--primary); }
+        .set { color: var(--primary, red); }
+        .missing { color: var(--unknown, red); }
+        .nested { color: var(--unknown, var(--alias)); }
+        .invalid { color: #00ff00; color: var(--unknown); }
+        .scoped { --primary: red; color: var(--primary); }";
+    let mapper = mapper(css);
+    let color = |class| mapper.get_style(class).unwrap().color;
+    assert_eq!(color("set"), Some(Color::BLUE));
+    assert_eq!(color("missing"), Some(Color::RED));
+    assert_eq!(color("nested"), Some(Color::BLUE));
+    // Like an invalid value, a var() that can't resolve drops only its declaration
+    assert_eq!(color("invalid"), Some(Color::GREEN));
+    assert_eq!(color("scoped"), Some(Color::RED));
+    assert_eq!(mapper.token_color("--alias"), Some(Color::BLUE));
+}
+
+#[test]
+fn current_color_follows_the_final_color() {
+    let css = ".swatch { background-color: currentColor; border-color: currentColor; }
+        .red { color: red; }
+        .blue { color: #0000ff; }
+        .blue.inherit { color: currentColor; }
+        .outlined { border-color: #00ff00; }";
+    let mapper = mapper(css);
+    let style = |classes: &[&str], parent: Option<&Style>| mapper.compute_style(classes, InteractionState::NONE, parent);
+
+    // Resolved once the cascade is done, whichever rule set the color
+    let swatch = style(&["swatch", "red"], None);
+    assert_eq!(swatch.background_color, Some(CssColor::Color(Color::RED)));
+    assert_eq!(swatch.border_color, Some(CssColor::Color(Color::RED)));
+    assert_eq!(mapper.get_color_for_class("swatch blue", ColorProperty::BackgroundColor), Some(Color::BLUE));
+    assert_eq!(style(&["swatch", "outlined", "red"], None).border_color, Some(CssColor::Color(Color::GREEN)));
+
+    // Or against the inherited color
+    let parent = style(&["red"], None);
+    assert_eq!(style(&["swatch"], Some(&parent)).fill_color(), Some(Color::RED));
+    assert_eq!(style(&["swatch"], None).background_color, None);
+
+    // As the color itself it is the inherited color, overriding earlier rules
+    let inherited = style(&["blue", "inherit", "swatch"], Some(&parent));
+    assert_eq!(inherited.color, Some(Color::RED));
+    assert_eq!(inherited.background_color, Some(CssColor::Color(Color::RED)));
+}
@@ -0,0 +1,86 @@
+use plinth_core::context::Theme;
+use plinth_primitives::Color;
+use plinth_styles::parser::MediaQueryList;
+use plinth_styles::{ClassMapper, MediaEnvironment};
+
+fn matches(query: &str, environment: MediaEnvironment) -> bool {
+    MediaQueryList::parse(query).matches(&environment)
+}
+
+#[test]
+fn width_queries_follow_the_surface_in_css_pixels() {
+    let narrow = MediaEnvironment::new(400.0, 800.0);
+    let wide = MediaEnvironment::new(1200.0, 800.0);
+    assert!(matches("(max-width: 600px)", narrow));
+    assert!(!matches("(max-width: 600px)", wide));
+    assert!(matches("(min-width: 600px)", wide));
+    assert!(matches("(min-width: 25em)", narrow));
+    assert!(matches("(400px <= width < 800px)", narrow));
+    assert!(!matches("(400px < width < 800px)", narrow));
+    assert!(matches("(width > 1000px) and (orientation: landscape)", wide));
+    assert!(matches("(orientation: portrait)", narrow));
+
+    // A comma is "or"; `not` negates one query; unknown features never match
+    assert!(matches("(max-width: 100px), (min-width: 1000px)", wide));
+    assert!(matches("not screen and (max-width: 600px)", wide));
+    assert!(!matches("(min-width: 600px) and (hover-colour: blue)", wide));
+    assert!(matches("(width >= 600px) and (hover-colour: blue), (width >= 600px)", wide));
+    assert!(!matches("print", wide));
+    assert!(matches("only screen", wide));
+}
+
+#[test]
+fn user_preference_and_resolution_queries() {
+    let light = MediaEnvironment::new(800.0, 600.0);
+    let dark = light.with_theme(Theme::Dark);
+    assert!(matches("(prefers-color-scheme: light)", light));
+    assert!(!matches("(prefers-color-scheme: dark)", light));
+    assert!(matches("(prefers-color-scheme: dark)", dark));
+
+    let reduced = light.with_reduced_motion(true);
+    assert!(matches("(prefers-reduced-motion: reduce)", reduced));
+    assert!(matches("(prefers-reduced-motion)", reduced));
+    assert!(!matches("(prefers-reduced-motion: reduce)", light));
+    assert!(matches("(prefers-reduced-motion: no-preference)", light));
+
+    let retina = light.with_resolution(2.0);
+    assert!(matches("(min-resolution: 2dppx)", retina));
+    assert!(matches("(min-resolution: 192dpi)", retina));
+    assert!(!matches("(min-resolution: 1.5x)", light));
+    assert!(matches("(resolution < 2x)", light));
+}
+
+#[test]
+fn media_rules_switch_with_the_environment() {
+    let css = ".card { color: red; opacity: 1; }
+        @media (max-width: 600px) { .card { opacity: 0.5; } }
+        @media (prefers-color-scheme: dark) { .card { color: #0000ff; } }
+        @media (prefers-reduced-motion: reduce) { .card { transition: none; } }
+        @media (min-resolution: 2dppx) { @media (min-width: 300px) { .card { width: 2px; } } }";
+    let mut mapper = ClassMapper::new();
+    mapper.load_css(css).unwrap();
+    assert_eq!(mapper.media_queries().len(), 5);
+
+    assert!(mapper.set_environment(MediaEnvironment::new(1000.0, 800.0)));
+    let card = mapper.get_style("card").unwrap();
+    assert_eq!((card.color, card.opacity, card.width), (Some(Color::RED), Some(1.0), None));
+    assert_eq!(card.transitions, None);
+
+    let generation = mapper.generation();
+    let environment = MediaEnvironment::new(500.0, 800.0)
+        .with_theme(Theme::Dark)
+        .with_reduced_motion(true)
+        .with_resolution(2.0);
+    assert!(mapper.set_environment(environment));
+    assert!(mapper.generation() > generation);
+    let card = mapper.get_style("card").unwrap();
+    assert_eq!((card.color, card.opacity, card.width), (Some(Color::BLUE), Some(0.5), Some(2.0)));
+    assert_eq!(card.transitions, Some(Vec::new()));
+    // @media rules stay out of the plain class view
+    assert_eq!(mapper.get_class("card").unwrap().style.opacity, Some(1.0));
+
+    // Nothing switches, so nothing to restyle
+    let generation = mapper.generation();
+    assert!(!mapper.set_environment(environment.with_resolution(3.0)));
+    assert_eq!(mapper.generation(), generation);
+}
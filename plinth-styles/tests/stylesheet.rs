@@ -0,0 +1,116 @@
+use plinth_primitives::InteractionState;
+use plinth_styles::parser::{CssRule, Declaration, Selector, StyleRule, Stylesheet};
+
+fn declaration(property: &str, value: &str, important: bool) -> Declaration {
+    Declaration { property: property.to_string(), value: value.to_string(), important }
+}
+
+fn selectors(stylesheet: &Stylesheet) -> Vec<String> {
+    stylesheet.style_rules().flat_map(|rule| rule.selectors.clone()).collect()
+}
+
+#[test]
+fn broken_rules_are_dropped_and_parsing_resumes_after_them() {
+    let css = "
+        } .stray { color: red; }
+        statement without a block;
+        { color: blue; }
+        .kept { color red; opacity: 0.5; : 1; 4px: 2px; width: ; height: 2px }
+        @import url(theme.css);
+        .last, .other  .descendant { color: green }
+        trailing junk";
+    let stylesheet = Stylesheet::parse(css).unwrap();
+    assert_eq!(selectors(&stylesheet), [".stray", ".kept", ".last", ".other .descendant"]);
+
+    let kept = stylesheet.style_rules().nth(1).unwrap();
+    assert_eq!(kept.declarations, [declaration("opacity", "0.5", false), declaration("height", "2px", false)]);
+
+    let import = stylesheet.at_rules("IMPORT").next().unwrap();
+    assert_eq!((import.prelude.as_str(), import.block.as_deref()), ("url(theme.css)", None));
+}
+
+#[test]
+fn comments_strings_and_nesting_keep_their_meaning() {
+    let css = r#"
+        /* .commented { color: red } */
+        .a /* note */ .b { font-family: "Semi; colon", 'Brace }'; color: rgb(0 /* red */ 0 255) }
+        @media (min-width: 100px) { .c { color: red } .d { color: blue } }"#;
+    let stylesheet = Stylesheet::parse(css).unwrap();
+    assert_eq!(selectors(&stylesheet), [".a .b"]);
+    let rule = stylesheet.style_rules().next().unwrap();
+    assert_eq!(
+        rule.declarations,
+        [
+            declaration("font-family", r#""Semi; colon", 'Brace }'"#, false),
+            declaration("color", "rgb(0   0 255)", false),
+        ]
+    );
+
+    let media = stylesheet.at_rules("media").next().unwrap();
+    assert_eq!(media.prelude, "(min-width: 100px)");
+    let nested = Stylesheet::parse(media.block.as_deref().unwrap()).unwrap();
+    assert_eq!(selectors(&nested), [".c", ".d"]);
+    assert!(matches!(&stylesheet.rules[1], CssRule::At(_)));
+}
+
+#[test]
+fn declarations_are_normalized_and_marked_important() {
+    let css = ".a { COLOR: Red !IMPORTANT; --Brand-Color: #FFF; --empty:; opacity: 1 ! important }";
+    let stylesheet = Stylesheet::parse(css).unwrap();
+    let rule = stylesheet.style_rules().next().unwrap();
+    assert_eq!(
+        rule.declarations,
+        [
+            declaration("color", "Red", true),
+            declaration("--Brand-Color", "#FFF", false),
+            declaration("--empty", "", false),
+            declaration("opacity", "1", true),
+        ]
+    );
+}
+
+#[test]
+fn unclosed_blocks_and_comments_close_at_the_end_of_the_sheet() {
+    let stylesheet = Stylesheet::parse(".first { opacity: 1 } .a { color: red; .b { }").unwrap();
+    let rules: Vec<&StyleRule> = stylesheet.style_rules().collect();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].declarations, [declaration("opacity", "1", false)]);
+    assert_eq!(rules[1].selectors, [".a"]);
+    assert_eq!(rules[1].declarations, [declaration("color", "red", false)]);
+
+    let stylesheet = Stylesheet::parse(".a { color: red } /* open .b { color: blue }").unwrap();
+    assert_eq!(stylesheet.style_rules().count(), 1);
+    let stylesheet = Stylesheet::parse(".a { color: red /* open").unwrap();
+    assert_eq!(stylesheet.style_rules().next().unwrap().declarations, [declaration("color", "red", false)]);
+
+    let stylesheet = Stylesheet::parse("@media (min-width: 10px) { .a { color: red").unwrap();
+    let media = stylesheet.at_rules("media").next().unwrap();
+    let nested = Stylesheet::parse(media.block.as_deref().unwrap()).unwrap();
+    assert_eq!(nested.style_rules().next().unwrap().declarations, [declaration("color", "red", false)]);
+    assert_eq!(Stylesheet::parse("  /* nothing */  ").unwrap(), Stylesheet::default());
+}
+
+#[test]
+fn selectors_parse_classes_and_interaction_states() {
+    let hover = Selector::parse(".button:hover").unwrap();
+    assert_eq!(hover, Selector::class("button").with_states(InteractionState::HOVER));
+    assert_eq!(hover.single_class(), None);
+    assert_eq!(hover.specificity(), 2);
+
+    let compound = Selector::parse(".button.primary:hover:active").unwrap();
+    assert_eq!(compound.classes, ["button", "primary"]);
+    assert_eq!(compound.states, InteractionState::HOVER | InteractionState::ACTIVE);
+    assert_eq!(compound.specificity(), 4);
+    assert!(compound.matches(&["primary", "button", "large"], InteractionState::HOVER | InteractionState::ACTIVE));
+    assert!(!compound.matches(&["primary", "button"], InteractionState::HOVER));
+    assert!(!compound.matches(&["button"], InteractionState::HOVER | InteractionState::ACTIVE));
+
+    assert_eq!(Selector::parse(".button").unwrap().single_class(), Some("button"));
+    assert_eq!(Selector::parse(":root"), Some(Selector::root()));
+    assert_eq!(Selector::parse("html"), Some(Selector::root()));
+    assert!(!Selector::root().matches(&[], InteractionState::NONE));
+
+    for unsupported in ["button", "#id", ".a .b", ".a > .b", ".a:visited", ".a::before", ".1st", "."] {
+        assert_eq!(Selector::parse(unsupported), None, "{}", unsupported);
+    }
+}
@@ -0,0 +1,85 @@
+use plinth_primitives::Color;
+use plinth_styles::mapping::StyleError;
+use plinth_styles::{ClassMapper, DesignTokens};
+
+fn theme() -> DesignTokens {
+    DesignTokens::new()
+        .with_token("--primary", "#0000ff")
+        .with_token("accent", "var(--primary)")
+        .with_token("--font-stack", "\"Fira Sans\", sans-serif")
+        .with_token("--spacing", "4px")
+        .with_token("--Mixed-Case", "rgb(255 0 0 / 50%)")
+        .with_token("--empty", "")
+}
+
+#[test]
+fn tokens_round_trip_through_css() {
+    let tokens = theme();
+    let css = tokens.to_css(":root");
+    assert!(css.starts_with(":root {\n"), "{}", css);
+    assert!(css.contains("  --accent: var(--primary);\n"), "{}", css);
+    assert_eq!(DesignTokens::from_css(&css).unwrap(), tokens);
+    assert_eq!(DesignTokens::from_css(&tokens.to_css("html")).unwrap(), tokens);
+
+    // Only root rules outside of @media count, later ones winning
+    let css = ":root { --a: 1; --b: 2; } .button { --c: 3; }
+        @media (min-width: 1px) { :root { --d: 4; } }
+        html { --b: 5; }";
+    let tokens = DesignTokens::from_css(css).unwrap();
+    assert_eq!(tokens.iter().collect::<Vec<_>>(), [("--a", "1"), ("--b", "5")]);
+}
+
+#[test]
+fn tokens_round_trip_through_json() {
+    let tokens = theme();
+    let json = tokens.to_json();
+    assert_eq!(DesignTokens::from_json(&json).unwrap(), tokens);
+
+    let nested = r##"{
+        "$description": "theme",
+        "color": {
+            "primary": { "$value": "#0000ff", "$type": "color" },
+            "accent": { "$value": "{color.primary}" }
+        },
+        "radius": 4,
+        "--flat": "red"
+    }"##;
+    let tokens = DesignTokens::from_json(nested).unwrap();
+    let expected = DesignTokens::new()
+        .with_token("--color-primary", "#0000ff")
+        .with_token("--color-accent", "var(--color-primary)")
+        .with_token("--radius", "4")
+        .with_token("--flat", "red");
+    assert_eq!(tokens, expected);
+    assert_eq!(DesignTokens::from_json(&tokens.to_json()).unwrap(), tokens);
+    assert_eq!(tokens.color("color-accent"), Some(Color::BLUE));
+
+    assert!(matches!(DesignTokens::from_json("[1, 2]"), Err(StyleError::InvalidTokens(_))));
+    assert!(matches!(DesignTokens::from_json(r#"{"a": [1]}"#), Err(StyleError::InvalidTokens(_))));
+    assert!(matches!(DesignTokens::from_json("{"), Err(StyleError::InvalidTokens(_))));
+}
+
+#[test]
+fn tokens_resolve_references_and_reject_cycles() {
+    let tokens = theme().with_token("--loop-a", "var(--loop-b)").with_token("--loop-b", "var(--loop-a)");
+    assert_eq!(tokens.get("accent"), Some("var(--primary)"));
+    assert_eq!(tokens.color("--accent"), Some(Color::BLUE));
+    let resolved = tokens.resolve("0 var(--spacing) calc(var(--missing, 2px) * 2)");
+    assert_eq!(resolved.as_deref(), Some("0 4px calc(2px * 2)"));
+    assert_eq!(tokens.resolve("var(--missing)"), None);
+    assert_eq!(tokens.resolve("var(--loop-a)"), None);
+}
+
+#[test]
+fn loaded_tokens_override_stylesheet_tokens() {
+    let mut mapper = ClassMapper::new();
+    mapper.load_css(":root { --primary: red; --secondary: red; } .button { color: var(--primary); }").unwrap();
+    mapper.load_tokens(&DesignTokens::new().with_token("--primary", "#0000ff")).unwrap();
+    assert_eq!(mapper.get_style("button").unwrap().color, Some(Color::BLUE));
+    assert_eq!(mapper.tokens().get("--secondary"), Some("red"));
+
+    // A second call replaces the first one's tokens
+    mapper.load_tokens(&DesignTokens::new().with_token("--secondary", "#0000ff")).unwrap();
+    assert_eq!(mapper.get_style("button").unwrap().color, Some(Color::RED));
+    assert_eq!(mapper.token_color("secondary"), Some(Color::BLUE));
+}
@@ -1,15 +1,26 @@
 // Rc is conditionally imported based on features
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
 use crate::plinth_app::PlinthApp;
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
 use std::cell::RefCell;
 
 // Winit imports (always available since it's the default)
 #[cfg(feature = "winit")]
+use crate::context::{Context, ControlState, FrameClock};
+#[cfg(feature = "winit")]
+use crate::loading::{JoinAll, LoadFuture};
+#[cfg(feature = "winit")]
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
+#[cfg(feature = "winit")]
 use crate::graphics::{create_graphics, Graphics, Rc};
 #[cfg(feature = "winit")]
+use crate::input::{PointerButton, PointerEvent, PointerEventKind};
+#[cfg(feature = "winit")]
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
-    event::WindowEvent,
+    event::{ElementState, MouseButton, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
     window::{Window, WindowId},
 };
@@ -23,10 +34,32 @@ use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
 
 // Winit-specific types (always available since it's the default)
+#[cfg(feature = "winit")]
+pub enum PlinthEvent {
+    // Sent by create_graphics once the surface and device exist
+    GraphicsReady(Box<Graphics>),
+    // A load future was woken and should be polled again (native)
+    LoadWake,
+    // Every load future has completed (wasm, where they run on spawn_local)
+    Loaded,
+}
+
 #[cfg(feature = "winit")]
 enum State {
-    Ready(Graphics),
-    Init(Option<EventLoopProxy<Graphics>>),
+    Ready(Box<Graphics>),
+    Init(Option<EventLoopProxy<PlinthEvent>>),
+}
+
+#[cfg(feature = "winit")]
+enum Loading {
+    NotStarted,
+    // Futures returned from PlinthApp::load, polled whenever the proxy waker fires
+    #[cfg(not(target_arch = "wasm32"))]
+    Polling(Pin<Box<JoinAll>>),
+    // Futures returned from PlinthApp::load, running on spawn_local
+    #[cfg(target_arch = "wasm32")]
+    Spawned,
+    Done,
 }
 
 #[cfg(feature = "winit")]
@@ -34,58 +67,206 @@ pub struct App {
     _title: String,
     state: State,
     user_app: Rc<RefCell<dyn PlinthApp>>,
+    proxy: EventLoopProxy<PlinthEvent>,
+    loading: Loading,
+    clock: FrameClock,
+    control: ControlState,
+    // Last cursor position; winit's MouseInput doesn't carry one
+    cursor_position: (f32, f32),
+}
+
+// Wakes the event loop through its proxy so pending load futures get polled again
+#[cfg(feature = "winit")]
+#[cfg(not(target_arch = "wasm32"))]
+struct ProxyWaker(std::sync::Mutex<EventLoopProxy<PlinthEvent>>);
+
+#[cfg(feature = "winit")]
+#[cfg(not(target_arch = "wasm32"))]
+impl std::task::Wake for ProxyWaker {
+    fn wake(self: std::sync::Arc<Self>) {
+        if let Ok(proxy) = self.0.lock() {
+            let _ = proxy.send_event(PlinthEvent::LoadWake);
+        }
+    }
 }
 
 #[cfg(feature = "winit")]
 impl App {
-    pub fn new(event_loop: &EventLoop<Graphics>, user_app: Rc<RefCell<dyn PlinthApp>>) -> Self {
+    pub fn new(event_loop: &EventLoop<PlinthEvent>, user_app: Rc<RefCell<dyn PlinthApp>>) -> Self {
         Self {
             _title: "WebGPU Example".to_string(),
             state: State::Init(Some(event_loop.create_proxy())),
             user_app,
+            proxy: event_loop.create_proxy(),
+            loading: Loading::NotStarted,
+            clock: FrameClock::new(),
+            control: ControlState::default(),
+            cursor_position: (0.0, 0.0),
         }
     }
 
-    fn draw(&mut self) {
+    fn draw(&mut self, event_loop: &ActiveEventLoop) {
         if let State::Ready(gfx) = &mut self.state {
-            self.user_app.borrow_mut().before_render();
-            self.user_app.borrow_mut().render(gfx);
-            self.user_app.borrow_mut().after_render();
+            if !matches!(self.loading, Loading::Done) {
+                self.user_app.borrow_mut().render_loading(gfx);
+                return;
+            }
+            gfx.reload_shaders();
+            let time = self.clock.tick();
+            let mut user_app = self.user_app.borrow_mut();
+            user_app.before_render(&mut Context::new(gfx, time, &mut self.control));
+            user_app.render(gfx);
+            user_app.after_render(&mut Context::new(gfx, time, &mut self.control));
         }
+        self.apply_control(event_loop);
     }
 
     fn resized(&mut self, size: PhysicalSize<u32>) {
         if let State::Ready(gfx) = &mut self.state {
             gfx.resize(size);
-            self.user_app.borrow_mut().render(gfx);
+            if matches!(self.loading, Loading::Done) {
+                self.user_app.borrow_mut().render(gfx);
+            } else {
+                self.user_app.borrow_mut().render_loading(gfx);
+            }
+        }
+    }
+
+    // Runs init and kicks off the app's load futures
+    fn start(&mut self) {
+        let State::Ready(gfx) = &mut self.state else {
+            return;
+        };
+        let time = self.clock.now();
+        let mut user_app = self.user_app.borrow_mut();
+        user_app.init(&mut Context::new(gfx, time, &mut self.control));
+        let futures: Vec<LoadFuture> = user_app.load(&mut Context::new(gfx, time, &mut self.control));
+        drop(user_app);
+
+        if futures.is_empty() {
+            self.finish_loading();
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.loading = Loading::Polling(Box::pin(JoinAll::new(futures)));
+            self.poll_loading();
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.loading = Loading::Spawned;
+            let proxy = self.proxy.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                JoinAll::new(futures).await;
+                let _ = proxy.send_event(PlinthEvent::Loaded);
+            });
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_loading(&mut self) {
+        let Loading::Polling(future) = &mut self.loading else {
+            return;
+        };
+        let waker = std::task::Waker::from(std::sync::Arc::new(ProxyWaker(std::sync::Mutex::new(
+            self.proxy.clone(),
+        ))));
+        let mut cx = std::task::Context::from_waker(&waker);
+        if future.as_mut().poll(&mut cx).is_ready() {
+            self.finish_loading();
+        }
+    }
+
+    fn finish_loading(&mut self) {
+        self.loading = Loading::Done;
+        if let State::Ready(gfx) = &mut self.state {
+            let time = self.clock.now();
+            self.user_app
+                .borrow_mut()
+                .loaded(&mut Context::new(gfx, time, &mut self.control));
+            if let Some(window) = &gfx.window {
+                window.request_redraw();
+            }
+        }
+    }
+
+    // Applies the exit / redraw requests made through the Context during the last hook
+    fn apply_control(&mut self, event_loop: &ActiveEventLoop) {
+        let control = self.control.take();
+        if control.exit_requested {
+            event_loop.exit();
+            return;
+        }
+        if control.redraw_requested
+            && let State::Ready(gfx) = &self.state
+            && let Some(window) = &gfx.window
+        {
+            window.request_redraw();
+        }
+    }
+
+    // Translates winit cursor and mouse events into PointerEvents
+    fn pointer_event(&mut self, event: &WindowEvent) -> Option<PointerEvent> {
+        let kind = match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = (position.x as f32, position.y as f32);
+                PointerEventKind::Moved
+            }
+            WindowEvent::CursorLeft { .. } => PointerEventKind::Left,
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => PointerButton::Primary,
+                    MouseButton::Right => PointerButton::Secondary,
+                    MouseButton::Middle => PointerButton::Middle,
+                    MouseButton::Back => PointerButton::Other(3),
+                    MouseButton::Forward => PointerButton::Other(4),
+                    MouseButton::Other(other) => PointerButton::Other(*other),
+                };
+                match state {
+                    ElementState::Pressed => PointerEventKind::Pressed(button),
+                    ElementState::Released => PointerEventKind::Released(button),
+                }
+            }
+            _ => return None,
+        };
+        Some(PointerEvent::new(kind, self.cursor_position))
+    }
+
     pub fn _set_title(&mut self, title: &str) {
         self._title = title.to_string();
     }
 }
 
 #[cfg(feature = "winit")]
-impl ApplicationHandler<Graphics> for App {
+impl ApplicationHandler<PlinthEvent> for App {
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
+        window_id: WindowId,
         event: WindowEvent,
     ) {
         match event {
             WindowEvent::Resized(size) => self.resized(size),
-            WindowEvent::RedrawRequested => self.draw(),
-            WindowEvent::CloseRequested => {
-                self.user_app.borrow_mut().on_close();
-                event_loop.exit()
-            }
+            WindowEvent::RedrawRequested => self.draw(event_loop),
+            WindowEvent::CloseRequested => self.control.exit_requested = true,
             _ => {}
         }
-        self.user_app
-            .borrow_mut()
-            .event_handler(event_loop, _window_id, &event);
+        let pointer_event = self.pointer_event(&event);
+        if let State::Ready(gfx) = &mut self.state {
+            let time = self.clock.now();
+            let mut user_app = self.user_app.borrow_mut();
+            let mut ctx = Context::new(gfx, time, &mut self.control);
+            user_app.event_handler(&mut ctx, window_id, &event);
+            if let Some(pointer_event) = &pointer_event {
+                user_app.pointer_event(&mut ctx, pointer_event);
+            }
+            if let WindowEvent::ThemeChanged(theme) = event {
+                user_app.theme_changed(&mut ctx, theme.into());
+            }
+        }
+        self.apply_control(event_loop);
     }
 
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
@@ -121,27 +302,46 @@ impl ApplicationHandler<Graphics> for App {
         }
     }
 
-    fn user_event(&mut self, _event_loop: &ActiveEventLoop, graphics: Graphics) {
-        self.state = State::Ready(graphics);
-        if let State::Ready(gfx) = &mut self.state {
-            if let Some(window) = &gfx.window {
-                let scale_factor = window.scale_factor();
-                let logical_size = window.inner_size();
-                       let physical_size = winit::dpi::PhysicalSize::new(
-                    (logical_size.width as f64 * scale_factor) as u32,
-                    (logical_size.height as f64 * scale_factor) as u32,
-                );
-                self.resized(physical_size);
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: PlinthEvent) {
+        match event {
+            PlinthEvent::GraphicsReady(graphics) => {
+                self.state = State::Ready(graphics);
+                if let State::Ready(gfx) = &mut self.state
+                    && let Some(window) = &gfx.window
+                {
+                    let scale_factor = window.scale_factor();
+                    let logical_size = window.inner_size();
+                    let physical_size = winit::dpi::PhysicalSize::new(
+                        (logical_size.width as f64 * scale_factor) as u32,
+                        (logical_size.height as f64 * scale_factor) as u32,
+                    );
+                    self.resized(physical_size);
+                }
+                self.start();
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            PlinthEvent::LoadWake => self.poll_loading(),
+            #[cfg(target_arch = "wasm32")]
+            PlinthEvent::LoadWake => {}
+            PlinthEvent::Loaded => self.finish_loading(),
+        }
+        self.apply_control(event_loop);
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let State::Ready(gfx) = &mut self.state {
+            let time = self.clock.now();
+            self.user_app
+                .borrow_mut()
+                .on_close(&mut Context::new(gfx, time, &mut self.control));
         }
-        self.user_app.borrow_mut().init();
     }
 }
 
 // Winit-based app functions (always available since it's the default)
 #[cfg(feature = "winit")]
 pub fn start_app(user_app: Rc<RefCell<dyn PlinthApp>>) {
-    let event_loop = EventLoop::<Graphics>::with_user_event().build().unwrap();
+    let event_loop = EventLoop::<PlinthEvent>::with_user_event().build().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
     let app = App::new(&event_loop, user_app);
     run_app(event_loop, app);
@@ -149,7 +349,7 @@ pub fn start_app(user_app: Rc<RefCell<dyn PlinthApp>>) {
 
 #[cfg(feature = "winit")]
 #[cfg(target_arch = "wasm32")]
-fn run_app(event_loop: EventLoop<Graphics>, app: App) {
+fn run_app(event_loop: EventLoop<PlinthEvent>, app: App) {
     // Sets up panics to go to the console.error in browser environments
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     console_log::init_with_level(log::Level::Error).expect("Couldn't initialize logger");
@@ -163,7 +363,7 @@ fn run_app(event_loop: EventLoop<Graphics>, app: App) {
 
 #[cfg(feature = "winit")]
 #[cfg(not(target_arch = "wasm32"))]
-fn run_app(event_loop: EventLoop<Graphics>, mut app: App) {
+fn run_app(event_loop: EventLoop<PlinthEvent>, mut app: App) {
     // Allows the setting of the log level through RUST_LOG env var.
     // It also allows wgpu logs to be seen.
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("error")).init();
@@ -0,0 +1,261 @@
+use std::time::Duration;
+
+use crate::graphics::Graphics;
+
+#[cfg(feature = "winit")]
+use winit::window::{Fullscreen, Window};
+
+#[cfg(feature = "web-canvas")]
+use crate::web_canvas::WebCanvas;
+
+// Timing information for the current frame, handed to every PlinthApp hook
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameTime {
+    // Time since the app started running
+    pub elapsed: Duration,
+    // Time since the previous frame started
+    pub delta: Duration,
+    // Number of frames started so far
+    pub frame: u64,
+}
+
+// Light or dark appearance preferred by the OS (winit) or the browser (web)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+#[cfg(feature = "winit")]
+impl From<winit::window::Theme> for Theme {
+    fn from(theme: winit::window::Theme) -> Self {
+        match theme {
+            winit::window::Theme::Light => Self::Light,
+            winit::window::Theme::Dark => Self::Dark,
+        }
+    }
+}
+
+// Monotonic clock shared by the winit and web backends
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+#[derive(Debug)]
+pub(crate) struct FrameClock {
+    start: f64,
+    last: f64,
+    current: FrameTime,
+}
+
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+impl FrameClock {
+    pub(crate) fn new() -> Self {
+        let now = now_secs();
+        Self {
+            start: now,
+            last: now,
+            current: FrameTime::default(),
+        }
+    }
+
+    // Advances the clock at the start of a frame
+    pub(crate) fn tick(&mut self) -> FrameTime {
+        let now = now_secs();
+        self.current = FrameTime {
+            elapsed: Duration::from_secs_f64((now - self.start).max(0.0)),
+            delta: Duration::from_secs_f64((now - self.last).max(0.0)),
+            frame: self.current.frame + 1,
+        };
+        self.last = now;
+        self.current
+    }
+
+    // Time outside of a frame (init, events, close) without advancing the frame count
+    pub(crate) fn now(&self) -> FrameTime {
+        FrameTime {
+            elapsed: Duration::from_secs_f64((now_secs() - self.start).max(0.0)),
+            ..self.current
+        }
+    }
+}
+
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Wall clock in seconds. std::time::Instant panics on wasm32-unknown-unknown.
+#[cfg(all(target_arch = "wasm32", any(feature = "winit", feature = "web-canvas")))]
+fn now_secs() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
+
+#[cfg(all(not(target_arch = "wasm32"), any(feature = "winit", feature = "web-canvas")))]
+fn now_secs() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+// Requests made by the app during a hook. The backend applies them once the hook returns.
+#[derive(Debug, Default)]
+pub(crate) struct ControlState {
+    pub(crate) exit_requested: bool,
+    pub(crate) redraw_requested: bool,
+}
+
+impl ControlState {
+    #[cfg(feature = "winit")]
+    pub(crate) fn take(&mut self) -> ControlState {
+        std::mem::take(self)
+    }
+}
+
+// Handle passed to every PlinthApp hook. Exposes the graphics context, the window or
+// canvas, control flow (exit / redraw) and the frame clock, identically on winit and web.
+pub struct Context<'a> {
+    gfx: &'a mut Graphics,
+    time: FrameTime,
+    control: &'a mut ControlState,
+}
+
+impl<'a> Context<'a> {
+    #[cfg(any(feature = "winit", feature = "web-canvas"))]
+    pub(crate) fn new(gfx: &'a mut Graphics, time: FrameTime, control: &'a mut ControlState) -> Self {
+        Self { gfx, time, control }
+    }
+
+    pub fn graphics(&self) -> &Graphics {
+        self.gfx
+    }
+
+    pub fn graphics_mut(&mut self) -> &mut Graphics {
+        self.gfx
+    }
+
+    pub fn time(&self) -> FrameTime {
+        self.time
+    }
+
+    // Current surface size in physical pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.gfx.surface_config.width, self.gfx.surface_config.height)
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        #[cfg(feature = "winit")]
+        if let Some(window) = &self.gfx.window {
+            return window.scale_factor();
+        }
+        #[cfg(feature = "web-canvas")]
+        if self.gfx.canvas.is_some() {
+            return web_sys::window().map(|w| w.device_pixel_ratio()).unwrap_or(1.0);
+        }
+        1.0
+    }
+
+    // The OS or browser theme. Winit reports None on platforms without one; that reads as Light.
+    pub fn theme(&self) -> Theme {
+        #[cfg(feature = "winit")]
+        if let Some(window) = &self.gfx.window {
+            return window.theme().map(Theme::from).unwrap_or_default();
+        }
+        #[cfg(feature = "web-canvas")]
+        if self.gfx.canvas.is_some() {
+            return if media_matches("(prefers-color-scheme: dark)") { Theme::Dark } else { Theme::Light };
+        }
+        Theme::Light
+    }
+
+    // Whether the user asked for less motion. Only browsers expose this; native reads false.
+    pub fn prefers_reduced_motion(&self) -> bool {
+        #[cfg(feature = "web-canvas")]
+        if self.gfx.canvas.is_some() {
+            return media_matches("(prefers-reduced-motion: reduce)");
+        }
+        false
+    }
+
+    // Ends the app after the current hook returns. `on_close` is still called.
+    pub fn exit(&mut self) {
+        self.control.exit_requested = true;
+    }
+
+    pub fn exit_requested(&self) -> bool {
+        self.control.exit_requested
+    }
+
+    // Schedules another frame after the current one
+    pub fn request_redraw(&mut self) {
+        self.control.redraw_requested = true;
+    }
+
+    pub fn set_title(&self, title: &str) {
+        #[cfg(feature = "winit")]
+        if let Some(window) = &self.gfx.window {
+            window.set_title(title);
+            return;
+        }
+        #[cfg(feature = "web-canvas")]
+        if self.gfx.canvas.is_some() {
+            if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+                document.set_title(title);
+            }
+            return;
+        }
+        let _ = title;
+    }
+
+    pub fn set_fullscreen(&self, fullscreen: bool) {
+        #[cfg(feature = "winit")]
+        if let Some(window) = &self.gfx.window {
+            window.set_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+            return;
+        }
+        #[cfg(feature = "web-canvas")]
+        if let Some(canvas) = &self.gfx.canvas {
+            if fullscreen {
+                let _ = canvas.get_canvas().request_fullscreen();
+            } else if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+                document.exit_fullscreen();
+            }
+            return;
+        }
+        let _ = fullscreen;
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        #[cfg(feature = "winit")]
+        if let Some(window) = &self.gfx.window {
+            return window.fullscreen().is_some();
+        }
+        #[cfg(feature = "web-canvas")]
+        if self.gfx.canvas.is_some() {
+            return web_sys::window()
+                .and_then(|w| w.document())
+                .and_then(|d| d.fullscreen_element())
+                .is_some();
+        }
+        false
+    }
+
+    #[cfg(feature = "winit")]
+    pub fn window(&self) -> Option<&Window> {
+        self.gfx.window.as_deref()
+    }
+
+    #[cfg(feature = "web-canvas")]
+    pub fn canvas(&self) -> Option<&WebCanvas> {
+        self.gfx.canvas.as_deref()
+    }
+}
+
+#[cfg(feature = "web-canvas")]
+pub(crate) fn media_matches(query: &str) -> bool {
+    web_sys::window()
+        .and_then(|w| w.match_media(query).ok().flatten())
+        .is_some_and(|list| list.matches())
+}
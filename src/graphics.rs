@@ -1,10 +1,20 @@
-use std::cell::RefCell;
-
 use wgpu::{
-    Adapter, Device, DeviceDescriptor, Features, Instance, Limits, MemoryHints, PowerPreference,
-    Queue, RenderPipeline, RequestAdapterOptions, Surface, SurfaceConfiguration,
+    Adapter, BindGroupLayout, ComputePipeline, Device, Instance, Queue, RenderPipeline, Sampler,
+    Surface, SurfaceConfiguration, TextureFormat, TextureView,
 };
 
+use crate::pipelines::{BuildFn, Handle, PipelineRegistry, PipelineTargets};
+use crate::shaders::ShaderLibrary;
+use crate::textures::{TextureError, TextureFilter, TextureId, TextureManager};
+#[cfg(feature = "winit")]
+use crate::app::PlinthEvent;
+
+// Only the backends create graphics
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+use std::cell::RefCell;
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+use wgpu::{DeviceDescriptor, Features, Limits, MemoryHints, PowerPreference, RequestAdapterOptions};
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
 use crate::plinth_app::PlinthApp;
 
 // Conditional imports
@@ -26,7 +36,7 @@ pub type Rc<T> = std::sync::Arc<T>;
 #[cfg(feature = "winit")]
 pub async fn create_graphics(
     window: Rc<Window>,
-    proxy: EventLoopProxy<Graphics>,
+    proxy: EventLoopProxy<PlinthEvent>,
     user_app: Rc<RefCell<dyn PlinthApp>>,
 ) {
     let instance = Instance::default();
@@ -61,7 +71,10 @@ pub async fn create_graphics(
     #[cfg(not(target_arch = "wasm32"))]
     surface.configure(&device, &surface_config);
 
-    let render_pipelines = vec![];
+    let pipelines = PipelineRegistry::new(PipelineTargets {
+        format: surface_config.format,
+        sample_count: 1,
+    });
 
     let mut gfx = Graphics {
         #[cfg(feature = "winit")]
@@ -74,14 +87,14 @@ pub async fn create_graphics(
         _adapter: adapter,
         device,
         queue,
-        render_pipelines,
+        pipelines,
+        msaa_view: None,
+        textures: TextureManager::new(),
     };
 
-    let render_pipeline = user_app.borrow_mut().create_pipeline(&mut gfx);
-
-    gfx.render_pipelines.push(render_pipeline);
+    user_app.borrow_mut().create_pipelines(&mut gfx);
 
-    let _ = proxy.send_event(gfx);
+    let _ = proxy.send_event(PlinthEvent::GraphicsReady(Box::new(gfx)));
 }
 
 // Web canvas-based graphics creation
@@ -161,7 +174,10 @@ pub async fn create_graphics_web(
     let surface_config = surface.get_default_config(&adapter, width, height).unwrap();
     surface.configure(&device, &surface_config);
 
-    let render_pipelines = vec![];
+    let pipelines = PipelineRegistry::new(PipelineTargets {
+        format: surface_config.format,
+        sample_count: 1,
+    });
 
     let mut gfx = Graphics {
         #[cfg(feature = "winit")]
@@ -174,15 +190,24 @@ pub async fn create_graphics_web(
         _adapter: adapter,
         device,
         queue,
-        render_pipelines,
+        pipelines,
+        msaa_view: None,
+        textures: TextureManager::new(),
     };
 
-    let render_pipeline = user_app.borrow_mut().create_pipeline(&mut gfx);
-    gfx.render_pipelines.push(render_pipeline);
+    user_app.borrow_mut().create_pipelines(&mut gfx);
 
     gfx
 }
 
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum GraphicsError {
+    #[error("Surface format {0:?} is not supported by the surface")]
+    UnsupportedSurfaceFormat(TextureFormat),
+    #[error("Sample count {0} is not supported for {1:?}")]
+    UnsupportedSampleCount(u32, TextureFormat),
+}
+
 #[derive(Debug)]
 pub struct Graphics {
     #[cfg(feature = "winit")]
@@ -195,7 +220,11 @@ pub struct Graphics {
     pub _adapter: Adapter,
     pub device: Device,
     pub queue: Queue,
-    pub render_pipelines: Vec<RenderPipeline>,
+    pub pipelines: PipelineRegistry,
+    // Multisampled color target, present while the sample count is above 1
+    pub msaa_view: Option<TextureView>,
+    // Images packed into atlas pages, drawn by sprites
+    pub textures: TextureManager,
 }
 
 impl Graphics {
@@ -203,13 +232,175 @@ impl Graphics {
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         self.surface_config.width = new_size.width.max(1);
         self.surface_config.height = new_size.height.max(1);
-        self.surface.configure(&self.device, &self.surface_config);
+        self.reconfigure();
     }
 
     #[cfg(feature = "web-canvas")]
     pub fn resize_web(&mut self, new_size: crate::web_canvas::WebSize) {
         self.surface_config.width = new_size.width.max(1);
         self.surface_config.height = new_size.height.max(1);
+        self.reconfigure();
+    }
+
+    // Applies `surface_config` to the surface and rebuilds pipelines if the format changed
+    pub fn reconfigure(&mut self) {
         self.surface.configure(&self.device, &self.surface_config);
+        let targets = PipelineTargets {
+            format: self.surface_config.format,
+            ..self.pipelines.targets()
+        };
+        self.pipelines.set_targets(&self.device, targets);
+        self.update_msaa_view();
+    }
+
+    // Switches the surface to `format`, which must be one the surface supports and, with MSAA
+    // on, multisample at the current sample count
+    pub fn set_surface_format(&mut self, format: TextureFormat) -> Result<(), GraphicsError> {
+        if !self.surface.get_capabilities(&self._adapter).formats.contains(&format) {
+            return Err(GraphicsError::UnsupportedSurfaceFormat(format));
+        }
+        self.check_sample_count(self.sample_count(), format)?;
+        self.surface_config.format = format;
+        self.reconfigure();
+        Ok(())
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.pipelines.targets().sample_count
+    }
+
+    // Switches MSAA on (count > 1) or off
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<(), GraphicsError> {
+        self.check_sample_count(sample_count, self.surface_config.format)?;
+        let targets = PipelineTargets {
+            sample_count,
+            ..self.pipelines.targets()
+        };
+        self.pipelines.set_targets(&self.device, targets);
+        self.update_msaa_view();
+        Ok(())
+    }
+
+    fn check_sample_count(&self, sample_count: u32, format: TextureFormat) -> Result<(), GraphicsError> {
+        let flags = self._adapter.get_texture_format_features(format).flags;
+        if sample_count > 1 && !flags.sample_count_supported(sample_count) {
+            return Err(GraphicsError::UnsupportedSampleCount(sample_count, format));
+        }
+        Ok(())
+    }
+
+    fn update_msaa_view(&mut self) {
+        let targets = self.pipelines.targets();
+        if targets.sample_count <= 1 {
+            self.msaa_view = None;
+            return;
+        }
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA Color Target"),
+            size: wgpu::Extent3d {
+                width: self.surface_config.width,
+                height: self.surface_config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: targets.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: targets.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        self.msaa_view = Some(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+    }
+
+    // Color attachment for a frame: renders into the MSAA target and resolves into `view`
+    // when multisampling is on, otherwise renders straight into `view`.
+    pub fn color_attachment<'a>(
+        &'a self,
+        view: &'a TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        let (view, resolve_target) = match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(view)),
+            None => (view, None),
+        };
+        wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+            depth_slice: None,
+        }
+    }
+
+    pub fn register_render_pipeline(
+        &mut self,
+        name: &str,
+        builder: impl BuildFn<RenderPipeline> + 'static,
+    ) -> Handle<RenderPipeline> {
+        self.pipelines.register_render(&self.device, name, builder)
+    }
+
+    pub fn register_compute_pipeline(
+        &mut self,
+        name: &str,
+        builder: impl BuildFn<ComputePipeline> + 'static,
+    ) -> Handle<ComputePipeline> {
+        self.pipelines.register_compute(&self.device, name, builder)
+    }
+
+    pub fn register_bind_group_layout(
+        &mut self,
+        name: &str,
+        builder: impl BuildFn<BindGroupLayout> + 'static,
+    ) -> Handle<BindGroupLayout> {
+        self.pipelines.register_bind_group_layout(&self.device, name, builder)
+    }
+
+    pub fn register_sampler(
+        &mut self,
+        name: &str,
+        builder: impl BuildFn<Sampler> + 'static,
+    ) -> Handle<Sampler> {
+        self.pipelines.register_sampler(&self.device, name, builder)
+    }
+
+    pub fn render_pipeline(&self, name: &str) -> Option<&RenderPipeline> {
+        self.pipelines.render.get_by_name(name)
+    }
+
+    pub fn compute_pipeline(&self, name: &str) -> Option<&ComputePipeline> {
+        self.pipelines.compute.get_by_name(name)
+    }
+
+    pub fn bind_group_layout(&self, name: &str) -> Option<&BindGroupLayout> {
+        self.pipelines.bind_group_layouts.get_by_name(name)
+    }
+
+    pub fn sampler(&self, name: &str) -> Option<&Sampler> {
+        self.pipelines.samplers.get_by_name(name)
+    }
+
+    // Decodes a PNG or JPEG image into `textures` under `name`
+    pub fn load_texture(&mut self, name: &str, bytes: &[u8], filter: TextureFilter) -> Result<TextureId, TextureError> {
+        self.textures.load(name, bytes, filter)
+    }
+
+    pub fn shaders(&self) -> &ShaderLibrary {
+        &self.pipelines.shaders
+    }
+
+    // Watches the WGSL files registered pipelines were built from. Edits are picked up by
+    // `reload_shaders`, which the app loop calls once per frame.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn enable_shader_hot_reload(&mut self) -> Result<(), notify::Error> {
+        self.pipelines.enable_hot_reload()
+    }
+
+    // Rebuilds pipelines whose shaders changed on disk. A shader that fails to compile is
+    // logged and its pipelines keep their last good version.
+    pub fn reload_shaders(&mut self) -> Vec<String> {
+        self.pipelines.reload_shaders(&self.device)
     }
 }
@@ -0,0 +1,37 @@
+// Backend-independent pointer input, delivered through PlinthApp::pointer_event by both the
+// winit loop and the web canvas loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerEvent {
+    pub kind: PointerEventKind,
+    // Physical pixels from the top-left of the surface
+    pub position: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerEventKind {
+    Moved,
+    Pressed(PointerButton),
+    Released(PointerButton),
+    // The pointer left the window or canvas
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerButton {
+    Primary,
+    Secondary,
+    Middle,
+    Other(u16),
+}
+
+impl PointerEvent {
+    pub fn new(kind: PointerEventKind, position: (f32, f32)) -> Self {
+        Self { kind, position }
+    }
+
+    // Position in clip space ([-1, 1], y up) for a surface of `size` physical pixels
+    pub fn clip_position(&self, size: (u32, u32)) -> (f32, f32) {
+        let (width, height) = (size.0.max(1) as f32, size.1.max(1) as f32);
+        (self.position.0 / width * 2.0 - 1.0, 1.0 - self.position.1 / height * 2.0)
+    }
+}
@@ -1,6 +1,12 @@
 pub mod app;
+pub mod context;
 pub mod graphics;
+pub mod input;
+pub mod loading;
+pub mod pipelines;
 pub mod plinth_app;
+pub mod shaders;
+pub mod textures;
 
 #[cfg(feature = "web-canvas")]
 pub mod web_canvas;
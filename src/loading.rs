@@ -0,0 +1,79 @@
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+use std::task::{Context as TaskContext, Poll};
+
+// Future returned from `PlinthApp::load`. Runs on the main thread (pollster / event loop
+// proxy on native, `spawn_local` on wasm), so it doesn't need to be `Send`.
+pub type LoadFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+// Drives every load future to completion, concurrently, on a single thread
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+pub(crate) struct JoinAll {
+    pending: Vec<LoadFuture>,
+}
+
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+impl JoinAll {
+    pub(crate) fn new(futures: Vec<LoadFuture>) -> Self {
+        Self { pending: futures }
+    }
+}
+
+#[cfg(any(feature = "winit", feature = "web-canvas"))]
+impl Future for JoinAll {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<()> {
+        self.pending.retain_mut(|future| future.as_mut().poll(cx).is_pending());
+        if self.pending.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error("Failed to read {0}: {1}")]
+    Io(String, String),
+    #[error("Failed to fetch {0}: {1}")]
+    Fetch(String, String),
+}
+
+// Reads an asset: from disk on native, through `fetch` in the browser
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn load_bytes(path: &str) -> Result<Vec<u8>, LoadError> {
+    std::fs::read(path).map_err(|e| LoadError::Io(path.to_string(), e.to_string()))
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web-canvas"))]
+pub async fn load_bytes(path: &str) -> Result<Vec<u8>, LoadError> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let fetch_err = |e: wasm_bindgen::JsValue| LoadError::Fetch(path.to_string(), format!("{:?}", e));
+
+    let window = web_sys::window().ok_or_else(|| LoadError::Fetch(path.to_string(), "No window".into()))?;
+    let response = JsFuture::from(window.fetch_with_str(path)).await.map_err(fetch_err)?;
+    let response: web_sys::Response = response.dyn_into().map_err(fetch_err)?;
+    if !response.ok() {
+        return Err(LoadError::Fetch(path.to_string(), format!("HTTP {}", response.status())));
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(fetch_err)?)
+        .await
+        .map_err(fetch_err)?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web-canvas"))]
+pub async fn load_string(path: &str) -> Result<String, LoadError> {
+    let bytes = load_bytes(path).await?;
+    String::from_utf8(bytes).map_err(|e| LoadError::Fetch(path.to_string(), e.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn load_string(path: &str) -> Result<String, LoadError> {
+    std::fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_string(), e.to_string()))
+}
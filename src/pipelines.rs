@@ -0,0 +1,404 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use wgpu::{BindGroupLayout, ComputePipeline, Device, RenderPipeline, Sampler, ShaderModule, TextureFormat};
+
+use crate::shaders::{ShaderLibrary, ShaderSource};
+
+// Everything a render pipeline depends on from the surface. When this changes, every
+// registered render pipeline is rebuilt from its builder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineTargets {
+    pub format: TextureFormat,
+    pub sample_count: u32,
+}
+
+impl PipelineTargets {
+    pub fn multisample(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        }
+    }
+}
+
+// Passed to every builder. Bind group layouts registered earlier can be looked up by name
+// so pipeline layouts can share them.
+pub struct BuildContext<'a> {
+    pub device: &'a Device,
+    pub targets: PipelineTargets,
+    pub bind_group_layouts: &'a Registry<BindGroupLayout>,
+    pub shaders: &'a ShaderLibrary,
+    // Shader files read by the builder, so the entry can be rebuilt when one changes
+    dependencies: RefCell<Vec<PathBuf>>,
+}
+
+impl<'a> BuildContext<'a> {
+    fn new(
+        device: &'a Device,
+        targets: PipelineTargets,
+        bind_group_layouts: &'a Registry<BindGroupLayout>,
+        shaders: &'a ShaderLibrary,
+    ) -> Self {
+        Self {
+            device,
+            targets,
+            bind_group_layouts,
+            shaders,
+            dependencies: RefCell::new(Vec::new()),
+        }
+    }
+
+    // Compiles `shader`, preferring the on-disk copy when hot reload is watching it. Builders
+    // should create their modules through this so shader edits rebuild the pipeline.
+    pub fn create_shader_module(&self, shader: &ShaderSource) -> ShaderModule {
+        if let Some(path) = shader.dev_path {
+            self.dependencies.borrow_mut().push(PathBuf::from(path));
+        }
+        self.shaders.create_module(self.device, shader)
+    }
+
+    fn into_dependencies(self) -> Vec<PathBuf> {
+        self.dependencies.into_inner()
+    }
+}
+
+// Builder closure for a registry entry. Graphics crosses the event loop proxy on native,
+// so builders must be Send + Sync there (wasm has no such requirement).
+#[cfg(not(target_arch = "wasm32"))]
+pub trait BuildFn<T>: Fn(&BuildContext) -> T + Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T, F: Fn(&BuildContext) -> T + Send + Sync> BuildFn<T> for F {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait BuildFn<T>: Fn(&BuildContext) -> T {}
+#[cfg(target_arch = "wasm32")]
+impl<T, F: Fn(&BuildContext) -> T> BuildFn<T> for F {}
+
+pub type Builder<T> = Box<dyn BuildFn<T>>;
+
+// Stable reference to a registry entry. Stays valid when the entry is rebuilt or re-registered.
+pub struct Handle<T> {
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    fn new(index: usize) -> Self {
+        Self {
+            index,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+struct Entry<T> {
+    name: String,
+    value: T,
+    builder: Builder<T>,
+    dependencies: Vec<PathBuf>,
+}
+
+// Named GPU objects of one kind, each kept together with the builder that created it
+pub struct Registry<T> {
+    entries: Vec<Entry<T>>,
+    by_name: HashMap<String, usize>,
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    // Builds and stores `value`. Registering an existing name replaces that entry in place,
+    // so handles to it keep working.
+    fn insert(&mut self, name: &str, value: T, builder: Builder<T>, dependencies: Vec<PathBuf>) -> Handle<T> {
+        let entry = Entry {
+            name: name.to_string(),
+            value,
+            builder,
+            dependencies,
+        };
+        match self.by_name.get(name) {
+            Some(&index) => {
+                self.entries[index] = entry;
+                Handle::new(index)
+            }
+            None => {
+                self.entries.push(entry);
+                let index = self.entries.len() - 1;
+                self.by_name.insert(name.to_string(), index);
+                Handle::new(index)
+            }
+        }
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> &T {
+        &self.entries[handle.index].value
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&T> {
+        self.by_name.get(name).map(|&index| &self.entries[index].value)
+    }
+
+    pub fn handle(&self, name: &str) -> Option<Handle<T>> {
+        self.by_name.get(name).map(|&index| Handle::new(index))
+    }
+
+    pub fn name(&self, handle: Handle<T>) -> &str {
+        &self.entries[handle.index].name
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn rebuild_entry(&mut self, index: usize, ctx: &BuildContext) {
+        let entry = &mut self.entries[index];
+        entry.value = (entry.builder)(ctx);
+    }
+
+    // Rebuilds every entry built from one of the `changed` shader files. A shader that fails
+    // validation is logged and the entry keeps its last good value.
+    fn rebuild_dependents<'a>(
+        &mut self,
+        device: &Device,
+        changed: &[PathBuf],
+        mut ctx: impl FnMut() -> BuildContext<'a>,
+    ) -> Vec<String> {
+        let mut rebuilt = Vec::new();
+        for entry in &mut self.entries {
+            if !entry.dependencies.iter().any(|path| changed.contains(path)) {
+                continue;
+            }
+            let ctx = ctx();
+            match crate::shaders::try_build(device, || (entry.builder)(&ctx)) {
+                Ok(value) => {
+                    entry.value = value;
+                    entry.dependencies = ctx.into_dependencies();
+                    log::info!("Rebuilt pipeline '{}'", entry.name);
+                    rebuilt.push(entry.name.clone());
+                }
+                Err(e) => log::error!("Keeping previous '{}' pipeline, shader failed to compile:\n{}", entry.name, e),
+            }
+        }
+        rebuilt
+    }
+
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    fn dependencies(&self) -> impl Iterator<Item = &PathBuf> {
+        self.entries.iter().flat_map(|entry| entry.dependencies.iter())
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Registry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+// Registry of every pipeline and pipeline-related object owned by Graphics
+#[derive(Debug)]
+pub struct PipelineRegistry {
+    targets: PipelineTargets,
+    pub render: Registry<RenderPipeline>,
+    pub compute: Registry<ComputePipeline>,
+    pub bind_group_layouts: Registry<BindGroupLayout>,
+    pub samplers: Registry<Sampler>,
+    pub shaders: ShaderLibrary,
+}
+
+impl PipelineRegistry {
+    pub fn new(targets: PipelineTargets) -> Self {
+        Self {
+            targets,
+            render: Registry::new(),
+            compute: Registry::new(),
+            bind_group_layouts: Registry::new(),
+            samplers: Registry::new(),
+            shaders: ShaderLibrary::new(),
+        }
+    }
+
+    pub fn targets(&self) -> PipelineTargets {
+        self.targets
+    }
+
+    pub fn register_render(
+        &mut self,
+        device: &Device,
+        name: &str,
+        builder: impl BuildFn<RenderPipeline> + 'static,
+    ) -> Handle<RenderPipeline> {
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        let value = builder(&ctx);
+        let dependencies = ctx.into_dependencies();
+        self.watch(&dependencies);
+        self.render.insert(name, value, Box::new(builder), dependencies)
+    }
+
+    pub fn register_compute(
+        &mut self,
+        device: &Device,
+        name: &str,
+        builder: impl BuildFn<ComputePipeline> + 'static,
+    ) -> Handle<ComputePipeline> {
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        let value = builder(&ctx);
+        let dependencies = ctx.into_dependencies();
+        self.watch(&dependencies);
+        self.compute.insert(name, value, Box::new(builder), dependencies)
+    }
+
+    // Replacing a layout that is already registered rebuilds every render and compute
+    // pipeline, since builders look layouts up by name and any of them may use it
+    pub fn register_bind_group_layout(
+        &mut self,
+        device: &Device,
+        name: &str,
+        builder: impl BuildFn<BindGroupLayout> + 'static,
+    ) -> Handle<BindGroupLayout> {
+        let replaced = self.bind_group_layouts.contains(name);
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        let value = builder(&ctx);
+        let dependencies = ctx.into_dependencies();
+        self.watch(&dependencies);
+        let handle = self.bind_group_layouts.insert(name, value, Box::new(builder), dependencies);
+        if replaced {
+            log::info!("Rebuilding pipelines for replaced bind group layout '{}'", name);
+            self.rebuild_render(device);
+            self.rebuild_compute(device);
+        }
+        handle
+    }
+
+    pub fn register_sampler(
+        &mut self,
+        device: &Device,
+        name: &str,
+        builder: impl BuildFn<Sampler> + 'static,
+    ) -> Handle<Sampler> {
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        let value = builder(&ctx);
+        let dependencies = ctx.into_dependencies();
+        self.watch(&dependencies);
+        self.samplers.insert(name, value, Box::new(builder), dependencies)
+    }
+
+    // Rebuilds every render pipeline if the targets differ from the ones they were built for.
+    // Returns whether anything was rebuilt.
+    pub fn set_targets(&mut self, device: &Device, targets: PipelineTargets) -> bool {
+        if self.targets == targets {
+            return false;
+        }
+        log::info!("Rebuilding {} render pipelines for {:?}", self.render.len(), targets);
+        self.targets = targets;
+        self.rebuild_render(device);
+        true
+    }
+
+    pub fn rebuild_render(&mut self, device: &Device) {
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        for index in 0..self.render.len() {
+            self.render.rebuild_entry(index, &ctx);
+        }
+    }
+
+    pub fn rebuild_compute(&mut self, device: &Device) {
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        for index in 0..self.compute.len() {
+            self.compute.rebuild_entry(index, &ctx);
+        }
+    }
+
+    // Rebuilds a single render pipeline, e.g. after its shader source changed
+    pub fn rebuild_render_pipeline(&mut self, device: &Device, handle: Handle<RenderPipeline>) {
+        let ctx = BuildContext::new(device, self.targets, &self.bind_group_layouts, &self.shaders);
+        self.render.rebuild_entry(handle.index, &ctx);
+    }
+
+    fn watch(&mut self, dependencies: &[PathBuf]) {
+        for path in dependencies {
+            self.shaders.watch(path);
+        }
+    }
+
+    // Starts watching every shader registered pipelines were built from, and any registered later
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn enable_hot_reload(&mut self) -> Result<(), notify::Error> {
+        self.shaders.enable_hot_reload()?;
+        let dependencies: Vec<PathBuf> = self
+            .render
+            .dependencies()
+            .chain(self.compute.dependencies())
+            .cloned()
+            .collect();
+        self.watch(&dependencies);
+        Ok(())
+    }
+
+    // Picks up shader edits on disk and rebuilds the render and compute pipelines that use
+    // them. Returns the names of the rebuilt pipelines.
+    pub fn reload_shaders(&mut self, device: &Device) -> Vec<String> {
+        let changed = self.shaders.poll().to_vec();
+        if changed.is_empty() {
+            return Vec::new();
+        }
+        let (targets, layouts, shaders) = (self.targets, &self.bind_group_layouts, &self.shaders);
+        let mut rebuilt = self
+            .render
+            .rebuild_dependents(device, &changed, || BuildContext::new(device, targets, layouts, shaders));
+        rebuilt.extend(
+            self.compute
+                .rebuild_dependents(device, &changed, || BuildContext::new(device, targets, layouts, shaders)),
+        );
+        rebuilt
+    }
+}
@@ -1,28 +1,38 @@
-use std::borrow::Cow;
-
 use wgpu::{
-    Color, CommandEncoderDescriptor, FragmentState, LoadOp, Operations, RenderPassColorAttachment,
-    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, ShaderModuleDescriptor,
-    ShaderSource, StoreOp, TextureViewDescriptor, VertexState,
+    Color, CommandEncoderDescriptor, FragmentState, LoadOp, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, TextureViewDescriptor, VertexState,
 };
+
+use crate::pipelines::BuildContext;
+use crate::shader_source;
+use crate::shaders::ShaderSource;
 #[cfg(feature = "winit")]
-use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::WindowId};
+use winit::{event::WindowEvent, window::WindowId};
 
+use crate::context::{Context, Theme};
 use crate::graphics::Graphics;
+use crate::input::PointerEvent;
+use crate::loading::LoadFuture;
 
 pub trait PlinthApp: PlinthRenderer {
-    fn init(&mut self) {}
-    fn before_render(&mut self) {}
-    fn after_render(&mut self) {}
-    #[cfg(feature = "winit")]
-    fn event_handler(
-        &mut self,
-        _event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
-        _event: &WindowEvent,
-    ) {
+    fn init(&mut self, _ctx: &mut Context) {}
+    // Called right after `init`. Futures returned here (fetching textures, fonts, shaders...)
+    // are driven to completion before the first frame; `render_loading` is drawn meanwhile.
+    // Futures are 'static, so hand results back through shared state and pick them up in `loaded`.
+    fn load(&mut self, _ctx: &mut Context) -> Vec<LoadFuture> {
+        Vec::new()
     }
-    fn on_close(&mut self) {}
+    // Called once every future returned by `load` has completed (immediately if there were none)
+    fn loaded(&mut self, _ctx: &mut Context) {}
+    fn before_render(&mut self, _ctx: &mut Context) {}
+    fn after_render(&mut self, _ctx: &mut Context) {}
+    #[cfg(feature = "winit")]
+    fn event_handler(&mut self, _ctx: &mut Context, _window_id: WindowId, _event: &WindowEvent) {}
+    // Mouse, pen and touch input from either backend, for hit-testing and interaction
+    fn pointer_event(&mut self, _ctx: &mut Context, _event: &PointerEvent) {}
+    // The OS or browser switched between light and dark; `ctx.theme()` already reports `theme`
+    fn theme_changed(&mut self, _ctx: &mut Context, _theme: Theme) {}
+    fn on_close(&mut self, _ctx: &mut Context) {}
 }
 
 pub trait PlinthRenderer {
@@ -41,54 +51,81 @@ pub trait PlinthRenderer {
         {
             let mut r_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: None,
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
+                color_attachments: &[Some(gfx.color_attachment(&view, LoadOp::Clear(Color::BLACK)))],
                 depth_stencil_attachment: None,
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
-            r_pass.set_pipeline(&gfx.render_pipelines[0]);
+            let pipeline = gfx
+                .render_pipeline(DEFAULT_PIPELINE)
+                .expect("No render pipeline registered as DEFAULT_PIPELINE");
+            r_pass.set_pipeline(pipeline);
             r_pass.draw(0..3, 0..1);
         } // `r_pass` dropped here
 
         gfx.queue.submit(Some(encoder.finish()));
         frame.present();
     }
-    fn create_pipeline(&mut self, gfx: &mut Graphics) -> RenderPipeline {
-        let device = &gfx.device;
-        let swap_chain_format = gfx.surface_config.format;
-        let shader = device.create_shader_module(ShaderModuleDescriptor {
-            label: None,
-            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/shader.wgsl"))),
+    // Drawn instead of `render` while the futures from `PlinthApp::load` are pending
+    fn render_loading(&mut self, gfx: &mut Graphics) {
+        let frame = gfx
+            .surface
+            .get_current_texture()
+            .expect("Failed to aquire next swap chain texture.");
+
+        let view = frame.texture.create_view(&TextureViewDescriptor::default());
+
+        let mut encoder = gfx
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Loading"),
+            color_attachments: &[Some(gfx.color_attachment(&view, LoadOp::Clear(Color::BLACK)))],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
         });
 
-        device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: None,
-            layout: None,
-            vertex: VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(swap_chain_format.into())],
-                compilation_options: Default::default(),
-            }),
-            primitive: Default::default(),
-            depth_stencil: None,
-            multisample: Default::default(),
-            multiview: None,
-            cache: None,
-        })
+        gfx.queue.submit(Some(encoder.finish()));
+        frame.present();
+    }
+    // Registers the app's pipelines, bind group layouts and samplers in `gfx.pipelines`.
+    // Entries are rebuilt from their builders when the surface format or sample count changes.
+    fn create_pipelines(&mut self, gfx: &mut Graphics) {
+        gfx.register_render_pipeline(DEFAULT_PIPELINE, default_pipeline);
     }
 }
+
+// Name of the pipeline drawn by the default `PlinthRenderer::render`
+pub const DEFAULT_PIPELINE: &str = "default";
+
+const DEFAULT_SHADER: ShaderSource = shader_source!("src/shaders/shader.wgsl");
+
+fn default_pipeline(ctx: &BuildContext) -> RenderPipeline {
+    let device = ctx.device;
+    let swap_chain_format = ctx.targets.format;
+    let shader = ctx.create_shader_module(&DEFAULT_SHADER);
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: None,
+        layout: None,
+        vertex: VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(swap_chain_format.into())],
+            compilation_options: Default::default(),
+        }),
+        primitive: Default::default(),
+        depth_stencil: None,
+        multisample: ctx.targets.multisample(),
+        multiview: None,
+        cache: None,
+    })
+}
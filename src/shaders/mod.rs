@@ -0,0 +1,185 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use wgpu::{Device, ShaderModule, ShaderModuleDescriptor};
+
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+mod watcher;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+pub use watcher::ShaderWatcher;
+
+// A WGSL shader baked into the binary, optionally paired with the file it was baked from.
+// With hot reload enabled the file on disk is watched and takes precedence.
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderSource {
+    pub label: &'static str,
+    pub embedded: &'static str,
+    pub dev_path: Option<&'static str>,
+}
+
+impl ShaderSource {
+    pub const fn new(label: &'static str, embedded: &'static str) -> Self {
+        Self {
+            label,
+            embedded,
+            dev_path: None,
+        }
+    }
+
+    pub const fn with_dev_path(mut self, path: &'static str) -> Self {
+        self.dev_path = Some(path);
+        self
+    }
+}
+
+// Embeds a shader relative to the calling crate's manifest directory, e.g.
+// `shader_source!("src/shaders/circle.wgsl")`. With hot reload it also remembers the file's
+// absolute path; otherwise no build machine paths end up in the binary. Chosen here rather
+// than in the expansion, where the cfg would test the calling crate's features.
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+#[macro_export]
+macro_rules! shader_source {
+    ($path:literal) => {
+        $crate::shaders::ShaderSource::new(
+            $path,
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)),
+        )
+        .with_dev_path(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path))
+    };
+}
+
+#[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+#[macro_export]
+macro_rules! shader_source {
+    ($path:literal) => {
+        $crate::shaders::ShaderSource::new($path, include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)))
+    };
+}
+
+// Latest shader text for every watched file. Without hot reload this only ever hands out
+// the embedded sources.
+#[derive(Debug, Default)]
+pub struct ShaderLibrary {
+    // Latest on-disk contents of watched files
+    loaded: HashMap<PathBuf, String>,
+    // Files whose contents changed during the last poll
+    changed: Vec<PathBuf>,
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    watcher: Option<ShaderWatcher>,
+}
+
+impl ShaderLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Text to compile for `shader`: the on-disk copy when it is being watched, else the embedded one
+    pub fn source(&self, shader: &ShaderSource) -> Cow<'_, str> {
+        shader
+            .dev_path
+            .and_then(|path| self.loaded.get(Path::new(path)))
+            .map(|source| Cow::Borrowed(source.as_str()))
+            .unwrap_or(Cow::Borrowed(shader.embedded))
+    }
+
+    pub fn create_module(&self, device: &Device, shader: &ShaderSource) -> ShaderModule {
+        device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(shader.label),
+            source: wgpu::ShaderSource::Wgsl(self.source(shader)),
+        })
+    }
+
+    pub fn changed(&self) -> &[PathBuf] {
+        &self.changed
+    }
+
+    pub fn has_changed(&self, path: &str) -> bool {
+        self.changed.iter().any(|changed| changed == Path::new(path))
+    }
+
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        {
+            self.watcher.is_some()
+        }
+        #[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+        {
+            false
+        }
+    }
+
+    // Starts watching `.wgsl` files on disk. Only available on native with the `hot-reload` feature.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn enable_hot_reload(&mut self) -> Result<(), notify::Error> {
+        if self.watcher.is_none() {
+            self.watcher = Some(ShaderWatcher::new()?);
+        }
+        Ok(())
+    }
+
+    // Starts watching `path` if hot reload is enabled. Called for every shader a registered
+    // pipeline is built from.
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        if let Some(watcher) = &mut self.watcher {
+            if self.loaded.contains_key(path) {
+                return;
+            }
+            match std::fs::read_to_string(path) {
+                Ok(source) => {
+                    self.loaded.insert(path.to_path_buf(), source);
+                    if let Err(e) = watcher.watch(path) {
+                        log::error!("Failed to watch shader {}: {}", path.display(), e);
+                    }
+                }
+                Err(e) => log::warn!("Shader {} not found on disk, using embedded copy: {}", path.display(), e),
+            }
+        }
+        #[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+        let _ = path;
+    }
+
+    // Re-reads every watched file that changed since the last poll and returns their paths
+    pub fn poll(&mut self) -> &[PathBuf] {
+        self.changed.clear();
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        if let Some(watcher) = &mut self.watcher {
+            for path in watcher.poll() {
+                let Some(previous) = self.loaded.get_mut(&path) else {
+                    continue;
+                };
+                match std::fs::read_to_string(&path) {
+                    Ok(source) if source != *previous => {
+                        log::info!("Shader changed: {}", path.display());
+                        *previous = source;
+                        self.changed.push(path);
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::error!("Failed to reload shader {}: {}", path.display(), e),
+                }
+            }
+        }
+        &self.changed
+    }
+}
+
+// Builds a GPU object, catching validation errors (e.g. a shader that no longer compiles)
+// instead of letting wgpu panic. Used when rebuilding after a shader changed so the last
+// good object can be kept.
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+pub fn try_build<T>(device: &Device, build: impl FnOnce() -> T) -> Result<T, wgpu::Error> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = build();
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
+
+// Without hot reload shaders never change at runtime, so there is nothing to catch
+#[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+pub fn try_build<T>(_device: &Device, build: impl FnOnce() -> T) -> Result<T, wgpu::Error> {
+    Ok(build())
+}
@@ -0,0 +1,78 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+// Watches shader files on disk. Parent directories are watched rather than the files
+// themselves so editors that save by renaming a temp file are picked up too.
+pub struct ShaderWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    // Canonical path -> path as registered
+    files: HashMap<PathBuf, PathBuf>,
+    directories: HashSet<PathBuf>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })?;
+        Ok(Self {
+            watcher,
+            events,
+            files: HashMap::new(),
+            directories: HashSet::new(),
+        })
+    }
+
+    pub fn watch(&mut self, path: &Path) -> notify::Result<()> {
+        let canonical = normalize(path);
+        if let Some(directory) = canonical.parent()
+            && !self.directories.contains(directory)
+        {
+            self.watcher.watch(directory, RecursiveMode::NonRecursive)?;
+            self.directories.insert(directory.to_path_buf());
+        }
+        self.files.insert(canonical, path.to_path_buf());
+        Ok(())
+    }
+
+    // Watched files modified since the last call, deduplicated
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    log::error!("Shader watcher error: {}", e);
+                    continue;
+                }
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+            for path in event.paths {
+                // Report paths the way they were registered
+                if let Some(registered) = self.files.get(&normalize(&path))
+                    && !changed.contains(registered)
+                {
+                    changed.push(registered.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+impl std::fmt::Debug for ShaderWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShaderWatcher").field("files", &self.files.values().collect::<Vec<_>>()).finish()
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+use std::ops::Range;
+
+use etagere::{size2, BucketedAtlasAllocator};
+use wgpu::{Device, Queue, Texture, TextureView};
+
+// Texels around each texture repeating its edge, so linear filtering at its border doesn't
+// pick up its neighbours
+const PADDING: u32 = 1;
+const BYTES_PER_TEXEL: u32 = 4;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TextureError {
+    #[error("Failed to decode texture {0}: {1}")]
+    Decode(String, String),
+    #[error("Texture {0} expects {1} bytes of RGBA pixels, got {2}")]
+    PixelCount(String, usize, usize),
+    #[error("Texture {0} has no pixels")]
+    Empty(String),
+}
+
+// How a texture is sampled between texels. Nearest keeps pixel art crisp; linear smooths
+// textures drawn at other sizes than their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TextureFilter {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+impl TextureFilter {
+    pub fn filter_mode(self) -> wgpu::FilterMode {
+        match self {
+            TextureFilter::Nearest => wgpu::FilterMode::Nearest,
+            TextureFilter::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureId(pub usize);
+
+// Where a texture's texels are: a page and a rect within it, padding excluded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureRegion {
+    pub page: usize,
+    pub position: [u32; 2],
+    pub size: [u32; 2],
+}
+
+struct TexturePage {
+    // None for a page holding a single texture too big to share one
+    allocator: Option<BucketedAtlasAllocator>,
+    filter: TextureFilter,
+    width: u32,
+    height: u32,
+    // Row-major RGBA
+    pixels: Vec<u8>,
+    // Rows written since the last upload
+    dirty: Option<Range<u32>>,
+    gpu: Option<(Texture, TextureView)>,
+    // Bumped whenever the GPU texture is recreated, so bind groups on it can be too
+    generation: u64,
+}
+
+impl TexturePage {
+    fn new(width: u32, height: u32, filter: TextureFilter, shared: bool) -> Self {
+        Self {
+            allocator: shared.then(|| BucketedAtlasAllocator::new(size2(width as i32, height as i32))),
+            filter,
+            width,
+            height,
+            pixels: vec![0; (width * height * BYTES_PER_TEXEL) as usize],
+            dirty: None,
+            gpu: None,
+            generation: 0,
+        }
+    }
+
+    // Top-left of a free `size` rect, doubling the page up to `max_size` until one fits
+    fn allocate(&mut self, size: [u32; 2], max_size: u32) -> Option<[u32; 2]> {
+        let allocator = self.allocator.as_mut()?;
+        loop {
+            if let Some(allocation) = allocator.allocate(size2(size[0] as i32, size[1] as i32)) {
+                let min = allocation.rectangle.min;
+                return Some([min.x as u32, min.y as u32]);
+            }
+            if self.width * 2 > max_size {
+                return None;
+            }
+            let grown = self.width * 2;
+            allocator.grow(size2(grown as i32, grown as i32));
+            let mut pixels = vec![0; (grown * grown * BYTES_PER_TEXEL) as usize];
+            let (old, new) = ((self.width * BYTES_PER_TEXEL) as usize, (grown * BYTES_PER_TEXEL) as usize);
+            for y in 0..self.height as usize {
+                pixels[y * new..y * new + old].copy_from_slice(&self.pixels[y * old..(y + 1) * old]);
+            }
+            self.pixels = pixels;
+            self.width = grown;
+            self.height = grown;
+            self.dirty = Some(0..grown);
+        }
+    }
+
+    // Copies `pixels` into the padded rect at `position`, extruding its edges into the padding
+    fn write(&mut self, position: [u32; 2], width: u32, height: u32, pixels: &[u8]) {
+        let stride = (self.width * BYTES_PER_TEXEL) as usize;
+        for y in 0..height + 2 * PADDING {
+            let source_y = y.saturating_sub(PADDING).min(height - 1);
+            let source = &pixels[(source_y * width * BYTES_PER_TEXEL) as usize..((source_y + 1) * width * BYTES_PER_TEXEL) as usize];
+            let row = (position[1] + y) as usize * stride + (position[0] * BYTES_PER_TEXEL) as usize;
+            for x in 0..width + 2 * PADDING {
+                let source_x = (x.saturating_sub(PADDING).min(width - 1) * BYTES_PER_TEXEL) as usize;
+                let target = row + (x * BYTES_PER_TEXEL) as usize;
+                self.pixels[target..target + BYTES_PER_TEXEL as usize]
+                    .copy_from_slice(&source[source_x..source_x + BYTES_PER_TEXEL as usize]);
+            }
+        }
+        let rows = position[1]..position[1] + height + 2 * PADDING;
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(rows.start)..dirty.end.max(rows.end),
+            None => rows,
+        });
+    }
+
+    fn upload(&mut self, device: &Device, queue: &Queue) {
+        let mut rows = self.dirty.take();
+        let stale = self
+            .gpu
+            .as_ref()
+            .is_none_or(|(texture, _)| texture.width() != self.width || texture.height() != self.height);
+        if stale {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Texture Page"),
+                size: wgpu::Extent3d {
+                    width: self.width,
+                    height: self.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                // Images are stored sRGB encoded, so sampling and filtering happen in linear space
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.gpu = Some((texture, view));
+            self.generation += 1;
+            rows = Some(0..self.height);
+        }
+        let (Some((texture, _)), Some(rows)) = (&self.gpu, rows) else {
+            return;
+        };
+        let stride = self.width * BYTES_PER_TEXEL;
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: 0, y: rows.start, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self.pixels[(rows.start * stride) as usize..(rows.end * stride) as usize],
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(stride),
+                rows_per_image: Some(rows.len() as u32),
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: rows.len() as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+// Images packed into RGBA atlas pages. Textures only share a page with others sampled the
+// same way; a page starts at `page_size` and doubles when one doesn't fit, up to
+// `max_page_size`, after which another page is started. Textures bigger than that get a page
+// of their own. Texels are kept on the CPU until `upload` sends what changed to the GPU.
+pub struct TextureManager {
+    pages: Vec<TexturePage>,
+    textures: Vec<(String, TextureRegion)>,
+    page_size: u32,
+    max_page_size: u32,
+}
+
+impl TextureManager {
+    pub const DEFAULT_PAGE_SIZE: u32 = 512;
+    pub const DEFAULT_MAX_PAGE_SIZE: u32 = 4096;
+
+    pub fn new() -> Self {
+        Self::with_page_size(Self::DEFAULT_PAGE_SIZE, Self::DEFAULT_MAX_PAGE_SIZE)
+    }
+
+    pub fn with_page_size(page_size: u32, max_page_size: u32) -> Self {
+        let page_size = page_size.max(1);
+        Self {
+            pages: Vec::new(),
+            textures: Vec::new(),
+            page_size,
+            max_page_size: max_page_size.max(page_size),
+        }
+    }
+
+    // Decodes a PNG or JPEG image and packs it under `name`. Loading a name again shadows
+    // the texture loaded before it.
+    pub fn load(&mut self, name: impl Into<String>, bytes: &[u8], filter: TextureFilter) -> Result<TextureId, TextureError> {
+        let name = name.into();
+        let image = image::load_from_memory(bytes).map_err(|e| TextureError::Decode(name.clone(), e.to_string()))?;
+        let image = image.into_rgba8();
+        let (width, height) = image.dimensions();
+        self.insert_rgba(name, width, height, image.as_raw(), filter)
+    }
+
+    // Packs `width` by `height` texels of row-major, straight alpha RGBA8 under `name`
+    pub fn insert_rgba(
+        &mut self,
+        name: impl Into<String>,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        filter: TextureFilter,
+    ) -> Result<TextureId, TextureError> {
+        let name = name.into();
+        if width == 0 || height == 0 {
+            return Err(TextureError::Empty(name));
+        }
+        let expected = width as usize * height as usize * BYTES_PER_TEXEL as usize;
+        if pixels.len() != expected {
+            return Err(TextureError::PixelCount(name, expected, pixels.len()));
+        }
+
+        let padded = [width + 2 * PADDING, height + 2 * PADDING];
+        let max_page_size = self.max_page_size;
+        let allocation = self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, page)| page.filter == filter)
+            .find_map(|(index, page)| page.allocate(padded, max_page_size).map(|position| (index, position)));
+        let (page, position) = match allocation {
+            Some(allocation) => allocation,
+            None if padded[0].max(padded[1]) > max_page_size => {
+                self.pages.push(TexturePage::new(padded[0], padded[1], filter, false));
+                (self.pages.len() - 1, [0, 0])
+            }
+            None => {
+                let size = self.page_size.max(padded[0].max(padded[1]).next_power_of_two()).min(max_page_size);
+                let mut page = TexturePage::new(size, size, filter, true);
+                let position = page.allocate(padded, max_page_size).expect("Texture fits an empty page");
+                self.pages.push(page);
+                (self.pages.len() - 1, position)
+            }
+        };
+        self.pages[page].write(position, width, height, pixels);
+
+        let region = TextureRegion {
+            page,
+            position: [position[0] + PADDING, position[1] + PADDING],
+            size: [width, height],
+        };
+        self.textures.push((name, region));
+        Ok(TextureId(self.textures.len() - 1))
+    }
+
+    // The most recently loaded texture named `name`
+    pub fn find(&self, name: &str) -> Option<TextureId> {
+        self.textures.iter().rposition(|(loaded, _)| loaded == name).map(TextureId)
+    }
+
+    pub fn region(&self, id: TextureId) -> Option<TextureRegion> {
+        self.textures.get(id.0).map(|(_, region)| *region)
+    }
+
+    pub fn name(&self, id: TextureId) -> Option<&str> {
+        self.textures.get(id.0).map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    // Width and height of a page in texels
+    pub fn page_size(&self, page: usize) -> Option<[u32; 2]> {
+        self.pages.get(page).map(|page| [page.width, page.height])
+    }
+
+    pub fn page_filter(&self, page: usize) -> Option<TextureFilter> {
+        self.pages.get(page).map(|page| page.filter)
+    }
+
+    // Row-major RGBA texels of a page
+    pub fn page_pixels(&self, page: usize) -> Option<&[u8]> {
+        self.pages.get(page).map(|page| page.pixels.as_slice())
+    }
+
+    // The page's texture once uploaded
+    pub fn page_view(&self, page: usize) -> Option<&TextureView> {
+        self.pages.get(page)?.gpu.as_ref().map(|(_, view)| view)
+    }
+
+    // Changes whenever the page's texture is recreated, which invalidates bind groups on
+    // its view
+    pub fn page_generation(&self, page: usize) -> u64 {
+        self.pages.get(page).map_or(0, |page| page.generation)
+    }
+
+    // Sends texels written since the last upload to the GPU, recreating pages that grew
+    pub fn upload(&mut self, device: &Device, queue: &Queue) {
+        for page in &mut self.pages {
+            page.upload(device, queue);
+        }
+    }
+
+    // Forgets every texture and page. Regions handed out before are invalid afterwards.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.textures.clear();
+    }
+}
+
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TextureManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextureManager")
+            .field("textures", &self.textures)
+            .field("pages", &self.pages.len())
+            .finish()
+    }
+}
@@ -5,7 +5,13 @@ use web_sys::HtmlCanvasElement;
 #[cfg(feature = "web-canvas")]
 use std::rc::Rc;
 #[cfg(feature = "web-canvas")]
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "web-canvas")]
+use crate::context::{Context, ControlState, FrameClock, FrameTime, Theme};
+#[cfg(feature = "web-canvas")]
+use crate::loading::JoinAll;
+#[cfg(feature = "web-canvas")]
+use crate::input::{PointerButton, PointerEvent, PointerEventKind};
 
 #[cfg(feature = "web-canvas")]
 #[derive(Debug, Clone, Copy)]
@@ -81,8 +87,56 @@ pub type WebRc<T> = ();
 pub struct WebEventLoop {
     canvas: Rc<WebCanvas>,
     graphics: Option<Rc<RefCell<crate::graphics::Graphics>>>,
+    shared: Rc<LoopShared>,
+}
+
+// State shared between the event loop handle and the requestAnimationFrame callbacks
+#[cfg(feature = "web-canvas")]
+struct LoopShared {
+    graphics: Rc<RefCell<crate::graphics::Graphics>>,
     user_app: Rc<RefCell<dyn crate::plinth_app::PlinthApp>>,
-    animation_frame_id: Option<i32>,
+    clock: RefCell<FrameClock>,
+    animation_frame_id: Cell<Option<i32>>,
+    initialized: Cell<bool>,
+    // Futures returned from PlinthApp::load are still running
+    loading: Cell<bool>,
+    closed: Cell<bool>,
+}
+
+#[cfg(feature = "web-canvas")]
+impl LoopShared {
+    // Runs a hook with a fresh Context and returns the control requests it made
+    fn with_context(
+        &self,
+        time: FrameTime,
+        hook: impl FnOnce(&mut dyn crate::plinth_app::PlinthApp, &mut Context),
+    ) -> ControlState {
+        let mut control = ControlState::default();
+        let mut gfx = self.graphics.borrow_mut();
+        hook(&mut *self.user_app.borrow_mut(), &mut Context::new(&mut gfx, time, &mut control));
+        control
+    }
+
+    // Applies the exit / redraw requests a hook made, the same way after every hook
+    fn apply_control(self: &Rc<Self>, control: ControlState) {
+        if control.exit_requested {
+            self.close();
+        } else if control.redraw_requested {
+            schedule_frame(Rc::clone(self));
+        }
+    }
+
+    fn close(&self) {
+        if self.closed.replace(true) {
+            return;
+        }
+        if let Some(id) = self.animation_frame_id.take() {
+            let window = web_sys::window().unwrap();
+            window.cancel_animation_frame(id).ok();
+        }
+        let time = self.clock.borrow().now();
+        self.with_context(time, |app, ctx| app.on_close(ctx));
+    }
 }
 
 #[cfg(feature = "web-canvas")]
@@ -92,46 +146,133 @@ impl WebEventLoop {
         graphics: Rc<RefCell<crate::graphics::Graphics>>,
         user_app: Rc<RefCell<dyn crate::plinth_app::PlinthApp>>
     ) -> Self {
+        let shared = Rc::new(LoopShared {
+            graphics: Rc::clone(&graphics),
+            user_app,
+            clock: RefCell::new(FrameClock::new()),
+            animation_frame_id: Cell::new(None),
+            initialized: Cell::new(false),
+            loading: Cell::new(false),
+            closed: Cell::new(false),
+        });
         Self { 
             canvas, 
             graphics: Some(graphics),
-            user_app,
-            animation_frame_id: None,
+            shared,
         }
     }
 
     pub fn start(&mut self) {
+        // Parallel to winit's user_event: init runs once, after graphics are ready
+        if !self.shared.initialized.replace(true) {
+            self.listen_for_pointer_events();
+            self.listen_for_theme_changes();
+            let time = self.shared.clock.borrow().now();
+            let mut futures = Vec::new();
+            let control = self.shared.with_context(time, |app, ctx| {
+                app.init(ctx);
+                futures = app.load(ctx);
+            });
+            if control.exit_requested {
+                self.shared.close();
+                return;
+            }
+
+            if futures.is_empty() {
+                let control = self.shared.with_context(time, |app, ctx| app.loaded(ctx));
+                if control.exit_requested {
+                    self.shared.close();
+                    return;
+                }
+            } else {
+                // The first frames show render_loading until every future has resolved
+                self.shared.loading.set(true);
+                let shared = Rc::clone(&self.shared);
+                wasm_bindgen_futures::spawn_local(async move {
+                    JoinAll::new(futures).await;
+                    shared.loading.set(false);
+                    if shared.closed.get() {
+                        return;
+                    }
+                    let time = shared.clock.borrow().now();
+                    let mut control = shared.with_context(time, |app, ctx| app.loaded(ctx));
+                    // The first real frame replaces the loading screen either way
+                    control.redraw_requested = true;
+                    shared.apply_control(control);
+                });
+            }
+        }
         self.request_redraw();
     }
 
     pub fn stop(&mut self) {
-        if let Some(id) = self.animation_frame_id.take() {
-            let window = web_sys::window().unwrap();
-            window.cancel_animation_frame(id).ok();
+        self.shared.close();
+    }
+
+    // Forwards canvas pointer events to PlinthApp::pointer_event
+    fn listen_for_pointer_events(&self) {
+        for event_type in ["pointermove", "pointerdown", "pointerup", "pointerleave"] {
+            let shared = Rc::clone(&self.shared);
+            let canvas = self.canvas.canvas.clone();
+            let closure = Closure::wrap(Box::new(move |event: web_sys::PointerEvent| {
+                if shared.closed.get() {
+                    return;
+                }
+                let button = match event.button() {
+                    0 => PointerButton::Primary,
+                    1 => PointerButton::Middle,
+                    2 => PointerButton::Secondary,
+                    other => PointerButton::Other(other.max(0) as u16),
+                };
+                let kind = match event.type_().as_str() {
+                    "pointerdown" => PointerEventKind::Pressed(button),
+                    "pointerup" => PointerEventKind::Released(button),
+                    "pointerleave" => PointerEventKind::Left,
+                    _ => PointerEventKind::Moved,
+                };
+                // offsetX/Y are CSS pixels; scale them to the canvas backing store
+                let scale_x = canvas.width() as f32 / canvas.client_width().max(1) as f32;
+                let scale_y = canvas.height() as f32 / canvas.client_height().max(1) as f32;
+                let position = (event.offset_x() as f32 * scale_x, event.offset_y() as f32 * scale_y);
+
+                let time = shared.clock.borrow().now();
+                let control = shared.with_context(time, |app, ctx| app.pointer_event(ctx, &PointerEvent::new(kind, position)));
+                shared.apply_control(control);
+            }) as Box<dyn FnMut(web_sys::PointerEvent)>);
+            self.canvas
+                .canvas
+                .add_event_listener_with_callback(event_type, closure.as_ref().unchecked_ref())
+                .expect("Failed to add pointer listener");
+            closure.forget();
         }
     }
 
+    // Parallel to winit's ThemeChanged: the browser's prefers-color-scheme flipped
+    fn listen_for_theme_changes(&self) {
+        let Some(list) = web_sys::window().and_then(|w| w.match_media("(prefers-color-scheme: dark)").ok().flatten()) else {
+            return;
+        };
+        let shared = Rc::clone(&self.shared);
+        let closure = Closure::wrap(Box::new(move |event: web_sys::MediaQueryListEvent| {
+            if shared.closed.get() {
+                return;
+            }
+            let theme = if event.matches() { Theme::Dark } else { Theme::Light };
+            let time = shared.clock.borrow().now();
+            let control = shared.with_context(time, |app, ctx| app.theme_changed(ctx, theme));
+            shared.apply_control(control);
+        }) as Box<dyn FnMut(web_sys::MediaQueryListEvent)>);
+        list.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())
+            .expect("Failed to add theme listener");
+        closure.forget();
+    }
+
+    pub fn canvas(&self) -> &WebCanvas {
+        &self.canvas
+    }
+
     pub fn request_redraw(&mut self) {
-        let graphics = self.graphics.clone().unwrap();
-        let user_app: Rc<RefCell<dyn crate::plinth_app::PlinthApp>> = Rc::clone(&self.user_app);
-        let canvas = Rc::clone(&self.canvas);
-        
-        // Create closure for requestAnimationFrame
-        let closure = Closure::wrap(Box::new(move || {
-            // This is the render loop - parallel to winit's draw() method
-            let mut gfx = graphics.borrow_mut();
-            user_app.borrow_mut().before_render();
-            user_app.borrow_mut().render(&mut gfx);
-            user_app.borrow_mut().after_render();
-        }) as Box<dyn FnMut()>);
-
-        let window = web_sys::window().unwrap();
-        let id = window
-            .request_animation_frame(closure.as_ref().unchecked_ref())
-            .expect("Failed to request animation frame");
-        
-        self.animation_frame_id = Some(id);
-        closure.forget(); // Prevent closure from being dropped
+        schedule_frame(Rc::clone(&self.shared));
     }
 
     // Handle canvas resize (parallel to winit's resized method)
@@ -166,6 +307,43 @@ impl WebEventLoop {
     }
 }
 
+// Renders one frame on the next animation frame. Keeps rescheduling itself for as long
+// as the app calls `Context::request_redraw`, and shuts down when it calls `Context::exit`.
+#[cfg(feature = "web-canvas")]
+fn schedule_frame(shared: Rc<LoopShared>) {
+    // Closed, or a frame is already pending that will pick up this request
+    if shared.closed.get() || shared.animation_frame_id.get().is_some() {
+        return;
+    }
+
+    let frame_shared = Rc::clone(&shared);
+    let closure = Closure::once_into_js(move || {
+        let shared = frame_shared;
+        shared.animation_frame_id.set(None);
+
+        if shared.loading.get() {
+            shared.user_app.borrow_mut().render_loading(&mut shared.graphics.borrow_mut());
+            return;
+        }
+
+        // This is the render loop - parallel to winit's draw() method
+        shared.graphics.borrow_mut().reload_shaders();
+        let time = shared.clock.borrow_mut().tick();
+        let mut control = shared.with_context(time, |app, ctx| app.before_render(ctx));
+        shared.user_app.borrow_mut().render(&mut shared.graphics.borrow_mut());
+        let after = shared.with_context(time, |app, ctx| app.after_render(ctx));
+        control.exit_requested |= after.exit_requested;
+        control.redraw_requested |= after.redraw_requested;
+        shared.apply_control(control);
+    });
+
+    let window = web_sys::window().unwrap();
+    let id = window
+        .request_animation_frame(closure.unchecked_ref())
+        .expect("Failed to request animation frame");
+    shared.animation_frame_id.set(Some(id));
+}
+
 // Add web event handling to PlinthApp trait
 #[cfg(feature = "web-canvas")]
 pub trait WebPlinthApp: crate::plinth_app::PlinthApp {
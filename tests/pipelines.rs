@@ -0,0 +1,194 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use plinth_core::pipelines::{BuildContext, PipelineRegistry, PipelineTargets};
+use plinth_core::wgpu::{self, Device, RenderPipeline};
+
+const SHADER: &str = "
+@group(0) @binding(0) var<uniform> offset: vec4<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(index), 0.0, 0.0, 1.0) + offset;
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+
+@compute @workgroup_size(1)
+fn cs_main() {
+    _ = offset;
+}
+";
+
+const TARGETS: PipelineTargets = PipelineTargets {
+    format: wgpu::TextureFormat::Rgba8Unorm,
+    sample_count: 1,
+};
+
+// Any adapter will do, including a software one. Returns None where there is none so the
+// tests pass trivially on machines without a GPU stack.
+fn device() -> Option<Device> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).ok()?;
+    let (device, _queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        required_limits: adapter.limits(),
+        ..Default::default()
+    }))
+    .ok()?;
+    Some(device)
+}
+
+fn uniform_layout(device: &Device, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("test uniforms"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+fn pipeline_layout(ctx: &BuildContext) -> wgpu::PipelineLayout {
+    ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("test layout"),
+        bind_group_layouts: &[ctx.bind_group_layouts.get_by_name("uniforms").unwrap()],
+        push_constant_ranges: &[],
+    })
+}
+
+fn render_pipeline(ctx: &BuildContext) -> RenderPipeline {
+    let module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("test shader"),
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+    ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("test pipeline"),
+        layout: Some(&pipeline_layout(ctx)),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(ctx.targets.format.into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: ctx.targets.multisample(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+// Targets each build of a builder saw, in order
+fn recorded() -> Arc<Mutex<Vec<PipelineTargets>>> {
+    Arc::new(Mutex::new(Vec::new()))
+}
+
+#[test]
+fn handles_stay_valid_when_an_entry_is_registered_again() {
+    let Some(device) = device() else { return };
+    let mut pipelines = PipelineRegistry::new(TARGETS);
+    pipelines.register_bind_group_layout(&device, "uniforms", |ctx| {
+        uniform_layout(ctx.device, wgpu::ShaderStages::VERTEX_FRAGMENT)
+    });
+
+    let first = pipelines.register_render(&device, "first", render_pipeline);
+    let second = pipelines.register_render(&device, "second", render_pipeline);
+    assert_ne!(first, second);
+
+    let again = pipelines.register_render(&device, "first", render_pipeline);
+    assert_eq!(again, first);
+    assert_eq!(pipelines.render.len(), 2);
+    assert_eq!(pipelines.render.name(first), "first");
+    assert_eq!(pipelines.render.name(second), "second");
+    assert_eq!(pipelines.render.handle("second"), Some(second));
+    assert_eq!(pipelines.render.handle("third"), None);
+}
+
+#[test]
+fn changing_the_sample_count_rebuilds_render_pipelines() {
+    let Some(device) = device() else { return };
+    let mut pipelines = PipelineRegistry::new(TARGETS);
+    pipelines.register_bind_group_layout(&device, "uniforms", |ctx| {
+        uniform_layout(ctx.device, wgpu::ShaderStages::VERTEX_FRAGMENT)
+    });
+    let builds = recorded();
+    let seen = builds.clone();
+    let handle = pipelines.register_render(&device, "quad", move |ctx| {
+        seen.lock().unwrap().push(ctx.targets);
+        render_pipeline(ctx)
+    });
+
+    assert!(!pipelines.set_targets(&device, TARGETS));
+    assert_eq!(builds.lock().unwrap().len(), 1);
+
+    let multisampled = PipelineTargets {
+        sample_count: 4,
+        ..TARGETS
+    };
+    assert!(pipelines.set_targets(&device, multisampled));
+    assert_eq!(pipelines.targets(), multisampled);
+    assert_eq!(*builds.lock().unwrap(), vec![TARGETS, multisampled]);
+    assert_eq!(pipelines.render.handle("quad"), Some(handle));
+}
+
+#[test]
+fn replacing_a_bind_group_layout_rebuilds_the_pipelines_using_it() {
+    let Some(device) = device() else { return };
+    let mut pipelines = PipelineRegistry::new(TARGETS);
+    pipelines.register_bind_group_layout(&device, "uniforms", |ctx| {
+        uniform_layout(ctx.device, wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::COMPUTE)
+    });
+    let render_builds = recorded();
+    let seen = render_builds.clone();
+    pipelines.register_render(&device, "quad", move |ctx| {
+        seen.lock().unwrap().push(ctx.targets);
+        render_pipeline(ctx)
+    });
+    let compute_builds = recorded();
+    let seen = compute_builds.clone();
+    pipelines.register_compute(&device, "step", move |ctx| {
+        seen.lock().unwrap().push(ctx.targets);
+        let module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("test shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("test compute"),
+            layout: Some(&pipeline_layout(ctx)),
+            module: &module,
+            entry_point: Some("cs_main"),
+            compilation_options: Default::default(),
+            cache: None,
+        })
+    });
+
+    // Registering an unrelated layout leaves the pipelines alone
+    pipelines.register_bind_group_layout(&device, "other", |ctx| {
+        uniform_layout(ctx.device, wgpu::ShaderStages::FRAGMENT)
+    });
+    assert_eq!(render_builds.lock().unwrap().len(), 1);
+    assert_eq!(compute_builds.lock().unwrap().len(), 1);
+
+    let handle = pipelines.bind_group_layouts.handle("uniforms");
+    let replaced = pipelines.register_bind_group_layout(&device, "uniforms", |ctx| {
+        uniform_layout(ctx.device, wgpu::ShaderStages::all())
+    });
+    assert_eq!(Some(replaced), handle);
+    assert_eq!(render_builds.lock().unwrap().len(), 2);
+    assert_eq!(compute_builds.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,178 @@
+use plinth_core::textures::{TextureError, TextureFilter, TextureId, TextureManager, TextureRegion};
+
+// 2x2: red, green / blue, transparent
+const CHECKER_PNG: &[u8] = include_bytes!("images/checker.png");
+// 8x8 of gray 200
+const GRAY_JPEG: &[u8] = include_bytes!("images/gray.jpg");
+
+fn texel(textures: &TextureManager, page: usize, x: u32, y: u32) -> [u8; 4] {
+    let width = textures.page_size(page).unwrap()[0];
+    let index = ((y * width + x) * 4) as usize;
+    textures.page_pixels(page).unwrap()[index..index + 4].try_into().unwrap()
+}
+
+fn filled(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+    color.repeat((width * height) as usize)
+}
+
+fn color(index: usize) -> [u8; 4] {
+    [index as u8 * 16, 255 - index as u8 * 16, 7, 255]
+}
+
+// Every texel of `region` and the 1 texel padding around it holds `color`
+fn assert_filled(textures: &TextureManager, region: TextureRegion, color: [u8; 4]) {
+    let [x, y] = region.position;
+    let [width, height] = region.size;
+    for ty in y - 1..y + height + 1 {
+        for tx in x - 1..x + width + 1 {
+            assert_eq!(texel(textures, region.page, tx, ty), color, "{:?} at ({}, {})", region, tx, ty);
+        }
+    }
+}
+
+#[test]
+fn packed_textures_keep_to_their_own_padded_rects() {
+    let mut textures = TextureManager::with_page_size(128, 128);
+    let sizes = [[10, 4], [3, 17], [20, 20], [1, 1], [30, 6], [6, 30], [12, 12], [2, 9]];
+    let ids: Vec<TextureId> = sizes
+        .iter()
+        .enumerate()
+        .map(|(index, &[width, height])| {
+            let pixels = filled(width, height, color(index));
+            textures.insert_rgba(format!("t{}", index), width, height, &pixels, TextureFilter::Linear).unwrap()
+        })
+        .collect();
+    assert_eq!(textures.page_count(), 1);
+    assert_eq!(textures.len(), sizes.len());
+
+    let regions: Vec<TextureRegion> = ids.iter().map(|id| textures.region(*id).unwrap()).collect();
+    for (index, region) in regions.iter().enumerate() {
+        assert_eq!(region.size, sizes[index]);
+        assert!(region.position[0] >= 1 && region.position[1] >= 1);
+        assert!(region.position[0] + region.size[0] < 128 && region.position[1] + region.size[1] < 128);
+        // Nothing packed later wrote over it, padding included
+        assert_filled(&textures, *region, color(index));
+    }
+}
+
+#[test]
+fn growing_a_page_keeps_earlier_texels_in_place() {
+    let mut textures = TextureManager::with_page_size(16, 64);
+    let mut packed = Vec::new();
+    for index in 0..6 {
+        let pixels = filled(12, 12, color(index));
+        let id = textures.insert_rgba(format!("t{}", index), 12, 12, &pixels, TextureFilter::Nearest).unwrap();
+        packed.push((id, textures.region(id).unwrap()));
+    }
+    // Six 14 texel squares outgrow 16 and 32 texel pages but fit one of 64
+    assert_eq!(textures.page_count(), 1);
+    assert_eq!(textures.page_size(0), Some([64, 64]));
+    assert_eq!(textures.page_pixels(0).unwrap().len(), 64 * 64 * 4);
+    for (index, (id, region)) in packed.into_iter().enumerate() {
+        assert_eq!(textures.region(id), Some(region));
+        assert_filled(&textures, region, color(index));
+    }
+}
+
+#[test]
+fn new_pages_fit_the_texture_that_starts_them() {
+    let mut textures = TextureManager::with_page_size(16, 256);
+    // 42 texels padded, rounded up to a power of two
+    let large = textures.insert_rgba("large", 40, 40, &filled(40, 40, color(1)), TextureFilter::Linear).unwrap();
+    assert_eq!(textures.page_size(0), Some([64, 64]));
+    assert_eq!(textures.region(large).unwrap().position, [1, 1]);
+
+    // Past the maximum a texture gets an unshared page of exactly its padded size
+    let huge = textures.insert_rgba("huge", 300, 2, &filled(300, 2, color(2)), TextureFilter::Linear).unwrap();
+    assert_eq!(textures.region(huge), Some(TextureRegion { page: 1, position: [1, 1], size: [300, 2] }));
+    assert_eq!(textures.page_size(1), Some([302, 4]));
+    assert_filled(&textures, textures.region(huge).unwrap(), color(2));
+    let small = textures.insert_rgba("small", 1, 1, &filled(1, 1, color(3)), TextureFilter::Linear).unwrap();
+    assert_eq!(textures.region(small).unwrap().page, 0);
+}
+
+#[test]
+fn decodes_png_and_jpeg_into_a_page() {
+    let mut textures = TextureManager::new();
+    let checker = textures.load("checker.png", CHECKER_PNG, TextureFilter::Nearest).unwrap();
+    let region = textures.region(checker).unwrap();
+    assert_eq!(region.size, [2, 2]);
+    let [x, y] = region.position;
+    assert_eq!(texel(&textures, 0, x, y), [255, 0, 0, 255]);
+    assert_eq!(texel(&textures, 0, x + 1, y), [0, 255, 0, 255]);
+    assert_eq!(texel(&textures, 0, x, y + 1), [0, 0, 255, 255]);
+    assert_eq!(texel(&textures, 0, x + 1, y + 1)[3], 0);
+    // Edges are repeated into the padding
+    assert_eq!(texel(&textures, 0, x - 1, y - 1), [255, 0, 0, 255]);
+    assert_eq!(texel(&textures, 0, x + 2, y - 1), [0, 255, 0, 255]);
+
+    let gray = textures.load("gray.jpg", GRAY_JPEG, TextureFilter::Linear).unwrap();
+    let region = textures.region(gray).unwrap();
+    assert_eq!(region.size, [8, 8]);
+    // JPEG is lossy, but a flat image stays close
+    let pixel = texel(&textures, region.page, region.position[0] + 4, region.position[1] + 4);
+    assert!(pixel[..3].iter().all(|channel| channel.abs_diff(200) <= 2) && pixel[3] == 255, "{:?}", pixel);
+
+    assert_eq!(textures.find("checker.png"), Some(checker));
+    assert_eq!(textures.name(gray), Some("gray.jpg"));
+    assert!(matches!(textures.load("broken.png", &[0u8; 16], TextureFilter::Linear), Err(TextureError::Decode(..))));
+    assert_eq!(
+        textures.insert_rgba("short", 2, 2, &[0; 15], TextureFilter::Linear),
+        Err(TextureError::PixelCount("short".into(), 16, 15))
+    );
+    assert_eq!(textures.insert_rgba("none", 0, 4, &[], TextureFilter::Linear), Err(TextureError::Empty("none".into())));
+    assert_eq!(textures.len(), 2);
+}
+
+#[test]
+fn full_pages_spill_over_and_names_can_be_loaded_again() {
+    let mut textures = TextureManager::with_page_size(16, 32);
+    // Four 14 texel squares fill a 32 texel page, so the fifth starts a new one
+    for index in 0..4 {
+        let pixels = filled(14, 14, color(index));
+        let id = textures.insert_rgba(format!("t{}", index), 14, 14, &pixels, TextureFilter::Linear).unwrap();
+        assert_eq!(textures.region(id).unwrap().page, 0);
+    }
+    let spilled = textures.insert_rgba("t4", 14, 14, &filled(14, 14, color(4)), TextureFilter::Linear).unwrap();
+    assert_eq!(textures.region(spilled).unwrap().page, 1);
+    assert_eq!(textures.page_size(0), Some([32, 32]));
+
+    // Loading a name again shadows the earlier texture
+    let first = textures.find("t0").unwrap();
+    let again = textures.insert_rgba("t0", 1, 1, &filled(1, 1, color(5)), TextureFilter::Linear).unwrap();
+    assert_ne!(again, first);
+    assert_eq!(textures.find("t0"), Some(again));
+    assert_eq!(textures.region(TextureId(99)), None);
+}
+
+#[test]
+fn filters_pick_the_sampler_and_keep_to_their_own_pages() {
+    assert_eq!(TextureFilter::default(), TextureFilter::Linear);
+    assert_eq!(TextureFilter::Nearest.filter_mode(), wgpu::FilterMode::Nearest);
+    assert_eq!(TextureFilter::Linear.filter_mode(), wgpu::FilterMode::Linear);
+
+    let mut textures = TextureManager::new();
+    let pixel = textures.insert_rgba("pixel", 4, 4, &filled(4, 4, color(1)), TextureFilter::Nearest).unwrap();
+    let smooth = textures.insert_rgba("smooth", 4, 4, &filled(4, 4, color(2)), TextureFilter::Linear).unwrap();
+    let pixel2 = textures.insert_rgba("pixel2", 4, 4, &filled(4, 4, color(3)), TextureFilter::Nearest).unwrap();
+    let pages = [pixel, smooth, pixel2].map(|id| textures.region(id).unwrap().page);
+    assert_eq!(pages, [0, 1, 0]);
+    assert_eq!(textures.page_filter(0), Some(TextureFilter::Nearest));
+    assert_eq!(textures.page_filter(1), Some(TextureFilter::Linear));
+    assert_eq!(textures.page_filter(2), None);
+    assert_eq!(textures.page_size(0), Some([TextureManager::DEFAULT_PAGE_SIZE; 2]));
+}
+
+#[test]
+fn pages_wait_for_an_upload_before_they_have_a_view() {
+    let mut textures = TextureManager::default();
+    textures.insert_rgba("a", 2, 2, &filled(2, 2, color(1)), TextureFilter::Linear).unwrap();
+    assert!(textures.page_view(0).is_none());
+    assert_eq!(textures.page_generation(0), 0);
+    assert_eq!(textures.page_generation(5), 0);
+
+    textures.clear();
+    assert!(textures.is_empty());
+    assert_eq!(textures.page_count(), 0);
+    assert_eq!(textures.find("a"), None);
+}
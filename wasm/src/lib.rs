@@ -1,27 +1,11 @@
-use plinth_core::{plinth_app::PlinthApp, plinth_app::PlinthRenderer, web_canvas::{WebCanvas, WebEventLoop, WebRc}};
+use plinth_core::{context::Context, plinth_app::PlinthApp, plinth_app::PlinthRenderer, web_canvas::{WebCanvas, WebEventLoop, WebRc}};
+use plinth_core::input::PointerEvent;
 use plinth_primitives::{Circle, Color, Transform, PrimitiveRenderer};
-use plinth_styles::{ClassMapper, CssWatcher};
-use plinth_styles::mapping::ColorProperty;
+use plinth_styles::{ClassMapper, CssWatcher, MediaEnvironment};
 
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 
-// Helper function to parse hex color strings
-fn parse_hex_color(hex: &str) -> Result<Color, String> {
-    let hex = hex.trim_start_matches('#');
-    if hex.len() != 6 {
-        return Err(format!("Invalid hex color length: {}", hex));
-    }
-    
-    let r = u8::from_str_radix(&hex[0..2], 16)
-        .map_err(|_| "Invalid hex color")?;
-    let g = u8::from_str_radix(&hex[2..4], 16)
-        .map_err(|_| "Invalid hex color")?;
-    let b = u8::from_str_radix(&hex[4..6], 16)
-        .map_err(|_| "Invalid hex color")?;
-    
-    Ok(Color::from_rgba(r, g, b, 255))
-}
 use glam::Vec2;
 
 // Import the `console.log` function from the `console` module
@@ -48,9 +32,9 @@ impl TestApp {
 
 struct PrimitivesTestApp {
     frame_count: u32,
+    // Handed to the renderer on init, which keeps them from then on
     circles: Vec<Circle>,
     class_mapper: WebRc<RefCell<ClassMapper>>,
-    time: f32,
     primitive_renderer: Option<PrimitiveRenderer>,
     css_watcher: Option<CssWatcher>,
 }
@@ -78,7 +62,6 @@ impl PrimitivesTestApp {
             frame_count: 0,
             circles,
             class_mapper,
-            time: 0.0,
             primitive_renderer: None,
             css_watcher: None,
         }
@@ -87,29 +70,44 @@ impl PrimitivesTestApp {
 
 
 impl PlinthApp for TestApp {
-    fn init(&mut self) {
-        console_log!("Test app initialized!");
+    fn init(&mut self, ctx: &mut Context) {
+        let (width, height) = ctx.size();
+        console_log!("Test app initialized at {}x{}!", width, height);
     }
 
-    fn before_render(&mut self) {
+    fn before_render(&mut self, _ctx: &mut Context) {
         self.frame_count += 1;
         // Frame counting - no logging needed
     }
 
-    fn after_render(&mut self) {
+    fn after_render(&mut self, _ctx: &mut Context) {
         // Optional: Add any post-render logic here
     }
 
-    fn on_close(&mut self) {
+    fn on_close(&mut self, _ctx: &mut Context) {
         console_log!("Test app closing after {} frames", self.frame_count);
     }
 }
 
 impl PlinthApp for PrimitivesTestApp {
-    fn init(&mut self) {
+    fn init(&mut self, ctx: &mut Context) {
         console_log!("Primitives test app initialized!");
+
+        // GPU resources can be created up front now that graphics are available. The renderer
+        // styles the circles from their classes and hover/active state through the mapper.
+        let gfx = ctx.graphics();
+        let mut primitive_renderer = PrimitiveRenderer::new(&gfx.device, gfx.surface_config.format)
+            .with_class_mapper(WebRc::clone(&self.class_mapper));
+        primitive_renderer.add_circles(std::mem::take(&mut self.circles));
+        self.primitive_renderer = Some(primitive_renderer);
+
         console_log!("About to create CSS watcher...");
         
+        // Page stylesheets provide the :hover/:active rules
+        if let Err(e) = self.class_mapper.borrow_mut().load_from_dom() {
+            console_log!("Failed to load stylesheets: {}", e);
+        }
+
         // Initialize CSS watcher
         let mut css_watcher = CssWatcher::new(WebRc::clone(&self.class_mapper));
         console_log!("CSS watcher created, about to start...");
@@ -118,9 +116,10 @@ impl PlinthApp for PrimitivesTestApp {
         css_watcher.watch_class("accent-button");
         
         // Set up callback to log when CSS changes
-        css_watcher.set_callback(|| {
-            console_log!("CSS custom properties changed, class mapper updated");
-            console_log!("CSS watcher callback triggered!");
+        css_watcher.set_callback(|changes| {
+            for change in changes {
+                console_log!("CSS watcher: .{} {} -> {} = {}", change.class_name, change.property, change.target, change.value);
+            }
         });
         
         if let Err(e) = css_watcher.start() {
@@ -132,36 +131,55 @@ impl PlinthApp for PrimitivesTestApp {
         self.css_watcher = Some(css_watcher);
     }
 
-    fn before_render(&mut self) {
+    fn before_render(&mut self, ctx: &mut Context) {
         self.frame_count += 1;
+        let time = ctx.time().elapsed.as_secs_f32();
         
-        // Apply CSS class overrides
-        for circle in &mut self.circles {
-            if let Some(ref class_name) = circle.css_class {
-                if let Some(override_color) = self.class_mapper.borrow().get_color_for_class(class_name, ColorProperty::Color) {
-                    circle.apply_css_override(override_color);
-                }
+        // What the CSS watcher costs, every ten seconds or so
+        if self.frame_count.is_multiple_of(600) {
+            if let Some(metrics) = self.css_watcher.as_ref().map(CssWatcher::metrics) {
+                console_log!(
+                    "CSS watcher: {} checks ({:.1}/s, last {:.2}ms), {}/{} relevant mutations",
+                    metrics.checks, metrics.checks_per_second, metrics.last_check_ms, metrics.relevant_mutations, metrics.mutations
+                );
             }
         }
-        
-        // Animate the circles
-        for (i, circle) in self.circles.iter_mut().enumerate() {
-            let offset = (self.time + i as f32 * 0.5) * 0.5;
+
+        // @media rules follow the canvas size and the browser's color scheme
+        let environment = MediaEnvironment::from_context(ctx);
+        if *self.class_mapper.borrow().environment() != environment {
+            self.class_mapper.borrow_mut().set_environment(environment);
+        }
+
+        // Animate the circles in place; the renderer re-evaluates hover under a still pointer
+        let Some(primitive_renderer) = self.primitive_renderer.as_mut() else {
+            return;
+        };
+        primitive_renderer.set_time(ctx.time().elapsed);
+        primitive_renderer.set_scale_factor(ctx.scale_factor() as f32);
+        for (i, circle) in primitive_renderer.circles_mut().iter_mut().enumerate() {
+            let offset = (time + i as f32 * 0.5) * 0.5;
             circle.center.x = 0.0 + 0.4 * offset.sin();
             circle.center.y = 0.0 + 0.3 * offset.cos();
         }
-        
-        // Update time for animation
-        self.time += 0.016; // ~60 FPS
-        
-        // Animation update - no logging needed
     }
 
-    fn after_render(&mut self) {
-        // Optional: Add any post-render logic here
+    fn after_render(&mut self, ctx: &mut Context) {
+        // Keep :hover transitions and @keyframes running to their end
+        if self.primitive_renderer.as_ref().is_some_and(PrimitiveRenderer::is_animating) {
+            ctx.request_redraw();
+        }
     }
 
-    fn on_close(&mut self) {
+    fn pointer_event(&mut self, ctx: &mut Context, event: &PointerEvent) {
+        if let Some(primitive_renderer) = self.primitive_renderer.as_mut() {
+            if primitive_renderer.handle_pointer_event(event, ctx.size()) {
+                ctx.request_redraw();
+            }
+        }
+    }
+
+    fn on_close(&mut self, _ctx: &mut Context) {
         // App closing - no logging needed
     }
 }
@@ -174,17 +192,8 @@ impl PlinthRenderer for TestApp {
 impl PlinthRenderer for PrimitivesTestApp {
     // Override the render method to draw primitives
     fn render(&mut self, graphics: &mut plinth_core::graphics::Graphics) {
-        // Initialize primitive renderer if not already done
-        if self.primitive_renderer.is_none() {
-            self.primitive_renderer = Some(PrimitiveRenderer::new(&graphics.device, graphics.surface_config.format));
-        }
-        
-        // Add circles to the primitive renderer
+        // Render the circles the renderer holds
         if let Some(ref mut primitive_renderer) = self.primitive_renderer {
-            primitive_renderer.clear_circles();
-            primitive_renderer.add_circles(self.circles.clone());
-            
-            // Render the circles using the primitive renderer
             primitive_renderer.render(&graphics.device, &graphics.queue, &graphics.surface, &graphics.surface_config);
         }
     }
@@ -275,8 +284,7 @@ impl WasmApp {
         let canvas_rc: WebRc<WebCanvas> = WebRc::new(canvas);
         
         // Create the primitives test app
-        let mut primitives_app = PrimitivesTestApp::new();
-        primitives_app.init(); // Manually call init to set up CSS watcher
+        let primitives_app = PrimitivesTestApp::new();
         let primitives_app_rc: WebRc<RefCell<PrimitivesTestApp>> = WebRc::new(RefCell::new(primitives_app));
         let app_rc: WebRc<RefCell<dyn PlinthApp>> = WebRc::clone(&primitives_app_rc) as WebRc<RefCell<dyn PlinthApp>>;
         
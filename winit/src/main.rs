@@ -1,4 +1,4 @@
-use plinth_core::{app::start_app, plinth_app::PlinthApp, plinth_app::PlinthRenderer};
+use plinth_core::{app::start_app, context::Context, plinth_app::PlinthApp, plinth_app::PlinthRenderer};
 use std::cell::RefCell;
 use std::sync::Arc;
 
@@ -13,22 +13,29 @@ impl TestApp {
 }
 
 impl PlinthApp for TestApp {
-    fn init(&mut self) {
-        println!("Test app initialized!");
+    fn init(&mut self, ctx: &mut Context) {
+        let (width, height) = ctx.size();
+        println!("Test app initialized at {}x{}!", width, height);
+        ctx.set_title("Plinth Winit Test");
+        if let Err(e) = ctx.graphics_mut().enable_shader_hot_reload() {
+            println!("Shader hot reload unavailable: {}", e);
+        }
+        ctx.request_redraw();
     }
 
-    fn before_render(&mut self) {
+    fn before_render(&mut self, ctx: &mut Context) {
         self.frame_count += 1;
         if self.frame_count % 60 == 0 {
-            println!("Frame: {}", self.frame_count);
+            println!("Frame: {} ({:.1}s)", self.frame_count, ctx.time().elapsed.as_secs_f32());
         }
     }
 
-    fn after_render(&mut self) {
-        // Optional: Add any post-render logic here
+    fn after_render(&mut self, ctx: &mut Context) {
+        // Keep rendering continuously
+        ctx.request_redraw();
     }
 
-    fn on_close(&mut self) {
+    fn on_close(&mut self, _ctx: &mut Context) {
         println!("Test app closing after {} frames", self.frame_count);
     }
 }